@@ -0,0 +1,59 @@
+/*
+ * C API for the Faderpunk controller, provided by the faderpunk_cli cdylib
+ * (libfaderpunk_cli.so / .dylib / faderpunk_cli.dll).
+ *
+ * Device state is exchanged as JSON strings in the same shape as the CLI's
+ * snapshot files. Strings returned by the library must be released with
+ * fp_string_free. Calls that fail return NULL or -1; fp_last_error then
+ * describes the failure.
+ *
+ * A handle must not be used from two threads at the same time.
+ */
+
+#ifndef FADERPUNK_H
+#define FADERPUNK_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct FpDevice FpDevice;
+
+/* Called with each device event as JSON, on a library thread. The string is
+ * only valid during the call. */
+typedef void (*FpEventCallback)(const char *event, void *user_data);
+
+/* Why the last call on this thread failed, or NULL. */
+const char *fp_last_error(void);
+
+/* Connect to the first Faderpunk found; NULL on failure. */
+FpDevice *fp_open(void);
+
+/* Close the connection. No callbacks run after this returns. */
+void fp_close(FpDevice *dev);
+
+void fp_string_free(char *s);
+
+/* 16 entries, each null or [app_id, channels, layout_id]. */
+char *fp_get_layout(FpDevice *dev);
+
+/* All param values of an app instance, as a JSON array. */
+char *fp_get_params(FpDevice *dev, uint8_t layout_id);
+
+/* One param value, as JSON. */
+char *fp_get_param(FpDevice *dev, uint8_t layout_id, size_t index);
+
+/* Set one param from JSON in the shape fp_get_param returns; 0 or -1. */
+int fp_set_param(FpDevice *dev, uint8_t layout_id, size_t index, const char *value_json);
+
+/* Receive device events; replaces any earlier callback, NULL stops. 0 or -1. */
+int fp_set_event_callback(FpDevice *dev, FpEventCallback callback, void *user_data);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* FADERPUNK_H */
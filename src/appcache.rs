@@ -0,0 +1,164 @@
+// Cache of the app metadata the device last reported, so a firmware update
+// that adds, removes, or reshapes apps gets noticed on the next command.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::display::AppInfo;
+use crate::paths;
+use crate::profile;
+use crate::protocol::Param;
+use crate::snapshot::Snapshot;
+
+const CACHE_FILE: &str = "apps.json";
+
+#[derive(Clone, Serialize, Deserialize)]
+struct CachedApp {
+    app_id: u8,
+    name: String,
+    channels: usize,
+    params: Vec<Param>,
+}
+
+impl CachedApp {
+    fn from_info(app: &AppInfo) -> Self {
+        CachedApp {
+            app_id: app.app_id,
+            name: app.name.clone(),
+            channels: app.channels,
+            params: app.params.clone(),
+        }
+    }
+
+    /// Whether the param metadata is identical (names, types, ranges).
+    fn same_params(&self, other: &CachedApp) -> bool {
+        serde_json::to_value(&self.params).ok() == serde_json::to_value(&other.params).ok()
+    }
+}
+
+fn path() -> Result<PathBuf> {
+    Ok(paths::data_dir()?.join(CACHE_FILE))
+}
+
+/// Compare the apps the device reports with the cached copy. If they differ,
+/// print what changed and which saved profiles it affects, then update the
+/// cache. The first run only fills the cache.
+pub fn check(apps: &[AppInfo]) -> Result<()> {
+    let path = path()?;
+    let current: Vec<CachedApp> = apps.iter().map(CachedApp::from_info).collect();
+    let cached: Option<Vec<CachedApp>> = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok());
+
+    if let Some(cached) = cached {
+        let changes = describe_changes(&cached, &current);
+        if changes.is_empty() {
+            return Ok(());
+        }
+        eprintln!("The apps on the device changed since it was last seen (firmware update?):");
+        for line in &changes {
+            eprintln!("  {}", line);
+        }
+        let affected = affected_profiles(&current)?;
+        if !affected.is_empty() {
+            eprintln!("Saved profiles that may no longer load as saved:");
+            for line in &affected {
+                eprintln!("  {}", line);
+            }
+        }
+    }
+
+    std::fs::write(&path, serde_json::to_string_pretty(&current)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn describe_changes(old: &[CachedApp], new: &[CachedApp]) -> Vec<String> {
+    let mut lines = Vec::new();
+    for app in new {
+        match old.iter().find(|a| a.app_id == app.app_id) {
+            None => lines.push(format!("+ {} (new, {} ch)", app.name, app.channels)),
+            Some(prev) if prev.name != app.name => {
+                lines.push(format!(
+                    "~ app {}: {} is now {}",
+                    app.app_id, prev.name, app.name
+                ));
+            }
+            Some(prev) if prev.channels != app.channels => lines.push(format!(
+                "~ {}: {} → {} channels",
+                app.name, prev.channels, app.channels
+            )),
+            Some(prev) if prev.params.len() != app.params.len() => lines.push(format!(
+                "~ {}: {} → {} params",
+                app.name,
+                prev.params.len(),
+                app.params.len()
+            )),
+            Some(prev) if !prev.same_params(app) => {
+                lines.push(format!("~ {}: params changed", app.name));
+            }
+            Some(_) => {}
+        }
+    }
+    for app in old {
+        if !new.iter().any(|a| a.app_id == app.app_id) {
+            lines.push(format!("- {} (removed)", app.name));
+        }
+    }
+    lines
+}
+
+/// Profiles that reference apps that are gone or whose params no longer fit.
+fn affected_profiles(apps: &[CachedApp]) -> Result<Vec<String>> {
+    let mut lines = Vec::new();
+    for name in profile::list()? {
+        // Profiles with unresolved template variables can't be checked here
+        let Ok(snapshot) = Snapshot::read(&profile::path(&name)?, &[]) else {
+            continue;
+        };
+        for problem in profile_problems(&snapshot, apps) {
+            lines.push(format!("{}: {}", name, problem));
+        }
+    }
+    Ok(lines)
+}
+
+fn profile_problems(snapshot: &Snapshot, apps: &[CachedApp]) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (app_id, _, layout_id) in snapshot.layout.0.iter().flatten().copied() {
+        let saved_name = snapshot
+            .metadata
+            .apps
+            .iter()
+            .find(|a| a.app_id == app_id)
+            .map(|a| a.name.as_str());
+        let Some(app) = apps.iter().find(|a| a.app_id == app_id) else {
+            problems.push(format!(
+                "uses removed app {}",
+                saved_name.map_or_else(|| app_id.to_string(), |n| format!("'{}'", n))
+            ));
+            continue;
+        };
+        if saved_name.is_some_and(|n| n != app.name) {
+            problems.push(format!(
+                "app {} was '{}' and is now '{}'",
+                app_id,
+                saved_name.unwrap_or_default(),
+                app.name
+            ));
+        }
+        if let Some(values) = snapshot.params_for(layout_id)
+            && values.len() != app.params.len()
+        {
+            problems.push(format!(
+                "{} has {} saved params, the app now has {}",
+                app.name,
+                values.len(),
+                app.params.len()
+            ));
+        }
+    }
+    problems.dedup();
+    problems
+}
@@ -0,0 +1,221 @@
+// Reference docs for the installed apps, generated from the metadata the
+// device reports (names, channels, and every param's type and range), plus
+// the notes on the rig's faders.
+
+use crate::display::{self, AppInfo};
+use crate::protocol::Param;
+
+/// Output format, picked from the file extension.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Markdown,
+    Html,
+}
+
+impl Format {
+    pub fn for_path(path: &str) -> Self {
+        let lower = path.to_lowercase();
+        if lower.ends_with(".html") || lower.ends_with(".htm") {
+            Format::Html
+        } else {
+            Format::Markdown
+        }
+    }
+}
+
+/// (fader, app, note) rows from `notes::rows`, shown as a "This rig"
+/// section when there are any.
+type NoteRows = [(String, String, String)];
+
+pub fn render(apps: &[AppInfo], notes: &NoteRows, format: Format) -> String {
+    match format {
+        Format::Markdown => markdown(apps, notes),
+        Format::Html => html(apps, notes),
+    }
+}
+
+/// Valid values of a param: its range, variants, or a fixed description.
+fn param_values(param: &Param) -> String {
+    fn list<T>(items: &[T], name: impl Fn(&T) -> String) -> String {
+        items.iter().map(name).collect::<Vec<_>>().join(", ")
+    }
+    match param {
+        Param::None => String::new(),
+        Param::Int { min, max, .. } => format!("{} to {}", min, max),
+        Param::Float { min, max, .. } => format!("{} to {}", min, max),
+        Param::Bool { .. } => "on, off".to_string(),
+        Param::Enum { variants, .. } => variants.join(", "),
+        Param::Curve { variants, .. } => list(variants, |v| format!("{:?}", v)),
+        Param::Waveform { variants, .. } => list(variants, |v| format!("{:?}", v)),
+        Param::Color { variants, .. } => list(variants, display::color_name),
+        Param::Range { variants, .. } => list(variants, |v| display::range_name(v).to_string()),
+        Param::Note { variants, .. } => list(variants, |v| format!("{:?}", v)),
+        Param::MidiCc { .. } => "0 to 127".to_string(),
+        Param::MidiChannel { .. } => "1 to 16".to_string(),
+        Param::MidiIn => "USB, DIN".to_string(),
+        Param::MidiMode => "Note, Cc".to_string(),
+        Param::MidiNote { .. } => "0 to 127".to_string(),
+        Param::MidiOut => "USB, Out1, Out2".to_string(),
+        Param::MidiNrpn => "on, off".to_string(),
+        Param::VoltPerOct => "Standard, Buchla".to_string(),
+    }
+}
+
+/// (index, name, type, values) for every param that has a name.
+fn param_rows(app: &AppInfo) -> Vec<(usize, String, &'static str, String)> {
+    app.params
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| !matches!(p, Param::None))
+        .map(|(i, p)| {
+            let name = display::get_param_name(p);
+            let kind = display::format_param_type(p);
+            (i, name, kind, param_values(p))
+        })
+        .collect()
+}
+
+fn channels_label(channels: usize) -> String {
+    if channels == 1 {
+        "1 channel".to_string()
+    } else {
+        format!("{} channels", channels)
+    }
+}
+
+fn generated_note() -> String {
+    format!(
+        "Generated from the apps installed on the device by faderpunk-cli {} on {}.",
+        env!("CARGO_PKG_VERSION"),
+        humantime::format_rfc3339_seconds(std::time::SystemTime::now())
+    )
+}
+
+fn markdown(apps: &[AppInfo], notes: &NoteRows) -> String {
+    // Pipes would end a table cell early
+    let cell = |s: &str| s.replace('|', "\\|");
+
+    let mut out = String::from("# Faderpunk apps\n\n");
+    out += &format!("{}\n\n", generated_note());
+    out += "| App | ID | Channels | Description |\n|---|---|---|---|\n";
+    for app in apps {
+        out += &format!(
+            "| {} | {} | {} | {} |\n",
+            cell(&app.name),
+            app.app_id,
+            app.channels,
+            cell(&app.description)
+        );
+    }
+
+    if !notes.is_empty() {
+        out += "\n## This rig\n\n| Fader | App | Note |\n|---|---|---|\n";
+        for (fader, app, note) in notes {
+            out += &format!("| {} | {} | {} |\n", fader, cell(app), cell(note));
+        }
+    }
+
+    for app in apps {
+        out += &format!("\n## {}\n\n", app.name);
+        if !app.description.is_empty() {
+            out += &format!("{}\n\n", app.description);
+        }
+        out += &format!(
+            "App ID {} · {} · color {}\n\n",
+            app.app_id,
+            channels_label(app.channels),
+            display::color_name(&app.color)
+        );
+
+        let rows = param_rows(app);
+        if rows.is_empty() {
+            out += "No parameters.\n";
+            continue;
+        }
+        out += "| # | Param | Type | Values |\n|---|---|---|---|\n";
+        for (i, name, kind, values) in rows {
+            out += &format!(
+                "| {} | {} | {} | {} |\n",
+                i,
+                cell(&name),
+                cell(kind),
+                cell(&values)
+            );
+        }
+    }
+    out
+}
+
+fn html(apps: &[AppInfo], notes: &NoteRows) -> String {
+    let esc = |s: &str| {
+        s.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    };
+
+    let mut out = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Faderpunk apps</title>\n\
+         <style>body{font-family:sans-serif;max-width:50em;margin:auto}\
+         table{border-collapse:collapse}td,th{border:1px solid #ccc;padding:.2em .5em;text-align:left}</style>\n\
+         </head>\n<body>\n<h1>Faderpunk apps</h1>\n",
+    );
+    out += &format!("<p>{}</p>\n", esc(&generated_note()));
+    out += "<table>\n<tr><th>App</th><th>ID</th><th>Channels</th><th>Description</th></tr>\n";
+    for app in apps {
+        out += &format!(
+            "<tr><td><a href=\"#app-{}\">{}</a></td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            app.app_id,
+            esc(&app.name),
+            app.app_id,
+            app.channels,
+            esc(&app.description)
+        );
+    }
+    out += "</table>\n";
+
+    if !notes.is_empty() {
+        out += "<h2>This rig</h2>\n<table>\n<tr><th>Fader</th><th>App</th><th>Note</th></tr>\n";
+        for (fader, app, note) in notes {
+            out += &format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                esc(fader),
+                esc(app),
+                esc(note)
+            );
+        }
+        out += "</table>\n";
+    }
+
+    for app in apps {
+        out += &format!("<h2 id=\"app-{}\">{}</h2>\n", app.app_id, esc(&app.name));
+        if !app.description.is_empty() {
+            out += &format!("<p>{}</p>\n", esc(&app.description));
+        }
+        out += &format!(
+            "<p>App ID {} · {} · color {}</p>\n",
+            app.app_id,
+            channels_label(app.channels),
+            esc(&display::color_name(&app.color))
+        );
+
+        let rows = param_rows(app);
+        if rows.is_empty() {
+            out += "<p>No parameters.</p>\n";
+            continue;
+        }
+        out += "<table>\n<tr><th>#</th><th>Param</th><th>Type</th><th>Values</th></tr>\n";
+        for (i, name, kind, values) in rows {
+            out += &format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                i,
+                esc(&name),
+                esc(kind),
+                esc(&values)
+            );
+        }
+        out += "</table>\n";
+    }
+    out += "</body>\n</html>\n";
+    out
+}
@@ -0,0 +1,243 @@
+// App metadata file (`apps export-meta`): every app the device reports, with
+// its channels and param schemas, for tools that work without the device
+// (validators, linters, third-party editors).
+//
+// The shape is this file's own rather than the wire types', so it stays put
+// when the protocol changes: params are tagged by `type`, and variants are
+// spelled as snapshot values are. Bump `FORMAT_VERSION` on any change a
+// reader could trip over.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::layout::AppInfo;
+use crate::protocol::{AppIcon, Color, Curve, Note, Param, Range, Waveform};
+
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AppMetaFile {
+    pub format: u32,
+    pub apps: Vec<AppMeta>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AppMeta {
+    pub app_id: u8,
+    pub name: String,
+    pub description: String,
+    /// Faders the app takes up.
+    pub channels: usize,
+    pub color: Color,
+    pub icon: AppIcon,
+    /// One per param index, including unused (`none`) ones, so indices line
+    /// up with the values in a snapshot.
+    pub params: Vec<ParamMeta>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ParamMeta {
+    None,
+    Int {
+        name: String,
+        min: i32,
+        max: i32,
+    },
+    Float {
+        name: String,
+        min: f32,
+        max: f32,
+    },
+    Bool {
+        name: String,
+    },
+    Enum {
+        name: String,
+        variants: Vec<String>,
+    },
+    Curve {
+        name: String,
+        variants: Vec<Curve>,
+    },
+    Waveform {
+        name: String,
+        variants: Vec<Waveform>,
+    },
+    Color {
+        name: String,
+        variants: Vec<Color>,
+    },
+    Range {
+        name: String,
+        variants: Vec<Range>,
+    },
+    Note {
+        name: String,
+        variants: Vec<Note>,
+    },
+    MidiCc {
+        name: String,
+    },
+    MidiChannel {
+        name: String,
+    },
+    MidiIn,
+    MidiMode,
+    MidiNote {
+        name: String,
+    },
+    MidiOut,
+    MidiNrpn,
+    VoltPerOct,
+}
+
+/// Every param `type` tag except `none`, as written in the file.
+pub const PARAM_TYPES: [&str; 17] = [
+    "int",
+    "float",
+    "bool",
+    "enum",
+    "curve",
+    "waveform",
+    "color",
+    "range",
+    "note",
+    "midi_cc",
+    "midi_channel",
+    "midi_in",
+    "midi_mode",
+    "midi_note",
+    "midi_out",
+    "midi_nrpn",
+    "volt_per_oct",
+];
+
+/// The `type` tag a param has in the file.
+pub fn param_type(param: &Param) -> &'static str {
+    match param {
+        Param::None => "none",
+        Param::Int { .. } => "int",
+        Param::Float { .. } => "float",
+        Param::Bool { .. } => "bool",
+        Param::Enum { .. } => "enum",
+        Param::Curve { .. } => "curve",
+        Param::Waveform { .. } => "waveform",
+        Param::Color { .. } => "color",
+        Param::Range { .. } => "range",
+        Param::Note { .. } => "note",
+        Param::MidiCc { .. } => "midi_cc",
+        Param::MidiChannel { .. } => "midi_channel",
+        Param::MidiIn => "midi_in",
+        Param::MidiMode => "midi_mode",
+        Param::MidiNote { .. } => "midi_note",
+        Param::MidiOut => "midi_out",
+        Param::MidiNrpn => "midi_nrpn",
+        Param::VoltPerOct => "volt_per_oct",
+    }
+}
+
+impl AppMetaFile {
+    pub fn new(apps: &[AppInfo]) -> Self {
+        AppMetaFile {
+            format: FORMAT_VERSION,
+            apps: apps.iter().map(AppMeta::from).collect(),
+        }
+    }
+
+    /// Read a file written by `apps export-meta`.
+    pub fn parse(json: &str) -> Result<Self> {
+        let file: AppMetaFile = serde_json::from_str(json)?;
+        if file.format > FORMAT_VERSION {
+            anyhow::bail!(
+                "App metadata format {} is newer than this build understands ({})",
+                file.format,
+                FORMAT_VERSION
+            );
+        }
+        Ok(file)
+    }
+
+    /// The apps as the device would report them.
+    pub fn app_info(&self) -> Vec<AppInfo> {
+        self.apps.iter().map(AppInfo::from).collect()
+    }
+}
+
+impl From<&AppInfo> for AppMeta {
+    fn from(app: &AppInfo) -> Self {
+        AppMeta {
+            app_id: app.app_id,
+            name: app.name.clone(),
+            description: app.description.clone(),
+            channels: app.channels,
+            color: app.color,
+            icon: app.icon,
+            params: app.params.iter().map(ParamMeta::from).collect(),
+        }
+    }
+}
+
+impl From<&AppMeta> for AppInfo {
+    fn from(app: &AppMeta) -> Self {
+        AppInfo {
+            app_id: app.app_id,
+            channels: app.channels,
+            name: app.name.clone(),
+            description: app.description.clone(),
+            color: app.color,
+            icon: app.icon,
+            params: app.params.iter().map(Param::from).collect(),
+        }
+    }
+}
+
+impl From<&Param> for ParamMeta {
+    fn from(param: &Param) -> Self {
+        match param.clone() {
+            Param::None => ParamMeta::None,
+            Param::Int { name, min, max } => ParamMeta::Int { name, min, max },
+            Param::Float { name, min, max } => ParamMeta::Float { name, min, max },
+            Param::Bool { name } => ParamMeta::Bool { name },
+            Param::Enum { name, variants } => ParamMeta::Enum { name, variants },
+            Param::Curve { name, variants } => ParamMeta::Curve { name, variants },
+            Param::Waveform { name, variants } => ParamMeta::Waveform { name, variants },
+            Param::Color { name, variants } => ParamMeta::Color { name, variants },
+            Param::Range { name, variants } => ParamMeta::Range { name, variants },
+            Param::Note { name, variants } => ParamMeta::Note { name, variants },
+            Param::MidiCc { name } => ParamMeta::MidiCc { name },
+            Param::MidiChannel { name } => ParamMeta::MidiChannel { name },
+            Param::MidiIn => ParamMeta::MidiIn,
+            Param::MidiMode => ParamMeta::MidiMode,
+            Param::MidiNote { name } => ParamMeta::MidiNote { name },
+            Param::MidiOut => ParamMeta::MidiOut,
+            Param::MidiNrpn => ParamMeta::MidiNrpn,
+            Param::VoltPerOct => ParamMeta::VoltPerOct,
+        }
+    }
+}
+
+impl From<&ParamMeta> for Param {
+    fn from(param: &ParamMeta) -> Self {
+        match param.clone() {
+            ParamMeta::None => Param::None,
+            ParamMeta::Int { name, min, max } => Param::Int { name, min, max },
+            ParamMeta::Float { name, min, max } => Param::Float { name, min, max },
+            ParamMeta::Bool { name } => Param::Bool { name },
+            ParamMeta::Enum { name, variants } => Param::Enum { name, variants },
+            ParamMeta::Curve { name, variants } => Param::Curve { name, variants },
+            ParamMeta::Waveform { name, variants } => Param::Waveform { name, variants },
+            ParamMeta::Color { name, variants } => Param::Color { name, variants },
+            ParamMeta::Range { name, variants } => Param::Range { name, variants },
+            ParamMeta::Note { name, variants } => Param::Note { name, variants },
+            ParamMeta::MidiCc { name } => Param::MidiCc { name },
+            ParamMeta::MidiChannel { name } => Param::MidiChannel { name },
+            ParamMeta::MidiIn => Param::MidiIn,
+            ParamMeta::MidiMode => Param::MidiMode,
+            ParamMeta::MidiNote { name } => Param::MidiNote { name },
+            ParamMeta::MidiOut => Param::MidiOut,
+            ParamMeta::MidiNrpn => Param::MidiNrpn,
+            ParamMeta::VoltPerOct => Param::VoltPerOct,
+        }
+    }
+}
@@ -0,0 +1,268 @@
+// Append-only audit log of every change sent to the device.
+//
+// Each mutating message (SetGlobalConfig, SetLayout, SetAppParams, FactoryReset)
+// is written as one JSON line to `<data dir>/audit.log`, together with the
+// command line that triggered it and the fields that changed.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as Json;
+
+use crate::paths;
+use crate::protocol::{ConfigMsgIn, ConfigMsgOut, GlobalConfig, Layout, Value};
+
+const LOG_FILE: &str = "audit.log";
+
+/// One recorded change to the device.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entry {
+    /// Unix timestamp (seconds).
+    pub ts: u64,
+    /// The command line that sent the message.
+    pub command: String,
+    /// Message name, e.g. "SetLayout".
+    pub message: String,
+    /// Target app instance for SetAppParams.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout_id: Option<u8>,
+    pub changes: Vec<Change>,
+}
+
+/// A single field that differs between the last known state and what was sent.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Change {
+    pub field: String,
+    pub old: Json,
+    pub new: Json,
+}
+
+impl Entry {
+    pub fn time(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.ts)
+    }
+}
+
+pub fn log_path() -> Result<PathBuf> {
+    Ok(paths::data_dir()?.join(LOG_FILE))
+}
+
+/// Last state seen from (or sent to) the device, so each write can be logged
+/// as a list of changed fields rather than a full dump.
+#[derive(Default)]
+pub struct Tracker {
+    global_config: Option<GlobalConfig>,
+    layout: Option<Layout>,
+    params: HashMap<u8, Vec<Value>>,
+}
+
+impl Tracker {
+    /// Update the known state from a device response.
+    pub fn observe(&mut self, msg: &ConfigMsgOut) {
+        match msg {
+            ConfigMsgOut::GlobalConfig(c) => self.global_config = Some(c.clone()),
+            ConfigMsgOut::Layout(l) => self.layout = Some(l.clone()),
+            ConfigMsgOut::AppState(layout_id, values) => {
+                self.params.insert(*layout_id, values.clone());
+            }
+            _ => {}
+        }
+    }
+
+    /// The log entry for a mutating message, against the state known before
+    /// it is sent; None for read-only messages. Pass it to `record` once the
+    /// device has taken the message.
+    pub fn entry(&self, msg: &ConfigMsgIn) -> Result<Option<Entry>> {
+        let Some(name) = mutation_name(msg) else {
+            return Ok(None);
+        };
+
+        let (layout_id, changes) = match msg {
+            ConfigMsgIn::SetGlobalConfig(config) => {
+                let old = serde_json::to_value(&self.global_config)?;
+                let new = serde_json::to_value(config)?;
+                (None, diff(&old, &new))
+            }
+            ConfigMsgIn::SetLayout(layout) => {
+                let changes = (0..layout.0.len())
+                    .filter_map(|i| {
+                        let old = self.layout.as_ref().and_then(|l| l.0[i]);
+                        let new = layout.0[i];
+                        (self.layout.is_none() || old != new).then(|| Change {
+                            field: format!("fader {}", i + 1),
+                            old: serde_json::to_value(old).unwrap_or(Json::Null),
+                            new: serde_json::to_value(new).unwrap_or(Json::Null),
+                        })
+                    })
+                    .collect();
+                (None, changes)
+            }
+            ConfigMsgIn::SetAppParams { layout_id, values } => {
+                let known = self.params.get(layout_id);
+                let changes = values
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, v)| {
+                        let new = (*v)?;
+                        let old = known.and_then(|k| k.get(i)).copied();
+                        (old != Some(new)).then(|| Change {
+                            field: format!("param {}", i),
+                            old: serde_json::to_value(old).unwrap_or(Json::Null),
+                            new: serde_json::to_value(new).unwrap_or(Json::Null),
+                        })
+                    })
+                    .collect();
+                (Some(*layout_id), changes)
+            }
+            _ => (None, Vec::new()),
+        };
+
+        Ok(Some(Entry {
+            ts: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            command: command_line(),
+            message: name.to_string(),
+            layout_id,
+            changes,
+        }))
+    }
+
+    /// Append the entry `entry` made for `msg`, which the device has taken.
+    pub fn record(&mut self, msg: &ConfigMsgIn, entry: &Entry) -> Result<()> {
+        // SetGlobalConfig gets no reply, so assume the device took it as sent
+        if let ConfigMsgIn::SetGlobalConfig(config) = msg {
+            self.global_config = Some(config.clone());
+        }
+        append(entry)
+    }
+}
+
+/// Name of a mutating message, or None for read-only requests.
+pub fn mutation_name(msg: &ConfigMsgIn) -> Option<&'static str> {
+    match msg {
+        ConfigMsgIn::SetGlobalConfig(_) => Some("SetGlobalConfig"),
+        ConfigMsgIn::SetLayout(_) => Some("SetLayout"),
+        ConfigMsgIn::SetAppParams { .. } => Some("SetAppParams"),
+        ConfigMsgIn::FactoryReset => Some("FactoryReset"),
+        _ => None,
+    }
+}
+
+/// The command line of the current process, with the binary path shortened.
+fn command_line() -> String {
+    let mut args = std::env::args();
+    let bin = args
+        .next()
+        .map(|a| {
+            std::path::Path::new(&a)
+                .file_name()
+                .map(|f| f.to_string_lossy().into_owned())
+                .unwrap_or(a)
+        })
+        .unwrap_or_default();
+    std::iter::once(bin)
+        .chain(args)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Append an entry to the log file.
+fn append(entry: &Entry) -> Result<()> {
+    let path = log_path()?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+/// Read all entries newer than `since` (or all entries if None).
+/// Lines that fail to parse are skipped so a truncated write can't poison the log.
+pub fn read(since: Option<Duration>) -> Result<Vec<Entry>> {
+    let path = log_path()?;
+    let data = match std::fs::read_to_string(&path) {
+        Ok(d) => d,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+
+    let cutoff = since.and_then(|d| SystemTime::now().checked_sub(d));
+    Ok(data
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Entry>(line).ok())
+        .filter(|e| cutoff.is_none_or(|c| e.time() >= c))
+        .collect())
+}
+
+/// Flatten two JSON values into a list of leaf-level differences.
+/// Field paths use dots for objects and brackets for arrays, e.g. `clock.internal_bpm`
+/// or `aux[1]`.
+pub fn diff(old: &Json, new: &Json) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_into(String::new(), old, new, &mut changes);
+    changes
+}
+
+fn diff_into(path: String, old: &Json, new: &Json, out: &mut Vec<Change>) {
+    match (old, new) {
+        // A missing old value (nothing known yet) still gets reported field by field
+        (Json::Object(_) | Json::Null, Json::Object(b)) => {
+            for (key, new_val) in b {
+                let sub = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                diff_into(sub, old.get(key).unwrap_or(&Json::Null), new_val, out);
+            }
+        }
+        (Json::Array(a), Json::Array(b)) if a.len() == b.len() => {
+            for (i, (old_val, new_val)) in a.iter().zip(b).enumerate() {
+                diff_into(format!("{}[{}]", path, i), old_val, new_val, out);
+            }
+        }
+        _ => {
+            if old != new {
+                out.push(Change {
+                    field: path,
+                    old: old.clone(),
+                    new: new.clone(),
+                });
+            }
+        }
+    }
+}
+
+/// Replace the value at a path in the format `diff` produces (`a.b[2]`).
+/// Returns false if the path doesn't exist in `root`.
+pub fn set(root: &mut Json, path: &str, value: Json) -> bool {
+    let mut cur = root;
+    for part in path.split('.') {
+        let (key, indices) = match part.find('[') {
+            Some(i) => (&part[..i], &part[i..]),
+            None => (part, ""),
+        };
+        if !key.is_empty() {
+            match cur.get_mut(key) {
+                Some(next) => cur = next,
+                None => return false,
+            }
+        }
+        for idx in indices.split(['[', ']']).filter(|s| !s.is_empty()) {
+            match idx.parse::<usize>().ok().and_then(|i| cur.get_mut(i)) {
+                Some(next) => cur = next,
+                None => return false,
+            }
+        }
+    }
+    *cur = value;
+    true
+}
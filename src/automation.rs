@@ -0,0 +1,389 @@
+// Automation files for `play`: a cue list of param and config changes, each
+// at a point in time (wall clock) or in the song (MIDI clock), e.g.
+//
+//     bpm: 120
+//     cues:
+//       - at: 0s
+//         params: ["3:cutoff=50", "4:level=80"]
+//       - at: 1m30s
+//         bpm: 128
+//       - at: "17:1"
+//         macro: darker
+//
+// and the MIDI clock reader that follows an external sequencer (or measures
+// the device's own clock, for clock measure).
+
+use std::io::Read;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use tokio::sync::mpsc;
+
+/// MIDI clock resolution: ticks per quarter note.
+pub const TICKS_PER_BEAT: u64 = 24;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawShow {
+    bpm: Option<f32>,
+    #[serde(default = "default_beats_per_bar")]
+    beats_per_bar: u32,
+    cues: Vec<RawCue>,
+}
+
+fn default_beats_per_bar() -> u32 {
+    4
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawCue {
+    at: RawAt,
+    label: Option<String>,
+    #[serde(default)]
+    params: Vec<String>,
+    #[serde(rename = "macro")]
+    macro_name: Option<String>,
+    bpm: Option<f32>,
+    profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RawAt {
+    Seconds(f64),
+    Text(String),
+}
+
+/// When a cue fires.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum At {
+    /// After the start of playback.
+    Time(Duration),
+    /// Quarter notes from the start of the song.
+    Beat(f64),
+}
+
+/// One entry of the cue list. Every action set on it runs, in the order
+/// profile, bpm, macro, params.
+#[derive(Debug)]
+pub struct Cue {
+    pub at: At,
+    /// The `at` as written, for output.
+    pub when: String,
+    pub label: Option<String>,
+    pub params: Vec<String>,
+    pub macro_name: Option<String>,
+    pub bpm: Option<f32>,
+    pub profile: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Show {
+    /// Tempo for turning BAR:BEAT cues into times when following the wall clock.
+    pub bpm: Option<f32>,
+    /// Cues sorted by time.
+    pub cues: Vec<Cue>,
+}
+
+impl Show {
+    pub fn read(path: &Path) -> Result<Self> {
+        let data = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let raw: RawShow = serde_yaml::from_str(&data)
+            .with_context(|| format!("Invalid automation file {}", path.display()))?;
+        if raw.beats_per_bar == 0 {
+            bail!("beats_per_bar must be at least 1");
+        }
+
+        let mut cues = Vec::with_capacity(raw.cues.len());
+        for (i, cue) in raw.cues.into_iter().enumerate() {
+            let (at, when) = parse_at(&cue.at, raw.beats_per_bar)
+                .with_context(|| format!("In cue {} of {}", i + 1, path.display()))?;
+            if cue.params.is_empty()
+                && cue.macro_name.is_none()
+                && cue.bpm.is_none()
+                && cue.profile.is_none()
+            {
+                bail!(
+                    "Cue {} ({}) of {} does nothing",
+                    i + 1,
+                    when,
+                    path.display()
+                );
+            }
+            cues.push(Cue {
+                at,
+                when,
+                label: cue.label,
+                params: cue.params,
+                macro_name: cue.macro_name,
+                bpm: cue.bpm,
+                profile: cue.profile,
+            });
+        }
+        // Stable, so cues at the same moment keep their file order
+        cues.sort_by(|a, b| key(a.at).total_cmp(&key(b.at)));
+        Ok(Show { bpm: raw.bpm, cues })
+    }
+
+    /// When each cue fires, following the wall clock: (cue index, seconds
+    /// from the start), in firing order.
+    pub fn seconds(&self) -> Result<Vec<(usize, f64)>> {
+        let mut out = self
+            .cues
+            .iter()
+            .enumerate()
+            .map(|(i, c)| match c.at {
+                At::Time(d) => Ok((i, d.as_secs_f64())),
+                At::Beat(beat) => match self.bpm {
+                    Some(bpm) if bpm > 0.0 => Ok((i, beat * 60.0 / bpm as f64)),
+                    _ => bail!(
+                        "Cue at {} is in bars; set bpm at the top of the file",
+                        c.when
+                    ),
+                },
+            })
+            .collect::<Result<Vec<_>>>()?;
+        // Mixed times and bars can change places once converted
+        out.sort_by(|a, b| a.1.total_cmp(&b.1));
+        Ok(out)
+    }
+
+    /// When each cue fires, following MIDI clock: (cue index, beats into
+    /// the song), in firing order.
+    pub fn beats(&self) -> Result<Vec<(usize, f64)>> {
+        self.cues
+            .iter()
+            .enumerate()
+            .map(|(i, c)| match c.at {
+                At::Beat(beat) => Ok((i, beat)),
+                At::Time(_) => bail!(
+                    "Cue at {} is a time; with --midi-clock every cue needs a BAR:BEAT position",
+                    c.when
+                ),
+            })
+            .collect()
+    }
+}
+
+/// Sort key putting both kinds of cue in a sensible order before tempo is known.
+fn key(at: At) -> f64 {
+    match at {
+        At::Time(d) => d.as_secs_f64(),
+        At::Beat(b) => b,
+    }
+}
+
+/// `90` (seconds), `1m30s` (a duration), or `17:1` / `17:2.5` (bar and beat, from 1).
+fn parse_at(at: &RawAt, beats_per_bar: u32) -> Result<(At, String)> {
+    match at {
+        RawAt::Seconds(s) if *s >= 0.0 => {
+            Ok((At::Time(Duration::from_secs_f64(*s)), format!("{}s", s)))
+        }
+        RawAt::Seconds(s) => bail!("Invalid time {} (must not be negative)", s),
+        RawAt::Text(text) => {
+            let text = text.trim();
+            if let Some((bar, beat)) = text.split_once(':') {
+                let bar: u32 = bar
+                    .trim()
+                    .parse()
+                    .ok()
+                    .filter(|b| *b >= 1)
+                    .with_context(|| format!("Invalid bar in '{}' (bars count from 1)", text))?;
+                let beat: f64 = beat
+                    .trim()
+                    .parse()
+                    .ok()
+                    .filter(|b: &f64| *b >= 1.0 && *b < beats_per_bar as f64 + 1.0)
+                    .with_context(|| {
+                        format!(
+                            "Invalid beat in '{}' (1 to {} per bar)",
+                            text, beats_per_bar
+                        )
+                    })?;
+                let position = (bar - 1) as f64 * beats_per_bar as f64 + (beat - 1.0);
+                return Ok((At::Beat(position), text.to_string()));
+            }
+            let duration = humantime::parse_duration(text).with_context(|| {
+                format!(
+                    "Invalid time '{}' (e.g. 90s, 1m30s, or 17:1 for bar 17)",
+                    text
+                )
+            })?;
+            Ok((At::Time(duration), text.to_string()))
+        }
+    }
+}
+
+// ── MIDI clock ──
+
+/// What the sequencer is doing, from its MIDI realtime messages.
+#[derive(Clone, Copy, Debug)]
+pub enum Clock {
+    /// Start: playback from the top.
+    Start,
+    Continue,
+    Stop,
+    /// One clock pulse (1/24 beat).
+    Tick,
+    /// Song position pointer: the sequencer jumped to this tick.
+    Position(u64),
+}
+
+/// Follow a raw MIDI input (e.g. `/dev/snd/midiC1D0` on Linux) on a reader
+/// thread, sending its clock messages with the time each arrived.
+/// Everything else is ignored. The channel closes when the device goes away.
+pub fn follow_clock(path: &Path) -> Result<mpsc::UnboundedReceiver<(Instant, Clock)>> {
+    let mut file = std::fs::File::open(path)
+        .with_context(|| format!("Failed to open MIDI input {}", path.display()))?;
+    let (tx, rx) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        let mut buf = [0u8; 256];
+        // Data bytes collected for a song position pointer
+        let mut spp: Option<Vec<u8>> = None;
+        while let Ok(n) = file.read(&mut buf) {
+            if n == 0 {
+                return;
+            }
+            let now = Instant::now();
+            for &byte in &buf[..n] {
+                let msg = match byte {
+                    0xF8 => Some(Clock::Tick),
+                    0xFA => Some(Clock::Start),
+                    0xFB => Some(Clock::Continue),
+                    0xFC => Some(Clock::Stop),
+                    // Other realtime bytes may sit inside any message
+                    0xF9..=0xFF => None,
+                    0xF2 => {
+                        spp = Some(Vec::new());
+                        None
+                    }
+                    0x80..=0xF7 => {
+                        spp = None;
+                        None
+                    }
+                    data => match &mut spp {
+                        Some(bytes) => {
+                            bytes.push(data);
+                            if bytes.len() == 2 {
+                                // In sixteenth notes
+                                let sixteenths = bytes[0] as u64 | (bytes[1] as u64) << 7;
+                                spp = None;
+                                Some(Clock::Position(sixteenths * TICKS_PER_BEAT / 4))
+                            } else {
+                                None
+                            }
+                        }
+                        None => None,
+                    },
+                };
+                if let Some(msg) = msg
+                    && tx.send((now, msg)).is_err()
+                {
+                    return;
+                }
+            }
+        }
+    });
+    Ok(rx)
+}
+
+/// Tempo and steadiness of a MIDI clock, from when its ticks arrived.
+pub struct ClockStats {
+    pub ticks: usize,
+    pub bpm: f64,
+    /// Standard deviation of the time between ticks.
+    pub jitter: Duration,
+    /// Largest distance of one tick interval from the mean.
+    pub worst: Duration,
+}
+
+/// Stats over runs of consecutive ticks; the gaps between runs (a stop, a
+/// jump) don't count. None with fewer than two ticks in any run.
+pub fn clock_stats(runs: &[Vec<Instant>]) -> Option<ClockStats> {
+    let intervals: Vec<f64> = runs
+        .iter()
+        .flat_map(|run| run.windows(2).map(|w| (w[1] - w[0]).as_secs_f64()))
+        .collect();
+    if intervals.is_empty() {
+        return None;
+    }
+    let mean = intervals.iter().sum::<f64>() / intervals.len() as f64;
+    let variance =
+        intervals.iter().map(|i| (i - mean).powi(2)).sum::<f64>() / intervals.len() as f64;
+    let worst = intervals
+        .iter()
+        .map(|i| (i - mean).abs())
+        .fold(0.0, f64::max);
+    Some(ClockStats {
+        ticks: runs.iter().map(Vec::len).sum(),
+        bpm: 60.0 / (mean * TICKS_PER_BEAT as f64),
+        jitter: Duration::from_secs_f64(variance.sqrt()),
+        worst: Duration::from_secs_f64(worst),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `count` ticks from `start`, `gaps` apart in turn.
+    fn ticks(start: Instant, count: usize, gaps: &[Duration]) -> Vec<Instant> {
+        let mut at = start;
+        (0..count)
+            .map(|i| {
+                if i > 0 {
+                    at += gaps[(i - 1) % gaps.len()];
+                }
+                at
+            })
+            .collect()
+    }
+
+    fn millis(ms: f64) -> Duration {
+        Duration::from_secs_f64(ms / 1000.0)
+    }
+
+    #[test]
+    fn steady_clock_has_its_tempo_and_no_jitter() {
+        // 120 BPM is 24 ticks every half second
+        let run = ticks(Instant::now(), 97, &[millis(500.0 / 24.0)]);
+        let stats = clock_stats(&[run]).unwrap();
+        assert_eq!(stats.ticks, 97);
+        assert!((stats.bpm - 120.0).abs() < 0.01, "{}", stats.bpm);
+        assert!(stats.jitter < Duration::from_micros(1));
+        assert!(stats.worst < Duration::from_micros(1));
+    }
+
+    #[test]
+    fn uneven_ticks_show_as_jitter() {
+        let run = ticks(Instant::now(), 49, &[millis(10.0), millis(30.0)]);
+        let stats = clock_stats(&[run]).unwrap();
+        // A 20ms mean is 125 BPM
+        assert!((stats.bpm - 125.0).abs() < 0.01, "{}", stats.bpm);
+        assert!(stats.jitter.abs_diff(millis(10.0)) < Duration::from_micros(1));
+        assert!(stats.worst.abs_diff(millis(10.0)) < Duration::from_micros(1));
+    }
+
+    #[test]
+    fn gaps_between_runs_dont_count() {
+        let start = Instant::now();
+        let gap = millis(500.0 / 24.0);
+        let first = ticks(start, 25, &[gap]);
+        let second = ticks(start + Duration::from_secs(5), 25, &[gap]);
+        let stats = clock_stats(&[first, second]).unwrap();
+        assert_eq!(stats.ticks, 50);
+        assert!((stats.bpm - 120.0).abs() < 0.01, "{}", stats.bpm);
+        assert!(stats.worst < Duration::from_micros(1));
+    }
+
+    #[test]
+    fn no_stats_without_two_ticks_in_a_run() {
+        assert!(clock_stats(&[]).is_none());
+        let now = Instant::now();
+        assert!(clock_stats(&[vec![now], vec![now + Duration::from_secs(1)]]).is_none());
+    }
+}
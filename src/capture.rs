@@ -0,0 +1,198 @@
+// Capture what a command prints to stdout, for modes that wrap each command's
+// output in a structured result (stdin mode, --output json), or take the
+// colors out of it on the way (--output plain).
+//
+// Like the pager, this works at the file descriptor level: fd 1 is pointed at
+// a pipe while the command runs, so every println! is captured unchanged.
+
+use std::io::{Read, Write};
+use std::thread::JoinHandle;
+
+use anyhow::Result;
+
+/// Output being captured; `finish` restores stdout and returns the text.
+pub struct Capture {
+    #[cfg(unix)]
+    saved: libc::c_int,
+    reader: JoinHandle<Vec<u8>>,
+}
+
+#[cfg(unix)]
+pub fn start() -> Result<Capture> {
+    let (saved, mut pipe) = redirect()?;
+    // Drain concurrently so a chatty command can't fill the pipe and block
+    let reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = pipe.read_to_end(&mut buf);
+        buf
+    });
+    Ok(Capture { saved, reader })
+}
+
+/// Point fd 1 at a new pipe. Returns the original stdout and the read end.
+#[cfg(unix)]
+fn redirect() -> Result<(libc::c_int, std::fs::File)> {
+    use std::os::fd::FromRawFd;
+
+    std::io::stdout().flush()?;
+    let mut fds = [0 as libc::c_int; 2];
+    // SAFETY: pipe fills both descriptors on success; dup/dup2 operate on
+    // descriptors we own, and fd 1 is swapped atomically.
+    let saved = unsafe {
+        if libc::pipe(fds.as_mut_ptr()) == -1 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        let saved = libc::dup(libc::STDOUT_FILENO);
+        if saved == -1 || libc::dup2(fds[1], libc::STDOUT_FILENO) == -1 {
+            let err = std::io::Error::last_os_error();
+            libc::close(fds[0]);
+            libc::close(fds[1]);
+            return Err(err.into());
+        }
+        // fd 1 now holds the write end
+        libc::close(fds[1]);
+        saved
+    };
+    // SAFETY: the read end is ours alone from here on
+    Ok((saved, unsafe { std::fs::File::from_raw_fd(fds[0]) }))
+}
+
+/// Put the original stdout back, which closes the last write end of the
+/// pipe so its reader sees EOF.
+#[cfg(unix)]
+fn restore(saved: libc::c_int) {
+    let _ = std::io::stdout().flush();
+    // SAFETY: `saved` is the original stdout, and nothing else closes it
+    unsafe {
+        libc::dup2(saved, libc::STDOUT_FILENO);
+        libc::close(saved);
+    }
+}
+
+#[cfg(not(unix))]
+pub fn start() -> Result<Capture> {
+    anyhow::bail!("Output capture is only supported on Unix")
+}
+
+impl Capture {
+    /// Put stdout back and return everything printed since `start`, without
+    /// color codes.
+    pub fn finish(self) -> String {
+        #[cfg(unix)]
+        restore(self.saved);
+        let bytes = self.reader.join().unwrap_or_default();
+        strip_ansi(&String::from_utf8_lossy(&bytes))
+    }
+
+    /// A handle on the stdout that was there before the capture, for output
+    /// that should get out while the rest is captured.
+    #[cfg(unix)]
+    pub fn stdout(&self) -> Result<std::fs::File> {
+        use std::os::fd::FromRawFd;
+        // SAFETY: dup gives a new descriptor of our own on success
+        let fd = unsafe { libc::dup(self.saved) };
+        if fd == -1 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(unsafe { std::fs::File::from_raw_fd(fd) })
+    }
+
+    #[cfg(not(unix))]
+    pub fn stdout(&self) -> Result<std::fs::File> {
+        anyhow::bail!("Output capture is only supported on Unix")
+    }
+}
+
+/// Stdout with color codes taken out as it's printed, for output formats
+/// that can't have any. Unlike a capture, output (prompts included) shows
+/// as soon as it's written.
+pub struct Stripped {
+    #[cfg(unix)]
+    saved: libc::c_int,
+    forwarder: JoinHandle<()>,
+}
+
+#[cfg(unix)]
+pub fn strip_colors() -> Result<Stripped> {
+    let (saved, mut pipe) = redirect()?;
+    let mut out = {
+        use std::os::fd::FromRawFd;
+        // SAFETY: dup gives a new descriptor of our own on success
+        let fd = unsafe { libc::dup(saved) };
+        if fd == -1 {
+            let err = std::io::Error::last_os_error();
+            restore(saved);
+            return Err(err.into());
+        }
+        unsafe { std::fs::File::from_raw_fd(fd) }
+    };
+    let forwarder = std::thread::spawn(move || {
+        let mut stripper = Stripper::default();
+        let mut buf = [0u8; 4096];
+        while let Ok(n @ 1..) = pipe.read(&mut buf) {
+            if out.write_all(&stripper.feed(&buf[..n])).is_err() {
+                break;
+            }
+            let _ = out.flush();
+        }
+    });
+    Ok(Stripped { saved, forwarder })
+}
+
+#[cfg(not(unix))]
+pub fn strip_colors() -> Result<Stripped> {
+    anyhow::bail!("Output capture is only supported on Unix")
+}
+
+impl Stripped {
+    /// Put stdout back once everything printed so far is through.
+    pub fn finish(self) {
+        #[cfg(unix)]
+        restore(self.saved);
+        let _ = self.forwarder.join();
+    }
+}
+
+/// Remove ANSI escape sequences (colors, bold) from text.
+pub fn strip_ansi(s: &str) -> String {
+    String::from_utf8_lossy(&Stripper::default().feed(s.as_bytes())).into_owned()
+}
+
+/// Removes ANSI escape sequences from a byte stream, including ones split
+/// across reads.
+#[derive(Default)]
+struct Stripper {
+    state: Escape,
+}
+
+#[derive(Default, Clone, Copy)]
+enum Escape {
+    #[default]
+    Outside,
+    /// After ESC
+    Start,
+    /// After ESC [, until the final byte
+    Parameters,
+}
+
+impl Stripper {
+    fn feed(&mut self, bytes: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(bytes.len());
+        for &b in bytes {
+            self.state = match (self.state, b) {
+                (Escape::Outside, 0x1b) => Escape::Start,
+                (Escape::Outside, _) => {
+                    out.push(b);
+                    Escape::Outside
+                }
+                (Escape::Start, b'[') => Escape::Parameters,
+                // Other escapes are two bytes long
+                (Escape::Start, _) => Escape::Outside,
+                // Parameters run until the final byte in @..~
+                (Escape::Parameters, b'@'..=b'~') => Escape::Outside,
+                (Escape::Parameters, _) => Escape::Parameters,
+            };
+        }
+        out
+    }
+}
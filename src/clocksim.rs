@@ -0,0 +1,65 @@
+// What the aux clock and reset outputs do at a given BPM (simulate clock),
+// worked out on the host so divisions can be checked against other gear
+// before any cables go in.
+//
+// The device counts its clock at 24 PPQN; a ClockOut(div) jack sends a pulse
+// every `div` ticks, starting on the first, and a ResetOut jack sends one
+// pulse when the clock starts. Swing isn't simulated.
+
+use crate::protocol::{AuxJackMode, ClockDivision};
+
+/// Clock ticks per quarter note.
+pub const PPQN: u32 = 24;
+/// Ticks in a 4/4 bar.
+pub const BAR: u32 = PPQN * 4;
+
+/// Ticks between pulses.
+pub fn ticks(div: ClockDivision) -> u32 {
+    div as u32
+}
+
+/// The note value a division works out to at 24 PPQN.
+pub fn note_value(div: ClockDivision) -> &'static str {
+    match div {
+        ClockDivision::_1 => "24 PPQN",
+        ClockDivision::_2 => "12 PPQN",
+        ClockDivision::_4 => "16th triplets",
+        ClockDivision::_6 => "16ths",
+        ClockDivision::_8 => "8th triplets",
+        ClockDivision::_12 => "8ths",
+        ClockDivision::_24 => "quarters",
+        ClockDivision::_96 => "bars",
+        ClockDivision::_192 => "every 2 bars",
+        ClockDivision::_384 => "every 4 bars",
+    }
+}
+
+/// Ticks in `0..len` (len > 0) at which a jack in `mode` sends a pulse.
+pub fn pulses(mode: AuxJackMode, len: u32) -> Vec<u32> {
+    match mode {
+        AuxJackMode::None => Vec::new(),
+        AuxJackMode::ClockOut(div) => (0..len).step_by(ticks(div) as usize).collect(),
+        AuxJackMode::ResetOut => vec![0],
+    }
+}
+
+/// Length of one tick in milliseconds.
+pub fn tick_ms(bpm: f32) -> f64 {
+    60_000.0 / bpm as f64 / PPQN as f64
+}
+
+/// Ticks per diagram column: as coarse as possible while every pulse still
+/// lands on a column of its own, and never coarser than a 16th.
+pub fn resolution(modes: &[AuxJackMode]) -> u32 {
+    modes
+        .iter()
+        .filter_map(|mode| match mode {
+            AuxJackMode::ClockOut(div) => Some(ticks(*div)),
+            _ => None,
+        })
+        .fold(PPQN / 4, gcd)
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
@@ -0,0 +1,9 @@
+// The larger command groups, one module each. Smaller commands and the
+// helpers shared between groups stay in main.rs.
+
+pub mod config;
+pub mod layout;
+pub mod midi;
+pub mod param;
+pub mod session;
+pub mod snapshot;
@@ -0,0 +1,292 @@
+// `config` commands: reading and changing the global config, and the
+// parsers for the values it takes.
+
+use anyhow::Result;
+
+use crate::display;
+use crate::invalid;
+use crate::limits::LIMITS;
+use crate::protocol::{self, ConfigMsgIn, ConfigMsgOut};
+use crate::usb::FaderpunkDevice;
+use crate::{ConfigAction, fetch_global_config};
+
+/// Write the global config and read back what the device kept: the firmware
+/// clamps values it won't take instead of refusing them.
+async fn set_global_config(
+    dev: &mut FaderpunkDevice,
+    config: protocol::GlobalConfig,
+) -> Result<protocol::GlobalConfig> {
+    dev.send(&ConfigMsgIn::SetGlobalConfig(config)).await?;
+    fetch_global_config(dev).await
+}
+
+/// Print the value a setter ended up with, and what was asked for if the
+/// device kept something else.
+fn report_set<T: PartialEq + std::fmt::Display>(what: &str, asked: T, kept: T) {
+    if kept == asked {
+        println!("{} set to {}", what, kept);
+    } else {
+        println!("{} set to {} (the device didn't take {})", what, kept, asked);
+    }
+}
+
+pub async fn cmd_config(action: ConfigAction) -> Result<()> {
+    if let ConfigAction::Env { prefix } = &action
+        && !prefix.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        anyhow::bail!("Invalid prefix '{}' (letters, digits and _ only)", prefix);
+    }
+    let mut dev = FaderpunkDevice::open()?;
+
+    match action {
+        ConfigAction::Show => {
+            let resp = dev.send_receive(&ConfigMsgIn::GetGlobalConfig).await?;
+            if let ConfigMsgOut::GlobalConfig(config) = resp {
+                display::print_global_config(&config);
+            }
+        }
+        ConfigAction::Bpm { value } => {
+            LIMITS.check_bpm(value)?;
+            let mut config = fetch_global_config(&mut dev).await?;
+            config.clock.internal_bpm = value;
+            let kept = set_global_config(&mut dev, config).await?;
+            report_set("BPM", value, kept.clock.internal_bpm);
+        }
+        ConfigAction::Brightness { value } => {
+            LIMITS.check_brightness(value)?;
+            let mut config = fetch_global_config(&mut dev).await?;
+            config.led_brightness = value;
+            let kept = set_global_config(&mut dev, config).await?;
+            report_set("LED brightness", value, kept.led_brightness);
+        }
+        ConfigAction::Ppqn { value } => {
+            let mut config = fetch_global_config(&mut dev).await?;
+            config.clock.ext_ppqn = value;
+            let kept = set_global_config(&mut dev, config).await?;
+            report_set("Ext PPQN", value, kept.clock.ext_ppqn);
+        }
+        ConfigAction::Limits => display::print_limits(&LIMITS),
+        ConfigAction::Takeover { mode } => {
+            let takeover = match mode.to_lowercase().as_str() {
+                "pickup" => protocol::TakeoverMode::Pickup,
+                "jump" => protocol::TakeoverMode::Jump,
+                "scale" => protocol::TakeoverMode::Scale,
+                _ => invalid!("Unknown takeover mode: {} (use: pickup, jump, scale)", mode),
+            };
+            let resp = dev.send_receive(&ConfigMsgIn::GetGlobalConfig).await?;
+            if let ConfigMsgOut::GlobalConfig(mut config) = resp {
+                config.takeover_mode = takeover;
+                dev.send(&ConfigMsgIn::SetGlobalConfig(config)).await?;
+                println!("Takeover mode set to {:?}", takeover);
+            }
+        }
+        ConfigAction::Clocksrc { source } => {
+            let src = parse_clock_src(&source)?;
+            let resp = dev.send_receive(&ConfigMsgIn::GetGlobalConfig).await?;
+            if let ConfigMsgOut::GlobalConfig(mut config) = resp {
+                config.clock.clock_src = src;
+                dev.send(&ConfigMsgIn::SetGlobalConfig(config)).await?;
+                println!("Clock source set to {:?}", src);
+            }
+        }
+        ConfigAction::I2c { mode } => {
+            let i2c = match mode.to_lowercase().as_str() {
+                "leader" => protocol::I2cMode::Leader,
+                "follower" => protocol::I2cMode::Follower,
+                "calibration" => protocol::I2cMode::Calibration,
+                _ => invalid!(
+                    "Unknown I2C mode: {} (use: leader, follower, calibration)",
+                    mode
+                ),
+            };
+            let resp = dev.send_receive(&ConfigMsgIn::GetGlobalConfig).await?;
+            if let ConfigMsgOut::GlobalConfig(mut config) = resp {
+                config.i2c_mode = i2c;
+                dev.send(&ConfigMsgIn::SetGlobalConfig(config)).await?;
+                println!("I2C mode set to {:?}", i2c);
+            }
+        }
+        ConfigAction::Env { prefix } => {
+            let config = fetch_global_config(&mut dev).await?;
+            let mut vars = Vec::new();
+            env_vars(&prefix, &serde_json::to_value(&config)?, &mut vars);
+            for (name, value) in vars {
+                println!("{}={}", name, shell_quote(&value));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Flatten a config value into environment variables: struct fields and
+/// array items extend the name (`FP_CLOCK_INTERNAL_BPM`, `FP_AUX_0`), and an
+/// enum variant with data gives the variant under the name itself and its
+/// data under `_VALUE` or its field names.
+fn env_vars(name: &str, value: &serde_json::Value, out: &mut Vec<(String, String)>) {
+    use serde_json::Value as Json;
+
+    let join = |key: &str| {
+        if name.ends_with('_') || name.is_empty() {
+            format!("{}{}", name, key.to_uppercase())
+        } else {
+            format!("{}_{}", name, key.to_uppercase())
+        }
+    };
+    match value {
+        Json::Object(map) => {
+            let variant = map
+                .iter()
+                .next()
+                .filter(|(key, _)| map.len() == 1 && key.starts_with(|c: char| c.is_ascii_uppercase()));
+            match variant {
+                Some((variant, inner)) => {
+                    out.push((name.to_string(), variant.clone()));
+                    match inner {
+                        Json::Object(_) => env_vars(name, inner, out),
+                        _ => env_vars(&join("value"), inner, out),
+                    }
+                }
+                None => {
+                    for (key, inner) in map {
+                        env_vars(&join(key), inner, out);
+                    }
+                }
+            }
+        }
+        Json::Array(items) => {
+            for (i, inner) in items.iter().enumerate() {
+                env_vars(&join(&i.to_string()), inner, out);
+            }
+        }
+        Json::String(s) => out.push((name.to_string(), s.clone())),
+        // Config floats are f32; print them as such, not widened to f64
+        Json::Number(n) if n.is_f64() => {
+            let f = n.as_f64().unwrap_or_default();
+            out.push((name.to_string(), (f as f32).to_string()));
+        }
+        Json::Null => out.push((name.to_string(), String::new())),
+        other => out.push((name.to_string(), other.to_string())),
+    }
+}
+
+/// Quote a value for sh if it needs it.
+fn shell_quote(value: &str) -> String {
+    if !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_.,+-/:".contains(c))
+    {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+pub fn parse_midi_ports_in(s: &str) -> Result<(bool, bool)> {
+    let lower = s.to_lowercase();
+    if lower == "none" || lower == "off" {
+        return Ok((false, false));
+    }
+    if lower == "all" || lower == "both" {
+        return Ok((true, true));
+    }
+    let usb = lower.contains("usb");
+    let din = lower.contains("din");
+    if !usb && !din {
+        invalid!("Expected MIDI input ports: 'usb', 'din', 'usb+din', 'all', or 'none'");
+    }
+    Ok((usb, din))
+}
+
+pub fn parse_midi_ports_out(s: &str) -> Result<(bool, bool, bool)> {
+    let lower = s.to_lowercase();
+    if lower == "none" || lower == "off" {
+        return Ok((false, false, false));
+    }
+    if lower == "all" {
+        return Ok((true, true, true));
+    }
+    let usb = lower.contains("usb");
+    let out1 = lower.contains("out1") || lower.contains("1");
+    let out2 = lower.contains("out2") || lower.contains("2");
+    if !usb && !out1 && !out2 {
+        invalid!("Expected MIDI output ports: 'usb', 'out1', 'out2', 'all', or 'none'");
+    }
+    Ok((usb, out1, out2))
+}
+
+pub fn parse_clock_src(source: &str) -> Result<protocol::ClockSrc> {
+    Ok(match source.to_lowercase().replace(['-', '_'], "").as_str() {
+        "internal" => protocol::ClockSrc::Internal,
+        "midiusb" | "usb" => protocol::ClockSrc::MidiUsb,
+        "midiin" | "din" => protocol::ClockSrc::MidiIn,
+        "atom" => protocol::ClockSrc::Atom,
+        "meteor" => protocol::ClockSrc::Meteor,
+        "cube" => protocol::ClockSrc::Cube,
+        "none" | "off" => protocol::ClockSrc::None,
+        _ => invalid!(
+            "Unknown clock source: {} (use: internal, midiusb, midiin, atom, meteor, cube, none)",
+            source
+        ),
+    })
+}
+
+pub fn parse_aux_mode(s: &str) -> Result<protocol::AuxJackMode> {
+    use protocol::{AuxJackMode, ClockDivision};
+    const DIVISIONS: [ClockDivision; 10] = [
+        ClockDivision::_1,
+        ClockDivision::_2,
+        ClockDivision::_4,
+        ClockDivision::_6,
+        ClockDivision::_8,
+        ClockDivision::_12,
+        ClockDivision::_24,
+        ClockDivision::_96,
+        ClockDivision::_192,
+        ClockDivision::_384,
+    ];
+    let lower = s.to_lowercase();
+    match lower.as_str() {
+        "off" | "none" => return Ok(AuxJackMode::None),
+        "reset" => return Ok(AuxJackMode::ResetOut),
+        _ => {}
+    }
+    let n = lower.strip_prefix("div").unwrap_or(&lower);
+    let Some(division) = DIVISIONS.into_iter().find(|d| n.parse() == Ok(*d as u32)) else {
+        invalid!(
+            "Unknown aux mode: {} (use: off, reset, or div1, div2, div4, div6, div8, div12, div24, div96, div192, div384)",
+            s
+        );
+    };
+    Ok(AuxJackMode::ClockOut(division))
+}
+
+pub fn parse_key(s: &str) -> Result<protocol::Key> {
+    use protocol::Key;
+    const KEYS: [Key; 17] = [
+        Key::Chromatic,
+        Key::Ionian,
+        Key::Dorian,
+        Key::Phrygian,
+        Key::Lydian,
+        Key::Mixolydian,
+        Key::Aeolian,
+        Key::Locrian,
+        Key::BluesMaj,
+        Key::BluesMin,
+        Key::PentatonicMaj,
+        Key::PentatonicMin,
+        Key::Folk,
+        Key::Japanese,
+        Key::Gamelan,
+        Key::HungarianMin,
+        Key::Off,
+    ];
+    let lower = s.to_lowercase().replace(['-', '_', ' '], "");
+    let Some(key) = KEYS.iter().find(|k| format!("{:?}", k).to_lowercase() == lower) else {
+        let options: Vec<_> = KEYS.iter().map(|k| format!("{:?}", k)).collect();
+        invalid!("Unknown key '{}'. Options: {}", s, options.join(", "));
+    };
+    Ok(*key)
+}
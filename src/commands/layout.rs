@@ -0,0 +1,403 @@
+// `layout` commands: showing the layout and placing, removing and packing
+// apps on the faders.
+
+use anyhow::Result;
+
+use crate::designer;
+use crate::display;
+use crate::favorites;
+use crate::groups;
+use crate::i18n::t;
+use crate::layout;
+use crate::notes;
+use crate::protocol::{self, GLOBAL_CHANNELS};
+use crate::random;
+use crate::slots;
+use crate::usb::FaderpunkDevice;
+use crate::{
+    LayoutAction, NON_INTERACTIVE, confirm, fetch_app_info, fetch_layout, find_entry_at_slot,
+    layout_entries, record_placed, resolve_app, send_layout, validate_slot,
+};
+
+pub async fn cmd_layout(action: Option<LayoutAction>) -> Result<()> {
+    match action.unwrap_or(LayoutAction::Show) {
+        LayoutAction::Show => layout_show().await,
+        LayoutAction::Set { slot, app, force } => layout_set(slot, &app, force).await,
+        LayoutAction::Remove { slot, force } => layout_remove(slot, force).await,
+        LayoutAction::Clear { force } => layout_clear(force).await,
+        LayoutAction::Fill { app, force } => layout_fill(&app, force).await,
+        LayoutAction::Plan { apps, keep, force } => layout_plan(&apps, keep, force).await,
+        LayoutAction::Random {
+            include,
+            max_per_app,
+            seed,
+            dry_run,
+            force,
+        } => layout_random(&include, max_per_app, seed, dry_run, force).await,
+        LayoutAction::Edit => layout_edit().await,
+    }
+}
+
+async fn layout_show() -> Result<()> {
+    let mut dev = FaderpunkDevice::open()?;
+    let app_info = fetch_app_info(&mut dev).await?;
+    let layout = fetch_layout(&mut dev).await?;
+    display::print_layout(&layout, Some(&app_info), &groups::load()?, &notes::load()?);
+    Ok(())
+}
+
+async fn layout_set(slot: u8, app_name: &str, force: bool) -> Result<()> {
+    validate_slot(slot)?;
+    let mut dev = FaderpunkDevice::open()?;
+    let app_info = fetch_app_info(&mut dev).await?;
+    let (app_id, channels) = resolve_app(app_name, &app_info)?;
+
+    let idx = slot as usize - 1;
+    let end = idx + channels;
+    if end > GLOBAL_CHANNELS {
+        anyhow::bail!(
+            "App '{}' needs {} fader(s), won't fit at slot {} (only {} slots remaining)",
+            app_name,
+            channels,
+            slots::label(idx),
+            GLOBAL_CHANNELS - idx
+        );
+    }
+
+    let mut layout = fetch_layout(&mut dev).await?;
+
+    // Check what would be displaced
+    let displaced = describe_displaced(&layout, idx, end, &app_info);
+    if !displaced.is_empty() && !force {
+        println!("This will displace:");
+        for d in &displaced {
+            println!("  - {}", d);
+        }
+        if !confirm("Continue?") {
+            println!("{}", t!("Cancelled."));
+            return Ok(());
+        }
+    }
+
+    // Clear any existing apps that overlap with the new placement
+    for i in 0..GLOBAL_CHANNELS {
+        if let Some((_, ch, _)) = layout.0[i] {
+            let app_end = i + ch;
+            if i < end && app_end > idx {
+                layout.0[i] = None;
+            }
+        }
+    }
+
+    // Find next available layout_id
+    let used_ids: Vec<u8> = layout
+        .0
+        .iter()
+        .filter_map(|s| s.map(|(_, _, lid)| lid))
+        .collect();
+    let layout_id = (0..GLOBAL_CHANNELS as u8)
+        .find(|id| !used_ids.contains(id))
+        .unwrap_or(0);
+
+    // Place the app
+    layout.0[idx] = Some((app_id, channels, layout_id));
+
+    let validated = send_layout(&mut dev, layout, &app_info).await?;
+    record_placed(&[app_id], &app_info);
+
+    let app = app_info.iter().find(|a| a.app_id == app_id).unwrap();
+    println!(
+        "Placed {} at fader{} {}",
+        app.name,
+        if channels > 1 { "s" } else { "" },
+        slots::range(idx, channels)
+    );
+    println!();
+    display::print_layout(&validated, Some(&app_info), &groups::load()?, &notes::load()?);
+
+    Ok(())
+}
+
+async fn layout_remove(slot: u8, force: bool) -> Result<()> {
+    validate_slot(slot)?;
+    let mut dev = FaderpunkDevice::open()?;
+    let app_info = fetch_app_info(&mut dev).await?;
+    let mut layout = fetch_layout(&mut dev).await?;
+    let entries = layout_entries(&layout);
+
+    if let Some(entry) = find_entry_at_slot(&entries, slot) {
+        let name = app_info
+            .iter()
+            .find(|a| a.app_id == entry.app_id)
+            .map(|a| a.name.as_str())
+            .unwrap_or("unknown");
+
+        if !force {
+            let range = if entry.size == 1 {
+                format!("fader {}", slots::label(entry.start))
+            } else {
+                format!("faders {}", slots::range(entry.start, entry.size))
+            };
+            if !confirm(&format!("Remove {} from {}?", name, range)) {
+                println!("{}", t!("Cancelled."));
+                return Ok(());
+            }
+        }
+
+        layout.0[entry.start] = None;
+        let validated = send_layout(&mut dev, layout, &app_info).await?;
+        println!("Removed {} from fader {}", name, slots::label(slot as usize - 1));
+        println!();
+        display::print_layout(&validated, Some(&app_info), &groups::load()?, &notes::load()?);
+    } else {
+        println!("Fader {} is already empty", slots::label(slot as usize - 1));
+    }
+
+    Ok(())
+}
+
+async fn layout_clear(force: bool) -> Result<()> {
+    let mut dev = FaderpunkDevice::open()?;
+
+    if !force {
+        let app_info = fetch_app_info(&mut dev).await?;
+        let layout = fetch_layout(&mut dev).await?;
+        let entries = layout_entries(&layout);
+
+        if !entries.is_empty() {
+            println!("Current layout has {} app(s):", entries.len());
+            for entry in &entries {
+                let name = app_info
+                    .iter()
+                    .find(|a| a.app_id == entry.app_id)
+                    .map(|a| a.name.as_str())
+                    .unwrap_or("unknown");
+                let range = if entry.size == 1 {
+                    format!("fader {}", slots::label(entry.start))
+                } else {
+                    format!("faders {}", slots::range(entry.start, entry.size))
+                };
+                println!("  - {} ({})", name, range);
+            }
+            if !confirm("Clear all?") {
+                println!("{}", t!("Cancelled."));
+                return Ok(());
+            }
+        }
+    }
+
+    let layout = protocol::Layout([None; GLOBAL_CHANNELS]);
+    send_layout(&mut dev, layout, &[]).await?;
+    println!("Layout cleared — all faders empty");
+    Ok(())
+}
+
+async fn layout_fill(app_name: &str, force: bool) -> Result<()> {
+    let mut dev = FaderpunkDevice::open()?;
+    let app_info = fetch_app_info(&mut dev).await?;
+    let (app_id, channels) = resolve_app(app_name, &app_info)?;
+
+    if !force {
+        let layout = fetch_layout(&mut dev).await?;
+        let entries = layout_entries(&layout);
+
+        if !entries.is_empty() {
+            println!("This will replace the current layout ({} app(s)):", entries.len());
+            for entry in &entries {
+                let name = app_info
+                    .iter()
+                    .find(|a| a.app_id == entry.app_id)
+                    .map(|a| a.name.as_str())
+                    .unwrap_or("unknown");
+                let range = if entry.size == 1 {
+                    format!("fader {}", slots::label(entry.start))
+                } else {
+                    format!("faders {}", slots::range(entry.start, entry.size))
+                };
+                println!("  - {} ({})", name, range);
+            }
+            let app = app_info.iter().find(|a| a.app_id == app_id).unwrap();
+            if !confirm(&format!("Fill all faders with {}?", app.name)) {
+                println!("{}", t!("Cancelled."));
+                return Ok(());
+            }
+        }
+    }
+
+    let mut layout = protocol::Layout([None; GLOBAL_CHANNELS]);
+    let mut pos = 0usize;
+    let mut layout_id = 0u8;
+
+    while pos + channels <= GLOBAL_CHANNELS {
+        layout.0[pos] = Some((app_id, channels, layout_id));
+        pos += channels;
+        layout_id += 1;
+    }
+
+    let validated = send_layout(&mut dev, layout, &app_info).await?;
+    record_placed(&[app_id], &app_info);
+
+    let app = app_info.iter().find(|a| a.app_id == app_id).unwrap();
+    let count = GLOBAL_CHANNELS / channels;
+    println!(
+        "Filled layout with {} x {} ({} ch each)",
+        count, app.name, channels
+    );
+    println!();
+    display::print_layout(&validated, Some(&app_info), &groups::load()?, &notes::load()?);
+
+    Ok(())
+}
+
+async fn layout_plan(app_names: &[String], keep: bool, force: bool) -> Result<()> {
+    let mut dev = FaderpunkDevice::open()?;
+    let app_info = fetch_app_info(&mut dev).await?;
+    let apps = app_names
+        .iter()
+        .map(|name| resolve_app(name, &app_info))
+        .collect::<Result<Vec<_>>>()?;
+
+    let current = fetch_layout(&mut dev).await?;
+    let planned = layout::pack(keep.then_some(&current), &apps)?;
+
+    let used: usize = planned.0.iter().flatten().map(|(_, ch, _)| ch).sum();
+    println!(
+        "Planned {} app(s) using {} of {} faders:",
+        apps.len(),
+        used,
+        GLOBAL_CHANNELS
+    );
+    println!();
+    display::print_layout(&planned, Some(&app_info), &groups::load()?, &notes::load()?);
+    println!();
+
+    if !force && !confirm("Apply this layout?") {
+        println!("{}", t!("Cancelled."));
+        return Ok(());
+    }
+
+    let validated = send_layout(&mut dev, planned, &app_info).await?;
+    let ids: Vec<u8> = apps.iter().map(|(id, _)| *id).collect();
+    record_placed(&ids, &app_info);
+    println!("{}", t!("Layout applied."));
+    println!();
+    display::print_layout(&validated, Some(&app_info), &groups::load()?, &notes::load()?);
+    Ok(())
+}
+
+async fn layout_random(
+    include: &[String],
+    max_per_app: Option<usize>,
+    seed: Option<u64>,
+    dry_run: bool,
+    force: bool,
+) -> Result<()> {
+    let mut dev = FaderpunkDevice::open()?;
+    let app_info = fetch_app_info(&mut dev).await?;
+    let mut apps = match include {
+        [] => app_info.iter().map(|a| (a.app_id, a.channels)).collect(),
+        names => names
+            .iter()
+            .map(|name| resolve_app(name.trim(), &app_info))
+            .collect::<Result<Vec<_>>>()?,
+    };
+    // Naming an app twice shouldn't make it twice as likely
+    apps.sort_unstable();
+    apps.dedup();
+
+    let seed = seed.unwrap_or_else(random::fresh_seed);
+    let planned = layout::random(&apps, max_per_app, &mut random::Rng::new(seed))?;
+
+    let placed: Vec<u8> = planned.0.iter().flatten().map(|(app_id, ..)| *app_id).collect();
+    let used: usize = planned.0.iter().flatten().map(|(_, ch, _)| ch).sum();
+    println!(
+        "Random layout (seed {}): {} app(s) using {} of {} faders:",
+        seed,
+        placed.len(),
+        used,
+        GLOBAL_CHANNELS
+    );
+    println!();
+    display::print_layout(&planned, Some(&app_info), &groups::load()?, &notes::load()?);
+    println!();
+    if dry_run {
+        return Ok(());
+    }
+
+    if !force && !confirm("Apply this layout?") {
+        println!("{}", t!("Cancelled."));
+        return Ok(());
+    }
+
+    let validated = send_layout(&mut dev, planned, &app_info).await?;
+    record_placed(&placed, &app_info);
+    println!("{}", t!("Layout applied."));
+    println!();
+    display::print_layout(&validated, Some(&app_info), &groups::load()?, &notes::load()?);
+    Ok(())
+}
+
+async fn layout_edit() -> Result<()> {
+    if NON_INTERACTIVE.load(std::sync::atomic::Ordering::SeqCst) {
+        anyhow::bail!("The layout designer needs a terminal; use 'layout set' or 'layout plan' here");
+    }
+    let mut dev = FaderpunkDevice::open()?;
+    let app_info = fetch_app_info(&mut dev).await?;
+    let current = fetch_layout(&mut dev).await?;
+
+    // The designer blocks on the keyboard; the device reader keeps running meanwhile
+    let favorites = favorites::Favorites::load()?;
+    let Some(designed) =
+        tokio::task::block_in_place(|| designer::run(&current, &app_info, &favorites))?
+    else {
+        println!("{}", t!("Cancelled."));
+        return Ok(());
+    };
+    if designed.0 == current.0 {
+        println!("Layout unchanged.");
+        return Ok(());
+    }
+    // Apps that weren't on the strip before count as used
+    let placed: Vec<u8> = designed
+        .0
+        .iter()
+        .flatten()
+        .filter(|(_, _, id)| !current.0.iter().flatten().any(|(_, _, old)| old == id))
+        .map(|(app_id, ..)| *app_id)
+        .collect();
+    let validated = send_layout(&mut dev, designed, &app_info).await?;
+    record_placed(&placed, &app_info);
+    println!("{}", t!("Layout applied."));
+    println!();
+    display::print_layout(&validated, Some(&app_info), &groups::load()?, &notes::load()?);
+    Ok(())
+}
+
+/// Describe apps that would be displaced by placing an app at idx..end.
+fn describe_displaced(
+    layout: &protocol::Layout,
+    idx: usize,
+    end: usize,
+    app_info: &[display::AppInfo],
+) -> Vec<String> {
+    let mut displaced = Vec::new();
+    for i in 0..GLOBAL_CHANNELS {
+        if let Some((app_id, ch, _)) = layout.0[i] {
+            let app_end = i + ch;
+            if i < end && app_end > idx {
+                let name = app_info
+                    .iter()
+                    .find(|a| a.app_id == app_id)
+                    .map(|a| a.name.as_str())
+                    .unwrap_or("unknown");
+                let range = if ch == 1 {
+                    format!("fader {}", slots::label(i))
+                } else {
+                    format!("faders {}", slots::range(i, ch))
+                };
+                displaced.push(format!("{} ({})", name, range));
+            }
+        }
+    }
+    displaced
+}
@@ -0,0 +1,226 @@
+// `midi` commands: moving CC assignments around and importing a DAW's
+// mapping.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::commands::param::{edit_params, fetch_app_params, param_values};
+use crate::display;
+use crate::groups;
+use crate::midimap;
+use crate::protocol::{self, Param, Value};
+use crate::slots;
+use crate::usb::FaderpunkDevice;
+use crate::{MidiAction, fetch_app_info, fetch_layout, layout_entries};
+
+pub async fn cmd_midi(action: MidiAction) -> Result<()> {
+    match action {
+        MidiAction::Remap {
+            from_channel,
+            to_channel,
+            slots,
+        } => midi_remap(from_channel, to_channel, slots.as_deref()).await,
+        MidiAction::AutoAssign { start } => midi_auto_assign(start).await,
+        MidiAction::Import {
+            file,
+            format,
+            slots,
+            dry_run,
+        } => midi_import(Path::new(&file), format, slots.as_deref(), dry_run).await,
+    }
+}
+
+async fn midi_remap(from: u8, to: u8, slots: Option<&str>) -> Result<()> {
+    let slots = slots.map(groups::parse_slots).transpose()?;
+    let mut dev = FaderpunkDevice::open()?;
+
+    let changed = edit_params(&mut dev, slots.as_deref(), |_, _, _, value| {
+        if *value != Value::MidiChannel(protocol::MidiChannel(from)) {
+            return false;
+        }
+        *value = Value::MidiChannel(protocol::MidiChannel(to));
+        true
+    })
+    .await?;
+
+    if changed == 0 {
+        println!("No MIDI channel params on channel {}", from);
+    } else {
+        println!("Moved {} param(s) from channel {} to {}", changed, from, to);
+    }
+    Ok(())
+}
+
+/// CCs with a fixed meaning in the MIDI spec: bank select, data entry,
+/// data increment/decrement, NRPN/RPN selection, and channel mode messages.
+fn is_reserved_cc(cc: u16) -> bool {
+    matches!(cc, 0 | 6 | 32 | 38 | 96..=101 | 120..=127)
+}
+
+async fn midi_auto_assign(start: u16) -> Result<()> {
+    let mut dev = FaderpunkDevice::open()?;
+    let mut next = start;
+    let mut unassigned = 0;
+
+    let changed = edit_params(&mut dev, None, |_, _, param, value| {
+        if !matches!(param, Param::MidiCc { .. }) {
+            return false;
+        }
+        while is_reserved_cc(next) && next < 128 {
+            next += 1;
+        }
+        if next >= 128 {
+            unassigned += 1;
+            return false;
+        }
+        let cc = next;
+        next += 1;
+        if *value == Value::MidiCc(protocol::MidiCc(cc)) {
+            return false;
+        }
+        *value = Value::MidiCc(protocol::MidiCc(cc));
+        true
+    })
+    .await?;
+
+    println!("Reassigned {} CC param(s)", changed);
+    if unassigned > 0 {
+        eprintln!(
+            "warning: ran out of free CC numbers; {} param(s) left unchanged (try a lower --start)",
+            unassigned
+        );
+    }
+    Ok(())
+}
+
+/// Param `idx` of the values read for an app, which should have one for
+/// every param its metadata lists.
+fn param_slot(values: &mut [Value], layout_id: u8, idx: usize) -> Result<&mut Value> {
+    let count = values.len();
+    values.get_mut(idx).with_context(|| {
+        format!(
+            "Device sent {} value(s) for layout ID {}, which has no param {}",
+            count, layout_id, idx
+        )
+    })
+}
+
+/// Put each mapping from a DAW file on a CC param: mappings that name a
+/// fader go to the first free CC param of the app there, the rest fill the
+/// CC params in fader order. An app's MIDI channel param follows the first
+/// mapping it gets.
+async fn midi_import(
+    path: &Path,
+    format: midimap::Format,
+    slots: Option<&str>,
+    dry_run: bool,
+) -> Result<()> {
+    let mappings = midimap::read(path, format)?;
+    let slots = slots.map(groups::parse_slots).transpose()?;
+    let mut dev = FaderpunkDevice::open()?;
+    let app_info = fetch_app_info(&mut dev).await?;
+    let layout = fetch_layout(&mut dev).await?;
+    let entries = layout_entries(&layout);
+
+    // Apps with CC params, in fader order
+    let targets: Vec<(&display::LayoutEntry, &display::AppInfo)> = entries
+        .iter()
+        .filter(|e| slots.as_ref().is_none_or(|s| s.contains(&(e.start as u8 + 1))))
+        .filter_map(|e| Some((e, app_info.iter().find(|a| a.app_id == e.app_id)?)))
+        .filter(|(_, app)| app.params.iter().any(|p| matches!(p, Param::MidiCc { .. })))
+        .collect();
+    if targets.is_empty() {
+        anyhow::bail!("No apps with a MIDI CC param on the selected faders");
+    }
+    let layout_ids: Vec<u8> = targets.iter().map(|(e, _)| e.layout_id).collect();
+    let read = fetch_app_params(&mut dev, &layout_ids).await?;
+    let mut current = read.clone();
+
+    // CC params not yet given a mapping, per target
+    let mut free: Vec<Vec<usize>> = targets
+        .iter()
+        .map(|(_, app)| {
+            app.params
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| matches!(p, Param::MidiCc { .. }))
+                .map(|(i, _)| i)
+                .collect()
+        })
+        .collect();
+    let mut channel_set = vec![false; targets.len()];
+    let mut changed = vec![false; targets.len()];
+    let mut skipped = 0;
+
+    // Mappings for a given fader first, so the others can't take their spot
+    let (pinned, rest): (Vec<_>, Vec<_>) = mappings.iter().partition(|m| m.slot.is_some());
+    for mapping in pinned.into_iter().chain(rest) {
+        let target = match mapping.slot {
+            Some(slot) => targets
+                .iter()
+                .position(|(e, _)| (e.start..e.start + e.size).contains(&(slot as usize - 1)))
+                .filter(|&t| !free[t].is_empty()),
+            None => free.iter().position(|f| !f.is_empty()),
+        };
+        let Some(t) = target else {
+            skipped += 1;
+            continue;
+        };
+        let (entry, app) = targets[t];
+        let (layout_id, values) = &mut current[t];
+        let idx = free[t].remove(0);
+        *param_slot(values, *layout_id, idx)? = Value::MidiCc(protocol::MidiCc(mapping.cc));
+        let mut note = format!("CC {}", mapping.cc);
+
+        if let Some(ch_idx) = app.params.iter().position(|p| matches!(p, Param::MidiChannel { .. })) {
+            let channel = Value::MidiChannel(protocol::MidiChannel(mapping.channel));
+            let value = param_slot(values, *layout_id, ch_idx)?;
+            if !channel_set[t] {
+                *value = channel;
+                channel_set[t] = true;
+            } else if *value != channel {
+                eprintln!(
+                    "warning: fader {}: {} has one MIDI channel; CC {} stays on the channel of its first mapping",
+                    slots::label(entry.start),
+                    app.name,
+                    mapping.cc
+                );
+            }
+            if *value == channel {
+                note.push_str(&format!(" on channel {}", mapping.channel));
+            }
+        }
+        changed[t] = true;
+
+        println!(
+            "  fader {:>2} {}: {} = {}{}",
+            slots::label(entry.start),
+            app.name,
+            display::param_label(app.params.get(idx), idx),
+            note,
+            mapping.name.as_ref().map(|n| format!("  ← {}", n)).unwrap_or_default()
+        );
+    }
+
+    if skipped > 0 {
+        eprintln!(
+            "warning: {} mapping(s) left over: no free CC param on the faders they need",
+            skipped
+        );
+    }
+    if dry_run {
+        println!("Dry run: nothing sent");
+        return Ok(());
+    }
+    let mut sent = 0;
+    for (((layout_id, values), (_, read)), changed) in current.iter().zip(&read).zip(changed) {
+        if changed {
+            dev.write_params(*layout_id, read, param_values(values), false)
+                .await?;
+            sent += 1;
+        }
+    }
+    println!("Imported {} mapping(s) into {} app(s)", mappings.len() - skipped, sent);
+    Ok(())
+}
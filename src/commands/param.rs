@@ -0,0 +1,1300 @@
+// `param` commands: showing, setting and randomizing app params, and the
+// parsers that turn typed values into param values.
+
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use crate::appmeta;
+use crate::audit;
+use crate::commands::config::{parse_midi_ports_in, parse_midi_ports_out};
+use crate::display;
+use crate::error;
+use crate::groups;
+use crate::i18n::t;
+use crate::invalid;
+use crate::protocol::{self, APP_MAX_PARAMS, ConfigMsgIn, ConfigMsgOut, Param, Value};
+use crate::random;
+use crate::randomize;
+use crate::render;
+use crate::settings;
+use crate::slots;
+use crate::usb::FaderpunkDevice;
+use crate::{
+    ParamAction, fetch_app_info, fetch_layout, find_entry_at_slot, layout_entries, validate_slot,
+};
+
+pub async fn cmd_param(action: Option<ParamAction>) -> Result<()> {
+    match action.unwrap_or(ParamAction::Show {
+        slots: None,
+        ascii: false,
+        json: false,
+        all_indices: false,
+        grouped: false,
+    }) {
+        ParamAction::Show {
+            slots,
+            ascii,
+            json,
+            all_indices,
+            grouped,
+        } => param_show(slots.as_deref(), ascii, json, all_indices, grouped).await,
+        ParamAction::Set {
+            slots,
+            param,
+            value,
+            clamp,
+            force,
+        } => match groups::parse_slots(&slots)?.as_slice() {
+            [slot] => param_set(*slot, &param, &value, clamp, force).await,
+            slots => param_set_many(slots, &param, &value, clamp, force).await,
+        },
+        ParamAction::SetRange { range, slots, .. } => {
+            param_set_range(&range, slots.as_deref()).await
+        }
+        ParamAction::Randomize {
+            slots,
+            constraints,
+            seed,
+            ..
+        } => {
+            param_randomize(
+                slots.as_deref(),
+                constraints.as_deref().map(Path::new),
+                seed,
+            )
+            .await
+        }
+        ParamAction::Toggle {
+            slots,
+            param,
+            on,
+            off,
+            force,
+        } => {
+            let to = match (on, off) {
+                (true, _) => Some(true),
+                (_, true) => Some(false),
+                _ => None,
+            };
+            param_toggle(&slots, &param, to, force).await
+        }
+        ParamAction::Find { kind, slots, json } => param_find(kind, slots.as_deref(), json).await,
+        ParamAction::History { slot, param, last } => param_history(slot, &param, last).await,
+    }
+}
+
+/// A param type as `appmeta` spells it, from any case and with or without
+/// `_` or `-` (midicc, midi-cc, MIDI_CC).
+pub fn parse_param_type(s: &str) -> Result<&'static str> {
+    let wanted = s.to_lowercase().replace(['-', '_'], "");
+    appmeta::PARAM_TYPES
+        .into_iter()
+        .find(|t| t.replace('_', "") == wanted)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Unknown param type: {} (use: {})",
+                s,
+                appmeta::PARAM_TYPES.map(|t| t.replace('_', "")).join(", ")
+            )
+        })
+}
+
+async fn param_find(kind: &str, slots: Option<&str>, json: bool) -> Result<()> {
+    let json = json || render::output().is_structured();
+    let mut dev = FaderpunkDevice::open()?;
+    let app_info = fetch_app_info(&mut dev).await?;
+    let layout = fetch_layout(&mut dev).await?;
+    let mut entries = layout_entries(&layout);
+    if let Some(slots) = slots {
+        let slots = groups::parse_slots(slots)?;
+        entries.retain(|e| {
+            slots
+                .iter()
+                .any(|&s| (e.start..e.start + e.size).contains(&(s as usize - 1)))
+        });
+    }
+    let layout_ids: Vec<u8> = entries.iter().map(|e| e.layout_id).collect();
+    let states = fetch_app_params(&mut dev, &layout_ids).await?;
+
+    let mut rows = Vec::new();
+    for (entry, (_, values)) in entries.iter().zip(&states) {
+        let Some(app) = app_info.iter().find(|a| a.app_id == entry.app_id) else {
+            continue;
+        };
+        for (i, (param, value)) in app.params.iter().zip(values).enumerate() {
+            if appmeta::param_type(param) == kind {
+                rows.push((entry, app, i, param, value));
+            }
+        }
+    }
+
+    if json {
+        let out: Vec<_> = rows
+            .iter()
+            .map(|(entry, app, i, param, value)| {
+                serde_json::json!({
+                    "slot": entry.start + 1,
+                    "layout_id": entry.layout_id,
+                    "app": app.name,
+                    "index": i,
+                    "name": display::get_param_name(param),
+                    "value": value,
+                })
+            })
+            .collect();
+        display::print_data(&serde_json::to_value(&out)?);
+        return Ok(());
+    }
+    let rows: Vec<_> = rows
+        .into_iter()
+        .map(|(entry, app, i, param, value)| {
+            (
+                slots::range(entry.start, entry.size),
+                app.name.clone(),
+                format!("{} [{}]", display::get_param_name(param), i),
+                display::format_value(value, Some(param)),
+            )
+        })
+        .collect();
+    display::print_param_matches(kind, &rows);
+    Ok(())
+}
+
+async fn param_show(
+    slots: Option<&str>,
+    ascii: bool,
+    json: bool,
+    all_indices: bool,
+    grouped: bool,
+) -> Result<()> {
+    let json = json || render::output().is_structured();
+    let settings = settings::Settings::load()?;
+    let mut dev = FaderpunkDevice::open()?;
+    let app_info = fetch_app_info(&mut dev).await?;
+    let layout = fetch_layout(&mut dev).await?;
+    let entries = layout_entries(&layout);
+
+    let states = if let Some(slots) = slots {
+        let slots = groups::parse_slots(slots)?;
+        let mut layout_ids: Vec<u8> = Vec::new();
+        for slot in slots {
+            let entry = find_entry_at_slot(&entries, slot)
+                .ok_or_else(|| error::invalid(t!("No app at fader {}", slots::label(slot as usize - 1))))?;
+            if !layout_ids.contains(&entry.layout_id) {
+                layout_ids.push(entry.layout_id);
+            }
+        }
+        fetch_app_params(&mut dev, &layout_ids).await?
+    } else {
+        dev.send_receive_batch(&ConfigMsgIn::GetAllAppParams)
+            .await?
+            .into_iter()
+            .filter_map(|resp| match resp {
+                ConfigMsgOut::AppState(layout_id, values) => Some((layout_id, values)),
+                _ => None,
+            })
+            .collect()
+    };
+
+    if json {
+        let out: Vec<_> = states
+            .iter()
+            .map(|(layout_id, values)| {
+                let groups = param_groups(&settings, *layout_id, &entries, &app_info, grouped);
+                params_json(*layout_id, values, &entries, &app_info, all_indices, groups)
+            })
+            .collect();
+        display::print_data(&serde_json::to_value(&out)?);
+        return Ok(());
+    }
+
+    for (layout_id, values) in states {
+        let groups = param_groups(&settings, layout_id, &entries, &app_info, grouped);
+        display::print_app_params(
+            layout_id,
+            &values,
+            Some(&entries),
+            Some(&app_info),
+            !ascii,
+            all_indices,
+            groups.as_deref(),
+        );
+    }
+
+    Ok(())
+}
+
+/// Headings for an app instance's params: its app's param_groups from
+/// config.toml, else (with `by_function`) groups made from the param types.
+fn param_groups(
+    settings: &settings::Settings,
+    layout_id: u8,
+    entries: &[display::LayoutEntry],
+    app_info: &[display::AppInfo],
+    by_function: bool,
+) -> Option<Vec<settings::ParamGroup>> {
+    let entry = entries.iter().find(|e| e.layout_id == layout_id)?;
+    let app = app_info.iter().find(|a| a.app_id == entry.app_id)?;
+    match settings.param_groups_for(&app.name) {
+        Some(groups) => Some(groups.to_vec()),
+        None if by_function => Some(display::groups_by_function(&app.params)),
+        None => None,
+    }
+}
+
+/// JSON description of one app instance's params for `param show --json`.
+/// Unused indices are left out unless `all_indices`, which marks them.
+/// With `groups`, params come in group order and each names its group.
+fn params_json(
+    layout_id: u8,
+    values: &[Value],
+    entries: &[display::LayoutEntry],
+    app_info: &[display::AppInfo],
+    all_indices: bool,
+    groups: Option<Vec<settings::ParamGroup>>,
+) -> serde_json::Value {
+    let entry = entries.iter().find(|e| e.layout_id == layout_id);
+    let app = entry.and_then(|e| app_info.iter().find(|a| a.app_id == e.app_id));
+    let shown: Vec<usize> = (0..values.len())
+        .filter(|&i| all_indices || !display::is_unused(app.and_then(|a| a.params.get(i))))
+        .collect();
+    let sections = match &groups {
+        Some(groups) => {
+            display::arrange_groups(groups, app.map(|a| a.params.as_slice()), &shown)
+        }
+        None => vec![(String::new(), shown)],
+    };
+    let params: Vec<_> = sections
+        .iter()
+        .flat_map(|(group, members)| members.iter().map(move |&i| (group, i)))
+        .map(|(group, i)| {
+            let param = app.and_then(|a| a.params.get(i));
+            let name = param.map(display::get_param_name).unwrap_or_default();
+            let mut row = serde_json::json!({ "index": i, "name": name, "value": values[i] });
+            if display::is_unused(param) {
+                row["unused"] = true.into();
+            }
+            if groups.is_some() {
+                row["group"] = group.as_str().into();
+            }
+            row
+        })
+        .collect();
+    serde_json::json!({
+        "layout_id": layout_id,
+        "slot": entry.map(|e| e.start + 1),
+        "channels": entry.map(|e| e.size),
+        "app": app.map(|a| a.name.as_str()),
+        "params": params,
+    })
+}
+
+/// Fetch params for a set of app instances in one pipelined exchange.
+pub async fn fetch_app_params(
+    dev: &mut FaderpunkDevice,
+    layout_ids: &[u8],
+) -> Result<Vec<(u8, Vec<Value>)>> {
+    let requests: Vec<_> = layout_ids
+        .iter()
+        .map(|&layout_id| ConfigMsgIn::GetAppParams { layout_id })
+        .collect();
+    dev.send_receive_pipelined(&requests)
+        .await?
+        .into_iter()
+        .zip(&requests)
+        .map(|(resp, msg)| match resp {
+            ConfigMsgOut::AppState(layout_id, values) => Ok((layout_id, values)),
+            other => Err(error::unexpected(msg, &other)),
+        })
+        .collect()
+}
+
+async fn param_set(
+    slot: u8,
+    param_ref: &str,
+    value_str: &str,
+    clamp: bool,
+    force: bool,
+) -> Result<()> {
+    validate_slot(slot)?;
+    let mut dev = FaderpunkDevice::open()?;
+    let app_info = fetch_app_info(&mut dev).await?;
+    let layout = fetch_layout(&mut dev).await?;
+    let entries = layout_entries(&layout);
+
+    let entry = find_entry_at_slot(&entries, slot)
+        .ok_or_else(|| error::invalid(t!("No app at fader {}", slots::label(slot as usize - 1))))?;
+
+    // Get current params to know the types
+    let msg = ConfigMsgIn::GetAppParams {
+        layout_id: entry.layout_id,
+    };
+    let current_values = match dev.send_receive(&msg).await? {
+        ConfigMsgOut::AppState(_, values) => values,
+        other => return Err(error::unexpected(&msg, &other)),
+    };
+
+    // Get param metadata for this app
+    let app = app_info
+        .iter()
+        .find(|a| a.app_id == entry.app_id)
+        .ok_or_else(|| anyhow::anyhow!(t!("App metadata not found")))?;
+
+    let param_idx = resolve_param(param_ref, app, current_values.len(), slot)?;
+
+    let param_meta = app.params.get(param_idx);
+    let new_value = parse_value(value_str, param_meta, &current_values[param_idx], clamp)?;
+
+    let values = param_change(&current_values, param_idx, new_value);
+    let values = dev.write_params(entry.layout_id, &current_values, values, force).await?;
+
+    // Only what changed; the firmware may have adjusted more than the one param
+    display::print_param_diff(
+        entry.layout_id,
+        &current_values,
+        &values,
+        Some(&entries),
+        Some(&app_info),
+    );
+
+    Ok(())
+}
+
+/// `param set` on several faders: sets the param on every app among them
+/// that has it. All values are checked before anything is sent.
+async fn param_set_many(
+    slots: &[u8],
+    param_ref: &str,
+    value_str: &str,
+    clamp: bool,
+    force: bool,
+) -> Result<()> {
+    let mut dev = FaderpunkDevice::open()?;
+    let app_info = fetch_app_info(&mut dev).await?;
+    let layout = fetch_layout(&mut dev).await?;
+    let entries = layout_entries(&layout);
+
+    let mut targets: Vec<&display::LayoutEntry> = slots
+        .iter()
+        .filter_map(|&s| find_entry_at_slot(&entries, s))
+        .collect();
+    targets.dedup_by_key(|e| e.layout_id);
+    if targets.is_empty() {
+        anyhow::bail!(t!("No apps on the selected faders"));
+    }
+    let layout_ids: Vec<u8> = targets.iter().map(|e| e.layout_id).collect();
+    let current = fetch_app_params(&mut dev, &layout_ids).await?;
+
+    let mut writes = Vec::new();
+    for (entry, (layout_id, current_values)) in targets.iter().zip(current) {
+        let Some(app) = app_info.iter().find(|a| a.app_id == entry.app_id) else {
+            continue;
+        };
+        let fader = slots::label(entry.start);
+        let idx = match resolve_param(param_ref, app, current_values.len(), entry.start as u8 + 1) {
+            Ok(idx) => idx,
+            Err(e) => {
+                println!("  fader {:>2} {}: skipped ({})", fader, app.name, e);
+                continue;
+            }
+        };
+        let param = app.params.get(idx);
+        let new_value = parse_value(value_str, param, &current_values[idx], clamp)
+            .with_context(|| format!("fader {}: {}", fader, app.name))?;
+        let values = param_change(&current_values, idx, new_value);
+        println!(
+            "  fader {:>2} {}: {} = {}",
+            fader,
+            app.name,
+            display::param_label(param, idx),
+            display::format_value(&new_value, param)
+        );
+        writes.push((layout_id, current_values, values));
+    }
+    if writes.is_empty() {
+        anyhow::bail!("No app on the selected faders has a param matching '{}'", param_ref);
+    }
+    for (layout_id, read, values) in &writes {
+        dev.write_params(*layout_id, read, *values, force).await?;
+    }
+    println!("Set {} app(s)", writes.len());
+    Ok(())
+}
+
+/// Faders for `param toggle`: a slot list or group, or `@APP` for every
+/// instance of an app in the layout when no group has that name.
+fn toggle_slots(
+    spec: &str,
+    entries: &[display::LayoutEntry],
+    app_info: &[display::AppInfo],
+) -> Result<Vec<u8>> {
+    if let Some(name) = spec.strip_prefix('@')
+        && !name.contains(',')
+        && !groups::load()?.contains_key(name)
+    {
+        let slots: Vec<u8> = entries
+            .iter()
+            .filter(|e| {
+                app_info
+                    .iter()
+                    .any(|a| a.app_id == e.app_id && a.name.eq_ignore_ascii_case(name))
+            })
+            .map(|e| e.start as u8 + 1)
+            .collect();
+        if !slots.is_empty() {
+            return Ok(slots);
+        }
+    }
+    groups::parse_slots(spec)
+}
+
+/// Flip a Bool (or NRPN on/off) param on every app on the faders, or set it
+/// to `to`. Apps without the param are skipped; one of another type is an
+/// error, before anything is written.
+async fn param_toggle(spec: &str, param_ref: &str, to: Option<bool>, force: bool) -> Result<()> {
+    let mut dev = FaderpunkDevice::open()?;
+    let app_info = fetch_app_info(&mut dev).await?;
+    let layout = fetch_layout(&mut dev).await?;
+    let entries = layout_entries(&layout);
+
+    let slots = toggle_slots(spec, &entries, &app_info)?;
+    let mut targets: Vec<&display::LayoutEntry> = slots
+        .iter()
+        .filter_map(|&s| find_entry_at_slot(&entries, s))
+        .collect();
+    targets.dedup_by_key(|e| e.layout_id);
+    if targets.is_empty() {
+        anyhow::bail!(t!("No apps on the selected faders"));
+    }
+    let layout_ids: Vec<u8> = targets.iter().map(|e| e.layout_id).collect();
+    let current = fetch_app_params(&mut dev, &layout_ids).await?;
+
+    let mut found = 0;
+    let mut writes = Vec::new();
+    for (entry, (layout_id, current_values)) in targets.iter().zip(current) {
+        let Some(app) = app_info.iter().find(|a| a.app_id == entry.app_id) else {
+            continue;
+        };
+        let fader = slots::label(entry.start);
+        let idx = match resolve_param(param_ref, app, current_values.len(), entry.start as u8 + 1) {
+            Ok(idx) => idx,
+            Err(e) if targets.len() > 1 => {
+                println!("  fader {:>2} {}: skipped ({})", fader, app.name, e);
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        let param = app.params.get(idx);
+        let old = current_values[idx];
+        let new = match old {
+            Value::Bool(on) => Value::Bool(to.unwrap_or(!on)),
+            Value::MidiNrpn(on) => Value::MidiNrpn(to.unwrap_or(!on)),
+            _ => anyhow::bail!(
+                "fader {}: {} {} is a {} param, not on/off. Use 'param set' for it.",
+                fader,
+                app.name,
+                display::param_label(param, idx),
+                param.map(display::format_param_type).unwrap_or("unknown")
+            ),
+        };
+        println!(
+            "  fader {:>2} {}: {} {} → {}",
+            fader,
+            app.name,
+            display::param_label(param, idx),
+            display::format_value(&old, param),
+            display::format_value(&new, param)
+        );
+        found += 1;
+        if new != old {
+            let values = param_change(&current_values, idx, new);
+            writes.push((layout_id, current_values, values));
+        }
+    }
+    if found == 0 {
+        anyhow::bail!("No app on the selected faders has a param matching '{}'", param_ref);
+    }
+    for (layout_id, read, values) in &writes {
+        dev.write_params(*layout_id, read, *values, force).await?;
+    }
+    println!("Changed {} app(s)", writes.len());
+    Ok(())
+}
+
+/// Columns in the `param history` graph.
+const HISTORY_COLUMNS: usize = 60;
+
+/// `param history`: the param's changes from the change log, and for
+/// numeric params a graph of its value over the window. Only changes sent by
+/// this CLI are logged, so moves made on the device itself don't show.
+async fn param_history(slot: u8, param_ref: &str, last: Duration) -> Result<()> {
+    let mut dev = FaderpunkDevice::open()?;
+    let app_info = fetch_app_info(&mut dev).await?;
+    let layout = fetch_layout(&mut dev).await?;
+    let entries = layout_entries(&layout);
+    let entry = find_entry_at_slot(&entries, slot)
+        .ok_or_else(|| error::invalid(t!("No app at fader {}", slots::label(slot as usize - 1))))?;
+    let app = app_info
+        .iter()
+        .find(|a| a.app_id == entry.app_id)
+        .ok_or_else(|| anyhow::anyhow!(t!("App metadata not found")))?;
+    let current = fetch_app_params(&mut dev, &[entry.layout_id])
+        .await?
+        .pop()
+        .map(|(_, values)| values)
+        .unwrap_or_default();
+    let idx = resolve_param(param_ref, app, current.len(), slot)?;
+    let param = app.params.get(idx);
+
+    // Every logged change to this param, oldest first
+    let field = format!("param {}", idx);
+    let changes: Vec<(std::time::SystemTime, Option<Value>, Value, String)> = audit::read(None)?
+        .into_iter()
+        .filter(|e| e.message == "SetAppParams" && e.layout_id == Some(entry.layout_id))
+        .flat_map(|e| {
+            let time = e.time();
+            let command = e.command;
+            e.changes
+                .into_iter()
+                .filter(|c| c.field == field)
+                .filter_map(|c| {
+                    let new = serde_json::from_value::<Value>(c.new).ok()?;
+                    let old = serde_json::from_value::<Value>(c.old).ok();
+                    Some((time, old, new, command.clone()))
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    let now = std::time::SystemTime::now();
+    let start = now.checked_sub(last).unwrap_or(std::time::UNIX_EPOCH);
+    let (before, within): (Vec<_>, Vec<_>) = changes.iter().partition(|c| c.0 < start);
+    // The value as the window opens: the last change before it, else what
+    // the first change in it replaced, else what it is now
+    let initial = before
+        .last()
+        .map(|c| c.2)
+        .or_else(|| within.first().and_then(|c| c.1))
+        .or_else(|| current.get(idx).copied());
+
+    let title = format!(
+        "{} on fader {}: {}",
+        app.name,
+        slots::label(entry.start),
+        display::param_label(param, idx)
+    );
+
+    // Value at the end of each column, where the values are numbers
+    let graph = initial.and_then(param_number).map(|first| {
+        let mut value = first;
+        let mut pending = within.iter().peekable();
+        let columns: Vec<f64> = (1..=HISTORY_COLUMNS)
+            .map(|col| {
+                let end = start + last.mul_f64(col as f64 / HISTORY_COLUMNS as f64);
+                while let Some(change) = pending.next_if(|c| c.0 <= end) {
+                    value = param_number(change.2).unwrap_or(value);
+                }
+                value
+            })
+            .collect();
+        let (min, max) = match param {
+            Some(Param::Int { min, max, .. }) => (*min as f64, *max as f64),
+            Some(Param::Float { min, max, .. }) => (*min as f64, *max as f64),
+            _ => columns
+                .iter()
+                .fold((f64::MAX, f64::MIN), |(lo, hi), v| (lo.min(*v), hi.max(*v))),
+        };
+        display::Sparkline { columns, min, max }
+    });
+
+    let points: Vec<display::HistoryPoint> = within
+        .iter()
+        .map(|(time, old, new, command)| display::HistoryPoint {
+            time: *time,
+            old: old.map(|v| display::format_value(&v, param)),
+            new: display::format_value(new, param),
+            command: command.clone(),
+        })
+        .collect();
+    display::print_param_history(&title, last, graph.as_ref(), &points);
+    Ok(())
+}
+
+/// A param value as a number for graphing, if it has one.
+fn param_number(value: Value) -> Option<f64> {
+    Some(match value {
+        Value::Int(v) => v as f64,
+        Value::Float(v) => v as f64,
+        Value::Bool(v) => v as u8 as f64,
+        Value::Enum(v) => v as f64,
+        Value::MidiCc(protocol::MidiCc(v)) => v as f64,
+        Value::MidiChannel(protocol::MidiChannel(v)) => v as f64,
+        Value::MidiNote(protocol::MidiNote(v)) => v as f64,
+        _ => return None,
+    })
+}
+
+async fn param_set_range(range_str: &str, slots: Option<&str>) -> Result<()> {
+    use protocol::Range;
+
+    let range = parse_range(range_str, &[Range::_0_10V, Range::_0_5V, Range::_Neg5_5V])?;
+    let slots = slots.map(groups::parse_slots).transpose()?;
+    let mut dev = FaderpunkDevice::open()?;
+
+    let changed = edit_params(&mut dev, slots.as_deref(), |app, idx, param, value| {
+        let Param::Range { variants, .. } = param else {
+            return false;
+        };
+        if !variants.contains(&range) {
+            eprintln!(
+                "warning: {} param {} doesn't support {}; left as is",
+                app.name,
+                idx,
+                display::range_name(&range)
+            );
+            return false;
+        }
+        if *value == Value::Range(range) {
+            return false;
+        }
+        *value = Value::Range(range);
+        true
+    })
+    .await?;
+
+    println!("Set {} range param(s) to {}", changed, display::range_name(&range));
+    Ok(())
+}
+
+/// What `param randomize` does with one param.
+enum Pick {
+    Keep,
+    Between(f64, f64, randomize::Distribution),
+    OneOf(Vec<Value>),
+}
+
+async fn param_randomize(
+    slots: Option<&str>,
+    constraints: Option<&Path>,
+    seed: Option<u64>,
+) -> Result<()> {
+    let constraints = match constraints {
+        Some(path) => randomize::Constraints::read(path)?,
+        None => randomize::Constraints::default(),
+    };
+    let slots = slots.map(groups::parse_slots).transpose()?;
+    let mut dev = FaderpunkDevice::open()?;
+
+    // Every rule is checked against the apps before anything is sent
+    let app_info = fetch_app_info(&mut dev).await?;
+    for name in constraints.app_names() {
+        if !app_info.iter().any(|a| a.name.eq_ignore_ascii_case(name)) {
+            eprintln!(
+                "warning: no app named '{}' on this device; its rules are unused",
+                name
+            );
+        }
+    }
+    let picks = app_info
+        .iter()
+        .map(|app| {
+            let picks = param_picks(app, constraints.for_app(&app.name))
+                .with_context(|| format!("In the rules for {}", app.name))?;
+            Ok((app.app_id, picks))
+        })
+        .collect::<Result<std::collections::HashMap<u8, Vec<Pick>>>>()?;
+
+    let seed = seed.unwrap_or_else(random::fresh_seed);
+    let mut rng = random::Rng::new(seed);
+    let changed = edit_params(&mut dev, slots.as_deref(), |app, idx, param, value| {
+        let Some(pick) = picks.get(&app.app_id).and_then(|p| p.get(idx)) else {
+            return false;
+        };
+        let new = match (pick, param) {
+            (Pick::Keep, _) => return false,
+            (Pick::Between(lo, hi, distribution), Param::Int { .. }) => {
+                Value::Int(randomize::sample(&mut rng, *lo, *hi, *distribution).round() as i32)
+            }
+            (Pick::Between(lo, hi, distribution), _) => {
+                Value::Float(randomize::sample(&mut rng, *lo, *hi, *distribution) as f32)
+            }
+            (Pick::OneOf(values), _) => values[rng.below(values.len())],
+        };
+        if new == *value {
+            return false;
+        }
+        *value = new;
+        true
+    })
+    .await?;
+
+    println!(
+        "Randomized {} param(s); --seed {} gives the same values again",
+        changed, seed
+    );
+    Ok(())
+}
+
+/// How to pick each of an app's params: the constraint rules that apply to it,
+/// and for the rest, any value the param takes. Output ranges, colors and MIDI
+/// settings are kept unless a rule names them, as a random value there can
+/// upset the gear around the Faderpunk. Rules written for the app must all
+/// match one of its params; "*" rules skip apps without the param.
+fn param_picks(
+    app: &display::AppInfo,
+    rules: Option<(&randomize::AppRules, bool)>,
+) -> Result<Vec<Pick>> {
+    let mut picks: Vec<Pick> = app
+        .params
+        .iter()
+        .map(|param| match param {
+            Param::Int { min, max, .. } => {
+                Pick::Between(*min as f64, *max as f64, randomize::Distribution::Uniform)
+            }
+            Param::Float { min, max, .. } => {
+                Pick::Between(*min as f64, *max as f64, randomize::Distribution::Uniform)
+            }
+            Param::Bool { .. }
+            | Param::Enum { .. }
+            | Param::Curve { .. }
+            | Param::Waveform { .. }
+            | Param::Note { .. } => Pick::OneOf(param_choices(param)),
+            _ => Pick::Keep,
+        })
+        .collect();
+    let Some((rules, own)) = rules else {
+        return Ok(picks);
+    };
+
+    let find = |key: &str| -> Result<Option<usize>> {
+        let found = match key.parse::<usize>() {
+            Ok(idx) => (idx < app.params.len()).then_some(idx),
+            Err(_) => app
+                .params
+                .iter()
+                .position(|p| display::get_param_name(p).eq_ignore_ascii_case(key)),
+        };
+        if found.is_none() && own {
+            anyhow::bail!("{} has no param '{}'", app.name, key);
+        }
+        Ok(found)
+    };
+
+    for (key, rule) in &rules.params {
+        let Some(idx) = find(key)? else {
+            continue;
+        };
+        let param = &app.params[idx];
+        let bounds = match param {
+            Param::Int { min, max, .. } => Some((*min as f64, *max as f64)),
+            Param::Float { min, max, .. } => Some((*min as f64, *max as f64)),
+            _ => None,
+        };
+        picks[idx] = match (&rule.choose, bounds) {
+            (Some(choose), _) => Pick::OneOf(
+                choose
+                    .iter()
+                    .map(|choice| {
+                        let text = randomize::choice_text(choice)?;
+                        parse_value(&text, Some(param), &Value::Bool(false), false)
+                            .with_context(|| format!("{}: can't choose '{}'", key, text))
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+            (None, Some((min, max))) => {
+                let lo = rule.min.map_or(min, |m| m.max(min));
+                let hi = rule.max.map_or(max, |m| m.min(max));
+                if lo > hi {
+                    anyhow::bail!(
+                        "{}: min/max are outside the param's range {}-{}",
+                        key,
+                        min,
+                        max
+                    );
+                }
+                Pick::Between(lo, hi, rule.distribution)
+            }
+            (None, None) if rule.min.is_some() || rule.max.is_some() => {
+                anyhow::bail!("{}: min/max only work on number params; use choose", key)
+            }
+            (None, None) => {
+                let choices = param_choices(param);
+                if choices.is_empty() {
+                    anyhow::bail!("{}: can't pick this param at random; use choose", key);
+                }
+                Pick::OneOf(choices)
+            }
+        };
+    }
+    for key in &rules.freeze {
+        if let Some(idx) = find(key)? {
+            picks[idx] = Pick::Keep;
+        }
+    }
+    Ok(picks)
+}
+
+/// Every value a choice param can take; empty for numbers and MIDI settings.
+fn param_choices(param: &Param) -> Vec<Value> {
+    match param {
+        Param::Bool { .. } => vec![Value::Bool(false), Value::Bool(true)],
+        Param::Enum { variants, .. } => (0..variants.len()).map(Value::Enum).collect(),
+        Param::Curve { variants, .. } => variants.iter().copied().map(Value::Curve).collect(),
+        Param::Waveform { variants, .. } => variants.iter().copied().map(Value::Waveform).collect(),
+        Param::Color { variants, .. } => variants.iter().copied().map(Value::Color).collect(),
+        Param::Range { variants, .. } => variants.iter().copied().map(Value::Range).collect(),
+        Param::Note { variants, .. } => variants.iter().copied().map(Value::Note).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Run `edit` over every param of the apps on `slots` (every app if None),
+/// then send the new values for each app where `edit` reported a change.
+/// Each change is printed; returns how many params changed.
+pub async fn edit_params(
+    dev: &mut FaderpunkDevice,
+    slots: Option<&[u8]>,
+    mut edit: impl FnMut(&display::AppInfo, usize, &Param, &mut Value) -> bool,
+) -> Result<usize> {
+    let app_info = fetch_app_info(dev).await?;
+    let layout = fetch_layout(dev).await?;
+    let entries = layout_entries(&layout);
+
+    let mut targets: Vec<&display::LayoutEntry> = match slots {
+        Some(slots) => slots
+            .iter()
+            .filter_map(|&s| find_entry_at_slot(&entries, s))
+            .collect(),
+        None => entries.iter().collect(),
+    };
+    targets.dedup_by_key(|e| e.layout_id);
+    if targets.is_empty() {
+        anyhow::bail!(t!("No apps on the selected faders"));
+    }
+
+    let layout_ids: Vec<u8> = targets.iter().map(|e| e.layout_id).collect();
+    let current = fetch_app_params(dev, &layout_ids).await?;
+
+    let mut changed = 0;
+    for (entry, (layout_id, read)) in targets.iter().zip(current) {
+        let Some(app) = app_info.iter().find(|a| a.app_id == entry.app_id) else {
+            continue;
+        };
+        let mut values = read.clone();
+        let mut app_changed = false;
+        for (idx, (param, value)) in app.params.iter().zip(values.iter_mut()).enumerate() {
+            let old = *value;
+            if edit(app, idx, param, value) {
+                println!(
+                    "  fader {:>2} {}: {} {} → {}",
+                    slots::label(entry.start),
+                    app.name,
+                    display::param_label(Some(param), idx),
+                    display::format_value(&old, Some(param)),
+                    display::format_value(value, Some(param))
+                );
+                changed += 1;
+                app_changed = true;
+            }
+        }
+        if app_changed {
+            dev.write_params(layout_id, &read, param_values(&values), false).await?;
+        }
+    }
+    Ok(changed)
+}
+
+/// Whether a value has the type a param expects.
+pub fn value_fits(value: &Value, param: &Param) -> bool {
+    matches!(
+        (param, value),
+        (Param::Int { .. }, Value::Int(_))
+            | (Param::Float { .. }, Value::Float(_))
+            | (Param::Bool { .. }, Value::Bool(_))
+            | (Param::Enum { .. }, Value::Enum(_))
+            | (Param::Curve { .. }, Value::Curve(_))
+            | (Param::Waveform { .. }, Value::Waveform(_))
+            | (Param::Color { .. }, Value::Color(_))
+            | (Param::Range { .. }, Value::Range(_))
+            | (Param::Note { .. }, Value::Note(_))
+            | (Param::MidiCc { .. }, Value::MidiCc(_))
+            | (Param::MidiChannel { .. }, Value::MidiChannel(_))
+            | (Param::MidiIn, Value::MidiIn(_))
+            | (Param::MidiMode, Value::MidiMode(_))
+            | (Param::MidiNote { .. }, Value::MidiNote(_))
+            | (Param::MidiOut, Value::MidiOut(_))
+            | (Param::MidiNrpn, Value::MidiNrpn(_))
+            | (Param::VoltPerOct, Value::VoltPerOct(_))
+            | (Param::None, _)
+    )
+}
+
+/// A neutral value for a param: zero (within range), off, or the first variant.
+pub fn default_value(param: &Param) -> Option<Value> {
+    Some(match param {
+        Param::None => return None,
+        Param::Int { min, max, .. } => Value::Int(0.clamp(*min, *max)),
+        Param::Float { min, max, .. } => Value::Float(0.0f32.clamp(*min, *max)),
+        Param::Bool { .. } => Value::Bool(false),
+        Param::Enum { .. } => Value::Enum(0),
+        Param::Curve { variants, .. } => Value::Curve(*variants.first()?),
+        Param::Waveform { variants, .. } => Value::Waveform(*variants.first()?),
+        Param::Color { variants, .. } => Value::Color(*variants.first()?),
+        Param::Range { variants, .. } => Value::Range(*variants.first()?),
+        Param::Note { variants, .. } => Value::Note(*variants.first()?),
+        Param::MidiCc { .. } => Value::MidiCc(protocol::MidiCc(0)),
+        Param::MidiChannel { .. } => Value::MidiChannel(protocol::MidiChannel(1)),
+        Param::MidiIn => Value::MidiIn(protocol::MidiIn([true, true])),
+        Param::MidiMode => Value::MidiMode(protocol::MidiMode::Note),
+        Param::MidiNote { .. } => Value::MidiNote(protocol::MidiNote(60)),
+        Param::MidiOut => Value::MidiOut(protocol::MidiOut([true, false, false])),
+        Param::MidiNrpn => Value::MidiNrpn(false),
+        Param::VoltPerOct => Value::VoltPerOct(protocol::VoltPerOct::Standard),
+    })
+}
+
+/// Build the fixed-size SetAppParams value array from a param list.
+pub fn param_values(values: &[Value]) -> [Option<Value>; APP_MAX_PARAMS] {
+    let mut out = [None; APP_MAX_PARAMS];
+    for (slot, v) in out.iter_mut().zip(values) {
+        *slot = Some(*v);
+    }
+    out
+}
+
+/// SetAppParams values that change param `index` to `value`. The firmware
+/// replaces every param at once, so this is all of `current` with the change.
+pub fn param_change(current: &[Value], index: usize, value: Value) -> [Option<Value>; APP_MAX_PARAMS] {
+    let mut values = param_values(current);
+    values[index] = Some(value);
+    values
+}
+
+/// Resolve a param reference — an index or a case-insensitive name fragment —
+/// to its index within an app with `count` params on fader `slot`.
+pub fn resolve_param(param_ref: &str, app: &display::AppInfo, count: usize, slot: u8) -> Result<usize> {
+    if let Ok(idx) = param_ref.parse::<usize>() {
+        if idx >= count {
+            invalid!(
+                "Param index {} out of range (app has {} params)",
+                idx,
+                count
+            );
+        }
+        return Ok(idx);
+    }
+
+    // Search by name (case-insensitive)
+    let lower = param_ref.to_lowercase();
+    let found: Vec<(usize, &Param)> = app
+        .params
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| {
+            let name = display::get_param_name(p);
+            !name.is_empty() && name.to_lowercase().contains(&lower)
+        })
+        .collect();
+
+    match found.len() {
+        0 => invalid!(
+            "No param matching '{}'. Use 'param show {}' to see available.",
+            param_ref,
+            slots::label(slot as usize - 1)
+        ),
+        1 => Ok(found[0].0),
+        _ => {
+            let names: Vec<_> = found
+                .iter()
+                .map(|(i, p)| format!("{} [{}]", display::get_param_name(p), i))
+                .collect();
+            invalid!(
+                "Ambiguous param '{}'. Matches: {}. Use the index instead.",
+                param_ref,
+                names.join(", ")
+            );
+        }
+    }
+}
+
+/// Parse a string value into the appropriate Value type based on param metadata.
+/// With `clamp`, out-of-range numbers are pulled to the nearest bound (with a
+/// warning) instead of failing.
+pub fn parse_value(s: &str, param: Option<&Param>, current: &Value, clamp: bool) -> Result<Value> {
+    // Use param metadata if available, otherwise infer from current value type
+    match param {
+        Some(Param::Int { min, max, .. }) => {
+            let v: i32 = s.parse().map_err(|_| error::invalid("Expected integer"))?;
+            Ok(Value::Int(check_range(v, *min, *max, clamp, "Value")?))
+        }
+        Some(Param::Float { min, max, .. }) => {
+            let v = parse_float(s)?;
+            Ok(Value::Float(check_range(v, *min, *max, clamp, "Value")?))
+        }
+        Some(Param::Bool { .. }) => {
+            let v = match s.to_lowercase().as_str() {
+                "true" | "on" | "1" | "yes" => true,
+                "false" | "off" | "0" | "no" => false,
+                _ => invalid!(t!("Expected bool (true/false, on/off, 1/0)")),
+            };
+            Ok(Value::Bool(v))
+        }
+        Some(Param::Enum { variants, .. }) => {
+            // Try by index first
+            if let Ok(idx) = s.parse::<usize>() {
+                if idx >= variants.len() {
+                    invalid!("Index {} out of range (0-{})", idx, variants.len() - 1);
+                }
+                return Ok(Value::Enum(idx));
+            }
+            // Try by name
+            let lower = s.to_lowercase();
+            let found: Vec<(usize, _)> = variants
+                .iter()
+                .enumerate()
+                .filter(|(_, v)| v.to_lowercase().contains(&lower))
+                .collect();
+            match found.len() {
+                0 => invalid!("No variant matching '{}'. Options: {}", s, variants.join(", ")),
+                1 => Ok(Value::Enum(found[0].0)),
+                _ => {
+                    let names: Vec<_> = found.iter().map(|(i, v)| format!("{} [{}]", v, i)).collect();
+                    invalid!("Ambiguous '{}'. Matches: {}", s, names.join(", "));
+                }
+            }
+        }
+        Some(Param::Curve { variants, .. }) => {
+            let lower = s.to_lowercase();
+            for v in variants {
+                if format!("{:?}", v).to_lowercase() == lower {
+                    return Ok(Value::Curve(*v));
+                }
+            }
+            let options: Vec<_> = variants.iter().map(|v| format!("{:?}", v)).collect();
+            invalid!("Unknown curve '{}'. Options: {}", s, options.join(", "))
+        }
+        Some(Param::Waveform { variants, .. }) => {
+            let lower = s.to_lowercase();
+            for v in variants {
+                if format!("{:?}", v).to_lowercase() == lower {
+                    return Ok(Value::Waveform(*v));
+                }
+            }
+            let options: Vec<_> = variants.iter().map(|v| format!("{:?}", v)).collect();
+            invalid!("Unknown waveform '{}'. Options: {}", s, options.join(", "))
+        }
+        Some(Param::Range { variants, .. }) => {
+            let v = parse_range(s, variants)?;
+            Ok(Value::Range(v))
+        }
+        Some(Param::MidiCc { .. }) => {
+            let v: i64 = s.parse().map_err(|_| error::invalid("Expected 0-127"))?;
+            let v = check_range(v, 0, 127, clamp, "CC")?;
+            Ok(Value::MidiCc(protocol::MidiCc(v as u16)))
+        }
+        Some(Param::MidiChannel { .. }) => {
+            let v: i64 = s.parse().map_err(|_| error::invalid("Expected 1-16"))?;
+            let v = check_range(v, 1, 16, clamp, "Channel")?;
+            Ok(Value::MidiChannel(protocol::MidiChannel(v as u8)))
+        }
+        Some(Param::MidiNote { .. }) => {
+            let v: i64 = s.parse().map_err(|_| error::invalid("Expected 0-127"))?;
+            let v = check_range(v, 0, 127, clamp, "Note")?;
+            Ok(Value::MidiNote(protocol::MidiNote(v as u8)))
+        }
+        Some(Param::MidiMode) => {
+            let v = match s.to_lowercase().as_str() {
+                "note" => protocol::MidiMode::Note,
+                "cc" => protocol::MidiMode::Cc,
+                _ => invalid!("Expected 'note' or 'cc'"),
+            };
+            Ok(Value::MidiMode(v))
+        }
+        Some(Param::MidiIn) => {
+            let (usb, din) = parse_midi_ports_in(s)?;
+            Ok(Value::MidiIn(protocol::MidiIn([usb, din])))
+        }
+        Some(Param::MidiOut) => {
+            let (usb, out1, out2) = parse_midi_ports_out(s)?;
+            Ok(Value::MidiOut(protocol::MidiOut([usb, out1, out2])))
+        }
+        Some(Param::MidiNrpn) => {
+            let v = match s.to_lowercase().as_str() {
+                "true" | "on" | "1" | "yes" => true,
+                "false" | "off" | "0" | "no" => false,
+                _ => invalid!(t!("Expected bool (true/false, on/off, 1/0)")),
+            };
+            Ok(Value::MidiNrpn(v))
+        }
+        Some(Param::VoltPerOct) => {
+            let v = match s.to_lowercase().as_str() {
+                "standard" | "std" | "1v" | "1v/oct" => protocol::VoltPerOct::Standard,
+                "buchla" | "1.2v" | "1.2v/oct" => protocol::VoltPerOct::Buchla,
+                _ => invalid!("Expected 'standard' or 'buchla'"),
+            };
+            Ok(Value::VoltPerOct(v))
+        }
+        Some(Param::Color { variants, .. }) => Ok(Value::Color(parse_color(s, variants)?)),
+        Some(Param::Note { variants, .. }) => {
+            let lower = s.to_lowercase();
+            for v in variants {
+                if format!("{:?}", v).to_lowercase() == lower {
+                    return Ok(Value::Note(*v));
+                }
+            }
+            let options: Vec<_> = variants.iter().map(|v| format!("{:?}", v)).collect();
+            invalid!("Unknown note '{}'. Options: {}", s, options.join(", "))
+        }
+        Some(Param::None) | None => {
+            // Infer from current value type
+            let number = |expected: &str| error::invalid(format!("Expected {}", expected));
+            match current {
+                Value::Int(_) => Ok(Value::Int(s.parse().map_err(|_| number("integer"))?)),
+                Value::Float(_) => Ok(Value::Float(parse_float(s)?)),
+                Value::Bool(_) => {
+                    let v = matches!(s.to_lowercase().as_str(), "true" | "on" | "1" | "yes");
+                    Ok(Value::Bool(v))
+                }
+                Value::Enum(_) => Ok(Value::Enum(s.parse().map_err(|_| number("an index"))?)),
+                Value::MidiCc(_) => {
+                    let cc = s.parse().map_err(|_| number("0-127"))?;
+                    Ok(Value::MidiCc(protocol::MidiCc(cc)))
+                }
+                Value::MidiChannel(_) => {
+                    let channel = s.parse().map_err(|_| number("1-16"))?;
+                    Ok(Value::MidiChannel(protocol::MidiChannel(channel)))
+                }
+                _ => invalid!("Can't infer type for this parameter. Specify by index."),
+            }
+        }
+    }
+}
+
+/// Check a number against its range, or clamp it into range with a warning.
+fn check_range<T: PartialOrd + std::fmt::Display + Copy>(
+    v: T,
+    min: T,
+    max: T,
+    clamp: bool,
+    what: &str,
+) -> Result<T> {
+    if v >= min && v <= max {
+        return Ok(v);
+    }
+    if !clamp {
+        invalid!("{} {} out of range ({}-{})", what, v, min, max);
+    }
+    let clamped = if v < min { min } else { max };
+    eprintln!(
+        "warning: {} {} out of range ({}-{}), clamped to {}",
+        what, v, min, max, clamped
+    );
+    Ok(clamped)
+}
+
+/// Parse a decimal number, accepting either `.` or `,` as the decimal separator
+/// so values pasted from comma-decimal locales work. A comma before exactly
+/// three digits could be grouping, so that's refused rather than guessed.
+pub fn parse_float(s: &str) -> Result<f32> {
+    let trimmed = s.trim();
+    if trimmed.matches(['.', ',']).count() > 1 {
+        invalid!("Expected a number, got '{}' (use a single decimal separator)", s);
+    }
+    // "1,000" is a thousand in some locales and one in others
+    if let Some((_, fraction)) = trimmed.split_once(',')
+        && fraction.len() == 3
+        && fraction.chars().all(|c| c.is_ascii_digit())
+    {
+        invalid!("'{}' is ambiguous; use '.' as the decimal separator and no grouping", s);
+    }
+    let Ok(value) = trimmed.replace(',', ".").parse() else {
+        invalid!(t!("Expected a number, got '{}'", s));
+    };
+    Ok(value)
+}
+
+/// Parse a color by name (from the param's variants), as hex (`#ff00aa`), or as `r,g,b`.
+fn parse_color(s: &str, variants: &[protocol::Color]) -> Result<protocol::Color> {
+    let lower = s.to_lowercase();
+    for v in variants {
+        if format!("{:?}", v).to_lowercase() == lower {
+            return Ok(*v);
+        }
+    }
+
+    let hex = lower.strip_prefix('#').unwrap_or(&lower);
+    if hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap_or(0);
+        return Ok(protocol::Color::Custom(channel(0), channel(2), channel(4)));
+    }
+
+    let parts: Vec<&str> = lower.split(',').map(str::trim).collect();
+    if parts.len() == 3 {
+        let rgb: Vec<u8> = parts.iter().filter_map(|p| p.parse().ok()).collect();
+        if rgb.len() != 3 {
+            invalid!("RGB components must be 0-255, got '{}'", s);
+        }
+        return Ok(protocol::Color::Custom(rgb[0], rgb[1], rgb[2]));
+    }
+
+    let options: Vec<_> = variants.iter().map(|v| format!("{:?}", v)).collect();
+    invalid!(
+        "Unknown color '{}'. Options: {}, or a custom color as #rrggbb or r,g,b",
+        s,
+        options.join(", ")
+    )
+}
+
+fn parse_range(s: &str, variants: &[protocol::Range]) -> Result<protocol::Range> {
+    let lower = s.to_lowercase().replace(' ', "");
+    for v in variants {
+        let label = match v {
+            protocol::Range::_0_10V => "0-10v",
+            protocol::Range::_0_5V => "0-5v",
+            protocol::Range::_Neg5_5V => "-5-5v",
+        };
+        if lower == label || lower == format!("{:?}", v).to_lowercase() {
+            return Ok(*v);
+        }
+    }
+    // Also accept common aliases
+    match lower.as_str() {
+        "10v" | "0-10" | "0-10v" => Ok(protocol::Range::_0_10V),
+        "5v" | "0-5" | "0-5v" => Ok(protocol::Range::_0_5V),
+        "bipolar" | "+-5v" | "+/-5v" | "-5-5v" | "-5v-5v" => Ok(protocol::Range::_Neg5_5V),
+        _ => {
+            let options: Vec<_> = variants.iter().map(|v| format!("{:?}", v)).collect();
+            invalid!("Unknown range '{}'. Options: {}", s, options.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::config::{parse_aux_mode, parse_key};
+    use crate::exitcode;
+
+    #[test]
+    fn bad_values_exit_invalid() {
+        let int = Param::Int {
+            name: "Level".to_string(),
+            min: 0,
+            max: 10,
+        };
+        let errors = [
+            parse_color("300,0,0", &[]).unwrap_err(),
+            parse_value("x", Some(&int), &Value::Int(0), false).unwrap_err(),
+            parse_value("x", None, &Value::Int(0), false).unwrap_err(),
+            parse_float("1,000").unwrap_err(),
+            parse_float("fast").unwrap_err(),
+            parse_aux_mode("div5").unwrap_err(),
+            parse_key("klingon").unwrap_err(),
+        ];
+        for err in &errors {
+            assert_eq!(exitcode::of(err), exitcode::INVALID, "{}", err);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+// `session` commands: replaying a recorded session.
+
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::error;
+use crate::protocol::ConfigMsgIn;
+use crate::session;
+use crate::usb::FaderpunkDevice;
+use crate::SessionAction;
+
+pub async fn cmd_session(action: SessionAction) -> Result<()> {
+    match action {
+        SessionAction::Replay {
+            file,
+            speed,
+            dry_run,
+        } => session_replay(Path::new(&file), speed, dry_run).await,
+    }
+}
+
+/// Re-send a recorded session's messages, each at its recorded time divided by
+/// `speed`. Commands are only printed, as their messages follow them.
+async fn session_replay(file: &Path, speed: f64, dry_run: bool) -> Result<()> {
+    let events = session::read(file)?;
+    if !events.iter().any(|e| e.message.is_some()) {
+        println!("{} has no changes to replay", file.display());
+        return Ok(());
+    }
+    if dry_run {
+        events.iter().for_each(print_session_event);
+        return Ok(());
+    }
+
+    let mut dev = FaderpunkDevice::open()?;
+    let start = tokio::time::Instant::now();
+    for event in &events {
+        tokio::select! {
+            _ = tokio::time::sleep_until(start + event.at().div_f64(speed)) => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+        print_session_event(event);
+        let Some(msg) = &event.message else {
+            continue;
+        };
+        // The same messages with and without a reply as when they were recorded
+        match msg {
+            ConfigMsgIn::SetGlobalConfig(_) | ConfigMsgIn::FactoryReset => dev.send(msg).await?,
+            _ => {
+                dev.send_receive(msg).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// One line per event, timed from the start of the recording: the command as
+/// typed, or the message sent and what it targets.
+fn print_session_event(event: &session::Event) {
+    let at = event.at().as_secs_f64();
+    let when = format!("{}:{:04.1}", (at / 60.0) as u64, at % 60.0);
+    if let Some(command) = &event.command {
+        println!("[{}] $ {}", when, command);
+    }
+    if let Some(msg) = &event.message {
+        let target = match msg {
+            ConfigMsgIn::SetAppParams { layout_id, values } => {
+                let count = values.iter().flatten().count();
+                format!(" (layout ID {}, {} param(s))", layout_id, count)
+            }
+            _ => String::new(),
+        };
+        println!("[{}]   {}{}", when, error::message_name(msg), target);
+    }
+}
@@ -0,0 +1,253 @@
+// `save` and `load`: device state to and from snapshot files, including
+// crossfading into one.
+
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use crate::commands::param::param_values;
+use crate::groups;
+use crate::i18n::t;
+use crate::notes;
+use crate::protocol::{ConfigMsgIn, ConfigMsgOut, GLOBAL_CHANNELS, Value};
+use crate::snapshot::{self, Snapshot};
+use crate::usb::FaderpunkDevice;
+use crate::{
+    apply_differences, fetch_app_info, fetch_global_config, fetch_layout, layout_entries,
+    send_layout,
+};
+
+pub async fn cmd_save(path: &str, delta: Option<&str>) -> Result<()> {
+    let mut dev = FaderpunkDevice::open()?;
+    let Some(base_path) = delta else {
+        save_snapshot(&mut dev, Path::new(path)).await?;
+        println!("Config saved to {}", path);
+        return Ok(());
+    };
+
+    let base = Snapshot::read(Path::new(base_path), &[])?;
+    let patch = fetch_snapshot(&mut dev).await?.delta_from(&base)?;
+    std::fs::write(path, serde_json::to_string_pretty(&patch)?)
+        .with_context(|| format!("Failed to write {}", path))?;
+    if patch.as_object().is_some_and(|m| m.is_empty()) {
+        println!("No differences from {}; wrote an empty overlay to {}", base_path, path);
+    } else {
+        println!("Changes since {} saved to {}", base_path, path);
+    }
+    Ok(())
+}
+
+pub async fn cmd_load(
+    path: &str,
+    overlays: &[String],
+    vars: &[(String, String)],
+    fade: Option<Duration>,
+) -> Result<()> {
+    let overlays: Vec<&Path> = overlays.iter().map(Path::new).collect();
+    let snapshot = match snapshot::SnapshotFile::read_layered(Path::new(path), &overlays, vars)? {
+        snapshot::SnapshotFile::Device(snapshot) => *snapshot,
+        snapshot::SnapshotFile::Surface(surface) => {
+            return load_surface(path, &surface, fade).await;
+        }
+    };
+    let mut dev = FaderpunkDevice::open()?;
+    match fade {
+        Some(duration) => fade_to_snapshot(&mut dev, &snapshot, duration).await?,
+        None => apply_snapshot(&mut dev, &snapshot).await?,
+    }
+    restore_groups(&snapshot)?;
+    if overlays.is_empty() {
+        println!("Config loaded from {}", path);
+    } else {
+        println!("Config loaded from {} with {} overlay(s)", path, overlays.len());
+    }
+    Ok(())
+}
+
+/// Load each unit's part of a surface file onto it.
+async fn load_surface(
+    path: &str,
+    surface: &snapshot::Surface,
+    fade: Option<Duration>,
+) -> Result<()> {
+    let parts = surface
+        .split()
+        .with_context(|| format!("Invalid surface {}", path))?;
+    // Every unit is opened before any is changed, so a missing one doesn't
+    // leave the surface half loaded
+    let mut devs = parts
+        .iter()
+        .map(|(serial, _)| FaderpunkDevice::open_serial(serial))
+        .collect::<Result<Vec<_>>>()?;
+    for (i, ((serial, snapshot), dev)) in parts.iter().zip(&mut devs).enumerate() {
+        match fade {
+            Some(duration) => fade_to_snapshot(dev, snapshot, duration).await?,
+            None => apply_snapshot(dev, snapshot).await?,
+        }
+        println!(
+            "Faders {}-{} loaded to {}",
+            i * GLOBAL_CHANNELS + 1,
+            (i + 1) * GLOBAL_CHANNELS,
+            serial
+        );
+    }
+    println!("Config loaded from {} across {} unit(s)", path, parts.len());
+    Ok(())
+}
+
+/// Time between the intermediate writes of a fade.
+const FADE_STEP: Duration = Duration::from_millis(50);
+
+/// Move the device to a snapshot gradually: Int and Float params and the
+/// internal BPM glide from their current values over `duration`, then
+/// everything else (enums, toggles, the rest of the config) switches at the end.
+async fn fade_to_snapshot(dev: &mut FaderpunkDevice, target: &Snapshot, duration: Duration) -> Result<()> {
+    let mut current = fetch_snapshot(dev).await?;
+    if current.layout.0 != target.layout.0 {
+        anyhow::bail!(
+            "--fade needs the snapshot's layout to match the device's; load it once without --fade first"
+        );
+    }
+
+    // (layout_id, from, to) for every app with a numeric param to glide
+    let glides: Vec<(u8, Vec<Value>, &[Value])> = current
+        .params
+        .iter()
+        .filter_map(|p| {
+            let to = target.params_for(p.layout_id)?;
+            let glides = p.values.iter().zip(to).any(|pair| match pair {
+                (Value::Int(a), Value::Int(b)) => a != b,
+                (Value::Float(a), Value::Float(b)) => a != b,
+                _ => false,
+            });
+            glides.then(|| (p.layout_id, p.values.clone(), to))
+        })
+        .collect();
+    // What each gliding app has on the device, as of the last step
+    let mut written: Vec<Vec<Value>> = glides.iter().map(|(_, from, _)| from.clone()).collect();
+    let from_bpm = current.global_config.clock.internal_bpm;
+    let to_bpm = target.global_config.clock.internal_bpm;
+
+    println!("Fading over {}...", humantime::format_duration(duration));
+    dev.set_audit(false);
+    let start = std::time::Instant::now();
+    loop {
+        let t = start.elapsed().as_secs_f32() / duration.as_secs_f32().max(f32::EPSILON);
+        if t >= 1.0 {
+            break;
+        }
+        if from_bpm != to_bpm {
+            let mut config = current.global_config.clone();
+            config.clock.internal_bpm = from_bpm + (to_bpm - from_bpm) * t;
+            dev.send(&ConfigMsgIn::SetGlobalConfig(config)).await?;
+        }
+        for ((layout_id, from, to), read) in glides.iter().zip(&mut written) {
+            let values: Vec<Value> = from
+                .iter()
+                .zip(to.iter())
+                .map(|pair| match pair {
+                    (Value::Int(a), Value::Int(b)) => {
+                        Value::Int(*a + ((*b - *a) as f32 * t).round() as i32)
+                    }
+                    (Value::Float(a), Value::Float(b)) => Value::Float(a + (b - a) * t),
+                    (from, _) => *from,
+                })
+                .collect();
+            *read = dev.write_params(*layout_id, read, param_values(&values), false).await?;
+        }
+        tokio::time::sleep(FADE_STEP).await;
+    }
+    dev.set_audit(true);
+    for ((layout_id, ..), values) in glides.iter().zip(written) {
+        if let Some(p) = current.params.iter_mut().find(|p| p.layout_id == *layout_id) {
+            p.values = values;
+        }
+    }
+
+    // Land exactly on the target, including everything that doesn't glide
+    apply_differences(dev, &current, target, &current.diff(target)?).await
+}
+
+/// Read the complete device state (config, layout, params) into a snapshot.
+pub async fn fetch_snapshot(dev: &mut FaderpunkDevice) -> Result<Snapshot> {
+    let global_config = fetch_global_config(dev).await?;
+    let app_info = fetch_app_info(dev).await?;
+    let layout = fetch_layout(dev).await?;
+
+    let mut params = Vec::new();
+    for resp in dev.send_receive_batch(&ConfigMsgIn::GetAllAppParams).await? {
+        if let ConfigMsgOut::AppState(layout_id, values) = resp {
+            params.push(snapshot::AppParams { layout_id, values });
+        }
+    }
+
+    let mut apps: Vec<snapshot::AppRef> = Vec::new();
+    for entry in layout_entries(&layout) {
+        if apps.iter().any(|a| a.app_id == entry.app_id) {
+            continue;
+        }
+        if let Some(info) = app_info.iter().find(|a| a.app_id == entry.app_id) {
+            apps.push(snapshot::AppRef {
+                app_id: info.app_id,
+                name: info.name.clone(),
+                channels: info.channels,
+            });
+        }
+    }
+
+    Ok(Snapshot {
+        version: snapshot::CURRENT_VERSION,
+        global_config,
+        layout,
+        params,
+        metadata: snapshot::Metadata {
+            groups: groups::load()?,
+            notes: notes::load()?,
+            ..snapshot::Metadata::now(apps)
+        },
+    })
+}
+
+/// Write the complete device state to a snapshot file.
+pub async fn save_snapshot(dev: &mut FaderpunkDevice, path: &Path) -> Result<()> {
+    fetch_snapshot(dev).await?.write(path)
+}
+
+/// Apply a snapshot to the device: global config, then layout, then params.
+pub async fn apply_snapshot(dev: &mut FaderpunkDevice, snapshot: &Snapshot) -> Result<()> {
+    dev.send(&ConfigMsgIn::SetGlobalConfig(snapshot.global_config.clone()))
+        .await?;
+    println!("{}", t!("Global config applied."));
+
+    let app_info = fetch_app_info(dev).await?;
+    let validated = send_layout(dev, snapshot.layout.clone(), &app_info).await?;
+    println!("{}", t!("Layout applied."));
+
+    let mut applied = 0;
+    for entry in layout_entries(&validated) {
+        if let Some(values) = snapshot.params_for(entry.layout_id) {
+            // A load replaces whatever the app has
+            dev.write_params(entry.layout_id, &[], param_values(values), true)
+                .await?;
+            applied += 1;
+        }
+    }
+    if applied > 0 {
+        println!("{}", t!("Params applied to {} app(s).", applied));
+    }
+
+    Ok(())
+}
+
+/// Make a snapshot's fader groups and notes the current ones. Snapshots
+/// without any leave the current ones alone.
+pub fn restore_groups(snapshot: &Snapshot) -> Result<()> {
+    if !snapshot.metadata.groups.is_empty() {
+        groups::save(&snapshot.metadata.groups)?;
+    }
+    if !snapshot.metadata.notes.is_empty() {
+        notes::save(&snapshot.metadata.notes)?;
+    }
+    Ok(())
+}
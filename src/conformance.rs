@@ -0,0 +1,508 @@
+// Protocol conformance suite (conformance run): a fixed sequence of writes
+// and read-backs that firmware should get right, with a pass/fail line per
+// check. Every global config field is set to each of its values, LED
+// brightness goes to the edges of its range and past them, layouts go from
+// empty to every fader used, and each app's params are set to their bounds.
+//
+// The suite leaves the device in whatever state the last check put it in;
+// the caller saves the state first and puts it back afterwards.
+
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::audit;
+use crate::display;
+use crate::error;
+use crate::layout::AppInfo;
+use crate::limits::Limits;
+use crate::protocol::{
+    APP_MAX_PARAMS, AuxJackMode, ClockDivision, ClockSrc, ConfigMsgIn, ConfigMsgOut,
+    GLOBAL_CHANNELS, GlobalConfig, I2cMode, Key, Layout, MidiCc, MidiChannel, MidiIn, MidiNote,
+    MidiOutMode, Note, Param, ResetSrc, TakeoverMode, Value,
+};
+use crate::usb::FaderpunkDevice;
+
+const PING_TIMEOUT: Duration = Duration::from_secs(1);
+
+const DIVISIONS: [ClockDivision; 10] = [
+    ClockDivision::_1,
+    ClockDivision::_2,
+    ClockDivision::_4,
+    ClockDivision::_6,
+    ClockDivision::_8,
+    ClockDivision::_12,
+    ClockDivision::_24,
+    ClockDivision::_96,
+    ClockDivision::_192,
+    ClockDivision::_384,
+];
+
+const KEYS: [Key; 17] = [
+    Key::Chromatic,
+    Key::Ionian,
+    Key::Dorian,
+    Key::Phrygian,
+    Key::Lydian,
+    Key::Mixolydian,
+    Key::Aeolian,
+    Key::Locrian,
+    Key::BluesMaj,
+    Key::BluesMin,
+    Key::PentatonicMaj,
+    Key::PentatonicMin,
+    Key::Folk,
+    Key::Japanese,
+    Key::Gamelan,
+    Key::HungarianMin,
+    Key::Off,
+];
+
+const NOTES: [Note; 12] = [
+    Note::C,
+    Note::CSharp,
+    Note::D,
+    Note::DSharp,
+    Note::E,
+    Note::F,
+    Note::FSharp,
+    Note::G,
+    Note::GSharp,
+    Note::A,
+    Note::ASharp,
+    Note::B,
+];
+
+/// The result of one check.
+pub struct Outcome {
+    pub group: &'static str,
+    pub name: String,
+    /// Why the check failed, or None if it passed.
+    pub failure: Option<String>,
+}
+
+impl Outcome {
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+struct Suite<'a> {
+    dev: &'a mut FaderpunkDevice,
+    outcomes: Vec<Outcome>,
+}
+
+impl Suite<'_> {
+    /// Record a check. A request that fails outright (timeout, wrong reply)
+    /// fails the check rather than ending the run.
+    fn record(&mut self, group: &'static str, name: String, result: Result<Option<String>>) {
+        let failure = match result {
+            Ok(failure) => failure,
+            Err(e) => Some(e.to_string()),
+        };
+        self.outcomes.push(Outcome {
+            group,
+            name,
+            failure,
+        });
+    }
+}
+
+/// Run every check, in order. Only failing to read the device's starting
+/// config ends the run early.
+pub async fn run(
+    dev: &mut FaderpunkDevice,
+    apps: &[AppInfo],
+    limits: &Limits,
+) -> Result<Vec<Outcome>> {
+    let start = get_config(dev).await?;
+    let mut suite = Suite {
+        dev,
+        outcomes: Vec::new(),
+    };
+
+    let ping = suite.dev.ping(PING_TIMEOUT).await.map(|_| None);
+    suite.record("Connection", "ping".to_string(), ping);
+
+    for (name, config) in config_cases(&start, limits) {
+        let result = round_trip(suite.dev, &config).await;
+        suite.record("Global config", name, result);
+    }
+    for (name, config, in_range) in clamp_cases(&start, limits) {
+        let result = clamped(suite.dev, config, limits, in_range).await;
+        suite.record("Out-of-range values", name, result);
+    }
+    // Later checks run from the config the device started with
+    let restored = round_trip(suite.dev, &start).await;
+    suite.record(
+        "Global config",
+        "back to the starting config".to_string(),
+        restored,
+    );
+
+    for (name, layout) in layout_cases(apps) {
+        let result = layout_round_trip(suite.dev, &layout, apps).await;
+        suite.record("Layout", name, result);
+    }
+
+    for app in apps {
+        let mut layout = Layout([None; GLOBAL_CHANNELS]);
+        layout.0[0] = Some((app.app_id, app.channels, 0));
+        let placed = layout_round_trip(suite.dev, &layout, apps).await;
+        let placed_ok = matches!(placed, Ok(None));
+        suite.record("Params", format!("{}: placed on its own", app.name), placed);
+        if !placed_ok {
+            continue;
+        }
+        for (index, param) in app.params.iter().enumerate() {
+            for value in bounds(param) {
+                let name = format!(
+                    "{}: {} = {}",
+                    app.name,
+                    display::param_label(Some(param), index),
+                    display::format_value(&value, Some(param))
+                );
+                let result = param_round_trip(suite.dev, 0, index, value).await;
+                suite.record("Params", name, result);
+            }
+        }
+    }
+
+    Ok(suite.outcomes)
+}
+
+// ── Global config ──
+
+async fn get_config(dev: &mut FaderpunkDevice) -> Result<GlobalConfig> {
+    match dev.send_receive(&ConfigMsgIn::GetGlobalConfig).await? {
+        ConfigMsgOut::GlobalConfig(config) => Ok(config),
+        other => Err(error::unexpected(&ConfigMsgIn::GetGlobalConfig, &other)),
+    }
+}
+
+async fn set_config(dev: &mut FaderpunkDevice, config: GlobalConfig) -> Result<GlobalConfig> {
+    dev.send(&ConfigMsgIn::SetGlobalConfig(config)).await?;
+    get_config(dev).await
+}
+
+/// Every config field through each of its values, one change at a time from
+/// the starting config, named the way the audit log names fields.
+fn config_cases(start: &GlobalConfig, limits: &Limits) -> Vec<(String, GlobalConfig)> {
+    let mut cases = Vec::new();
+    let mut case = |name: String, change: &dyn Fn(&mut GlobalConfig)| {
+        let mut config = start.clone();
+        change(&mut config);
+        cases.push((name, config));
+    };
+
+    // No range is known for these, so common values stand in for the edges
+    for bpm in [60.0, 120.0, 180.0] {
+        case(format!("clock.internal_bpm = {}", bpm), &|c| {
+            c.clock.internal_bpm = bpm
+        });
+    }
+    for ppqn in [1, 24] {
+        case(format!("clock.ext_ppqn = {}", ppqn), &|c| {
+            c.clock.ext_ppqn = ppqn
+        });
+    }
+    for src in [
+        ClockSrc::None,
+        ClockSrc::Atom,
+        ClockSrc::Meteor,
+        ClockSrc::Cube,
+        ClockSrc::Internal,
+        ClockSrc::MidiIn,
+        ClockSrc::MidiUsb,
+    ] {
+        case(format!("clock.clock_src = {:?}", src), &|c| {
+            c.clock.clock_src = src
+        });
+    }
+    for src in [
+        ResetSrc::None,
+        ResetSrc::Atom,
+        ResetSrc::Meteor,
+        ResetSrc::Cube,
+    ] {
+        case(format!("clock.reset_src = {:?}", src), &|c| {
+            c.clock.reset_src = src
+        });
+    }
+    case("clock.swing_amount = 0".to_string(), &|c| {
+        c.clock.swing_amount = 0
+    });
+
+    for jack in 0..3 {
+        let modes = [AuxJackMode::None, AuxJackMode::ResetOut]
+            .into_iter()
+            .chain(DIVISIONS.map(AuxJackMode::ClockOut));
+        for mode in modes {
+            case(format!("aux.{} = {:?}", jack, mode), &|c| {
+                c.aux[jack] = mode
+            });
+        }
+    }
+
+    // Calibration mode takes over the outputs, so it's left out
+    for mode in [I2cMode::Leader, I2cMode::Follower] {
+        case(format!("i2c_mode = {:?}", mode), &|c| c.i2c_mode = mode);
+    }
+
+    for brightness in [limits.brightness_min, limits.brightness_max] {
+        case(format!("led_brightness = {}", brightness), &|c| {
+            c.led_brightness = brightness
+        });
+    }
+
+    for out in 0..3 {
+        for on in [false, true] {
+            case(format!("midi.outs.{}.send_clock = {}", out, on), &|c| {
+                c.midi.outs[out].send_clock = on
+            });
+            case(format!("midi.outs.{}.send_transport = {}", out, on), &|c| {
+                c.midi.outs[out].send_transport = on
+            });
+        }
+        let modes = [
+            MidiOutMode::None,
+            MidiOutMode::Local,
+            MidiOutMode::MidiThru {
+                sources: MidiIn([false, true]),
+            },
+            MidiOutMode::MidiMerge {
+                sources: MidiIn([true, true]),
+            },
+        ];
+        for mode in modes {
+            case(format!("midi.outs.{}.mode = {:?}", out, mode), &|c| {
+                c.midi.outs[out].mode = mode
+            });
+        }
+    }
+
+    for key in KEYS {
+        case(format!("quantizer.key = {:?}", key), &|c| {
+            c.quantizer.key = key
+        });
+    }
+    for tonic in NOTES {
+        case(format!("quantizer.tonic = {:?}", tonic), &|c| {
+            c.quantizer.tonic = tonic
+        });
+    }
+
+    for mode in [
+        TakeoverMode::Pickup,
+        TakeoverMode::Jump,
+        TakeoverMode::Scale,
+    ] {
+        case(format!("takeover_mode = {:?}", mode), &|c| {
+            c.takeover_mode = mode
+        });
+    }
+
+    cases
+}
+
+/// Write a config and read it back: passes if every field comes back as sent.
+async fn round_trip(dev: &mut FaderpunkDevice, config: &GlobalConfig) -> Result<Option<String>> {
+    let kept = set_config(dev, config.clone()).await?;
+    let changes = audit::diff(
+        &serde_json::to_value(config)?,
+        &serde_json::to_value(&kept)?,
+    );
+    if changes.is_empty() {
+        return Ok(None);
+    }
+    let changes: Vec<String> = changes
+        .iter()
+        .map(|c| format!("{}: sent {}, got {}", c.field, c.old, c.new))
+        .collect();
+    Ok(Some(changes.join("; ")))
+}
+
+/// A config with a value just past the edge of its range, and a check that
+/// what the device kept is back in range.
+type ClampCase = (
+    String,
+    GlobalConfig,
+    fn(&GlobalConfig, &Limits) -> bool,
+);
+
+fn clamp_cases(start: &GlobalConfig, limits: &Limits) -> Vec<ClampCase> {
+    let brightness_ok: fn(&GlobalConfig, &Limits) -> bool =
+        |c, limits| (limits.brightness_min..=limits.brightness_max).contains(&c.led_brightness);
+
+    let mut cases = Vec::new();
+    // u8 can't go past the edges of a 0-255 range
+    let below = limits.brightness_min.checked_sub(1);
+    let above = limits.brightness_max.checked_add(1);
+    for brightness in [below, above].into_iter().flatten() {
+        let mut config = start.clone();
+        config.led_brightness = brightness;
+        let name = format!("led_brightness = {} is kept in range", brightness);
+        cases.push((name, config, brightness_ok));
+    }
+    cases
+}
+
+async fn clamped(
+    dev: &mut FaderpunkDevice,
+    config: GlobalConfig,
+    limits: &Limits,
+    in_range: fn(&GlobalConfig, &Limits) -> bool,
+) -> Result<Option<String>> {
+    let kept = set_config(dev, config).await?;
+    if in_range(&kept, limits) {
+        return Ok(None);
+    }
+    Ok(Some(format!("kept brightness {}", kept.led_brightness)))
+}
+
+// ── Layout ──
+
+/// An empty layout, then the strip packed with the narrowest app (the most
+/// instances the firmware has to track) and with the widest.
+fn layout_cases(apps: &[AppInfo]) -> Vec<(String, Layout)> {
+    let mut cases = vec![("empty".to_string(), Layout([None; GLOBAL_CHANNELS]))];
+    let narrowest = apps.iter().min_by_key(|a| a.channels);
+    let widest = apps.iter().max_by_key(|a| a.channels);
+    for app in [narrowest, widest].into_iter().flatten() {
+        let count = GLOBAL_CHANNELS / app.channels;
+        let mut layout = Layout([None; GLOBAL_CHANNELS]);
+        for i in 0..count {
+            layout.0[i * app.channels] = Some((app.app_id, app.channels, i as u8));
+        }
+        let name = format!("full: {} x {}", count, app.name);
+        if !cases.iter().any(|(n, _)| *n == name) {
+            cases.push((name, layout));
+        }
+    }
+    cases
+}
+
+/// Send a layout, then check the reply, a fresh read, and that there's one
+/// set of params per app instance.
+async fn layout_round_trip(
+    dev: &mut FaderpunkDevice,
+    layout: &Layout,
+    apps: &[AppInfo],
+) -> Result<Option<String>> {
+    crate::layout::validate(layout, apps)?;
+    let msg = ConfigMsgIn::SetLayout(layout.clone());
+    let reply = match dev.send_receive(&msg).await? {
+        ConfigMsgOut::Layout(reply) => reply,
+        other => return Err(error::unexpected(&msg, &other)),
+    };
+    if reply.0 != layout.0 {
+        return Ok(Some("the reply differs from the layout sent".to_string()));
+    }
+    let read = match dev.send_receive(&ConfigMsgIn::GetLayout).await? {
+        ConfigMsgOut::Layout(read) => read,
+        other => return Err(error::unexpected(&ConfigMsgIn::GetLayout, &other)),
+    };
+    if read.0 != layout.0 {
+        return Ok(Some("reading it back gives a different layout".to_string()));
+    }
+
+    let mut expected: Vec<u8> = layout.0.iter().flatten().map(|(_, _, id)| *id).collect();
+    let mut got: Vec<u8> = Vec::new();
+    for reply in dev
+        .send_receive_batch(&ConfigMsgIn::GetAllAppParams)
+        .await?
+    {
+        if let ConfigMsgOut::AppState(layout_id, _) = reply {
+            got.push(layout_id);
+        }
+    }
+    expected.sort_unstable();
+    got.sort_unstable();
+    if got != expected {
+        return Ok(Some(format!(
+            "params for layout_ids {:?}, expected {:?}",
+            got, expected
+        )));
+    }
+    Ok(None)
+}
+
+// ── Params ──
+
+/// The edge values of a param: its min and max, or its first and last
+/// variant. Params without edges worth testing give none.
+fn bounds(param: &Param) -> Vec<Value> {
+    fn ends<T: Copy>(variants: &[T], value: fn(T) -> Value) -> Vec<Value> {
+        match (variants.first(), variants.last()) {
+            (Some(&first), Some(&last)) => vec![value(first), value(last)],
+            _ => Vec::new(),
+        }
+    }
+    match param {
+        Param::Int { min, max, .. } => vec![Value::Int(*min), Value::Int(*max)],
+        Param::Float { min, max, .. } => vec![Value::Float(*min), Value::Float(*max)],
+        Param::Bool { .. } => vec![Value::Bool(false), Value::Bool(true)],
+        Param::MidiNrpn => vec![Value::MidiNrpn(false), Value::MidiNrpn(true)],
+        Param::Enum { variants, .. } if !variants.is_empty() => {
+            vec![Value::Enum(0), Value::Enum(variants.len() - 1)]
+        }
+        Param::Curve { variants, .. } => ends(variants, Value::Curve),
+        Param::Waveform { variants, .. } => ends(variants, Value::Waveform),
+        Param::Color { variants, .. } => ends(variants, Value::Color),
+        Param::Range { variants, .. } => ends(variants, Value::Range),
+        Param::Note { variants, .. } => ends(variants, Value::Note),
+        Param::MidiCc { .. } => vec![Value::MidiCc(MidiCc(0)), Value::MidiCc(MidiCc(127))],
+        Param::MidiChannel { .. } => vec![
+            Value::MidiChannel(MidiChannel(1)),
+            Value::MidiChannel(MidiChannel(16)),
+        ],
+        Param::MidiNote { .. } => {
+            vec![Value::MidiNote(MidiNote(0)), Value::MidiNote(MidiNote(127))]
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Set one param, leaving the others as they are, and check both the reply
+/// and a fresh read.
+async fn param_round_trip(
+    dev: &mut FaderpunkDevice,
+    layout_id: u8,
+    index: usize,
+    value: Value,
+) -> Result<Option<String>> {
+    let read = ConfigMsgIn::GetAppParams { layout_id };
+    let current = match dev.send_receive(&read).await? {
+        ConfigMsgOut::AppState(_, values) => values,
+        other => return Err(error::unexpected(&read, &other)),
+    };
+    if index >= current.len() {
+        return Ok(Some(format!(
+            "the app reports only {} values",
+            current.len()
+        )));
+    }
+
+    let mut values = [None; APP_MAX_PARAMS];
+    for (slot, v) in values.iter_mut().zip(&current) {
+        *slot = Some(*v);
+    }
+    values[index] = Some(value);
+    let replied = dev.write_params(layout_id, &current, values, false).await?;
+    if replied.get(index) != Some(&value) {
+        return Ok(Some(format!("the reply has {:?}", replied.get(index))));
+    }
+    // The reply to the write took the read's place in the cache
+    dev.forget(&read);
+    let stored = match dev.send_receive(&read).await? {
+        ConfigMsgOut::AppState(_, values) => values,
+        other => return Err(error::unexpected(&read, &other)),
+    };
+    if stored.get(index) != Some(&value) {
+        return Ok(Some(format!(
+            "reading it back gives {:?}",
+            stored.get(index)
+        )));
+    }
+    Ok(None)
+}
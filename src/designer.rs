@@ -0,0 +1,271 @@
+// Full-screen layout designer for `layout edit`: pick apps from a list, place
+// and move them along the strip with the arrow keys, and see what's wrong
+// with the layout as you go. Nothing reaches the device until it's written.
+
+use std::io::Write;
+
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::{cursor, execute, queue, terminal};
+use owo_colors::OwoColorize;
+
+use crate::display::{self, AppInfo};
+use crate::favorites::{Favorites, Mark};
+use crate::layout;
+use crate::protocol::{GLOBAL_CHANNELS, Layout};
+use crate::slots;
+
+/// Width of one fader on screen.
+const CELL: usize = 5;
+/// App list rows shown at once.
+const LIST_ROWS: usize = 12;
+
+const HELP: &str = "←/→ fader  ↑/↓ app  enter place  </> move  del remove  w write  esc quit";
+
+/// Puts the terminal back however the designer exits.
+struct Screen;
+
+impl Screen {
+    fn enter() -> Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(
+            std::io::stdout(),
+            terminal::EnterAlternateScreen,
+            cursor::Hide
+        )?;
+        Ok(Screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(
+            std::io::stdout(),
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+struct Designer<'a> {
+    layout: Layout,
+    apps: &'a [AppInfo],
+    /// The app list: starred and recent apps first.
+    list: Vec<(&'a AppInfo, Option<Mark>)>,
+    /// Fader under the cursor, in screen order (0 is leftmost).
+    column: usize,
+    /// Selected entry of `list`.
+    selected: usize,
+    /// One-off note about the last key, e.g. why a move didn't happen.
+    note: Option<String>,
+}
+
+/// Run the designer on a copy of `start`. Returns the layout to write, or
+/// None if the user quit without writing.
+pub fn run(start: &Layout, apps: &[AppInfo], favorites: &Favorites) -> Result<Option<Layout>> {
+    if apps.is_empty() {
+        anyhow::bail!("The device reported no apps");
+    }
+    let mut designer = Designer {
+        layout: start.clone(),
+        apps,
+        list: favorites.order(apps),
+        column: 0,
+        selected: 0,
+        note: None,
+    };
+    let _screen = Screen::enter()?;
+    loop {
+        designer.draw()?;
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            _ => continue,
+        };
+        designer.note = None;
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(None),
+            KeyCode::Esc | KeyCode::Char('q') => return Ok(None),
+            KeyCode::Left if key.modifiers.contains(KeyModifiers::SHIFT) => designer.shift(-1),
+            KeyCode::Right if key.modifiers.contains(KeyModifiers::SHIFT) => designer.shift(1),
+            KeyCode::Char('<') | KeyCode::Char(',') => designer.shift(-1),
+            KeyCode::Char('>') | KeyCode::Char('.') => designer.shift(1),
+            KeyCode::Left => designer.column = designer.column.saturating_sub(1),
+            KeyCode::Right => designer.column = (designer.column + 1).min(GLOBAL_CHANNELS - 1),
+            KeyCode::Home => designer.column = 0,
+            KeyCode::End => designer.column = GLOBAL_CHANNELS - 1,
+            KeyCode::Up => designer.selected = designer.selected.saturating_sub(1),
+            KeyCode::Down => designer.selected = (designer.selected + 1).min(apps.len() - 1),
+            KeyCode::Enter | KeyCode::Char(' ') => designer.place(),
+            KeyCode::Delete | KeyCode::Backspace | KeyCode::Char('x') => designer.remove(),
+            KeyCode::Char('w') => match layout::validate(&designer.layout, apps) {
+                Ok(()) => return Ok(Some(designer.layout)),
+                Err(_) => designer.note = Some("Fix the errors below before writing".to_string()),
+            },
+            _ => {}
+        }
+    }
+}
+
+impl Designer<'_> {
+    /// Firmware index of a screen column.
+    fn index(column: usize) -> usize {
+        if slots::reversed() {
+            GLOBAL_CHANNELS - 1 - column
+        } else {
+            column
+        }
+    }
+
+    /// Start of the app covering firmware index `index`. With overlapping
+    /// apps, the one starting nearest wins.
+    fn owner(&self, index: usize) -> Option<usize> {
+        (0..=index)
+            .rev()
+            .find(|&s| self.layout.0[s].is_some_and(|(_, ch, _)| s + ch > index))
+    }
+
+    fn app(&self, app_id: u8) -> Option<&AppInfo> {
+        self.apps.iter().find(|a| a.app_id == app_id)
+    }
+
+    fn place(&mut self) {
+        let index = Self::index(self.column);
+        let (app, _) = self.list[self.selected];
+        self.layout.0[index] = None;
+        let layout_id = layout::next_layout_id(&self.layout);
+        self.layout.0[index] = Some((app.app_id, app.channels, layout_id));
+    }
+
+    fn remove(&mut self) {
+        match self.owner(Self::index(self.column)) {
+            Some(start) => self.layout.0[start] = None,
+            None => self.note = Some("No app on this fader".to_string()),
+        }
+    }
+
+    /// Move the app under the cursor one fader left (-1) or right (1) on
+    /// screen, taking the cursor along.
+    fn shift(&mut self, step: isize) {
+        let Some(start) = self.owner(Self::index(self.column)) else {
+            self.note = Some("No app on this fader".to_string());
+            return;
+        };
+        let column = self.column as isize + step;
+        let target = if slots::reversed() {
+            start as isize - step
+        } else {
+            start as isize + step
+        };
+        if !(0..GLOBAL_CHANNELS as isize).contains(&column)
+            || !(0..GLOBAL_CHANNELS as isize).contains(&target)
+        {
+            self.note = Some("Already at the end of the strip".to_string());
+            return;
+        }
+        let target = target as usize;
+        if self.layout.0[target].is_some() {
+            self.note = Some(format!(
+                "Another app starts at fader {}",
+                slots::label(target)
+            ));
+            return;
+        }
+        self.layout.0[target] = self.layout.0[start].take();
+        self.column = column as usize;
+    }
+
+    fn draw(&self) -> Result<()> {
+        let mut lines: Vec<String> = Vec::new();
+        lines.push(format!("{}  {}", "Layout designer".bold(), HELP.dimmed()));
+        lines.push(String::new());
+
+        // Fader labels, the apps on them, and the cursor
+        let labels: String = (0..GLOBAL_CHANNELS)
+            .map(|c| format!("{:^CELL$}", slots::label(Self::index(c))))
+            .collect();
+        lines.push(format!("  {}", labels.dimmed()));
+        let mut strip = String::new();
+        let mut column = 0;
+        while column < GLOBAL_CHANNELS {
+            let owner = self.owner(Self::index(column));
+            let mut width = 1;
+            while column + width < GLOBAL_CHANNELS
+                && self.owner(Self::index(column + width)) == owner
+            {
+                width += 1;
+            }
+            strip += &match owner.and_then(|s| self.layout.0[s]) {
+                Some((app_id, ..)) => {
+                    let (name, style) = match self.app(app_id) {
+                        Some(app) => (app.name.clone(), display::bg_style_for_color(&app.color)),
+                        None => (
+                            format!("app {}", app_id),
+                            owo_colors::Style::new().reversed(),
+                        ),
+                    };
+                    let text: String = name.chars().take(width * CELL - 2).collect();
+                    format!(
+                        " {} ",
+                        format!("{:^w$}", text, w = width * CELL - 2).style(style)
+                    )
+                }
+                None => format!("{:^CELL$}", "·").repeat(width).dimmed().to_string(),
+            };
+            column += width;
+        }
+        lines.push(format!("  {}", strip));
+        lines.push(format!(
+            "  {}{:^CELL$}",
+            " ".repeat(self.column * CELL),
+            "▲".bold()
+        ));
+        lines.push(String::new());
+
+        // App list, scrolled to keep the selection in view
+        lines.push(format!("  {}", "Apps".bold()));
+        let first = self
+            .selected
+            .saturating_sub(LIST_ROWS / 2)
+            .min(self.list.len().saturating_sub(LIST_ROWS));
+        for (i, (app, mark)) in self.list.iter().enumerate().skip(first).take(LIST_ROWS) {
+            let row = format!(
+                "{} {:<20} {:>2} ch  {}",
+                mark.map_or(" ", Mark::symbol),
+                app.name,
+                app.channels,
+                app.description.dimmed()
+            );
+            if i == self.selected {
+                lines.push(format!("  {} {}", "›".bold(), row.bold()));
+            } else {
+                lines.push(format!("    {}", row));
+            }
+        }
+        lines.push(String::new());
+
+        if let Some(note) = &self.note {
+            lines.push(format!("  {}", note.yellow()));
+        }
+        match layout::validate(&self.layout, self.apps) {
+            Ok(()) => lines.push(format!(
+                "  {}",
+                "Layout OK — w writes it to the device".green()
+            )),
+            Err(e) => lines.extend(e.to_string().lines().map(|l| format!("  {}", l.red()))),
+        }
+
+        let mut out = std::io::stdout();
+        queue!(
+            out,
+            cursor::MoveTo(0, 0),
+            terminal::Clear(terminal::ClearType::All)
+        )?;
+        for line in lines {
+            // Raw mode doesn't turn \n into a new line at column 0
+            write!(out, "{}\r\n", line)?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+}
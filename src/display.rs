@@ -1,10 +1,29 @@
 // Pretty-printing helpers with color and visual fader layout.
 
+use std::io::IsTerminal;
+use std::sync::Mutex;
+use std::time::Duration;
+
 use owo_colors::OwoColorize;
 use owo_colors::Style;
 
+use crate::audit;
+use crate::automation::ClockStats;
+use crate::clocksim;
+use crate::conformance;
+use crate::frame;
+use crate::usb::{Health, Pacing, UsbInfo};
+use crate::groups::{self, Groups};
+use crate::limits::Limits;
+use crate::notes::{self, Notes};
+use crate::i18n::t;
+use crate::render;
+use crate::settings::ParamGroup;
+use crate::slots;
 use crate::protocol::*;
 
+pub use crate::layout::AppInfo;
+
 // ── Color mapping ──
 // Maps Faderpunk LED colors to their actual RGB values (from libfp/src/colors.rs)
 
@@ -30,15 +49,23 @@ fn color_to_rgb(color: &Color) -> (u8, u8, u8) {
     }
 }
 
+/// Color name as shown to the user; custom colors are shown as hex.
+pub fn color_name(color: &Color) -> String {
+    match color {
+        Color::Custom(r, g, b) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        other => format!("{:?}", other),
+    }
+}
+
 fn style_for_color(color: &Color) -> Style {
     let (r, g, b) = color_to_rgb(color);
     Style::new().color(owo_colors::Rgb(r, g, b))
 }
 
-fn bg_style_for_color(color: &Color) -> Style {
+pub fn bg_style_for_color(color: &Color) -> Style {
     let (r, g, b) = color_to_rgb(color);
     // Use dark text on bright colors, light text on dark colors
-    let luminance = (r as u16 * 299 + g as u16 * 587 + b as u16 * 114) / 1000;
+    let luminance = (r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000;
     let (fr, fg_, fb) = if luminance > 140 { (0, 0, 0) } else { (255, 255, 255) };
     Style::new()
         .on_color(owo_colors::Rgb(r, g, b))
@@ -69,59 +96,149 @@ fn icon_char(icon: &AppIcon) -> &'static str {
     }
 }
 
-// ── Section header ──
+// ── Shape previews ──
+// Tiny sparklines so waveform and curve params read at a glance.
+
+fn waveform_glyph(waveform: &Waveform) -> &'static str {
+    match waveform {
+        Waveform::Triangle => "▁▄█▄▁",
+        Waveform::Saw => "▁▃▅█▁",
+        Waveform::SawInv => "█▅▃▁█",
+        Waveform::Square => "██▁▁█",
+        Waveform::Sine => "▄▇▄▁▄",
+    }
+}
+
+fn curve_glyph(curve: &Curve) -> &'static str {
+    match curve {
+        Curve::Linear => "▁▃▅█",
+        Curve::Logarithmic => "▄▆▇█",
+        Curve::Exponential => "▁▁▃█",
+    }
+}
+
+// ── Output primitives ──
+// Everything below is shown through the selected renderer (--output); see
+// render.rs.
 
 fn header(title: &str) {
-    let bar = "─".repeat(title.len() + 2);
-    println!("┌{}┐", bar);
-    println!("│ {} │", title.bold());
-    println!("└{}┘", bar);
+    render::with(|r| r.section(title));
 }
 
 fn sub_header(title: &str) {
-    println!();
-    println!("  {} {}", "▸".dimmed(), title.bold());
+    render::with(|r| r.subsection(title, ""));
 }
 
 fn kv(key: &str, value: &str) {
-    println!("    {:<16} {}", format!("{}:", key).dimmed(), value);
+    render::with(|r| r.field(key, value));
+}
+
+/// A table row: cells named by `columns`, and `line`, the row as drawn on a
+/// terminal.
+fn row(columns: &[&str], cells: Vec<String>, line: &str) {
+    render::with(|r| r.row(columns, &cells, line));
+}
+
+/// A line of text without structure; empty for spacing.
+fn line(text: &str) {
+    render::with(|r| r.text(text));
+}
+
+/// Data a command builds itself, shown in the selected format.
+pub fn print_data(value: &serde_json::Value) {
+    render::with(|r| r.data(value));
+}
+
+/// Announce one command of a batch.
+pub fn print_step(command: &str) {
+    line("");
+    line(&format!("{} {}", "$".dimmed(), command.bold()));
+}
+
+/// A failed command: the error, what caused it, and what to try.
+pub fn print_error(err: &anyhow::Error, hint: Option<&str>) {
+    error_line(&format!("{} {}", format!("{}:", t!("error")).red().bold(), err));
+    for cause in err.chain().skip(1) {
+        error_line(&format!("  {} {}", format!("{}:", t!("caused by")).dimmed(), cause));
+    }
+    if let Some(hint) = hint {
+        let mut lines = hint.lines();
+        if let Some(first) = lines.next() {
+            error_line(&format!("  {} {}", format!("{}:", t!("hint")).cyan().bold(), first));
+        }
+        for line in lines {
+            error_line(&format!("  {}", line));
+        }
+    }
+}
+
+/// Whether errors on stderr are colored, once --color is known.
+static STDERR_COLOR: Mutex<Option<bool>> = Mutex::new(None);
+
+pub fn set_stderr_color(color: bool) {
+    *STDERR_COLOR.lock().unwrap() = Some(color);
+}
+
+fn error_line(line: &str) {
+    let color = STDERR_COLOR
+        .lock()
+        .unwrap()
+        .unwrap_or_else(|| render::ColorChoice::Auto.enabled(std::io::stderr().is_terminal()));
+    if color {
+        eprintln!("{}", line);
+    } else {
+        eprintln!("{}", crate::capture::strip_ansi(line));
+    }
 }
 
 // ── Global config ──
 
+/// Print the value ranges the config setters check.
+pub fn print_limits(limits: &Limits) {
+    header(t!("Limits"));
+    kv(
+        t!("LED brightness"),
+        &format!("{}-{}", limits.brightness_min, limits.brightness_max),
+    );
+}
+
 pub fn print_global_config(config: &GlobalConfig) {
-    header("Global Config");
+    header(t!("Global Config"));
 
-    sub_header("Clock");
-    kv("Source", &format!("{:?}", config.clock.clock_src));
-    kv("BPM", &format!("{}", config.clock.internal_bpm));
-    kv("Ext PPQN", &format!("{}", config.clock.ext_ppqn));
-    kv("Reset source", &format!("{:?}", config.clock.reset_src));
+    sub_header(t!("Clock"));
+    kv(t!("Source"), &format!("{:?}", config.clock.clock_src));
+    kv(t!("BPM"), &format!("{}", config.clock.internal_bpm));
+    kv(t!("Ext PPQN"), &format!("{}", config.clock.ext_ppqn));
+    kv(t!("Reset source"), &format!("{:?}", config.clock.reset_src));
 
-    sub_header("Control");
-    kv("Takeover mode", &format!("{:?}", config.takeover_mode));
-    kv("LED brightness", &format!("{}", config.led_brightness));
-    kv("I2C mode", &format!("{:?}", config.i2c_mode));
+    sub_header(t!("Control"));
+    kv(t!("Takeover mode"), &format!("{:?}", config.takeover_mode));
+    kv(t!("LED brightness"), &format!("{}", config.led_brightness));
+    kv(t!("I2C mode"), &format!("{:?}", config.i2c_mode));
 
-    sub_header("Quantizer");
-    kv("Key", &format!("{:?}", config.quantizer.key));
-    kv("Tonic", &format!("{:?}", config.quantizer.tonic));
+    sub_header(t!("Quantizer"));
+    kv(t!("Key"), &format!("{:?}", config.quantizer.key));
+    kv(t!("Tonic"), &format!("{:?}", config.quantizer.tonic));
 
-    sub_header("Aux Jacks");
+    sub_header(t!("Aux Jacks"));
     for (i, aux) in config.aux.iter().enumerate() {
-        kv(&format!("Aux {}", i + 1), &format_aux(aux));
+        kv(&t!("Aux {}", i + 1), &format_aux(aux));
     }
 
-    sub_header("MIDI Outputs");
-    let labels = ["USB", "Out 1", "Out 2"];
+    sub_header(t!("MIDI Outputs"));
+    let labels = ["USB", t!("Out 1"), t!("Out 2")];
     for (i, out) in config.midi.outs.iter().enumerate() {
         let clock_icon = if out.send_clock { "●" } else { "○" };
         let transport_icon = if out.send_transport { "●" } else { "○" };
         kv(
             labels[i],
             &format!(
-                "{} clk  {} transport  {:?}",
-                clock_icon, transport_icon, out.mode
+                "{} {}  {} {}  {:?}",
+                clock_icon,
+                t!("clk"),
+                transport_icon,
+                t!("transport"),
+                out.mode
             ),
         );
     }
@@ -130,8 +247,8 @@ pub fn print_global_config(config: &GlobalConfig) {
 fn format_aux(aux: &AuxJackMode) -> String {
     match aux {
         AuxJackMode::None => "─".dimmed().to_string(),
-        AuxJackMode::ClockOut(div) => format!("Clock ÷{}", clock_div_value(div)),
-        AuxJackMode::ResetOut => "Reset".to_string(),
+        AuxJackMode::ClockOut(div) => t!("Clock ÷{}", clock_div_value(div)),
+        AuxJackMode::ResetOut => t!("Reset").to_string(),
     }
 }
 
@@ -150,18 +267,108 @@ fn clock_div_value(div: &ClockDivision) -> &'static str {
     }
 }
 
-// ── Layout (visual fader strip) ──
+// ── Clock simulation ──
 
-/// App info needed to render layout and params.
-pub struct AppInfo {
-    pub app_id: u8,
-    pub channels: usize,
-    pub name: String,
-    pub color: Color,
-    pub icon: AppIcon,
-    pub params: Vec<Param>,
+/// Print what the aux jacks do over `bars` bars at `bpm`: a line per jack
+/// with its period, then a timing diagram per bar, one column per `res`
+/// ticks.
+pub fn print_clock_sim(bpm: f32, aux: &[AuxJackMode; 3], bars: u32) {
+    const JACKS: [&str; 3] = ["Atom", "Meteor", "Cube"];
+    let tick = clocksim::tick_ms(bpm);
+    let len = bars * clocksim::BAR;
+    header(&t!("Clock at {} BPM", bpm));
+
+    sub_header(t!("Outputs"));
+    for (i, mode) in aux.iter().enumerate() {
+        let label = format!("{} ({})", t!("Aux {}", i + 1), JACKS[i]);
+        let count = clocksim::pulses(*mode, len).len();
+        let value = match mode {
+            AuxJackMode::None => "─".dimmed().to_string(),
+            AuxJackMode::ClockOut(div) => t!(
+                "÷{}  {}, every {} ms, {} pulses",
+                clock_div_value(div),
+                clocksim::note_value(*div),
+                format!("{:.1}", clocksim::ticks(*div) as f64 * tick),
+                count
+            ),
+            AuxJackMode::ResetOut => t!("Reset, once on start").to_string(),
+        };
+        kv(&label, &value);
+    }
+    kv(
+        t!("Beat"),
+        &t!(
+            "{} ms ({} ms per tick)",
+            format!("{:.1}", tick * clocksim::PPQN as f64),
+            format!("{:.2}", tick)
+        ),
+    );
+
+    let res = clocksim::resolution(aux);
+    let columns = (clocksim::BAR / res) as usize;
+    let per_beat = (clocksim::PPQN / res) as usize;
+    let lanes: Vec<Vec<u32>> = aux.iter().map(|mode| clocksim::pulses(*mode, len)).collect();
+    for bar in 0..bars {
+        sub_header(&t!("Bar {}", bar + 1));
+        let beats: String = (1..=4).map(|b| format!("{:<width$}", b, width = per_beat)).collect();
+        line(&format!("    {:<8} {}", "", beats.trim_end().dimmed()));
+        for (i, pulses) in lanes.iter().enumerate() {
+            if aux[i] == AuxJackMode::None {
+                continue;
+            }
+            let start = bar * clocksim::BAR;
+            let lane: String = (0..columns)
+                .map(|c| {
+                    let from = start + c as u32 * res;
+                    if pulses.iter().any(|&p| (from..from + res).contains(&p)) {
+                        "█".green().to_string()
+                    } else if c % per_beat == 0 {
+                        "┊".dimmed().to_string()
+                    } else {
+                        "·".dimmed().to_string()
+                    }
+                })
+                .collect();
+            line(&format!("    {:<8} {}", JACKS[i], lane));
+        }
+    }
 }
 
+/// Print what `clock measure` found, against `expect` BPM when there is one.
+pub fn print_clock_stats(stats: &ClockStats, expect: Option<f32>) {
+    header(t!("Clock measurement"));
+    kv(t!("Ticks"), &stats.ticks.to_string());
+    kv(t!("Tempo"), &format!("{:.2} BPM", stats.bpm));
+    if let Some(expect) = expect {
+        let expect = expect as f64;
+        let off = (stats.bpm - expect) / expect * 100.0;
+        // Time gained or lost over a minute of the expected tempo
+        let drift = (60_000.0 * (expect / stats.bpm - 1.0)).abs();
+        let drift = if stats.bpm < expect {
+            t!("{} ms behind per minute", format!("{:.1}", drift))
+        } else {
+            t!("{} ms ahead per minute", format!("{:.1}", drift))
+        };
+        let line = format!("{} BPM ({:+.3}%, {})", expect, off, drift);
+        let line = if off.abs() < 0.1 {
+            line.green().to_string()
+        } else {
+            line.yellow().to_string()
+        };
+        kv(t!("Expected"), &line);
+    }
+    kv(
+        t!("Jitter"),
+        &t!(
+            "{} ms (worst {} ms)",
+            format!("{:.2}", stats.jitter.as_secs_f64() * 1000.0),
+            format!("{:.2}", stats.worst.as_secs_f64() * 1000.0)
+        ),
+    );
+}
+
+// ── Layout (visual fader strip) ──
+
 /// A resolved layout entry with app info for params display.
 pub struct LayoutEntry {
     pub start: usize,
@@ -170,11 +377,11 @@ pub struct LayoutEntry {
     pub layout_id: u8,
 }
 
-/// Print the layout as a visual fader strip.
-/// If `apps` is provided, renders with colors and names.
+/// Print the layout as a visual fader strip, with a header over the apps of
+/// each group. If `apps` is provided, renders with colors and names.
 /// Falls back to a plain table if no app info is available.
-pub fn print_layout(layout: &Layout, apps: Option<&[AppInfo]>) {
-    header("Layout");
+pub fn print_layout(layout: &Layout, apps: Option<&[AppInfo]>, groups: &Groups, notes: &Notes) {
+    header(t!("Layout"));
 
     // Collect occupied slot ranges: (start, size, app_id, layout_id)
     let mut entries: Vec<(usize, usize, u8, u8)> = Vec::new();
@@ -183,29 +390,53 @@ pub fn print_layout(layout: &Layout, apps: Option<&[AppInfo]>) {
             entries.push((i, *channels, *app_id, *layout_id));
         }
     }
+    // Draw the strip the way round the unit is mounted
+    if slots::reversed() {
+        entries.reverse();
+    }
 
     if entries.is_empty() {
-        println!("  {}", "(empty layout)".dimmed());
+        line(&format!("  {}", t!("(empty layout)").dimmed()));
         return;
     }
 
     // Print the visual fader strip
-    println!();
+    line("");
+
+    // Group headers: one span over each run of apps in the same group
+    let entry_groups: Vec<Option<&str>> = entries.iter().map(|e| groups::of(groups, e.0)).collect();
+    if entry_groups.iter().any(Option::is_some) {
+        let mut spans = String::from("  ");
+        let mut i = 0;
+        while i < entries.len() {
+            let group = entry_groups[i];
+            let mut width = 0;
+            while i < entries.len() && entry_groups[i] == group {
+                width += entries[i].1 * 5 + 1;
+                i += 1;
+            }
+            match group {
+                Some(name) => {
+                    let name: String = name.chars().take(width.saturating_sub(4)).collect();
+                    let span = format!("{:─^width$}", format!(" @{} ", name), width = width);
+                    spans += &span.bold().to_string();
+                }
+                None => spans += &" ".repeat(width),
+            }
+        }
+        line(&spans);
+    }
 
     // Top border
-    print!("  ");
-    for (i, entry) in entries.iter().enumerate() {
+    let mut top = String::from("  ");
+    for entry in &entries {
         let width = entry.1 * 5;
-        if i == 0 {
-            print!("┌{}┐", "─".repeat(width - 1));
-        } else {
-            print!("┌{}┐", "─".repeat(width - 1));
-        }
+        top += &format!("┌{}┐", "─".repeat(width - 1));
     }
-    println!();
+    line(&top);
 
     // App names row (colored)
-    print!("  ");
+    let mut names = String::from("  ");
     for entry in &entries {
         let (_, size, app_id, _) = entry;
         let width = size * 5;
@@ -215,10 +446,10 @@ pub fn print_layout(layout: &Layout, apps: Option<&[AppInfo]>) {
             if let Some(info) = apps.iter().find(|a| a.app_id == *app_id) {
                 (info.name.clone(), info.color, info.icon)
             } else {
-                (format!("App {}", app_id), Color::White, AppIcon::Fader)
+                (t!("App {}", app_id), Color::White, AppIcon::Fader)
             }
         } else {
-            (format!("App {}", app_id), Color::White, AppIcon::Fader)
+            (t!("App {}", app_id), Color::White, AppIcon::Fader)
         };
 
         let style = bg_style_for_color(&color);
@@ -228,162 +459,420 @@ pub fn print_layout(layout: &Layout, apps: Option<&[AppInfo]>) {
         } else {
             format!("{:^width$}", label, width = inner)
         };
-        print!("│{}│", format!("{}", label).style(style));
+        names += &format!("│{}│", label.style(style));
     }
-    println!();
+    line(&names);
 
     // Fader number row
-    print!("  ");
+    let mut numbers = String::from("  ");
     for entry in &entries {
         let (start, size, _, _) = entry;
         let width = size * 5;
         let inner = width - 1;
 
-        let range = if *size == 1 {
-            format!("{}", start + 1)
-        } else {
-            format!("{}-{}", start + 1, start + size)
-        };
-        print!("│{:^width$}│", range.dimmed(), width = inner);
+        let range = slots::range(*start, *size);
+        numbers += &format!("│{:^width$}│", range.dimmed(), width = inner);
     }
-    println!();
+    line(&numbers);
 
     // Bottom border
-    print!("  ");
+    let mut bottom = String::from("  ");
     for entry in &entries {
         let width = entry.1 * 5;
-        print!("└{}┘", "─".repeat(width - 1));
-    }
-    println!();
-    println!();
-
-    // Legend table
-    println!(
-        "  {:>4}  {:>8}  {:>6}  {}",
-        "Slot".dimmed(),
-        "Layout ID".dimmed(),
-        "App ID".dimmed(),
-        "App".dimmed()
-    );
+        bottom += &format!("└{}┘", "─".repeat(width - 1));
+    }
+    line(&bottom);
+    line("");
+
+    // Legend table; its heading is drawn with the first row
+    let columns = [t!("Slot"), t!("Layout ID"), t!("App ID"), t!("App"), t!("Note")];
+    let mut heading = Some(format!(
+        "  {:>4}  {:>8}  {:>6}  {}\n",
+        columns[0].dimmed(),
+        columns[1].dimmed(),
+        columns[2].dimmed(),
+        columns[3].dimmed()
+    ));
     for (start, size, app_id, layout_id) in &entries {
         let (name, color) = if let Some(apps) = apps {
             if let Some(info) = apps.iter().find(|a| a.app_id == *app_id) {
                 (info.name.clone(), info.color)
             } else {
-                (format!("App {}", app_id), Color::White)
+                (t!("App {}", app_id), Color::White)
             }
         } else {
-            (format!("App {}", app_id), Color::White)
+            (t!("App {}", app_id), Color::White)
         };
 
         let style = style_for_color(&color);
-        let range = if *size == 1 {
-            format!("{}", start + 1)
-        } else {
-            format!("{}-{}", start + 1, start + size)
-        };
+        let range = slots::range(*start, *size);
         let dot = "●".style(style);
-        println!("  {:>4}  {:>8}  {:>6}  {} {}", range, layout_id, app_id, dot, name);
+        let note = notes::on(notes, *start, *size);
+        let shown_note = note
+            .as_ref()
+            .map(|note| format!("  {}", note.italic()))
+            .unwrap_or_default();
+        let text = format!(
+            "{}  {:>4}  {:>8}  {:>6}  {} {}{}",
+            heading.take().unwrap_or_default(),
+            range,
+            layout_id,
+            app_id,
+            dot,
+            name,
+            shown_note
+        );
+        let cells = vec![
+            range,
+            layout_id.to_string(),
+            app_id.to_string(),
+            name,
+            note.unwrap_or_default(),
+        ];
+        row(&columns, cells, &text);
     }
 }
 
 // ── Apps list ──
 
-pub fn print_app_list(apps: &[(u8, usize, String, String, Color, AppIcon)]) {
-    header(&format!("Apps ({})", apps.len()));
-    println!();
+/// Heading for the app list; rows follow one by one via `print_app_row`.
+pub fn print_app_list_header(count: usize) {
+    header(&t!("Apps ({})", count));
+    line("");
+}
 
-    for (app_id, channels, name, description, color, icon) in apps {
-        let style = style_for_color(color);
-        let dot = "●".style(style);
-        let icon_str = icon_char(icon);
-        let ch_label = if *channels == 1 {
-            "1 ch".to_string()
-        } else {
-            format!("{} ch", channels)
-        };
-        println!(
-            "  {} {} {:>2}  {} {}  {}",
-            dot,
-            icon_str,
-            format!("[{}]", app_id).dimmed(),
-            name.bold(),
-            format!("({})", ch_label).dimmed(),
-            description.dimmed(),
-        );
-    }
+pub fn print_app_row(
+    app_id: u8,
+    channels: usize,
+    name: &str,
+    description: &str,
+    color: &Color,
+    icon: &AppIcon,
+    mark: Option<&str>,
+) {
+    let style = style_for_color(color);
+    let dot = "●".style(style);
+    let icon_str = icon_char(icon);
+    let ch_label = if channels == 1 {
+        "1 ch".to_string()
+    } else {
+        format!("{} ch", channels)
+    };
+    let text = format!(
+        "{} {} {} {:>2}  {} {}  {}",
+        mark.unwrap_or(" ").bold(),
+        dot,
+        icon_str,
+        format!("[{}]", app_id).dimmed(),
+        name.bold(),
+        format!("({})", ch_label).dimmed(),
+        description.dimmed(),
+    );
+    let cells = vec![
+        app_id.to_string(),
+        name.to_string(),
+        channels.to_string(),
+        description.to_string(),
+        mark.unwrap_or_default().to_string(),
+    ];
+    row(
+        &["App ID", "Name", "Channels", "Description", "Mark"],
+        cells,
+        &text,
+    );
 }
 
 // ── App params ──
 
-/// Print parameters for an app, with names from metadata when available.
-pub fn print_app_params(
+/// An app instance's name, param metadata and dimmed fader range, resolved
+/// via layout_id → app_id → AppInfo.
+fn app_heading<'a>(
     layout_id: u8,
-    values: &[Value],
     layout_entries: Option<&[LayoutEntry]>,
-    apps: Option<&[AppInfo]>,
-) {
-    // Resolve the app name, color, and param metadata via layout_id → app_id → AppInfo
-    let (app_name, color, param_meta, fader_range) =
+    apps: Option<&'a [AppInfo]>,
+) -> (String, Option<&'a [Param]>, String) {
+    let (app_name, param_meta, fader_range) =
         if let (Some(entries), Some(apps)) = (layout_entries, apps) {
             if let Some(entry) = entries.iter().find(|e| e.layout_id == layout_id) {
                 let info = apps.iter().find(|a| a.app_id == entry.app_id);
-                let name = info.map(|i| i.name.clone()).unwrap_or_else(|| format!("App {}", entry.app_id));
-                let color = info.map(|i| i.color).unwrap_or(Color::White);
+                let name = info.map(|i| i.name.clone()).unwrap_or_else(|| t!("App {}", entry.app_id));
                 let params = info.map(|i| i.params.as_slice());
                 let range = if entry.size == 1 {
-                    format!("fader {}", entry.start + 1)
+                    format!("fader {}", slots::label(entry.start))
                 } else {
-                    format!("faders {}-{}", entry.start + 1, entry.start + entry.size)
+                    format!("faders {}", slots::range(entry.start, entry.size))
                 };
-                (name, color, params, range)
+                (name, params, range)
             } else {
-                (format!("layout_id={}", layout_id), Color::White, None, String::new())
+                (format!("layout_id={}", layout_id), None, String::new())
             }
         } else {
-            (format!("layout_id={}", layout_id), Color::White, None, String::new())
+            (format!("layout_id={}", layout_id), None, String::new())
         };
 
-    let style = style_for_color(&color);
-    let dot = "●".style(style);
     let range_str = if fader_range.is_empty() {
         String::new()
     } else {
-        format!(" {}", format!("({})", fader_range).dimmed())
+        format!("({})", fader_range).dimmed().to_string()
     };
-    println!("  {} {} {}{}", "▸".dimmed(), dot, app_name.bold(), range_str);
+    (app_name, param_meta, range_str)
+}
 
-    // Find the longest param name for alignment
-    let max_name_len = if let Some(params) = param_meta {
-        values
-            .iter()
-            .enumerate()
-            .map(|(i, _)| param_name(params.get(i)).len())
-            .max()
-            .unwrap_or(0)
-    } else {
-        0
+/// Print parameters for an app, with names from metadata when available.
+/// Print one app instance's param values. Unused indices (`Param::None`)
+/// are left out unless `all_indices`, which numbers every row instead.
+/// With `groups`, rows are listed under their group's heading.
+pub fn print_app_params(
+    layout_id: u8,
+    values: &[Value],
+    layout_entries: Option<&[LayoutEntry]>,
+    apps: Option<&[AppInfo]>,
+    bars: bool,
+    all_indices: bool,
+    groups: Option<&[ParamGroup]>,
+) {
+    let (app_name, param_meta, range_str) = app_heading(layout_id, layout_entries, apps);
+    render::with(|r| r.subsection(&app_name, &range_str));
+
+    // (index, name, label, value) for each row; the label is the name as
+    // shown, empty without metadata
+    let rows: Vec<(usize, String, String, &Value)> = values
+        .iter()
+        .enumerate()
+        .filter_map(|(i, val)| {
+            let meta = param_meta.and_then(|p| p.get(i));
+            let name = match param_name(meta) {
+                _ if is_unused(meta) => t!("(unused)").to_string(),
+                name => name,
+            };
+            let label = match &name {
+                _ if is_unused(meta) || name.is_empty() => name.clone(),
+                name => format!("{}:", name),
+            };
+            (all_indices || !is_unused(meta)).then_some((i, name, label, val))
+        })
+        .collect();
+    let width = rows.iter().map(|(_, _, label, _)| label.chars().count()).max().unwrap_or(0);
+
+    let shown: Vec<usize> = rows.iter().map(|(i, ..)| *i).collect();
+    let sections = match groups {
+        Some(groups) => arrange_groups(groups, param_meta, &shown),
+        None => vec![(String::new(), shown)],
     };
+    let indent = if groups.is_some() { "      " } else { "    " };
 
-    for (i, val) in values.iter().enumerate() {
-        let formatted = format_value(val);
-        if let Some(params) = param_meta {
-            let name = param_name(params.get(i));
-            if name.is_empty() {
-                println!("    {:>2}  {}", format!("{}.", i).dimmed(), formatted);
+    for (group, members) in sections {
+        if groups.is_some() {
+            line(&format!("    {}", group.bold()));
+        }
+        for i in members {
+            let Some((_, name, label, val)) = rows.iter().find(|(index, ..)| *index == i) else {
+                continue;
+            };
+            let val = *val;
+            let meta = param_meta.and_then(|p| p.get(i));
+            let formatted = match (bars, value_fraction(val, meta)) {
+                (true, Some(fraction)) => {
+                    format!("{} {}", bar(fraction), format_with_max(val, meta))
+                }
+                _ => format_value(val, meta),
+            };
+            let index = format!("{:>3}", format!("{}.", i));
+            let label = format!("{:<width$}", label, width = width);
+            let text = if all_indices && param_meta.is_some() {
+                format!("{}{}  {}  {}", indent, index.dimmed(), label.dimmed(), formatted)
+            } else if label.trim().is_empty() {
+                format!("{}{}  {}", indent, index.dimmed(), formatted)
+            } else {
+                format!("{}{}  {}", indent, label.dimmed(), formatted)
+            };
+            let value = format_value(val, meta);
+            if groups.is_some() {
+                let cells = vec![group.clone(), i.to_string(), name.clone(), value];
+                row(&["Group", "Index", "Param", "Value"], cells, &text);
             } else {
-                println!(
-                    "    {:<width$}  {}",
-                    format!("{}:", name).dimmed(),
-                    formatted,
-                    width = max_name_len + 1
-                );
+                row(&["Index", "Param", "Value"], vec![i.to_string(), name.clone(), value], &text);
             }
-        } else {
-            println!("    {:>2}  {}", format!("{}.", i).dimmed(), formatted);
         }
     }
-    println!();
+}
+
+/// Params under each heading, in the groups' order (a group's own params in
+/// the order it lists them), then the rest under "Other". Only indices in
+/// `shown` are placed; groups left empty are dropped.
+pub fn arrange_groups(
+    groups: &[ParamGroup],
+    params: Option<&[Param]>,
+    shown: &[usize],
+) -> Vec<(String, Vec<usize>)> {
+    let mut placed: Vec<usize> = Vec::new();
+    let mut sections = Vec::new();
+    for group in groups {
+        let members: Vec<usize> = group
+            .params
+            .iter()
+            .filter_map(|p| group_param_index(p, params))
+            .filter(|i| shown.contains(i) && !placed.contains(i))
+            .collect();
+        placed.extend(&members);
+        if !members.is_empty() {
+            sections.push((group.name.clone(), members));
+        }
+    }
+    let rest: Vec<usize> = shown.iter().copied().filter(|i| !placed.contains(i)).collect();
+    if !rest.is_empty() {
+        sections.push((t!("Other").to_string(), rest));
+    }
+    sections
+}
+
+/// Index of a param named in a group: a number, or the param's name.
+fn group_param_index(name: &str, params: Option<&[Param]>) -> Option<usize> {
+    if let Ok(index) = name.trim().parse() {
+        return Some(index);
+    }
+    params?
+        .iter()
+        .position(|p| param_name(Some(p)).eq_ignore_ascii_case(name.trim()))
+}
+
+/// Headings for `param show --grouped` on apps without groups of their own,
+/// in display order.
+const FUNCTIONS: [&str; 6] = ["General", "Shape", "Pitch", "CV", "MIDI", "LED"];
+
+/// Which of `FUNCTIONS` a param belongs under, by its type.
+fn param_function(param: &Param) -> &'static str {
+    match param {
+        Param::Curve { .. } | Param::Waveform { .. } => "Shape",
+        Param::Note { .. } | Param::VoltPerOct => "Pitch",
+        Param::Range { .. } => "CV",
+        Param::MidiCc { .. }
+        | Param::MidiChannel { .. }
+        | Param::MidiIn
+        | Param::MidiMode
+        | Param::MidiNote { .. }
+        | Param::MidiOut
+        | Param::MidiNrpn => "MIDI",
+        Param::Color { .. } => "LED",
+        _ => "General",
+    }
+}
+
+/// Groups made from the params' types, for apps nobody has grouped.
+pub fn groups_by_function(params: &[Param]) -> Vec<ParamGroup> {
+    FUNCTIONS
+        .iter()
+        .map(|function| ParamGroup {
+            name: function.to_string(),
+            params: params
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| param_function(p) == *function)
+                .map(|(i, _)| i.to_string())
+                .collect(),
+        })
+        .filter(|group| !group.params.is_empty())
+        .collect()
+}
+
+/// The params whose values differ between `before` and `after`, each as
+/// `old → new`, to show what a write did without listing the rest.
+pub fn print_param_diff(
+    layout_id: u8,
+    before: &[Value],
+    after: &[Value],
+    layout_entries: Option<&[LayoutEntry]>,
+    apps: Option<&[AppInfo]>,
+) {
+    let (app_name, param_meta, range_str) = app_heading(layout_id, layout_entries, apps);
+    render::with(|r| r.subsection(&app_name, &range_str));
+
+    let changed: Vec<usize> =
+        (0..after.len()).filter(|&i| before.get(i) != Some(&after[i])).collect();
+    if changed.is_empty() {
+        line(&format!("    {}", t!("(no change)").dimmed()));
+        return;
+    }
+    let labels: Vec<String> = changed
+        .iter()
+        .map(|&i| format!("{}:", param_label(param_meta.and_then(|p| p.get(i)), i)))
+        .collect();
+    let width = labels.iter().map(|label| label.chars().count()).max().unwrap_or(0);
+
+    for (&i, label) in changed.iter().zip(labels) {
+        let meta = param_meta.and_then(|p| p.get(i));
+        let old = before.get(i).map(|v| format_value(v, meta)).unwrap_or_default();
+        let new = format_value(&after[i], meta);
+        let text = format!(
+            "    {}  {} {} {}",
+            format!("{:<width$}", label, width = width).dimmed(),
+            old.dimmed().strikethrough(),
+            "→".dimmed(),
+            new.bold()
+        );
+        let cells = vec![i.to_string(), param_label(meta, i), old, new];
+        row(&["Index", "Param", "Before", "After"], cells, &text);
+    }
+}
+
+const BAR_WIDTH: usize = 8;
+
+/// Position of a numeric value within its param's min/max, as 0.0-1.0.
+fn value_fraction(val: &Value, param: Option<&Param>) -> Option<f32> {
+    let (v, min, max) = match (val, param) {
+        (Value::Int(v), Some(Param::Int { min, max, .. })) => (*v as f32, *min as f32, *max as f32),
+        (Value::Float(v), Some(Param::Float { min, max, .. })) => (*v, *min, *max),
+        _ => return None,
+    };
+    if max <= min {
+        return None;
+    }
+    Some(((v - min) / (max - min)).clamp(0.0, 1.0))
+}
+
+/// Proportional mini bar, e.g. `▇▇▇░░░░░`.
+fn bar(fraction: f32) -> String {
+    let filled = (fraction * BAR_WIDTH as f32).round() as usize;
+    format!(
+        "{}{}",
+        "▇".repeat(filled),
+        "░".repeat(BAR_WIDTH - filled).dimmed()
+    )
+}
+
+/// `37/127` style value for numeric params.
+fn format_with_max(val: &Value, param: Option<&Param>) -> String {
+    match (val, param) {
+        (Value::Int(v), Some(Param::Int { max, .. })) => format!("{}{}", v, format!("/{}", max).dimmed()),
+        (Value::Float(v), Some(Param::Float { max, .. })) => {
+            format!("{:.1}{}", v, format!("/{:.1}", max).dimmed())
+        }
+        _ => format_value(val, param),
+    }
+}
+
+/// Short description of a param's type, for listings.
+pub fn format_param_type(param: &Param) -> &'static str {
+    match param {
+        Param::None => "",
+        Param::Int { .. } => "integer",
+        Param::Float { .. } => "number",
+        Param::Bool { .. } => "bool",
+        Param::Enum { .. } => "enum",
+        Param::Curve { .. } => "curve",
+        Param::Waveform { .. } => "waveform",
+        Param::Color { .. } => "color",
+        Param::Range { .. } => "range",
+        Param::Note { .. } => "note",
+        Param::MidiCc { .. } => "CC 0-127",
+        Param::MidiChannel { .. } => "channel 1-16",
+        Param::MidiIn => "midi in",
+        Param::MidiMode => "note/cc",
+        Param::MidiNote { .. } => "note 0-127",
+        Param::MidiOut => "midi out",
+        Param::MidiNrpn => "nrpn on/off",
+        Param::VoltPerOct => "v/oct std|buchla",
+    }
 }
 
 /// Extract the human-readable name from a Param definition.
@@ -391,6 +880,22 @@ pub fn get_param_name(param: &Param) -> String {
     param_name(Some(param))
 }
 
+/// Whether a param index is a placeholder. Apps with gaps in their param
+/// list report `Param::None` there, and the value at that index means nothing.
+pub fn is_unused(param: Option<&Param>) -> bool {
+    matches!(param, Some(Param::None))
+}
+
+/// A param's name for messages: its own, else `param N`, marked when the
+/// index is unused.
+pub fn param_label(param: Option<&Param>, index: usize) -> String {
+    match param_name(param) {
+        name if !name.is_empty() => name,
+        _ if is_unused(param) => format!("param {} (unused)", index),
+        _ => format!("param {}", index),
+    }
+}
+
 fn param_name(param: Option<&Param>) -> String {
     match param {
         Some(Param::None) | None => String::new(),
@@ -414,7 +919,17 @@ fn param_name(param: Option<&Param>) -> String {
     }
 }
 
-fn format_value(val: &Value) -> String {
+/// Format a param value. With the param's metadata, enum values show their
+/// variant name instead of the raw index.
+pub fn range_name(range: &Range) -> &'static str {
+    match range {
+        Range::_0_10V => "0–10V",
+        Range::_0_5V => "0–5V",
+        Range::_Neg5_5V => "±5V",
+    }
+}
+
+pub fn format_value(val: &Value, param: Option<&Param>) -> String {
     match val {
         Value::Int(v) => format!("{}", v),
         Value::Float(v) => format!("{:.1}", v),
@@ -425,18 +940,17 @@ fn format_value(val: &Value) -> String {
                 "○".dimmed().to_string()
             }
         }
-        Value::Enum(v) => format!("option {}", v),
-        Value::Curve(c) => format!("{:?}", c),
-        Value::Waveform(w) => format!("{:?}", w),
+        Value::Enum(v) => match param {
+            Some(Param::Enum { variants, .. }) if *v < variants.len() => variants[*v].clone(),
+            _ => format!("option {}", v),
+        },
+        Value::Curve(c) => format!("{} {:?}", curve_glyph(c).dimmed(), c),
+        Value::Waveform(w) => format!("{} {:?}", waveform_glyph(w).dimmed(), w),
         Value::Color(c) => {
             let style = style_for_color(c);
-            format!("{} {:?}", "●".style(style), c)
+            format!("{} {}", "●".style(style), color_name(c))
         }
-        Value::Range(r) => match r {
-            Range::_0_10V => "0–10V".to_string(),
-            Range::_0_5V => "0–5V".to_string(),
-            Range::_Neg5_5V => "±5V".to_string(),
-        },
+        Value::Range(r) => range_name(r).to_string(),
         Value::Note(n) => format!("{:?}", n),
         Value::MidiCc(MidiCc(cc)) => format!("CC {}", cc),
         Value::MidiChannel(MidiChannel(ch)) => format!("Ch {}", ch),
@@ -472,3 +986,387 @@ fn format_value(val: &Value) -> String {
         Value::VoltPerOct(v) => format!("{:?}", v),
     }
 }
+
+// ── Pinned params ──
+
+/// One row of the `pinned` view: the value, or why it can't be shown.
+pub struct PinnedRow {
+    pub fader: String,
+    pub app: String,
+    pub param: String,
+    pub value: Result<String, String>,
+}
+
+pub fn print_pinned(rows: &[PinnedRow]) {
+    header(&t!("Pinned ({})", rows.len()));
+    line("");
+    for (i, pinned) in rows.iter().enumerate() {
+        let number = format!("{:>3}", i + 1);
+        let text = match &pinned.value {
+            Ok(value) => format!(
+                "  {}  {:>5}  {:<16} {:<20} {}",
+                number.dimmed(),
+                pinned.fader,
+                pinned.app,
+                pinned.param,
+                value.bold()
+            ),
+            Err(why) => format!(
+                "  {}  {:>5}  {:<16} {:<20} {}",
+                number.dimmed(),
+                pinned.fader,
+                pinned.app.dimmed(),
+                pinned.param.dimmed(),
+                format!("({})", why).yellow()
+            ),
+        };
+        let value = match &pinned.value {
+            Ok(value) => value.clone(),
+            Err(why) => format!("({})", why),
+        };
+        let cells = vec![
+            (i + 1).to_string(),
+            pinned.fader.clone(),
+            pinned.app.clone(),
+            pinned.param.clone(),
+            value,
+        ];
+        row(&["Pin", "Faders", "App", "Param", "Value"], cells, &text);
+    }
+}
+
+/// Print `param find` results: (faders, app, param, value) rows.
+pub fn print_param_matches(kind: &str, rows: &[(String, String, String, String)]) {
+    header(&t!("{} params ({})", kind, rows.len()));
+    line("");
+    if rows.is_empty() {
+        line(&format!("  {}", t!("(none in the layout)").dimmed()));
+        return;
+    }
+    for (fader, app, param, value) in rows {
+        let text = format!("  {:>5}  {:<16} {:<20} {}", fader, app, param, value.bold());
+        let cells = vec![fader.clone(), app.clone(), param.clone(), value.clone()];
+        row(&["Faders", "App", "Param", "Value"], cells, &text);
+    }
+}
+
+// ── Stats ──
+
+/// Rig summary for the `stats` command.
+pub struct RigStats {
+    pub channels_used: usize,
+    /// (app name, instance count), most used first
+    pub app_counts: Vec<(String, usize)>,
+    /// (start, size, app name, param count) per layout entry
+    pub slot_params: Vec<(usize, usize, String, usize)>,
+    /// Number of apps listening on each input: [usb, din]
+    pub midi_in: [usize; 2],
+    /// Number of apps sending to each output: [usb, out1, out2]
+    pub midi_out: [usize; 3],
+    /// (app name, times placed) from the audit log, most used first
+    pub history: Vec<(String, usize)>,
+}
+
+pub fn print_stats(stats: &RigStats) {
+    header(t!("Rig Stats"));
+
+    sub_header(t!("Channels"));
+    kv(t!("Used"), &format!("{}", stats.channels_used));
+    kv(t!("Free"), &format!("{}", GLOBAL_CHANNELS - stats.channels_used));
+
+    sub_header(t!("Apps"));
+    if stats.app_counts.is_empty() {
+        line(&format!("    {}", t!("(empty layout)").dimmed()));
+    }
+    for (name, count) in &stats.app_counts {
+        kv(name, &format!("{}×", count));
+    }
+
+    sub_header(t!("Params per slot"));
+    for (start, size, name, count) in &stats.slot_params {
+        let range = slots::range(*start, *size);
+        let text = format!("    {:>5}  {:<16} {}", range.dimmed(), name, count);
+        let cells = vec![range, name.clone(), count.to_string()];
+        row(&["Faders", "App", "Params"], cells, &text);
+    }
+
+    sub_header(t!("MIDI ports in use"));
+    for (label, count) in [t!("USB in"), t!("DIN in")].iter().zip(stats.midi_in) {
+        kv(label, &format!("{} app(s)", count));
+    }
+    for (label, count) in [t!("USB out"), t!("Out 1"), t!("Out 2")].iter().zip(stats.midi_out) {
+        kv(label, &format!("{} app(s)", count));
+    }
+
+    sub_header(t!("History (placements from change log)"));
+    if stats.history.is_empty() {
+        line(&format!("    {}", t!("(no layout changes recorded)").dimmed()));
+    }
+    for (name, count) in &stats.history {
+        kv(name, &format!("{}", count));
+    }
+}
+
+// ── Param history ──
+
+/// A param's value over a time window, one value per column.
+pub struct Sparkline {
+    pub columns: Vec<f64>,
+    pub min: f64,
+    pub max: f64,
+}
+
+/// One logged change in `param history`.
+pub struct HistoryPoint {
+    pub time: std::time::SystemTime,
+    /// None if the value before wasn't known when it was logged.
+    pub old: Option<String>,
+    pub new: String,
+    pub command: String,
+}
+
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+pub fn print_param_history(
+    title: &str,
+    span: Duration,
+    graph: Option<&Sparkline>,
+    points: &[HistoryPoint],
+) {
+    header(title);
+    line("");
+    let span = humantime::format_duration(span).to_string();
+    if let Some(graph) = graph {
+        let range = graph.max - graph.min;
+        let spark: String = graph
+            .columns
+            .iter()
+            .map(|v| {
+                let level = if range > 0.0 {
+                    ((v - graph.min) / range * (BARS.len() - 1) as f64).round()
+                } else {
+                    0.0
+                };
+                BARS[(level.max(0.0) as usize).min(BARS.len() - 1)]
+            })
+            .collect();
+        let width = graph.columns.len();
+        line(&format!("  {:>8} {}", format!("{}", graph.max).dimmed(), "┐".dimmed()));
+        line(&format!("  {:>8} {}", "", spark.cyan()));
+        line(&format!("  {:>8} {}", format!("{}", graph.min).dimmed(), "┘".dimmed()));
+        let from = format!("{} ago", span);
+        line(&format!(
+            "  {:>8} {}{:>w$}",
+            "",
+            from.dimmed(),
+            "now".dimmed(),
+            w = width.saturating_sub(from.chars().count())
+        ));
+        line("");
+    }
+    if points.is_empty() {
+        line(&format!("  {}", format!("(no changes in the last {})", span).dimmed()));
+        return;
+    }
+    for point in points {
+        let ts = humantime::format_rfc3339_seconds(point.time).to_string();
+        let old = point.old.as_deref().unwrap_or("?");
+        let text = format!(
+            "  {}  {} {} {}  {}",
+            ts.dimmed(),
+            old,
+            "→".dimmed(),
+            point.new.bold(),
+            point.command.dimmed()
+        );
+        let cells = vec![ts, old.to_string(), point.new.clone(), point.command.clone()];
+        row(&["Time", "Old", "New", "Command"], cells, &text);
+    }
+}
+
+// ── Connection health ──
+
+/// The connection block of `status`: bus details, ping round trips out of
+/// `pings` tried, and the connection's counters so far.
+pub fn print_health(
+    usb: &UsbInfo,
+    pacing: Pacing,
+    health: &Health,
+    rtts: &[Duration],
+    pings: usize,
+) {
+    header(t!("Connection"));
+    kv(t!("USB speed"), usb.speed.unwrap_or("unknown"));
+    if let Some(serial) = &usb.serial {
+        kv(t!("Serial"), serial);
+    }
+    kv(t!("Device release"), &usb.release);
+    let pacing = if pacing.is_none() {
+        "none".to_string()
+    } else {
+        format!(
+            "{} ms between messages, {} ms between packets",
+            pacing.message_gap.as_millis(),
+            pacing.chunk_gap.as_millis()
+        )
+    };
+    kv(t!("Write pacing"), &pacing);
+    let ms = |d: &Duration| d.as_secs_f64() * 1000.0;
+    let lost = pings - rtts.len();
+    let ping = match (rtts.iter().min(), rtts.iter().max()) {
+        (Some(min), Some(max)) => {
+            let avg = rtts.iter().map(ms).sum::<f64>() / rtts.len() as f64;
+            format!(
+                "{:.1} ms avg ({:.1}–{:.1} ms over {})",
+                avg,
+                ms(min),
+                ms(max),
+                pings
+            )
+        }
+        _ => "no answer".red().to_string(),
+    };
+    let lost = match lost {
+        0 => String::new(),
+        n => format!(", {} lost", n).red().to_string(),
+    };
+    kv(t!("Ping"), &format!("{}{}", ping, lost));
+    let last = health
+        .last_rtt
+        .map_or_else(|| "-".to_string(), |d| format!("{:.1} ms", ms(&d)));
+    kv(t!("Last ping"), &last);
+    let count = |n: u64, what: &str| {
+        let text = format!("{} {}", n, what);
+        if n == 0 {
+            text
+        } else {
+            text.yellow().to_string()
+        }
+    };
+    kv(
+        t!("This session"),
+        &format!(
+            "{} sent, {}, {}",
+            health.sent,
+            count(health.errors, "errors"),
+            count(health.timeouts, "timeouts")
+        ),
+    );
+}
+
+// ── Conformance ──
+
+/// The conformance report: a count per group of checks, with every failure
+/// and why. The caller reports the number of failures.
+pub fn print_conformance(outcomes: &[conformance::Outcome]) {
+    header(t!("Conformance"));
+    let mut groups: Vec<&str> = Vec::new();
+    for outcome in outcomes {
+        if !groups.contains(&outcome.group) {
+            groups.push(outcome.group);
+        }
+    }
+    for group in groups {
+        let checks: Vec<_> = outcomes.iter().filter(|o| o.group == group).collect();
+        let passed = checks.iter().filter(|o| o.passed()).count();
+        let count = t!("{}/{} passed", passed, checks.len());
+        let count = if passed == checks.len() {
+            count.green().to_string()
+        } else {
+            count.red().to_string()
+        };
+        render::with(|r| r.subsection(group, &count));
+        for check in checks {
+            if let Some(failure) = &check.failure {
+                let mark = "✗".red();
+                let text = format!("    {} {}\n      {}", mark, check.name, failure.dimmed());
+                let cells = vec![check.name.clone(), failure.clone()];
+                row(&["Check", "Failure"], cells, &text);
+            }
+        }
+    }
+
+    if outcomes.iter().all(|o| o.passed()) {
+        line("");
+        line(&format!("{}", t!("All {} checks passed.", outcomes.len()).green()));
+    }
+}
+
+// ── Frames ──
+
+fn hex(bytes: &[u8]) -> String {
+    let hex: Vec<String> = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    hex.join(" ")
+}
+
+/// One frame for `debug dump-stream`: the bytes on the wire, the payload
+/// inside them, and as much of a message as could be read out of it.
+pub fn print_frame(number: usize, at: Duration, wire: &[u8], inspection: &frame::Inspection) {
+    let title = format!("#{}", number);
+    let detail = format!("{}  {} bytes", format!("+{:.3}s", at.as_secs_f64()).dimmed(), wire.len());
+    render::with(|r| r.subsection(&title, &detail));
+    kv("wire", &hex(wire));
+    match &inspection.payload {
+        Ok(payload) => kv("payload", &hex(payload)),
+        Err(why) => kv("payload", &why.red().to_string()),
+    }
+    if let (Some(declared), Ok(payload)) = (inspection.declared_len, &inspection.payload) {
+        let length = format!("prefix says {}, payload is {}", declared, payload.len());
+        kv("length", &length.yellow().to_string());
+    }
+    if let Some((index, name)) = inspection.variant {
+        let name = name.map_or_else(|| "unknown to this build".yellow().to_string(), str::to_string);
+        kv("variant", &format!("{} {}", index, name));
+    }
+    match &inspection.message {
+        Ok(msg) => kv("decoded", &format!("{:?}", msg)),
+        Err(why) => kv("decoded", &format!("failed: {}", why).red().to_string()),
+    }
+    for shape in &inspection.shapes {
+        kv("reads as", shape);
+    }
+}
+
+// ── Events ──
+
+/// Print one unsolicited device message with a timestamp.
+pub fn print_event(msg: &ConfigMsgOut) {
+    let ts = humantime::format_rfc3339_seconds(std::time::SystemTime::now()).to_string();
+    let summary = match msg {
+        ConfigMsgOut::Layout(_) => t!("Layout changed").to_string(),
+        ConfigMsgOut::GlobalConfig(_) => t!("Global config changed").to_string(),
+        ConfigMsgOut::AppState(layout_id, values) => {
+            t!("Params changed (layout_id={}, {} values)", layout_id, values.len())
+        }
+        other => format!("{:?}", other),
+    };
+    let text = format!("  {}  {}", ts.dimmed(), summary);
+    row(&["Time", "Event"], vec![ts, summary], &text);
+}
+
+// ── Audit log ──
+
+pub fn print_audit_log(entries: &[audit::Entry]) {
+    header(&t!("Change Log ({})", entries.len()));
+
+    if entries.is_empty() {
+        line(&format!("  {}", t!("(no changes recorded)").dimmed()));
+        return;
+    }
+
+    for entry in entries {
+        let ts = humantime::format_rfc3339_seconds(entry.time()).to_string();
+        let target = entry
+            .layout_id
+            .map(|id| format!(" layout_id={}", id))
+            .unwrap_or_default();
+        let detail = format!("{}{}  {}", ts, target, entry.command).dimmed().to_string();
+        render::with(|r| r.subsection(&entry.message, &detail));
+        if entry.changes.is_empty() {
+            line(&format!("    {}", t!("(no field changes)").dimmed()));
+        }
+        for change in &entry.changes {
+            kv(&change.field, &format!("{} {} {}", change.old, "→".dimmed(), change.new));
+        }
+    }
+}
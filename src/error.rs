@@ -0,0 +1,251 @@
+// Errors reported by (or about) the device, as opposed to host-side failures.
+//
+// Every one names the request it answers, so "the device said no" can be
+// told apart from a broken connection and traced back to what was sent.
+// Callers that need to react to them can `downcast_ref::<DeviceError>()`,
+// or `downcast_ref::<ConnectionError>()` for a connection that failed.
+// Input refused before anything is sent is an `Invalid`.
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::protocol::{ConfigMsgIn, ConfigMsgOut};
+
+/// The device answered a request with something other than its reply.
+#[derive(Clone, Debug)]
+pub struct DeviceError {
+    /// Name of the request that triggered it, e.g. "SetAppParams".
+    pub request: String,
+    pub kind: DeviceErrorKind,
+}
+
+#[derive(Clone, Debug)]
+pub enum DeviceErrorKind {
+    /// The firmware refused the request with an error code and reason.
+    Rejected { code: u8, reason: String },
+    /// A message variant this build doesn't know, which didn't read as an
+    /// error either. Usually means the firmware is newer than the CLI.
+    Unknown { variant: u32, body: Vec<u8> },
+    /// A known message that isn't the reply to the request.
+    Unexpected(String),
+    /// The device applied a write differently than sent (a rearranged
+    /// layout, a clamped param), one line per difference. Only reported in
+    /// strict mode; otherwise the correction is silently accepted.
+    Corrected(Vec<String>),
+    /// The values a write was based on moved on the device (another program,
+    /// or a hand on the unit) since they were read, one line per difference.
+    /// Nothing was written.
+    Changed(Vec<String>),
+    /// The request encodes to a longer frame than the CLI sends (`size` and
+    /// `limit` in bytes; see `usb::MAX_FRAME_SIZE`). Nothing was sent.
+    TooLarge { size: usize, limit: usize },
+}
+
+impl fmt::Display for DeviceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            DeviceErrorKind::Rejected { .. } => {
+                write!(f, "Device rejected {}: {}", self.request, self.kind)
+            }
+            DeviceErrorKind::Corrected(_) => {
+                write!(f, "Device corrected {} (strict mode): {}", self.request, self.kind)
+            }
+            DeviceErrorKind::Changed(_) => write!(
+                f,
+                "State changed on the device since it was read; {} not sent: {}",
+                self.request, self.kind
+            ),
+            DeviceErrorKind::TooLarge { .. } => {
+                write!(f, "{} not sent: {}", self.request, self.kind)
+            }
+            _ => write!(f, "Device answered {} with {}", self.request, self.kind),
+        }
+    }
+}
+
+impl fmt::Display for DeviceErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeviceErrorKind::Rejected { code, reason } => write!(f, "{} (error {})", reason, code),
+            DeviceErrorKind::Unknown { variant, body } => {
+                let hex: Vec<String> = body.iter().map(|b| format!("{:02x}", b)).collect();
+                write!(
+                    f,
+                    "unknown message #{} [{}] (firmware newer than this CLI?)",
+                    variant,
+                    hex.join(" ")
+                )
+            }
+            DeviceErrorKind::Unexpected(reply) => f.write_str(reply),
+            DeviceErrorKind::TooLarge { size, limit } => write!(
+                f,
+                "it's a {}-byte frame, and the CLI sends at most {}",
+                size, limit
+            ),
+            DeviceErrorKind::Corrected(changes) | DeviceErrorKind::Changed(changes) => {
+                for change in changes {
+                    write!(f, "\n  {}", change)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for DeviceError {}
+
+/// The device couldn't be reached over USB, or stopped answering.
+#[derive(Clone, Debug)]
+pub enum ConnectionError {
+    /// No Faderpunk on the bus.
+    NotFound,
+    /// A Faderpunk without the vendor-class (WebUSB) interface.
+    NoInterface,
+    /// More than one vendor-class interface could be the configurator; each
+    /// candidate described as "NUMBER (name, subclass/protocol)".
+    AmbiguousInterface(Vec<String>),
+    /// The interface asked for (--interface, FP_INTERFACE) isn't on the
+    /// device; with the interfaces it does have.
+    MissingInterface { wanted: String, available: Vec<String> },
+    /// The configurator interface (this number) is held by something else,
+    /// and couldn't be taken over.
+    Busy { interface: u8, holder: InterfaceHolder },
+    /// The connection dropped (unplugged, reset, read error), for this reason.
+    Closed(String),
+    /// No reply to a request within the time allowed.
+    Timeout { request: String, after: Duration },
+}
+
+/// What holds an interface the CLI couldn't claim, as far as can be told.
+#[derive(Clone, Debug)]
+pub enum InterfaceHolder {
+    /// A kernel driver that couldn't be detached (no permission), with the
+    /// interface's sysfs name, e.g. "1-1.2:1.0".
+    Driver { driver: String, interface: String },
+    /// Other processes with the device open, each "PID (name)".
+    Processes(Vec<String>),
+    /// Nothing this platform lets us see.
+    Unknown,
+}
+
+impl fmt::Display for ConnectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionError::NotFound => {
+                f.write_str("Faderpunk not found — is it connected via USB?")
+            }
+            ConnectionError::NoInterface => f.write_str("No WebUSB interface found on device"),
+            ConnectionError::AmbiguousInterface(candidates) => write!(
+                f,
+                "The device has more than one vendor interface: {}",
+                candidates.join("; ")
+            ),
+            ConnectionError::MissingInterface { wanted, available } => write!(
+                f,
+                "The device has no interface {} (it has: {})",
+                wanted,
+                available.join("; ")
+            ),
+            ConnectionError::Busy { interface, holder } => match holder {
+                InterfaceHolder::Driver { driver, .. } => write!(
+                    f,
+                    "Interface {} is bound to the kernel driver '{}', which couldn't be detached",
+                    interface, driver
+                ),
+                InterfaceHolder::Processes(processes) => write!(
+                    f,
+                    "Interface {} is in use by another program: {}",
+                    interface,
+                    processes.join(", ")
+                ),
+                InterfaceHolder::Unknown => {
+                    write!(f, "Interface {} is in use by another program or driver", interface)
+                }
+            },
+            ConnectionError::Closed(reason) => write!(f, "Device connection closed: {}", reason),
+            ConnectionError::Timeout { request, after } => {
+                write!(f, "No reply to {} within {:?}", request, after)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConnectionError {}
+
+/// Input refused before anything was sent: an argument, value, slot or
+/// layout that can't be right. Only the message; there's no request to name.
+#[derive(Clone, Debug)]
+pub struct Invalid(pub String);
+
+impl fmt::Display for Invalid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Invalid {}
+
+/// Error for input the command refuses.
+pub fn invalid(message: impl fmt::Display) -> anyhow::Error {
+    Invalid(message.to_string()).into()
+}
+
+/// `bail!` for input the command refuses: returns an `Invalid` error, from a
+/// format string or from any message.
+#[macro_export]
+macro_rules! invalid {
+    ($fmt:literal $(, $arg:expr)* $(,)?) => {
+        return Err($crate::error::invalid(format!($fmt $(, $arg)*)))
+    };
+    ($message:expr $(,)?) => {
+        return Err($crate::error::invalid($message))
+    };
+}
+
+/// Error for a request that got no reply within `after`.
+pub fn timeout(request: &ConfigMsgIn, after: Duration) -> anyhow::Error {
+    ConnectionError::Timeout {
+        request: message_name(request),
+        after,
+    }
+    .into()
+}
+
+/// Error for a reply that doesn't answer `request`.
+pub fn unexpected(request: &ConfigMsgIn, reply: &ConfigMsgOut) -> anyhow::Error {
+    DeviceError {
+        request: message_name(request),
+        kind: DeviceErrorKind::Unexpected(message_name(reply)),
+    }
+    .into()
+}
+
+/// Error for a write not sent because what it was based on changed, with
+/// one line per difference.
+pub fn changed(request: &ConfigMsgIn, changes: Vec<String>) -> anyhow::Error {
+    DeviceError {
+        request: message_name(request),
+        kind: DeviceErrorKind::Changed(changes),
+    }
+    .into()
+}
+
+/// Error for a request not sent because its frame is longer than the
+/// device takes.
+pub fn too_large(request: &ConfigMsgIn, size: usize, limit: usize) -> anyhow::Error {
+    DeviceError {
+        request: message_name(request),
+        kind: DeviceErrorKind::TooLarge { size, limit },
+    }
+    .into()
+}
+
+/// Variant name of a message, e.g. "SetAppParams", without its contents.
+pub fn message_name(msg: &impl fmt::Debug) -> String {
+    let debug = format!("{:?}", msg);
+    debug
+        .split([' ', '(', '{'])
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
@@ -0,0 +1,44 @@
+// Exit codes, so scripts can branch on what went wrong without reading the
+// output. Like the hints, the code comes from the first typed error in the
+// chain (a connection failure, an error from the device, input the command
+// refused); anything else is a plain failure.
+//
+//   0  success
+//   1  any other failure
+//   2  no device: not found, no configurator interface, or held by another program
+//   3  invalid input: arguments, values, slots or layouts refused before sending
+//   4  protocol error: the device rejected a request or answered it wrongly
+//   5  connection lost: unplugged, reset or stopped answering mid-command
+//   6  stale state: what a write was based on changed on the device; nothing was written
+
+use faderpunk_cli::error::{ConnectionError, DeviceError, DeviceErrorKind, Invalid};
+
+pub const FAILURE: u8 = 1;
+pub const NO_DEVICE: u8 = 2;
+pub const INVALID: u8 = 3;
+pub const PROTOCOL: u8 = 4;
+pub const CONNECTION_LOST: u8 = 5;
+pub const STALE: u8 = 6;
+
+/// The exit code for a failed command.
+pub fn of(err: &anyhow::Error) -> u8 {
+    err.chain()
+        .find_map(|cause| {
+            if let Some(e) = cause.downcast_ref::<ConnectionError>() {
+                Some(match e {
+                    ConnectionError::Closed(_) | ConnectionError::Timeout { .. } => CONNECTION_LOST,
+                    _ => NO_DEVICE,
+                })
+            } else if let Some(e) = cause.downcast_ref::<DeviceError>() {
+                Some(match e.kind {
+                    DeviceErrorKind::Changed(_) => STALE,
+                    _ => PROTOCOL,
+                })
+            } else if cause.is::<Invalid>() || cause.is::<clap::Error>() {
+                Some(INVALID)
+            } else {
+                None
+            }
+        })
+        .unwrap_or(FAILURE)
+}
@@ -0,0 +1,128 @@
+// Starred and recently placed apps, listed first wherever an app is picked
+// (`apps`, the layout designer, the init wizard, completions) so the usual
+// choices don't mean scrolling the whole list.
+//
+// Kept by name in `<data dir>/favorites.json`, so they survive firmware
+// updates that renumber apps.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::display::AppInfo;
+use crate::paths;
+
+const FAVORITES_FILE: &str = "favorites.json";
+
+/// Recently placed apps remembered.
+const RECENT_MAX: usize = 5;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Favorites {
+    /// Starred with `apps star`, in the order starred.
+    pub starred: Vec<String>,
+    /// Placed on the strip lately, most recent first.
+    pub recent: Vec<String>,
+}
+
+/// Why an app is listed early.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mark {
+    Starred,
+    Recent,
+}
+
+impl Mark {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Mark::Starred => "★",
+            Mark::Recent => "↺",
+        }
+    }
+}
+
+fn path() -> Result<PathBuf> {
+    Ok(paths::data_dir()?.join(FAVORITES_FILE))
+}
+
+fn contains(names: &[String], name: &str) -> bool {
+    names.iter().any(|n| n.eq_ignore_ascii_case(name))
+}
+
+impl Favorites {
+    /// The saved favorites. A missing file means none.
+    pub fn load() -> Result<Self> {
+        let path = path()?;
+        let data = match std::fs::read_to_string(&path) {
+            Ok(d) => d,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        serde_json::from_str(&data)
+            .with_context(|| format!("Invalid favorites in {}", path.display()))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = path()?;
+        std::fs::write(&path, serde_json::to_string_pretty(self)? + "\n")
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.starred.is_empty() && self.recent.is_empty()
+    }
+
+    /// Star an app. Returns false if it already was.
+    pub fn star(&mut self, name: &str) -> bool {
+        if contains(&self.starred, name) {
+            return false;
+        }
+        self.starred.push(name.to_string());
+        true
+    }
+
+    /// Unstar an app. Returns false if it wasn't starred.
+    pub fn unstar(&mut self, name: &str) -> bool {
+        let before = self.starred.len();
+        self.starred.retain(|n| !n.eq_ignore_ascii_case(name));
+        self.starred.len() != before
+    }
+
+    /// Apps in picking order: starred, then recent, then the rest as the
+    /// device lists them.
+    pub fn order<'a>(&self, apps: &'a [AppInfo]) -> Vec<(&'a AppInfo, Option<Mark>)> {
+        let find = |name: &String| apps.iter().find(|a| a.name.eq_ignore_ascii_case(name));
+        let mut out: Vec<(&AppInfo, Option<Mark>)> = Vec::with_capacity(apps.len());
+        for (names, mark) in [(&self.starred, Mark::Starred), (&self.recent, Mark::Recent)] {
+            for app in names.iter().filter_map(find) {
+                if !out.iter().any(|(a, _)| a.app_id == app.app_id) {
+                    out.push((app, Some(mark)));
+                }
+            }
+        }
+        for app in apps {
+            if !out.iter().any(|(a, _)| a.app_id == app.app_id) {
+                out.push((app, None));
+            }
+        }
+        out
+    }
+}
+
+/// Remember apps just placed on the strip. Failing to save only warns: the
+/// placement itself worked.
+pub fn record_used(names: &[&str]) {
+    let result = Favorites::load().and_then(|mut favorites| {
+        for name in names.iter().rev() {
+            favorites.recent.retain(|n| !n.eq_ignore_ascii_case(name));
+            favorites.recent.insert(0, name.to_string());
+        }
+        favorites.recent.truncate(RECENT_MAX);
+        favorites.save()
+    });
+    if let Err(e) = result {
+        eprintln!("warning: couldn't update recent apps: {:#}", e);
+    }
+}
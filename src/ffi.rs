@@ -0,0 +1,291 @@
+// C API for embedding the transport in a DAW plugin (VST/CLAP) or a Max
+// external. Built into the cdylib; the declarations live in
+// include/faderpunk.h.
+//
+// Device state crosses the boundary as JSON strings in the same shape as the
+// CLI's snapshot files, so the C side needs no mirror of the protocol types.
+// Strings returned by the library must be released with `fp_string_free`.
+// Functions that can fail return NULL or -1 and leave a message for
+// `fp_last_error`.
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char, c_int, c_void};
+use std::panic::{self, AssertUnwindSafe};
+
+use anyhow::{Context, Result, anyhow, bail};
+use tokio::runtime::Runtime;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+
+use crate::error;
+use crate::protocol::{APP_MAX_PARAMS, ConfigMsgIn, ConfigMsgOut, Value};
+use crate::usb::FaderpunkDevice;
+
+/// An open connection, handed to C as an opaque pointer.
+pub struct FpDevice {
+    runtime: Runtime,
+    dev: FaderpunkDevice,
+    events: Option<JoinHandle<()>>,
+}
+
+/// Called with each device event as JSON, from a library thread.
+pub type FpEventCallback = extern "C" fn(event: *const c_char, user_data: *mut c_void);
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_error(err: &anyhow::Error) {
+    let msg = CString::new(format!("{:#}", err).replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(msg));
+}
+
+/// Run `f`, turning an error into `fail` plus a message for `fp_last_error`.
+/// A panic is caught and reported the same way, as unwinding into C is
+/// undefined behaviour.
+fn guarded<T>(fail: T, f: impl FnOnce() -> Result<T>) -> T {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(v)) => v,
+        Ok(Err(e)) => {
+            set_error(&e);
+            fail
+        }
+        Err(payload) => {
+            let why = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown cause".to_string());
+            set_error(&anyhow!("Internal error: {}", why));
+            fail
+        }
+    }
+}
+
+fn to_c_string(json: &serde_json::Value) -> Result<*mut c_char> {
+    Ok(CString::new(json.to_string())?.into_raw())
+}
+
+/// The handle behind a pointer from `fp_open`. The handle lives for the
+/// whole plugin session, so cached reads are dropped on every call: each
+/// call sees the device as it is now.
+///
+/// # Safety
+/// `dev` must be NULL or a pointer returned by `fp_open` and not yet closed.
+unsafe fn device<'a>(dev: *mut FpDevice) -> Result<&'a mut FpDevice> {
+    // SAFETY: guaranteed by the caller
+    let dev = unsafe { dev.as_mut() }.context("Device handle is NULL")?;
+    dev.dev.clear_cache();
+    Ok(dev)
+}
+
+/// Message describing why the last call on this thread failed, or NULL.
+/// Valid until the next failing call on the same thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn fp_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(std::ptr::null(), |s| s.as_ptr()))
+}
+
+/// Connect to the first Faderpunk found. Returns NULL on failure.
+#[unsafe(no_mangle)]
+pub extern "C" fn fp_open() -> *mut FpDevice {
+    guarded(std::ptr::null_mut(), || {
+        let runtime = Runtime::new().context("Failed to start the I/O runtime")?;
+        // The read task is spawned onto the runtime, so open inside it
+        let dev = runtime.block_on(async { FaderpunkDevice::open() })?;
+        Ok(Box::into_raw(Box::new(FpDevice {
+            runtime,
+            dev,
+            events: None,
+        })))
+    })
+}
+
+/// Close a connection opened with `fp_open`. Stops event delivery first, so
+/// the callback is not called after this returns. NULL is ignored.
+///
+/// # Safety
+/// `dev` must be NULL or a pointer returned by `fp_open` and not yet closed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fp_close(dev: *mut FpDevice) {
+    if dev.is_null() {
+        return;
+    }
+    guarded((), || {
+        // SAFETY: guaranteed by the caller
+        let dev = unsafe { Box::from_raw(dev) };
+        if let Some(task) = &dev.events {
+            task.abort();
+        }
+        drop(dev);
+        Ok(())
+    })
+}
+
+/// Release a string returned by this library. NULL is ignored.
+///
+/// # Safety
+/// `s` must be NULL or a string returned by this library and not yet freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fp_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        // SAFETY: guaranteed by the caller
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+/// The fader layout as JSON: 16 entries, each `null` or
+/// `[app_id, channels, layout_id]` at an app's first fader.
+///
+/// # Safety
+/// `dev` must be a live handle from `fp_open`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fp_get_layout(dev: *mut FpDevice) -> *mut c_char {
+    guarded(std::ptr::null_mut(), || {
+        // SAFETY: guaranteed by the caller
+        let dev = unsafe { device(dev) }?;
+        let layout = match dev
+            .runtime
+            .block_on(dev.dev.send_receive(&ConfigMsgIn::GetLayout))?
+        {
+            ConfigMsgOut::Layout(l) => l,
+            other => return Err(error::unexpected(&ConfigMsgIn::GetLayout, &other)),
+        };
+        to_c_string(&serde_json::to_value(&layout)?)
+    })
+}
+
+/// Current param values of the app instance `layout_id`, as a JSON array.
+///
+/// # Safety
+/// `dev` must be a live handle from `fp_open`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fp_get_params(dev: *mut FpDevice, layout_id: u8) -> *mut c_char {
+    guarded(std::ptr::null_mut(), || {
+        // SAFETY: guaranteed by the caller
+        let dev = unsafe { device(dev) }?;
+        let values = dev.runtime.block_on(get_params(&mut dev.dev, layout_id))?;
+        to_c_string(&serde_json::to_value(&values)?)
+    })
+}
+
+/// Value of one param of app instance `layout_id`, as JSON.
+///
+/// # Safety
+/// `dev` must be a live handle from `fp_open`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fp_get_param(
+    dev: *mut FpDevice,
+    layout_id: u8,
+    index: usize,
+) -> *mut c_char {
+    guarded(std::ptr::null_mut(), || {
+        // SAFETY: guaranteed by the caller
+        let dev = unsafe { device(dev) }?;
+        let values = dev.runtime.block_on(get_params(&mut dev.dev, layout_id))?;
+        let value = values
+            .get(index)
+            .with_context(|| format!("App {} has no param {}", layout_id, index))?;
+        to_c_string(&serde_json::to_value(value)?)
+    })
+}
+
+/// Set one param of app instance `layout_id` from its JSON value (the same
+/// shape `fp_get_param` returns). Returns 0 on success, -1 on failure.
+///
+/// # Safety
+/// `dev` must be a live handle from `fp_open`; `value_json` must be a
+/// NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fp_set_param(
+    dev: *mut FpDevice,
+    layout_id: u8,
+    index: usize,
+    value_json: *const c_char,
+) -> c_int {
+    guarded(-1, || {
+        // SAFETY: guaranteed by the caller
+        let dev = unsafe { device(dev) }?;
+        if value_json.is_null() {
+            bail!("Value is NULL");
+        }
+        // SAFETY: guaranteed by the caller
+        let json = unsafe { CStr::from_ptr(value_json) }.to_str()?;
+        let value: Value = serde_json::from_str(json).context("Invalid param value")?;
+
+        dev.runtime.block_on(async {
+            let current = get_params(&mut dev.dev, layout_id).await?;
+            if index >= current.len().min(APP_MAX_PARAMS) {
+                bail!("App {} has no param {}", layout_id, index);
+            }
+            // The firmware replaces all values at once
+            let mut values: [Option<Value>; APP_MAX_PARAMS] = Default::default();
+            for (slot, v) in values.iter_mut().zip(&current) {
+                *slot = Some(*v);
+            }
+            values[index] = Some(value);
+            dev.dev.write_params(layout_id, &current, values, false).await?;
+            Ok(0)
+        })
+    })
+}
+
+/// Deliver every event the device sends (param changes made on the
+/// hardware, layout changes) to `callback` as JSON. The JSON string is only
+/// valid during the call. Replaces any earlier callback; pass NULL to stop.
+/// Returns 0 on success, -1 on failure.
+///
+/// # Safety
+/// `dev` must be a live handle from `fp_open`. `callback` runs on a library
+/// thread and must be safe to call with `user_data` from there.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fp_set_event_callback(
+    dev: *mut FpDevice,
+    callback: Option<FpEventCallback>,
+    user_data: *mut c_void,
+) -> c_int {
+    guarded(-1, || {
+        // SAFETY: guaranteed by the caller
+        let dev = unsafe { device(dev) }?;
+        if let Some(task) = dev.events.take() {
+            task.abort();
+        }
+        let Some(callback) = callback else {
+            return Ok(0);
+        };
+
+        struct UserData(*mut c_void);
+        // SAFETY: the caller promised the pointer may be used from our thread
+        unsafe impl Send for UserData {}
+        let user_data = UserData(user_data);
+
+        let mut events = dev.dev.subscribe();
+        dev.events = Some(dev.runtime.spawn(async move {
+            let user_data = user_data;
+            loop {
+                match events.recv().await {
+                    Ok(msg) => {
+                        let Ok(json) = serde_json::to_string(&msg) else {
+                            continue;
+                        };
+                        let Ok(json) = CString::new(json) else {
+                            continue;
+                        };
+                        callback(json.as_ptr(), user_data.0);
+                    }
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return,
+                }
+            }
+        }));
+        Ok(0)
+    })
+}
+
+async fn get_params(dev: &mut FaderpunkDevice, layout_id: u8) -> Result<Vec<Value>> {
+    let msg = ConfigMsgIn::GetAppParams { layout_id };
+    match dev.send_receive(&msg).await? {
+        ConfigMsgOut::AppState(_, values) => Ok(values),
+        other => Err(error::unexpected(&msg, &other)),
+    }
+}
@@ -0,0 +1,240 @@
+// Wire framing shared by every transport: postcard-serialized messages with a
+// length prefix, COBS encoded and delimited by a zero byte.
+//
+// Wire format: [2-byte big-endian payload length] [postcard payload] → COBS encode → [0x00 delimiter]
+
+use std::fmt::Debug;
+
+use anyhow::{Context, Result, bail};
+use serde::de::DeserializeOwned;
+
+use crate::error::DeviceErrorKind;
+use crate::protocol::{ConfigMsgIn, ConfigMsgOut, Value};
+
+pub const DELIMITER: u8 = 0x00;
+
+/// ConfigMsgOut variants this build knows, by index. A higher variant index
+/// is a message added to the firmware since, not corruption.
+const VARIANT_NAMES: [&str; 7] = [
+    "Pong",
+    "BatchMsgStart",
+    "BatchMsgEnd",
+    "GlobalConfig",
+    "Layout",
+    "AppConfig",
+    "AppState",
+];
+const KNOWN_VARIANTS: u32 = VARIANT_NAMES.len() as u32;
+
+/// One frame from the device.
+#[allow(clippy::large_enum_variant)] // short-lived; not worth boxing
+pub enum Decoded {
+    Message(ConfigMsgOut),
+    /// A message this build can't decode; see `DeviceErrorKind`.
+    Error(DeviceErrorKind),
+}
+
+/// Encode a message as one complete frame, delimiter included.
+pub fn encode(msg: &ConfigMsgIn) -> Result<Vec<u8>> {
+    let serialized = postcard::to_allocvec(msg).context("Failed to serialize message")?;
+
+    // Prepend 2-byte big-endian length
+    let payload_len = serialized.len();
+    if payload_len > u16::MAX as usize {
+        bail!(
+            "{} is {} bytes, more than a frame's length prefix can describe",
+            crate::error::message_name(msg),
+            payload_len
+        );
+    }
+    let mut with_len = Vec::with_capacity(payload_len + 2);
+    with_len.push(((payload_len >> 8) & 0xFF) as u8);
+    with_len.push((payload_len & 0xFF) as u8);
+    with_len.extend_from_slice(&serialized);
+
+    // COBS encode
+    let mut cobs_buf = vec![0u8; with_len.len() + with_len.len() / 254 + 2];
+    let cobs_len = cobs::try_encode(&with_len, &mut cobs_buf)
+        .map_err(|_| anyhow::anyhow!("COBS encoding failed"))?;
+
+    // Append frame delimiter
+    let mut frame = Vec::with_capacity(cobs_len + 1);
+    frame.extend_from_slice(&cobs_buf[..cobs_len]);
+    frame.push(DELIMITER);
+    Ok(frame)
+}
+
+/// Decode one COBS frame (delimiter already stripped) into a message.
+///
+/// A variant newer than this build is read as an error report: libfp has no
+/// error message yet, and one appended as `Error(u8, &str)` (code, reason)
+/// decodes that way. Anything else unknown is passed on as raw bytes.
+pub fn decode(frame: &[u8]) -> Result<Decoded> {
+    let mut decode_buf = frame.to_vec();
+    let decoded_len = cobs::decode_in_place(&mut decode_buf)
+        .map_err(|_| anyhow::anyhow!("COBS decode failed"))?;
+
+    if decoded_len < 2 {
+        bail!("Corrupted message (too short after COBS decode)");
+    }
+
+    // Skip the 2-byte length prefix, deserialize the rest
+    let payload = &decode_buf[2..decoded_len];
+    match postcard::from_bytes(payload) {
+        Ok(msg) => Ok(Decoded::Message(msg)),
+        Err(e) => match postcard::take_from_bytes::<u32>(payload) {
+            Ok((variant, body)) if variant >= KNOWN_VARIANTS => {
+                Ok(Decoded::Error(match postcard::from_bytes::<(u8, String)>(body) {
+                    Ok((code, reason)) => DeviceErrorKind::Rejected { code, reason },
+                    Err(_) => DeviceErrorKind::Unknown {
+                        variant,
+                        body: body.to_vec(),
+                    },
+                }))
+            }
+            _ => Err(e).context("Failed to deserialize device response"),
+        },
+    }
+}
+
+/// A frame taken apart as far as it goes, for looking at messages from
+/// firmware that's ahead of (or disagrees with) this build.
+#[derive(Debug)]
+pub struct Inspection {
+    /// Payload after COBS decoding, length prefix removed.
+    pub payload: Result<Vec<u8>, String>,
+    /// Length the prefix claims, when it disagrees with the payload.
+    pub declared_len: Option<usize>,
+    /// Variant index, and its name if this build knows it.
+    pub variant: Option<(u32, Option<&'static str>)>,
+    /// The message, or why it didn't deserialize.
+    pub message: Result<ConfigMsgOut, String>,
+    /// For a message that didn't deserialize: common shapes the bytes after
+    /// the variant index read as exactly, e.g. `(u8, String) = (3, "busy")`.
+    pub shapes: Vec<String>,
+}
+
+/// Take a frame (delimiter already stripped) apart without giving up at the
+/// first thing this build doesn't understand.
+pub fn inspect(frame: &[u8]) -> Inspection {
+    let mut buf = frame.to_vec();
+    let len = match cobs::decode_in_place(&mut buf) {
+        Ok(len) if len >= 2 => len,
+        Ok(_) => return Inspection::undecodable("too short for the length prefix"),
+        Err(_) => return Inspection::undecodable("COBS decode failed"),
+    };
+    let payload = &buf[2..len];
+    let declared = u16::from_be_bytes([buf[0], buf[1]]) as usize;
+    let message = postcard::from_bytes(payload).map_err(|e| e.to_string());
+
+    let mut variant = None;
+    let mut shapes = Vec::new();
+    if let Ok((index, body)) = postcard::take_from_bytes::<u32>(payload) {
+        variant = Some((index, VARIANT_NAMES.get(index as usize).copied()));
+        if message.is_err() {
+            shape::<()>(body, "()", &mut shapes);
+            shape::<u8>(body, "u8", &mut shapes);
+            shape::<u32>(body, "u32", &mut shapes);
+            shape::<bool>(body, "bool", &mut shapes);
+            shape::<String>(body, "String", &mut shapes);
+            shape::<(u8, String)>(body, "(u8, String)", &mut shapes);
+            shape::<Vec<u8>>(body, "Vec<u8>", &mut shapes);
+            shape::<Vec<Value>>(body, "Vec<Value>", &mut shapes);
+            shape::<(u8, Vec<Value>)>(body, "(u8, Vec<Value>)", &mut shapes);
+        }
+    }
+    Inspection {
+        payload: Ok(payload.to_vec()),
+        declared_len: (declared != payload.len()).then_some(declared),
+        variant,
+        message,
+        shapes,
+    }
+}
+
+impl Inspection {
+    fn undecodable(reason: &str) -> Self {
+        Inspection {
+            payload: Err(reason.to_string()),
+            declared_len: None,
+            variant: None,
+            message: Err("no payload".to_string()),
+            shapes: Vec::new(),
+        }
+    }
+}
+
+/// Note `name` in `shapes` if `body` deserializes as `T` with nothing left over.
+fn shape<T: DeserializeOwned + Debug>(body: &[u8], name: &str, shapes: &mut Vec<String>) {
+    if let Ok((value, [])) = postcard::take_from_bytes::<T>(body) {
+        shapes.push(format!("{} = {:?}", name, value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Serialize;
+
+    /// ConfigMsgOut as firmware ahead of this build might send it: the known
+    /// variants (bodies left out where the tests don't send them), an error
+    /// report appended at index 7, and something newer after that.
+    #[derive(Serialize)]
+    #[allow(dead_code)]
+    enum Newer {
+        Pong,
+        BatchMsgStart(usize),
+        BatchMsgEnd,
+        GlobalConfig(()),
+        Layout(()),
+        AppConfig(()),
+        AppState(u8, Vec<Value>),
+        Error(u8, String),
+        Other(bool),
+    }
+
+    /// A frame as the read loop hands it over: length prefix, COBS, no delimiter.
+    fn frame(msg: &impl Serialize) -> Vec<u8> {
+        let payload = postcard::to_allocvec(msg).unwrap();
+        let mut with_len = (payload.len() as u16).to_be_bytes().to_vec();
+        with_len.extend_from_slice(&payload);
+        let mut buf = vec![0u8; with_len.len() + with_len.len() / 254 + 2];
+        let len = cobs::try_encode(&with_len, &mut buf).unwrap();
+        buf.truncate(len);
+        buf
+    }
+
+    #[test]
+    fn decodes_known_messages() {
+        let decoded = decode(&frame(&Newer::AppState(2, vec![Value::Int(5)]))).unwrap();
+        let Decoded::Message(ConfigMsgOut::AppState(2, values)) = decoded else {
+            panic!("not decoded as AppState");
+        };
+        assert_eq!(values, [Value::Int(5)]);
+    }
+
+    #[test]
+    fn reads_an_error_after_the_known_variants_as_a_rejection() {
+        let decoded = decode(&frame(&Newer::Error(3, "busy".to_string()))).unwrap();
+        assert!(matches!(
+            decoded,
+            Decoded::Error(DeviceErrorKind::Rejected { code: 3, ref reason }) if reason == "busy"
+        ));
+    }
+
+    #[test]
+    fn passes_other_new_variants_on_raw() {
+        let decoded = decode(&frame(&Newer::Other(true))).unwrap();
+        assert!(matches!(
+            decoded,
+            Decoded::Error(DeviceErrorKind::Unknown { variant: 8, ref body }) if body == &[1]
+        ));
+    }
+
+    #[test]
+    fn fails_on_a_broken_known_variant_or_a_short_frame() {
+        // AppState with its values cut off
+        assert!(decode(&frame(&(6u8, 1u8))).is_err());
+        assert!(decode(&frame(&())).is_err());
+    }
+}
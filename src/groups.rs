@@ -0,0 +1,107 @@
+// Named groups of faders, e.g. "drums" for faders 1-6, so a set of apps can
+// be addressed as `@drums` anywhere a slot list is taken.
+//
+// The device knows nothing about groups. The current ones live in
+// `<data dir>/groups.json`; snapshots and profiles carry a copy in their
+// metadata, and loading one brings its groups back.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+
+use crate::error;
+use crate::paths;
+use crate::slots;
+
+const GROUPS_FILE: &str = "groups.json";
+
+/// Group name → fader slots (firmware numbers, sorted).
+pub type Groups = BTreeMap<String, Vec<u8>>;
+
+fn path() -> Result<PathBuf> {
+    Ok(paths::data_dir()?.join(GROUPS_FILE))
+}
+
+/// The current groups. A missing file means none.
+pub fn load() -> Result<Groups> {
+    let path = path()?;
+    let data = match std::fs::read_to_string(&path) {
+        Ok(d) => d,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Groups::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    serde_json::from_str(&data).with_context(|| format!("Invalid groups in {}", path.display()))
+}
+
+pub fn save(groups: &Groups) -> Result<()> {
+    let path = path()?;
+    std::fs::write(&path, serde_json::to_string_pretty(groups)? + "\n")
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+pub fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        anyhow::bail!(
+            "Invalid group name '{}' (letters, digits, - and _ only)",
+            name
+        );
+    }
+    Ok(())
+}
+
+/// Parse a slot list where any part may also be a group, e.g. `@drums,9-12`.
+pub fn parse_slots(s: &str) -> Result<Vec<u8>> {
+    let (named, plain): (Vec<&str>, Vec<&str>) = s
+        .split(',')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .partition(|p| p.starts_with('@'));
+    if named.is_empty() {
+        return slots::parse_list(s);
+    }
+
+    let groups = load()?;
+    let mut out = Vec::new();
+    for part in named {
+        let name = &part[1..];
+        let members = groups.get(name).ok_or_else(|| {
+            error::invalid(format!(
+                "No group named '{}'. Use 'group list' to see groups.",
+                name
+            ))
+        })?;
+        out.extend(members);
+    }
+    if !plain.is_empty() {
+        out.extend(slots::parse_list(&plain.join(","))?);
+    }
+    out.sort_unstable();
+    out.dedup();
+    Ok(out)
+}
+
+/// Slots as runs of fader names, e.g. "1-6, 9".
+pub fn describe(members: &[u8]) -> String {
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for &slot in members {
+        let idx = slot as usize - 1;
+        match runs.last_mut() {
+            Some((start, size)) if *start + *size == idx => *size += 1,
+            _ => runs.push((idx, 1)),
+        }
+    }
+    runs.iter()
+        .map(|&(start, size)| slots::range(start, size))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The group a fader belongs to (the first by name if several), for
+/// drawing group headers over the layout.
+pub fn of(groups: &Groups, index: usize) -> Option<&str> {
+    groups
+        .iter()
+        .find(|(_, members)| members.contains(&(index as u8 + 1)))
+        .map(|(name, _)| name.as_str())
+}
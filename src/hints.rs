@@ -0,0 +1,121 @@
+// What to try when a command fails. Errors are matched by type anywhere in
+// their cause chain, so a context line added on the way up doesn't hide the
+// USB or protocol error underneath. Errors without a known remedy get no hint.
+
+use std::io::ErrorKind;
+
+use crate::error::{ConnectionError, DeviceError, DeviceErrorKind, InterfaceHolder};
+use crate::i18n::t;
+use crate::usb;
+
+/// A "try this" line for `err`, from the first cause that has one.
+pub fn hint(err: &anyhow::Error) -> Option<String> {
+    err.chain().find_map(|cause| {
+        if let Some(e) = cause.downcast_ref::<ConnectionError>() {
+            connection_hint(e)
+        } else if let Some(e) = cause.downcast_ref::<DeviceError>() {
+            device_hint(e)
+        } else if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+            io_hint(e)
+        } else {
+            None
+        }
+    })
+}
+
+fn connection_hint(err: &ConnectionError) -> Option<String> {
+    Some(match err {
+        ConnectionError::NotFound => t!(
+            "Check that the Faderpunk is powered and plugged in with a data cable (charge-only cables won't do). Try another port, or leave out the hub."
+        )
+        .to_string(),
+        ConnectionError::NoInterface => t!(
+            "The device doesn't offer the configurator interface. Update its firmware to v1.8.0 or later."
+        )
+        .to_string(),
+        ConnectionError::AmbiguousInterface(_) | ConnectionError::MissingInterface { .. } => t!(
+            "Pick the configurator interface by number or name with --interface, or set FP_INTERFACE."
+        )
+        .to_string(),
+        ConnectionError::Busy { holder, .. } => busy_hint(holder),
+        ConnectionError::Closed(_) => t!(
+            "The device was unplugged or reset. Replug it and run the command again; if it keeps dropping, try another cable or port."
+        )
+        .to_string(),
+        ConnectionError::Timeout { .. } => t!(
+            "The device is connected but not answering. Replug it; 'status' shows lost pings and retries if it keeps happening."
+        )
+        .to_string(),
+    })
+}
+
+fn device_hint(err: &DeviceError) -> Option<String> {
+    match &err.kind {
+        DeviceErrorKind::Rejected { .. } => None,
+        DeviceErrorKind::Unknown { .. } => Some(
+            t!("The firmware is newer than this CLI. Update faderpunk-cli to a release that matches it.")
+                .to_string(),
+        ),
+        DeviceErrorKind::Unexpected(_) => Some(t!(
+            "Replies got out of step with requests. Check the firmware is v1.8.0 or later, then replug the device and retry."
+        )
+        .to_string()),
+        DeviceErrorKind::Changed(_) => Some(
+            t!("Re-run the command to start from the device's current values.")
+                .to_string(),
+        ),
+        DeviceErrorKind::TooLarge { .. } => Some(
+            t!("Messages built from sane input aren't this long. Check the file or values it was built from, and that this faderpunk-cli release matches the firmware.")
+                .to_string(),
+        ),
+        DeviceErrorKind::Corrected(_) => Some(
+            t!("Bring the listed values within range, or leave out --strict to accept the device's version.")
+                .to_string(),
+        ),
+    }
+}
+
+fn io_hint(err: &std::io::Error) -> Option<String> {
+    if err.kind() == ErrorKind::PermissionDenied {
+        return Some(permission_hint());
+    }
+    if err.kind() == ErrorKind::ResourceBusy || err.raw_os_error() == Some(usb::EBUSY) {
+        return Some(busy_hint(&InterfaceHolder::Unknown));
+    }
+    None
+}
+
+fn busy_hint(holder: &InterfaceHolder) -> String {
+    match holder {
+        InterfaceHolder::Driver { driver, interface } => t!(
+            "Run the command as root so the driver can be detached, or unbind it yourself:\n  echo '{}' | sudo tee /sys/bus/usb/drivers/{}/unbind",
+            interface,
+            driver
+        ),
+        InterfaceHolder::Processes(_) => t!(
+            "Close the programs listed (a browser tab with the web configurator, another faderpunk-cli), or stop a running daemon (daemon uninstall-service)."
+        )
+        .to_string(),
+        InterfaceHolder::Unknown => t!(
+            "Another program has the device open. Close the web configurator, or stop a running daemon (daemon uninstall-service)."
+        )
+        .to_string(),
+    }
+}
+
+fn permission_hint() -> String {
+    if cfg!(target_os = "linux") {
+        let (vid, pid) = usb::device_ids();
+        let rule = format!(
+            r#"SUBSYSTEM=="usb", ATTR{{idVendor}}=="{:04x}", ATTR{{idProduct}}=="{:04x}", TAG+="uaccess""#,
+            vid, pid
+        );
+        t!(
+            "Allow your user to open the device with a udev rule, then replug it:\n  echo '{}' | sudo tee /etc/udev/rules.d/70-faderpunk.rules\n  sudo udevadm control --reload",
+            rule
+        )
+    } else {
+        t!("The system refused access to the device. Close other programs using it, then replug it.")
+            .to_string()
+    }
+}
@@ -0,0 +1,403 @@
+// Translated user-facing messages. Messages are written in English where
+// they're used and looked up with `t!` in the catalog of the chosen language.
+// Anything a catalog doesn't have yet stays English, so a partial catalog is
+// always safe to ship.
+//
+// The language comes from --lang, or else the usual locale variables
+// (LC_ALL, LC_MESSAGES, LANG). To add one, write a catalog like `DE` below
+// and list it in `LANGUAGES`. Placeholders are `{}` in order, or `{0}`,
+// `{1}`... where a translation needs the values in another order.
+
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+use anyhow::Result;
+
+type Catalog = &'static [(&'static str, &'static str)];
+
+/// Languages with a catalog, by code. English is the messages themselves.
+const LANGUAGES: &[(&str, Catalog)] = &[("de", DE)];
+
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+/// Pick the language for the rest of the process: `lang` if given (an error
+/// if there's no catalog for it), else the locale, falling back to English.
+pub fn configure(lang: Option<&str>) -> Result<()> {
+    let catalog = match lang {
+        Some(code) => {
+            let code = code.to_lowercase();
+            if code == "en" {
+                &[]
+            } else {
+                let known: Vec<&str> = LANGUAGES.iter().map(|(c, _)| *c).collect();
+                catalog_for(&code).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "No translations for '{}' (available: en, {})",
+                        code,
+                        known.join(", ")
+                    )
+                })?
+            }
+        }
+        None => locale().and_then(|code| catalog_for(&code)).unwrap_or(&[]),
+    };
+    let _ = CATALOG.set(catalog);
+    Ok(())
+}
+
+fn catalog_for(code: &str) -> Option<Catalog> {
+    LANGUAGES
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map(|(_, cat)| *cat)
+}
+
+/// Language code of the first locale variable that's set, e.g. "de" for
+/// `de_DE.UTF-8`.
+fn locale() -> Option<String> {
+    let value = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|v| !v.is_empty())?;
+    let code = value.split(['_', '.', '@', '-']).next()?.to_lowercase();
+    (!code.is_empty() && code != "c" && code != "posix").then_some(code)
+}
+
+/// The translation of `msg`, or `msg` itself.
+pub fn lookup(msg: &'static str) -> &'static str {
+    CATALOG
+        .get()
+        .and_then(|cat| cat.iter().find(|(en, _)| *en == msg))
+        .map_or(msg, |(_, translated)| translated)
+}
+
+/// Put `args` into a message's placeholders.
+pub fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut next = 0;
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        let Some(close) = rest[open..].find('}') else {
+            break;
+        };
+        out.push_str(&rest[..open]);
+        let inside = &rest[open + 1..open + close];
+        let index = if inside.is_empty() {
+            next += 1;
+            Some(next - 1)
+        } else {
+            inside.parse::<usize>().ok()
+        };
+        match index.and_then(|i| args.get(i)) {
+            Some(arg) => out.push_str(&arg.to_string()),
+            None => out.push_str(&rest[open..=open + close]),
+        }
+        rest = &rest[open + close + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// A message in the chosen language: `t!("Cancelled.")`, or with values for
+/// its placeholders, `t!("No app at fader {}", label)`.
+macro_rules! t {
+    ($msg:literal) => {
+        $crate::i18n::lookup($msg)
+    };
+    ($msg:literal, $($arg:expr),+ $(,)?) => {
+        $crate::i18n::fill($crate::i18n::lookup($msg), &[$(&$arg),+])
+    };
+}
+pub(crate) use t;
+
+// ── German ──
+
+const DE: Catalog = &[
+    // Prompts
+    ("[y/N]", "[j/N]"),
+    ("y", "j"),
+    ("yes", "ja"),
+    (
+        "no prompts in this mode; use --force",
+        "keine Rückfragen in diesem Modus; --force verwenden",
+    ),
+    ("Cancelled.", "Abgebrochen."),
+    ("Apply these changes?", "Diese Änderungen übernehmen?"),
+    ("Run the conformance suite?", "Konformitätstests ausführen?"),
+    ("Input closed", "Eingabe geschlossen"),
+    // Outcomes
+    ("Faderpunk is connected!", "Faderpunk ist verbunden!"),
+    ("No changes.", "Keine Änderungen."),
+    (
+        "No changes. The device matches {}.",
+        "Keine Änderungen. Das Gerät entspricht {}.",
+    ),
+    ("Changes:", "Änderungen:"),
+    (
+        "Changes needed to match {}:",
+        "Nötige Änderungen, um {} zu entsprechen:",
+    ),
+    (
+        "Plan: {} config, {} layout, {} param change(s).",
+        "Plan: {} Konfigurations-, {} Layout-, {} Parameteränderung(en).",
+    ),
+    (
+        "Global config applied.",
+        "Globale Konfiguration übernommen.",
+    ),
+    (
+        "Global config updated.",
+        "Globale Konfiguration aktualisiert.",
+    ),
+    ("Layout applied.", "Layout übernommen."),
+    ("Layout updated.", "Layout aktualisiert."),
+    (
+        "Params applied to {} app(s).",
+        "Parameter für {} App(s) übernommen.",
+    ),
+    (
+        "Params updated for {} app(s).",
+        "Parameter für {} App(s) aktualisiert.",
+    ),
+    ("Matches profile '{}'", "Entspricht Profil '{}'"),
+    ("State revision {}", "Zustandsrevision {}"),
+    ("Profile '{}' saved", "Profil '{}' gespeichert"),
+    ("Profile '{}' loaded", "Profil '{}' geladen"),
+    ("Profile '{}' deleted", "Profil '{}' gelöscht"),
+    (
+        "No saved profiles. Use 'profile save <name>' to create one.",
+        "Keine gespeicherten Profile. Mit 'profile save <name>' eines anlegen.",
+    ),
+    (
+        "Watching for device events (Ctrl-C to stop)...",
+        "Warte auf Ereignisse vom Gerät (Strg-C zum Beenden)...",
+    ),
+    // Errors
+    ("No app at fader {}", "Keine App auf Fader {}"),
+    ("App metadata not found", "App-Metadaten nicht gefunden"),
+    (
+        "No apps on the selected faders",
+        "Keine Apps auf den gewählten Fadern",
+    ),
+    (
+        "Expected bool (true/false, on/off, 1/0)",
+        "Erwartet: Wahrheitswert (true/false, on/off, 1/0)",
+    ),
+    (
+        "Expected a number, got '{}'",
+        "Erwartet: eine Zahl, erhalten: '{}'",
+    ),
+    ("error", "Fehler"),
+    ("caused by", "Ursache"),
+    ("hint", "Tipp"),
+    (
+        "Check that the Faderpunk is powered and plugged in with a data cable (charge-only cables won't do). Try another port, or leave out the hub.",
+        "Prüfe, ob der Faderpunk eingeschaltet und mit einem Datenkabel verbunden ist (reine Ladekabel reichen nicht). Versuche einen anderen Anschluss oder lass den Hub weg.",
+    ),
+    (
+        "The device doesn't offer the configurator interface. Update its firmware to v1.8.0 or later.",
+        "Das Gerät bietet keine Konfigurationsschnittstelle. Aktualisiere die Firmware auf v1.8.0 oder neuer.",
+    ),
+    (
+        "Pick the configurator interface by number or name with --interface, or set FP_INTERFACE.",
+        "Wähle die Konfigurator-Schnittstelle per Nummer oder Name mit --interface, oder setze FP_INTERFACE.",
+    ),
+    (
+        "The device was unplugged or reset. Replug it and run the command again; if it keeps dropping, try another cable or port.",
+        "Das Gerät wurde getrennt oder zurückgesetzt. Stecke es neu ein und wiederhole den Befehl; bricht die Verbindung weiter ab, versuche ein anderes Kabel oder einen anderen Anschluss.",
+    ),
+    (
+        "The device is connected but not answering. Replug it; 'status' shows lost pings and retries if it keeps happening.",
+        "Das Gerät ist verbunden, antwortet aber nicht. Stecke es neu ein; 'status' zeigt verlorene Pings und Wiederholungen, falls es wieder passiert.",
+    ),
+    (
+        "The firmware is newer than this CLI. Update faderpunk-cli to a release that matches it.",
+        "Die Firmware ist neuer als dieses CLI. Aktualisiere faderpunk-cli auf eine passende Version.",
+    ),
+    (
+        "Replies got out of step with requests. Check the firmware is v1.8.0 or later, then replug the device and retry.",
+        "Antworten passen nicht mehr zu den Anfragen. Prüfe, ob die Firmware v1.8.0 oder neuer ist, stecke das Gerät neu ein und versuche es erneut.",
+    ),
+    (
+        "Re-run the command to start from the device's current values.",
+        "Führe den Befehl erneut aus, um von den aktuellen Werten des Geräts auszugehen.",
+    ),
+    (
+        "Messages built from sane input aren't this long. Check the file or values it was built from, and that this faderpunk-cli release matches the firmware.",
+        "Aus sinnvollen Eingaben entstehen keine so langen Nachrichten. Prüfe die Datei oder Werte, aus denen sie entstand, und ob diese Version von faderpunk-cli zur Firmware passt.",
+    ),
+    (
+        "Bring the listed values within range, or leave out --strict to accept the device's version.",
+        "Bringe die aufgeführten Werte in den gültigen Bereich oder lass --strict weg, um die Fassung des Geräts zu übernehmen.",
+    ),
+    (
+        "Another program has the device open. Close the web configurator, or stop a running daemon (daemon uninstall-service).",
+        "Ein anderes Programm hat das Gerät geöffnet. Schließe den Web-Konfigurator oder beende einen laufenden Daemon (daemon uninstall-service).",
+    ),
+    (
+        "Close the programs listed (a browser tab with the web configurator, another faderpunk-cli), or stop a running daemon (daemon uninstall-service).",
+        "Schließe die aufgeführten Programme (einen Browser-Tab mit dem Web-Konfigurator, ein weiteres faderpunk-cli) oder beende einen laufenden Daemon (daemon uninstall-service).",
+    ),
+    (
+        "Run the command as root so the driver can be detached, or unbind it yourself:\n  echo '{}' | sudo tee /sys/bus/usb/drivers/{}/unbind",
+        "Führe den Befehl als root aus, damit der Treiber gelöst werden kann, oder löse ihn selbst:\n  echo '{}' | sudo tee /sys/bus/usb/drivers/{}/unbind",
+    ),
+    (
+        "Allow your user to open the device with a udev rule, then replug it:\n  echo '{}' | sudo tee /etc/udev/rules.d/70-faderpunk.rules\n  sudo udevadm control --reload",
+        "Erlaube deinem Benutzer den Zugriff per udev-Regel und stecke das Gerät neu ein:\n  echo '{}' | sudo tee /etc/udev/rules.d/70-faderpunk.rules\n  sudo udevadm control --reload",
+    ),
+    (
+        "The system refused access to the device. Close other programs using it, then replug it.",
+        "Das System verweigert den Zugriff auf das Gerät. Schließe andere Programme, die es nutzen, und stecke es neu ein.",
+    ),
+    // Section headers
+    ("Global Config", "Globale Konfiguration"),
+    ("Clock", "Takt"),
+    ("Control", "Bedienung"),
+    ("Quantizer", "Quantisierer"),
+    ("Aux Jacks", "Aux-Buchsen"),
+    ("MIDI Outputs", "MIDI-Ausgänge"),
+    ("Layout", "Layout"),
+    ("Apps ({})", "Apps ({})"),
+    ("Pinned ({})", "Angeheftet ({})"),
+    ("Rig Stats", "Rig-Statistik"),
+    ("Channels", "Kanäle"),
+    ("Apps", "Apps"),
+    ("Params per slot", "Parameter pro Slot"),
+    ("MIDI ports in use", "Belegte MIDI-Ports"),
+    (
+        "History (placements from change log)",
+        "Verlauf (Platzierungen laut Änderungsprotokoll)",
+    ),
+    ("Connection", "Verbindung"),
+    ("Change Log ({})", "Änderungsprotokoll ({})"),
+    ("Limits", "Grenzwerte"),
+    ("Clock at {} BPM", "Taktausgänge bei {} BPM"),
+    ("Outputs", "Ausgänge"),
+    ("Clock measurement", "Taktmessung"),
+    ("Conformance", "Konformität"),
+    ("{} params ({})", "{}-Parameter ({})"),
+    ("Bar {}", "Takt {}"),
+    // Labels
+    ("Source", "Quelle"),
+    ("BPM", "BPM"),
+    ("Ext PPQN", "Ext. PPQN"),
+    ("Reset source", "Reset-Quelle"),
+    ("Beat", "Schlag"),
+    ("Ticks", "Ticks"),
+    ("Tempo", "Tempo"),
+    ("Expected", "Erwartet"),
+    ("Jitter", "Jitter"),
+    ("{} ms behind per minute", "{} ms Rückstand pro Minute"),
+    ("{} ms ahead per minute", "{} ms Vorsprung pro Minute"),
+    ("{} ms (worst {} ms)", "{} ms (schlimmstenfalls {} ms)"),
+    ("{}/{} passed", "{}/{} bestanden"),
+    ("All {} checks passed.", "Alle {} Prüfungen bestanden."),
+    ("Running checks...", "Prüfungen laufen..."),
+    (
+        "This writes hundreds of settings, layouts and params to the device.",
+        "Dabei werden Hunderte Einstellungen, Layouts und Parameter auf das Gerät geschrieben.",
+    ),
+    (
+        "The current state is saved first and put back at the end.",
+        "Der aktuelle Zustand wird vorher gesichert und am Ende wiederhergestellt.",
+    ),
+    (
+        "÷{}  {}, every {} ms, {} pulses",
+        "÷{}  {}, alle {} ms, {} Impulse",
+    ),
+    ("Reset, once on start", "Reset, einmal beim Start"),
+    ("{} ms ({} ms per tick)", "{} ms ({} ms pro Tick)"),
+    ("Takeover mode", "Übernahmemodus"),
+    ("LED brightness", "LED-Helligkeit"),
+    ("I2C mode", "I2C-Modus"),
+    ("Key", "Tonart"),
+    ("Tonic", "Grundton"),
+    ("Aux {}", "Aux {}"),
+    ("Reset", "Reset"),
+    ("Clock ÷{}", "Takt ÷{}"),
+    ("Out 1", "Ausgang 1"),
+    ("Out 2", "Ausgang 2"),
+    ("USB in", "USB ein"),
+    ("DIN in", "DIN ein"),
+    ("USB out", "USB aus"),
+    ("clk", "Takt"),
+    ("transport", "Transport"),
+    ("Used", "Belegt"),
+    ("Free", "Frei"),
+    ("Slot", "Slot"),
+    ("Layout ID", "Layout-ID"),
+    ("App ID", "App-ID"),
+    ("App", "App"),
+    ("App {}", "App {}"),
+    ("USB speed", "USB-Geschwindigkeit"),
+    ("Serial", "Seriennummer"),
+    ("Device release", "Geräte-Release"),
+    ("Write pacing", "Schreibtakt"),
+    ("Ping", "Ping"),
+    ("Last ping", "Letzter Ping"),
+    ("This session", "Diese Sitzung"),
+    ("(empty layout)", "(leeres Layout)"),
+    ("(unused)", "(unbenutzt)"),
+    ("(no change)", "(keine Änderung)"),
+    ("Other", "Sonstige"),
+    ("(none in the layout)", "(keine im Layout)"),
+    (
+        "(no layout changes recorded)",
+        "(keine Layoutänderungen aufgezeichnet)",
+    ),
+    ("(no changes recorded)", "(keine Änderungen aufgezeichnet)"),
+    ("(no field changes)", "(keine Feldänderungen)"),
+    // Device events
+    ("Layout changed", "Layout geändert"),
+    ("Global config changed", "Globale Konfiguration geändert"),
+    (
+        "Params changed (layout_id={}, {} values)",
+        "Parameter geändert (layout_id={}, {} Werte)",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_takes_args_in_order_or_by_index() {
+        assert_eq!(fill("{} of {}", &[&1, &"two"]), "1 of two");
+        assert_eq!(fill("{1} before {0}", &[&"a", &"b"]), "b before a");
+    }
+
+    #[test]
+    fn fill_keeps_placeholders_it_cannot_fill() {
+        assert_eq!(fill("{} and {} {x}", &[&1]), "1 and {} {x}");
+        assert_eq!(fill("open { brace", &[&1]), "open { brace");
+    }
+
+    /// Which args a message uses, as `fill` numbers them.
+    fn placeholders(message: &str) -> Vec<String> {
+        let args: Vec<String> = (0..8).map(|i| format!("<{}>", i)).collect();
+        let args: Vec<&dyn Display> = args.iter().map(|a| a as &dyn Display).collect();
+        let filled = fill(message, &args);
+        let mut used: Vec<String> = (0..8)
+            .map(|i| format!("<{}>", i))
+            .filter(|a| filled.contains(a.as_str()))
+            .collect();
+        used.sort();
+        used
+    }
+
+    #[test]
+    fn translations_use_the_same_placeholders() {
+        for (code, catalog) in LANGUAGES {
+            for (en, translated) in *catalog {
+                assert_eq!(
+                    placeholders(en),
+                    placeholders(translated),
+                    "{}: {:?}",
+                    code,
+                    translated
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,296 @@
+// Host-side layout algorithms: packing apps onto the 16-fader strip, random
+// layouts, and validating a layout before it is sent.
+
+use anyhow::Result;
+
+use crate::invalid;
+use crate::protocol::{AppIcon, Color, GLOBAL_CHANNELS, Layout, Param};
+use crate::random::Rng;
+use crate::slots;
+
+/// App info needed to render layout and params.
+pub struct AppInfo {
+    pub app_id: u8,
+    pub channels: usize,
+    pub name: String,
+    pub description: String,
+    pub color: Color,
+    pub icon: AppIcon,
+    pub params: Vec<Param>,
+}
+
+/// Free runs of faders in a layout, as (start, len).
+fn free_gaps(layout: &Layout) -> Vec<(usize, usize)> {
+    let mut occupied = [false; GLOBAL_CHANNELS];
+    for (i, slot) in layout.0.iter().enumerate() {
+        if let Some((_, ch, _)) = slot {
+            for o in occupied.iter_mut().skip(i).take(*ch) {
+                *o = true;
+            }
+        }
+    }
+
+    let mut gaps = Vec::new();
+    let mut i = 0;
+    while i < GLOBAL_CHANNELS {
+        if occupied[i] {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < GLOBAL_CHANNELS && !occupied[i] {
+            i += 1;
+        }
+        gaps.push((start, i - start));
+    }
+    gaps
+}
+
+/// Lowest layout_id not used in the layout.
+pub fn next_layout_id(layout: &Layout) -> u8 {
+    let used: Vec<u8> = layout
+        .0
+        .iter()
+        .filter_map(|s| s.map(|(_, _, lid)| lid))
+        .collect();
+    (0..GLOBAL_CHANNELS as u8)
+        .find(|id| !used.contains(id))
+        .unwrap_or(0)
+}
+
+/// Pack a list of (app_id, channels) onto the strip.
+///
+/// With no base layout the apps are placed left to right in the order given.
+/// With a base layout, existing apps stay put and the new ones are fitted into
+/// the free gaps, widest first, each into the tightest gap that holds it.
+pub fn pack(base: Option<&Layout>, apps: &[(u8, usize)]) -> Result<Layout> {
+    let needed: usize = apps.iter().map(|(_, ch)| ch).sum();
+
+    let Some(base) = base else {
+        if needed > GLOBAL_CHANNELS {
+            invalid!(
+                "Apps need {} faders in total, {} more than the {} available",
+                needed,
+                needed - GLOBAL_CHANNELS,
+                GLOBAL_CHANNELS
+            );
+        }
+        let mut layout = Layout([None; GLOBAL_CHANNELS]);
+        let mut pos = 0;
+        for (layout_id, (app_id, ch)) in apps.iter().enumerate() {
+            layout.0[pos] = Some((*app_id, *ch, layout_id as u8));
+            pos += ch;
+        }
+        return Ok(layout);
+    };
+
+    let mut layout = base.clone();
+    let free: usize = free_gaps(&layout).iter().map(|(_, len)| len).sum();
+    if needed > free {
+        invalid!(
+            "Apps need {} faders in total, but only {} are free in the current layout",
+            needed,
+            free
+        );
+    }
+
+    let mut order: Vec<&(u8, usize)> = apps.iter().collect();
+    order.sort_by_key(|(_, ch)| std::cmp::Reverse(*ch));
+
+    for (app_id, ch) in order {
+        let gap = free_gaps(&layout)
+            .into_iter()
+            .filter(|(_, len)| len >= ch)
+            .min_by_key(|(_, len)| *len);
+        let Some((start, _)) = gap else {
+            invalid!(
+                "No free run of {} fader(s) left — the free faders are too fragmented. \
+                 Try without --keep to repack the whole strip.",
+                ch
+            );
+        };
+        layout.0[start] = Some((*app_id, *ch, next_layout_id(&layout)));
+    }
+
+    Ok(layout)
+}
+
+/// Fill the strip left to right with apps picked at random from `apps`
+/// (app_id, channels). Each pick is one that fits the faders still free and
+/// hasn't been placed `max_per_app` times yet; the strip is left partly empty
+/// only when nothing fits the rest.
+pub fn random(apps: &[(u8, usize)], max_per_app: Option<usize>, rng: &mut Rng) -> Result<Layout> {
+    let mut picks: Vec<(u8, usize)> = Vec::new();
+    let mut free = GLOBAL_CHANNELS;
+    loop {
+        let candidates: Vec<&(u8, usize)> = apps
+            .iter()
+            .filter(|(app_id, ch)| {
+                let placed = picks.iter().filter(|(id, _)| id == app_id).count();
+                // An app without faders would never use any up
+                (1..=free).contains(ch) && max_per_app.is_none_or(|max| placed < max)
+            })
+            .collect();
+        if candidates.is_empty() {
+            break;
+        }
+        let pick = *candidates[rng.below(candidates.len())];
+        free -= pick.1;
+        picks.push(pick);
+    }
+    if picks.is_empty() {
+        invalid!("None of the apps can be placed (too wide, no faders, or --max-per-app is 0)");
+    }
+    pack(None, &picks)
+}
+
+/// Check a layout before sending it, so mistakes produce precise errors instead
+/// of being silently corrected by the firmware. Every problem is reported at once.
+pub fn validate(layout: &Layout, apps: &[AppInfo]) -> Result<()> {
+    let mut errors = Vec::new();
+    let mut owner: [Option<usize>; GLOBAL_CHANNELS] = [None; GLOBAL_CHANNELS];
+    let mut seen_ids: Vec<(u8, usize)> = Vec::new();
+
+    for (i, slot) in layout.0.iter().enumerate() {
+        let Some((app_id, ch, layout_id)) = *slot else {
+            continue;
+        };
+        let fader = slots::label(i);
+
+        if !(1..=GLOBAL_CHANNELS).contains(&ch) {
+            errors.push(format!(
+                "fader {}: app {} has {} channels (must be 1-{})",
+                fader, app_id, ch, GLOBAL_CHANNELS
+            ));
+            continue;
+        }
+        if i + ch > GLOBAL_CHANNELS {
+            errors.push(format!(
+                "fader {}: app {} needs {} faders but only {} remain",
+                fader,
+                app_id,
+                ch,
+                GLOBAL_CHANNELS - i
+            ));
+        }
+
+        match apps.iter().find(|a| a.app_id == app_id) {
+            None => errors.push(format!(
+                "fader {}: no app with ID {} on this device",
+                fader, app_id
+            )),
+            Some(info) if info.channels != ch => errors.push(format!(
+                "fader {}: {} uses {} channel(s), layout says {}",
+                fader, info.name, info.channels, ch
+            )),
+            Some(_) => {}
+        }
+
+        if let Some((_, other)) = seen_ids.iter().find(|(id, _)| *id == layout_id) {
+            errors.push(format!(
+                "fader {}: layout_id {} is already used at fader {}",
+                fader,
+                layout_id,
+                slots::label(*other)
+            ));
+        }
+        seen_ids.push((layout_id, i));
+
+        for (j, o) in owner.iter_mut().enumerate().skip(i).take(ch) {
+            if let Some(other) = *o {
+                errors.push(format!(
+                    "fader {}: app at fader {} overlaps the app at fader {}",
+                    slots::label(j),
+                    fader,
+                    slots::label(other)
+                ));
+            } else {
+                *o = Some(i);
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        invalid!("Invalid layout:\n  - {}", errors.join("\n  - "));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Faders each app in a layout takes, in order: (app_id, channels).
+    fn placed(layout: &Layout) -> Vec<(u8, usize)> {
+        layout.0.iter().flatten().map(|&(app_id, ch, _)| (app_id, ch)).collect()
+    }
+
+    fn app(app_id: u8, channels: usize) -> AppInfo {
+        AppInfo {
+            app_id,
+            channels,
+            name: format!("App {}", app_id),
+            description: String::new(),
+            color: Color::White,
+            icon: AppIcon::Fader,
+            params: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn pack_places_left_to_right() {
+        let layout = pack(None, &[(1, 2), (2, 1)]).unwrap();
+        assert_eq!(layout.0[0], Some((1, 2, 0)));
+        assert_eq!(layout.0[2], Some((2, 1, 1)));
+        assert!(pack(None, &[(1, 8), (2, 9)]).is_err());
+    }
+
+    #[test]
+    fn pack_fits_new_apps_into_the_tightest_gap() {
+        let mut base = Layout([None; GLOBAL_CHANNELS]);
+        base.0[0] = Some((1, 2, 0));
+        base.0[4] = Some((1, 12, 1));
+        // Faders 3-4 are the only gap; the 2-wide app goes there
+        let layout = pack(Some(&base), &[(2, 2)]).unwrap();
+        assert_eq!(layout.0[2], Some((2, 2, 2)));
+        assert!(pack(Some(&base), &[(2, 3)]).is_err());
+    }
+
+    #[test]
+    fn validate_reports_every_problem() {
+        let apps = [app(1, 1), app(2, 2)];
+        let ok = pack(None, &[(1, 1), (2, 2)]).unwrap();
+        assert!(validate(&ok, &apps).is_ok());
+
+        let mut bad = Layout([None; GLOBAL_CHANNELS]);
+        bad.0[0] = Some((2, 2, 0));
+        bad.0[1] = Some((1, 1, 0));
+        bad.0[5] = Some((9, 1, 1));
+        let message = validate(&bad, &apps).unwrap_err().to_string();
+        assert!(message.contains("overlaps"), "{}", message);
+        assert!(message.contains("layout_id 0 is already used"), "{}", message);
+        assert!(message.contains("no app with ID 9"), "{}", message);
+    }
+
+    #[test]
+    fn random_fills_the_strip() {
+        let layout = random(&[(1, 1), (2, 2)], None, &mut Rng::new(7)).unwrap();
+        let used: usize = placed(&layout).iter().map(|(_, ch)| ch).sum();
+        assert_eq!(used, GLOBAL_CHANNELS);
+    }
+
+    #[test]
+    fn random_respects_max_per_app() {
+        let layout = random(&[(1, 1), (2, 4)], Some(2), &mut Rng::new(3)).unwrap();
+        let apps = placed(&layout);
+        assert_eq!(apps.iter().filter(|(id, _)| *id == 1).count(), 2);
+        assert_eq!(apps.iter().filter(|(id, _)| *id == 2).count(), 2);
+    }
+
+    #[test]
+    fn random_skips_apps_without_faders() {
+        let layout = random(&[(1, 0), (2, 8)], None, &mut Rng::new(1)).unwrap();
+        assert_eq!(placed(&layout), vec![(2, 8), (2, 8)]);
+        assert!(random(&[(1, 0)], None, &mut Rng::new(1)).is_err());
+    }
+}
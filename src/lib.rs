@@ -0,0 +1,27 @@
+// Library half of the crate: the protocol, framing, snapshot and layout logic
+// the CLI is built on, the USB transport, and a C API (ffi.rs) for DAW plugins
+// and externals that want to talk to the device directly.
+//
+// Without the `usb` feature only the transport-independent modules are built,
+// which also compile for wasm32 (e.g. for a web editor).
+
+pub mod appmeta;
+pub mod audit;
+pub mod error;
+#[cfg(feature = "usb")]
+pub mod ffi;
+pub mod frame;
+pub mod layout;
+pub mod limits;
+pub mod paths;
+pub mod protocol;
+pub mod random;
+#[cfg(feature = "usb")]
+mod router;
+pub mod session;
+pub mod slots;
+pub mod snapshot;
+pub mod template;
+pub mod timing;
+#[cfg(feature = "usb")]
+pub mod usb;
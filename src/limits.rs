@@ -0,0 +1,66 @@
+// Host-side limits the config setters check before sending a value. libfp
+// doesn't define ranges for the global config and the protocol has no way to
+// ask the device for any, so these are the CLI's own: the LED brightness range
+// it has always enforced, and a positive BPM. Anything else goes to the
+// device as given, and the setters read the config back to show what it kept.
+
+use anyhow::Result;
+
+use crate::invalid;
+
+/// The accepted ranges for the checked global config values.
+pub struct Limits {
+    /// Lowest LED brightness; below it the LEDs are hard to read.
+    pub brightness_min: u8,
+    pub brightness_max: u8,
+}
+
+pub const LIMITS: Limits = Limits {
+    brightness_min: 100,
+    brightness_max: 255,
+};
+
+impl Limits {
+    /// Refuse a BPM that can't be a tempo.
+    pub fn check_bpm(&self, bpm: f32) -> Result<()> {
+        if !(bpm.is_finite() && bpm > 0.0) {
+            invalid!("BPM must be positive");
+        }
+        Ok(())
+    }
+
+    /// Refuse a brightness outside the range, naming the range.
+    pub fn check_brightness(&self, brightness: u8) -> Result<()> {
+        if !(self.brightness_min..=self.brightness_max).contains(&brightness) {
+            invalid!(
+                "Brightness must be {}-{}",
+                self.brightness_min,
+                self.brightness_max
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bpm_must_be_a_positive_number() {
+        assert!(LIMITS.check_bpm(120.0).is_ok());
+        assert!(LIMITS.check_bpm(0.5).is_ok());
+        assert!(LIMITS.check_bpm(0.0).is_err());
+        assert!(LIMITS.check_bpm(-1.0).is_err());
+        assert!(LIMITS.check_bpm(f32::NAN).is_err());
+        assert!(LIMITS.check_bpm(f32::INFINITY).is_err());
+    }
+
+    #[test]
+    fn brightness_range_is_inclusive() {
+        assert!(LIMITS.check_brightness(100).is_ok());
+        assert!(LIMITS.check_brightness(255).is_ok());
+        let message = LIMITS.check_brightness(99).unwrap_err().to_string();
+        assert_eq!(message, "Brightness must be 100-255");
+    }
+}
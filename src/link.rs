@@ -0,0 +1,166 @@
+// Ableton Link session discovery, for `clock link`: the tempo and start/stop
+// state of the Link peers on the local network.
+//
+// Link peers announce their session timeline to a multicast group every few
+// seconds and whenever it changes. We only listen: the Faderpunk follows the
+// session and never announces a timeline of its own, so it can't pull
+// anybody else's tempo around.
+
+use std::net::{Ipv4Addr, SocketAddrV4};
+
+use anyhow::{Context, Result};
+use tokio::net::UdpSocket;
+
+const MULTICAST_ADDR: Ipv4Addr = Ipv4Addr::new(224, 76, 78, 75);
+pub const PORT: u16 = 20808;
+
+/// Every discovery message starts with this, protocol version 1.
+const PROTOCOL_HEADER: &[u8; 8] = b"_asdp_v\x01";
+/// Message type, TTL, session group and the sender's node ID.
+const MESSAGE_HEADER_LEN: usize = 1 + 1 + 2 + 8;
+
+const ALIVE: u8 = 1;
+const RESPONSE: u8 = 2;
+
+const TIMELINE_KEY: &[u8; 4] = b"tmln";
+const START_STOP_KEY: &[u8; 4] = b"stst";
+
+/// What a peer said about the session.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Announcement {
+    pub node: u64,
+    /// Session tempo in BPM, when the message carried a timeline.
+    pub tempo: Option<f64>,
+    /// Whether the session is playing, from peers that share start/stop.
+    pub playing: Option<bool>,
+}
+
+pub struct Listener {
+    socket: UdpSocket,
+}
+
+impl Listener {
+    /// Join the discovery group. Other Link apps on this machine listen on
+    /// the same port, so the address is shared.
+    pub fn bind() -> Result<Self> {
+        use socket2::{Domain, Protocol, Socket, Type};
+        let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+        socket.set_reuse_address(true)?;
+        socket.set_nonblocking(true)?;
+        socket
+            .bind(&SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, PORT).into())
+            .with_context(|| format!("Failed to listen on UDP port {}", PORT))?;
+        socket
+            .join_multicast_v4(&MULTICAST_ADDR, &Ipv4Addr::UNSPECIFIED)
+            .context("Failed to join the Link multicast group")?;
+        Ok(Listener {
+            socket: UdpSocket::from_std(socket.into())?,
+        })
+    }
+
+    /// The next announcement from a peer. Anything else on the port is
+    /// skipped.
+    pub async fn recv(&self) -> Result<Announcement> {
+        let mut buf = [0u8; 512];
+        loop {
+            let (n, _) = self.socket.recv_from(&mut buf).await?;
+            if let Some(announcement) = parse(&buf[..n]) {
+                return Ok(announcement);
+            }
+        }
+    }
+}
+
+/// Decode an ALIVE or RESPONSE message: headers, then entries of a 4-byte
+/// key, a big-endian u32 size and the value.
+fn parse(data: &[u8]) -> Option<Announcement> {
+    let rest = data.strip_prefix(PROTOCOL_HEADER)?;
+    if rest.len() < MESSAGE_HEADER_LEN || !matches!(rest[0], ALIVE | RESPONSE) {
+        return None;
+    }
+    let mut announcement = Announcement {
+        node: u64::from_be_bytes(rest[4..12].try_into().ok()?),
+        ..Default::default()
+    };
+
+    let mut entries = &rest[MESSAGE_HEADER_LEN..];
+    while entries.len() >= 8 {
+        let key = &entries[..4];
+        let size = u32::from_be_bytes(entries[4..8].try_into().ok()?) as usize;
+        let value = entries.get(8..8 + size)?;
+        if key == TIMELINE_KEY && size >= 8 {
+            // Tempo as microseconds per beat, then the beat and time origins
+            let micros_per_beat = i64::from_be_bytes(value[..8].try_into().ok()?);
+            if micros_per_beat > 0 {
+                announcement.tempo = Some(60_000_000.0 / micros_per_beat as f64);
+            }
+        } else if key == START_STOP_KEY && size >= 1 {
+            announcement.playing = Some(value[0] != 0);
+        }
+        entries = &entries[8 + size..];
+    }
+    Some(announcement)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A discovery message of `kind` from node 7 with the given entries.
+    fn message(kind: u8, entries: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+        let mut data = PROTOCOL_HEADER.to_vec();
+        data.extend_from_slice(&[kind, 5, 0, 0]);
+        data.extend_from_slice(&7u64.to_be_bytes());
+        for (key, value) in entries {
+            data.extend_from_slice(*key);
+            data.extend_from_slice(&(value.len() as u32).to_be_bytes());
+            data.extend_from_slice(value);
+        }
+        data
+    }
+
+    fn timeline(micros_per_beat: i64) -> Vec<u8> {
+        let mut value = micros_per_beat.to_be_bytes().to_vec();
+        // Beat and time origins, which we don't read
+        value.extend_from_slice(&[0; 16]);
+        value
+    }
+
+    #[test]
+    fn reads_tempo_and_transport() {
+        let data = message(
+            ALIVE,
+            &[
+                (b"sess", &[1; 8]),
+                (TIMELINE_KEY, &timeline(500_000)),
+                (START_STOP_KEY, &[1, 0, 0]),
+            ],
+        );
+        let announcement = parse(&data).unwrap();
+        assert_eq!(announcement.node, 7);
+        assert_eq!(announcement.tempo, Some(120.0));
+        assert_eq!(announcement.playing, Some(true));
+
+        let data = message(RESPONSE, &[(START_STOP_KEY, &[0])]);
+        let announcement = parse(&data).unwrap();
+        assert_eq!(announcement.tempo, None);
+        assert_eq!(announcement.playing, Some(false));
+    }
+
+    #[test]
+    fn skips_a_tempo_that_isnt_positive() {
+        let data = message(ALIVE, &[(TIMELINE_KEY, &timeline(0))]);
+        assert_eq!(parse(&data).unwrap().tempo, None);
+    }
+
+    #[test]
+    fn ignores_other_messages_and_truncated_entries() {
+        // A BYEBYE, and something that isn't Link at all
+        assert!(parse(&message(3, &[])).is_none());
+        assert!(parse(b"hello").is_none());
+
+        let mut data = message(ALIVE, &[(TIMELINE_KEY, &timeline(500_000))]);
+        data.truncate(data.len() - 4);
+        assert!(parse(&data).is_none());
+    }
+}
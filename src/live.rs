@@ -0,0 +1,143 @@
+// Connection supervision for long-running modes: periodic pings, disconnect
+// detection, and reconnecting (with a fresh copy of the device state) when the
+// device comes back.
+
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::error;
+use crate::protocol::{ConfigMsgIn, ConfigMsgOut, GlobalConfig, Layout};
+use crate::usb::FaderpunkDevice;
+
+pub const PING_INTERVAL: Duration = Duration::from_secs(2);
+const PING_TIMEOUT: Duration = Duration::from_secs(1);
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A device connection that survives cable pulls and reboots, plus a cache of
+/// the device state that is refreshed on every reconnect.
+pub struct LiveConnection {
+    dev: FaderpunkDevice,
+    /// The unit to reconnect to, if a particular one was asked for.
+    serial: Option<String>,
+    pub global_config: GlobalConfig,
+    pub layout: Layout,
+}
+
+impl LiveConnection {
+    pub async fn open() -> Result<Self> {
+        Self::connect(None).await
+    }
+
+    /// Like `open`, for the unit with this USB serial number.
+    pub async fn open_serial(serial: &str) -> Result<Self> {
+        Self::connect(Some(serial.to_string())).await
+    }
+
+    async fn connect(serial: Option<String>) -> Result<Self> {
+        let mut dev = reopen(serial.as_deref())?;
+        let (global_config, layout) = fetch_state(&mut dev).await?;
+        Ok(LiveConnection {
+            dev,
+            serial,
+            global_config,
+            layout,
+        })
+    }
+
+    pub fn device(&mut self) -> &mut FaderpunkDevice {
+        &mut self.dev
+    }
+
+    /// Ping the device; if it doesn't answer, wait for it to come back and
+    /// re-sync the cached state. Returns true if a reconnect happened, in which
+    /// case any event subscriptions must be renewed. Either way, the next reads
+    /// go to the device rather than the connection's read cache.
+    pub async fn check(&mut self) -> Result<bool> {
+        if self.dev.ping(PING_TIMEOUT).await.is_ok() {
+            self.dev.clear_cache();
+            return Ok(false);
+        }
+
+        eprintln!("{} disconnected, waiting to reconnect…", self.name());
+        let mut dev = loop {
+            tokio::time::sleep(RECONNECT_INTERVAL).await;
+            let Ok(mut dev) = reopen(self.serial.as_deref()) else {
+                continue;
+            };
+            if dev.ping(PING_TIMEOUT).await.is_ok() {
+                break dev;
+            }
+        };
+
+        let (global_config, layout) = fetch_state(&mut dev).await?;
+        let changed = serde_json::to_value(&global_config)?
+            != serde_json::to_value(&self.global_config)?
+            || serde_json::to_value(&layout)? != serde_json::to_value(&self.layout)?;
+        self.dev = dev;
+        self.global_config = global_config;
+        self.layout = layout;
+
+        if changed {
+            eprintln!(
+                "{} reconnected — its state changed while it was away; cache refreshed",
+                self.name()
+            );
+        } else {
+            eprintln!("{} reconnected", self.name());
+        }
+        Ok(true)
+    }
+
+    /// The device, by serial number if one was asked for, for messages.
+    fn name(&self) -> String {
+        match &self.serial {
+            Some(serial) => format!("Device {}", serial),
+            None => "Device".to_string(),
+        }
+    }
+}
+
+fn reopen(serial: Option<&str>) -> Result<FaderpunkDevice> {
+    match serial {
+        Some(serial) => FaderpunkDevice::open_serial(serial),
+        None => FaderpunkDevice::open(),
+    }
+}
+
+async fn fetch_state(dev: &mut FaderpunkDevice) -> Result<(GlobalConfig, Layout)> {
+    let config = match dev.send_receive(&ConfigMsgIn::GetGlobalConfig).await? {
+        ConfigMsgOut::GlobalConfig(c) => c,
+        other => return Err(error::unexpected(&ConfigMsgIn::GetGlobalConfig, &other)),
+    };
+    let layout = match dev.send_receive(&ConfigMsgIn::GetLayout).await? {
+        ConfigMsgOut::Layout(l) => l,
+        other => return Err(error::unexpected(&ConfigMsgIn::GetLayout, &other)),
+    };
+    Ok((config, layout))
+}
+
+/// SIGHUP, the conventional "reload your configuration" signal for
+/// long-running processes. Never fires on platforms without it.
+pub struct ReloadSignal {
+    #[cfg(unix)]
+    inner: tokio::signal::unix::Signal,
+}
+
+impl ReloadSignal {
+    pub fn new() -> Result<Self> {
+        Ok(ReloadSignal {
+            #[cfg(unix)]
+            inner: tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())?,
+        })
+    }
+
+    pub async fn recv(&mut self) {
+        #[cfg(unix)]
+        {
+            self.inner.recv().await;
+        }
+        #[cfg(not(unix))]
+        std::future::pending::<()>().await;
+    }
+}
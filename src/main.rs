@@ -1,21 +1,135 @@
+mod appcache;
+mod appdocs;
+mod automation;
+mod capture;
+mod clocksim;
+mod commands;
+mod conformance;
+mod designer;
 mod display;
-mod protocol;
-mod usb;
+mod exitcode;
+mod favorites;
+mod groups;
+mod hints;
+mod i18n;
+mod link;
+mod live;
+mod midimap;
+mod mirror;
+mod notes;
+mod pager;
+mod patchsheet;
+mod pins;
+mod profile;
+mod randomize;
+mod render;
+mod service;
+mod settings;
 
-use std::io::{Write, BufRead};
+use std::ffi::OsString;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::Duration;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{CommandFactory, Parser, Subcommand};
 use clap_complete::Shell;
+use faderpunk_cli::{appmeta, audit, error, frame, layout, limits, paths, protocol, random, session, slots, snapshot, template, timing, usb};
+use faderpunk_cli::invalid;
 
-use protocol::{ConfigMsgIn, ConfigMsgOut, Param, Value, APP_MAX_PARAMS, GLOBAL_CHANNELS};
+use commands::config::{
+    cmd_config, parse_aux_mode, parse_clock_src, parse_key, parse_midi_ports_out,
+};
+use commands::layout::cmd_layout;
+use commands::midi::cmd_midi;
+use commands::param::{
+    cmd_param, default_value, fetch_app_params, param_change, param_values, parse_float,
+    parse_param_type, parse_value, resolve_param, value_fits,
+};
+use commands::session::cmd_session;
+use commands::snapshot::{
+    apply_snapshot, cmd_load, cmd_save, fetch_snapshot, restore_groups, save_snapshot,
+};
+use i18n::t;
+use limits::LIMITS;
+use protocol::{ConfigMsgIn, ConfigMsgOut, Param, Value, GLOBAL_CHANNELS};
+use snapshot::Snapshot;
 use usb::FaderpunkDevice;
 
 #[derive(Parser)]
-#[command(name = "fp", about = "CLI tool for the Faderpunk controller")]
+#[command(
+    name = "fp",
+    about = "CLI tool for the Faderpunk controller",
+    // Lets a flag typed on the command line override the same one from [defaults]
+    args_override_self = true
+)]
 struct Cli {
+    /// Don't send long output through a pager
+    #[arg(long, global = true)]
+    no_pager: bool,
+
+    /// Report how long opening the device, each request, and the CLI itself took
+    #[arg(long, global = true)]
+    profile: bool,
+
+    /// Bytes per USB read request (a multiple of 64)
+    #[arg(long, global = true, value_name = "BYTES")]
+    usb_transfer_size: Option<usize>,
+
+    /// Number of USB read requests kept in flight
+    #[arg(long, global = true, value_name = "N")]
+    usb_queue_depth: Option<usize>,
+
+    /// USB vendor id to look for, in hex (default: f569, or FP_VID)
+    #[arg(long, global = true, value_name = "ID", value_parser = usb::parse_id)]
+    vid: Option<u16>,
+
+    /// USB product id to look for, in hex (default: 0001, or FP_PID)
+    #[arg(long, global = true, value_name = "ID", value_parser = usb::parse_id)]
+    pid: Option<u16>,
+
+    /// USB interface to talk to, by number or name (default: FP_INTERFACE, or
+    /// the configurator's vendor interface)
+    #[arg(long, global = true, value_name = "INTERFACE", value_parser = usb::parse_interface)]
+    interface: Option<usb::InterfaceChoice>,
+
+    /// Read commands from stdin, one per line, and print one JSON result per line
+    #[arg(long)]
+    stdin: bool,
+
+    /// Language for messages, e.g. de (default: from LANG)
+    #[arg(long, global = true, value_name = "LANG")]
+    lang: Option<String>,
+
+    /// Fail when the device applies a write differently than sent (a layout
+    /// it rearranged, a param it clamped) instead of accepting the correction
+    #[arg(long, global = true)]
+    strict: bool,
+
+    /// Ignore the [defaults] in config.toml for this command
+    #[arg(long, global = true)]
+    no_defaults: bool,
+
+    /// Output format: pretty, plain, json, ndjson, markdown, yaml or toml
+    #[arg(long, global = true, value_name = "FORMAT", value_parser = render::parse_output)]
+    output: Option<render::Output>,
+
+    /// Color the output: auto (on a terminal, unless NO_COLOR is set), always or never
+    #[arg(long, global = true, value_name = "WHEN", value_parser = render::parse_color)]
+    color: Option<render::ColorChoice>,
+
+    /// Print only errors and warnings; the exit code tells how the command went
+    #[arg(short, long, global = true, conflicts_with = "output")]
+    quiet: bool,
+
+    /// Record the commands run and every change sent to the device, with
+    /// timings, to this session file (see `session replay`)
+    #[arg(long, global = true, value_name = "FILE")]
+    record: Option<std::path::PathBuf>,
+
     #[command(subcommand)]
-    command: Commands,
+    command: Option<Commands>,
 }
 
 #[derive(Subcommand)]
@@ -23,11 +137,34 @@ enum Commands {
     /// Check if the Faderpunk is connected
     Ping,
 
+    /// Run several commands over one device connection
+    Batch {
+        /// Commands, one per argument, e.g. 'layout set 1 fader' 'config bpm 120'
+        #[arg(required = true)]
+        commands: Vec<String>,
+        /// Continue with the next command when one fails
+        #[arg(short, long)]
+        keep_going: bool,
+    },
+
     /// Show current device configuration
-    Status,
+    Status {
+        /// Show the state mirrored by 'daemon mirror' instead of asking the device
+        #[arg(long)]
+        cached: bool,
+    },
 
     /// List available apps on the device
-    Apps,
+    Apps {
+        #[command(subcommand)]
+        action: Option<AppsAction>,
+    },
+
+    /// Summarize the current rig and historical app usage
+    Stats,
+
+    /// Print messages the device sends on its own, until interrupted
+    Monitor,
 
     /// View or modify the fader layout
     Layout {
@@ -35,28 +172,246 @@ enum Commands {
         action: Option<LayoutAction>,
     },
 
+    /// Name groups of faders, usable as @NAME wherever a slot list is
+    Group {
+        #[command(subcommand)]
+        action: Option<GroupAction>,
+    },
+
     /// View or set app parameters
     Param {
         #[command(subcommand)]
         action: Option<ParamAction>,
     },
 
+    /// Note what a fader is for; notes show in status and layout, and are saved with snapshots
+    Note {
+        #[command(subcommand)]
+        action: Option<NoteAction>,
+    },
+
+    /// Pin params for quick access with `pinned`
+    Pin {
+        #[command(subcommand)]
+        action: Option<PinAction>,
+    },
+
+    /// Show the pinned params across the rig; set them by row as N=VALUE
+    Pinned {
+        /// Values to set, as ROW=VALUE (rows as numbered in the view)
+        #[arg(value_name = "N=VALUE")]
+        assignments: Vec<String>,
+        /// Prompt for each pinned value in turn
+        #[arg(short, long, conflicts_with = "assignments")]
+        edit: bool,
+        /// Clamp out-of-range values to the nearest bound instead of failing
+        #[arg(long)]
+        clamp: bool,
+    },
+
+    /// Bulk-edit the MIDI params of the apps in the layout
+    Midi {
+        #[command(subcommand)]
+        action: MidiAction,
+    },
+
+    /// Run a macro (a named set of param changes) from config.toml; lists them without a name
+    Macro {
+        /// Macro name
+        name: Option<String>,
+    },
+
+    /// Play an automation file: timed param, macro, BPM and profile changes
+    Play {
+        /// Automation file (YAML cue list)
+        file: String,
+        /// Follow MIDI clock from this raw MIDI input (e.g. /dev/snd/midiC1D0) instead of the wall clock
+        #[arg(long, value_name = "DEVICE")]
+        midi_clock: Option<String>,
+        /// Only print the cue list
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Replay a session recorded with --record
+    Session {
+        #[command(subcommand)]
+        action: SessionAction,
+    },
+
     /// Get or set global configuration
     Config {
         #[command(subcommand)]
         action: ConfigAction,
     },
 
+    /// Sync the device clock with other gear
+    Clock {
+        #[command(subcommand)]
+        action: ClockAction,
+    },
+
+    /// Work out what the device would do, without touching it
+    Simulate {
+        #[command(subcommand)]
+        action: SimulateAction,
+    },
+
+    /// Write documents generated from the device config
+    Export {
+        #[command(subcommand)]
+        action: ExportAction,
+    },
+
+    /// Check that the firmware handles the config protocol as it should
+    Conformance {
+        #[command(subcommand)]
+        action: ConformanceAction,
+    },
+
+    /// Compare a slot's params against a tweaked version, switching with a keypress
+    Ab {
+        /// Fader slot (1-16)
+        #[arg(value_parser = slots::parse)]
+        slot: u8,
+    },
+
+    /// Print a hash of the complete device state, for checking whether it changed
+    Fingerprint {
+        /// Hash this snapshot file instead of the device
+        #[arg(long, value_name = "PATH")]
+        file: Option<String>,
+        /// Print the mirror's state revision instead, a counter that goes up with
+        /// every change `daemon mirror` sees
+        #[arg(long)]
+        revision: bool,
+    },
+
     /// Save current device config to a JSON file
     Save {
         /// Output file path
         path: String,
+        /// Only write what differs from this snapshot (an overlay for 'load --overlay')
+        #[arg(long, value_name = "BASE")]
+        delta: Option<String>,
     },
 
     /// Load a config from a JSON file and apply it to the device
     Load {
         /// Input file path
         path: String,
+        /// Partial snapshot merged over the file (JSON merge patch); repeatable, applied in order
+        #[arg(long = "overlay", value_name = "PATH")]
+        overlays: Vec<String>,
+        /// Value for a ${NAME} placeholder in the file, as NAME=value; repeatable
+        #[arg(long = "var", value_name = "NAME=VALUE", value_parser = template::parse_var)]
+        vars: Vec<(String, String)>,
+        /// Glide numeric params and BPM to the snapshot's values over this long (e.g. 5s)
+        #[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration)]
+        fade: Option<Duration>,
+    },
+
+    /// Bring the device to a snapshot's state, changing only what differs
+    Ensure {
+        /// Desired state (snapshot file)
+        path: String,
+        /// Only print the plan
+        #[arg(long)]
+        dry_run: bool,
+        /// Skip confirmation prompt
+        #[arg(short, long)]
+        force: bool,
+        /// Value for a ${NAME} placeholder in the file, as NAME=value; repeatable
+        #[arg(long = "var", value_name = "NAME=VALUE", value_parser = template::parse_var)]
+        vars: Vec<(String, String)>,
+    },
+
+    /// Edit the device state in $EDITOR, then review and apply the changes
+    Edit {
+        #[command(subcommand)]
+        what: Option<EditTarget>,
+        /// Skip confirmation prompt
+        #[arg(short, long, global = true)]
+        force: bool,
+    },
+
+    /// Watch the device and report (or undo) drift from a known-good snapshot
+    Guard {
+        /// Baseline snapshot file
+        baseline: String,
+        /// Re-apply the baseline when drift is found instead of only reporting it
+        #[arg(long)]
+        restore: bool,
+        /// Param that may change freely, as SLOT:PARAM (name or index); repeatable
+        #[arg(long = "allow", value_name = "SLOT:PARAM")]
+        allow: Vec<String>,
+        /// Global config field that may change freely, e.g. clock.internal_bpm; repeatable
+        #[arg(long = "allow-config", value_name = "FIELD")]
+        allow_config: Vec<String>,
+        /// Seconds between checks
+        #[arg(long, default_value_t = 5)]
+        interval: u64,
+    },
+
+    /// Keep a snapshot file and the device in step: file edits go to the device, device changes to the file
+    Sync {
+        /// Snapshot file (written from the device if it doesn't exist)
+        path: String,
+        /// Keep syncing until interrupted instead of syncing once
+        #[arg(long)]
+        watch: bool,
+        /// Side that wins where both changed the same value: device, file
+        #[arg(long, default_value = "device", value_parser = parse_sync_side)]
+        prefer: SyncSide,
+        /// Seconds between device checks, for changes that send no event (with --watch)
+        #[arg(long, default_value_t = 5)]
+        interval: u64,
+    },
+
+    /// Copy layout and param changes from one unit to another as they happen (same firmware on both)
+    Mirror {
+        /// Serial number of the unit to copy from (shown by 'status')
+        #[arg(long, value_name = "SERIAL")]
+        leader: String,
+        /// Serial number of the unit that follows it
+        #[arg(long, value_name = "SERIAL")]
+        follower: String,
+        /// Seconds between checks, for changes that send no event
+        #[arg(long, default_value_t = 5)]
+        interval: u64,
+    },
+
+    /// Run guard, monitor, clock link or mirror as a login service, or keep a state mirror
+    Daemon {
+        #[command(subcommand)]
+        action: DaemonAction,
+    },
+
+    /// Save, recall, and list named profiles
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
+
+    /// Guided first-run setup: clock, MIDI, quantizer, and a starting layout
+    Init,
+
+    /// Show the log of changes made to the device
+    Log {
+        #[command(subcommand)]
+        action: Option<LogAction>,
+    },
+
+    /// Low-level commands with stable JSON input and output, for tools and scripts
+    Plumb {
+        #[command(subcommand)]
+        action: PlumbAction,
+    },
+
+    /// Tools for firmware development
+    Debug {
+        #[command(subcommand)]
+        action: DebugAction,
     },
 
     /// Generate shell completions
@@ -73,6 +428,107 @@ enum Commands {
     },
 }
 
+impl Commands {
+    /// Read-only commands whose output can run past a screen and should be paged.
+    fn is_long_output(&self) -> bool {
+        match self {
+            Commands::Status { .. } | Commands::Stats => true,
+            Commands::Apps { action } => matches!(action, None | Some(AppsAction::List)),
+            Commands::Layout { action } => matches!(action, None | Some(LayoutAction::Show)),
+            Commands::Param { action } => matches!(
+                action,
+                None | Some(ParamAction::Show { .. } | ParamAction::Find { json: false, .. })
+            ),
+            Commands::Config { action } => matches!(action, ConfigAction::Show),
+            Commands::Log { action } => matches!(action, None | Some(LogAction::Show { .. })),
+            _ => false,
+        }
+    }
+
+    /// Commands that draw their own screen and need stdout to be the terminal.
+    fn is_full_screen(&self) -> bool {
+        matches!(self, Commands::Layout { action: Some(LayoutAction::Edit) })
+    }
+}
+
+#[derive(Subcommand)]
+enum AppsAction {
+    /// List installed apps (default)
+    List,
+
+    /// Write a reference of every installed app and its params (Markdown, or HTML for .html)
+    ExportDocs {
+        /// Output file path, or - for stdout
+        path: String,
+    },
+
+    /// Write the metadata of every installed app (channels, param schemas) as JSON, for tools
+    ExportMeta {
+        /// Output file path, or - for stdout
+        path: String,
+    },
+
+    /// Star an app so it's listed first in apps, pickers and completions
+    Star {
+        /// App name or ID
+        app: String,
+    },
+
+    /// Remove an app's star
+    Unstar {
+        /// App name or ID
+        app: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum EditTarget {
+    /// Edit only the layout
+    Layout,
+    /// Edit only the params of the app on one fader
+    Params {
+        /// Fader slot (1-16)
+        #[arg(value_parser = slots::parse)]
+        slot: u8,
+    },
+}
+
+#[derive(Subcommand)]
+enum DebugAction {
+    /// Log every frame the device sends, with hex and best-effort decoding,
+    /// including frames this build can't decode
+    DumpStream {
+        /// Stop after this many seconds (default: until Ctrl-C)
+        #[arg(long)]
+        seconds: Option<u64>,
+    },
+}
+
+/// Plumbing: unlike the other commands, whose output is meant for people and
+/// may change, these print one line of compact JSON (or nothing) and keep
+/// their arguments and output stable. Values use the snapshot file format.
+#[derive(Subcommand)]
+enum PlumbAction {
+    /// Print the layout: 16 entries, each null or [app_id, channels, layout_id]
+    GetLayout,
+    /// Print the global config
+    GetConfig,
+    /// Print the param values of an app instance as an array
+    GetParams {
+        /// Layout ID of the app instance (see get-layout)
+        layout_id: u8,
+    },
+    /// Set one param of an app instance; prints nothing on success
+    SetParam {
+        /// Layout ID of the app instance (see get-layout)
+        layout_id: u8,
+        /// Param index
+        index: usize,
+        /// New value as JSON, in the shape get-params prints, e.g. '{"Range":"_0_5V"}'
+        value: String,
+    },
+}
+
 #[derive(Subcommand)]
 enum CompleteTarget {
     /// List app names (one per line, tab-separated with description)
@@ -80,7 +536,169 @@ enum CompleteTarget {
     /// List slot numbers with current occupant
     Slots,
     /// List param names for a given slot
-    Params { slot: u8 },
+    Params {
+        #[arg(value_parser = slots::parse)]
+        slot: u8,
+    },
+    /// List layout IDs with the app they belong to
+    LayoutIds,
+}
+
+#[derive(Subcommand)]
+enum GroupAction {
+    /// List groups and the faders in each
+    List,
+
+    /// Create a group from a slot list (e.g. 1-6, or 1-3,9)
+    Create {
+        /// Group name
+        name: String,
+        /// Fader slots in the group
+        slots: String,
+    },
+
+    /// Delete a group (the apps on its faders are untouched)
+    Delete {
+        /// Group name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum NoteAction {
+    /// List notes by fader (default)
+    List,
+
+    /// Note what faders are for, e.g. "send to delay return"
+    Set {
+        /// Fader slots (e.g. 3, 1-4, @group)
+        slots: String,
+        /// Note text
+        text: String,
+    },
+
+    /// Remove the notes from faders
+    Clear {
+        /// Fader slots (e.g. 3, 1-4, @group)
+        slots: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum PinAction {
+    /// List pins (default)
+    List,
+
+    /// Pin params, e.g. 3:cutoff
+    Add {
+        /// Params as SLOT:PARAM (name or index)
+        #[arg(required = true, value_name = "SLOT:PARAM")]
+        params: Vec<String>,
+    },
+
+    /// Unpin params
+    Remove {
+        /// Params as SLOT:PARAM, as listed by 'pin list'
+        #[arg(required = true, value_name = "SLOT:PARAM")]
+        params: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProfileAction {
+    /// List saved profiles
+    List,
+
+    /// Save the current device config as a named profile
+    Save {
+        /// Profile name
+        name: String,
+    },
+
+    /// Apply a saved profile to the device
+    Load {
+        /// Profile name
+        name: String,
+        /// Value for a ${NAME} placeholder in the profile, as NAME=value; repeatable
+        #[arg(long = "var", value_name = "NAME=VALUE", value_parser = template::parse_var)]
+        vars: Vec<(String, String)>,
+    },
+
+    /// Update a profile's apps and params to match the apps on the connected device
+    Migrate {
+        /// Profile name
+        name: String,
+        /// Show what would change without rewriting the profile
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Delete a saved profile
+    Delete {
+        /// Profile name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum DaemonAction {
+    /// Install a user service (systemd, or launchd on macOS) that runs a command at login
+    InstallService {
+        /// Service name
+        #[arg(long, default_value = "faderpunk")]
+        name: String,
+        /// Print the service file instead of installing it
+        #[arg(long)]
+        print: bool,
+        /// Overwrite an existing service file
+        #[arg(short, long)]
+        force: bool,
+        /// Command to run, e.g. guard rig.json --restore
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+
+    /// Remove a service installed with install-service
+    UninstallService {
+        /// Service name
+        #[arg(long, default_value = "faderpunk")]
+        name: String,
+    },
+
+    /// Keep a JSON mirror of the device state in the data directory, for other tools and 'status --cached'
+    Mirror {
+        /// Seconds between refreshes (device events refresh it right away)
+        #[arg(long, default_value_t = 5)]
+        interval: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum SessionAction {
+    /// Re-send a recorded session's changes to the device, keeping their timing
+    Replay {
+        /// Session file written by --record
+        file: String,
+        /// Play back faster or slower, e.g. 2x or 0.5x
+        #[arg(long, default_value = "1x", value_parser = session::parse_speed)]
+        speed: f64,
+        /// Only print what would be sent
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum LogAction {
+    /// Show recorded changes (default)
+    Show {
+        /// Only show changes newer than this (e.g. 30m, 1h, 2days)
+        #[arg(long, value_parser = humantime::parse_duration)]
+        since: Option<std::time::Duration>,
+    },
+
+    /// Print the location of the log file
+    Path,
 }
 
 #[derive(Subcommand)]
@@ -91,6 +709,7 @@ enum LayoutAction {
     /// Assign an app to a fader slot (1-16)
     Set {
         /// Fader slot number (1-16)
+        #[arg(value_parser = slots::parse)]
         slot: u8,
         /// App name or ID (use 'apps' command to see available)
         app: String,
@@ -102,6 +721,7 @@ enum LayoutAction {
     /// Remove an app from a fader slot
     Remove {
         /// Fader slot number (1-16)
+        #[arg(value_parser = slots::parse)]
         slot: u8,
         /// Skip confirmation prompt
         #[arg(short, long)]
@@ -123,24 +743,187 @@ enum LayoutAction {
         #[arg(short, long)]
         force: bool,
     },
+
+    /// Pack a list of apps onto the strip, preview, and apply
+    Plan {
+        /// App names or IDs, in the order they should appear
+        #[arg(required = true)]
+        apps: Vec<String>,
+        /// Keep the current layout and fit the apps into its free faders
+        #[arg(short, long)]
+        keep: bool,
+        /// Skip confirmation prompt
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Fill the strip with randomly picked apps, preview, and apply
+    Random {
+        /// Only pick from these apps, by name or ID (default: every app)
+        #[arg(long, value_delimiter = ',', value_name = "APPS")]
+        include: Vec<String>,
+        /// Place no app more than this many times
+        #[arg(long, value_name = "N")]
+        max_per_app: Option<usize>,
+        /// Seed for the random picks; the same seed gives the same layout again
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Only show the layout, don't apply it
+        #[arg(long)]
+        dry_run: bool,
+        /// Skip confirmation prompt
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Arrange apps on the strip in a full-screen designer, then write the layout
+    Edit,
 }
 
 #[derive(Subcommand)]
 enum ParamAction {
     /// Show parameters for all apps (default)
     Show {
-        /// Optional: fader slot to show (1-16)
-        slot: Option<u8>,
+        /// Optional: fader slots to show (e.g. 8, 1-4, 1,3,9-12, @group)
+        slots: Option<String>,
+        /// Plain values without bar graphs
+        #[arg(long)]
+        ascii: bool,
+        /// Machine-readable JSON output
+        #[arg(long, conflicts_with = "ascii")]
+        json: bool,
+        /// Also list unused param indices, numbering every row (for firmware debugging)
+        #[arg(long)]
+        all_indices: bool,
+        /// Group the params of apps without param_groups in config.toml by what they do
+        #[arg(long)]
+        grouped: bool,
     },
 
     /// Set a parameter value
     Set {
-        /// Fader slot number (1-16)
-        slot: u8,
+        /// Fader slot (1-16), or a slot list or @group to set it on several apps
+        slots: String,
         /// Parameter name or index (0-based)
         param: String,
         /// Value to set
         value: String,
+        /// Clamp out-of-range numbers to the param's min/max instead of failing
+        #[arg(long)]
+        clamp: bool,
+        /// Write even if the app's params changed on the device since they were read
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Flip an on/off param, or turn it on or off, without knowing its current state
+    Toggle {
+        /// Fader slot (1-16), slot list, @group, or @APP for every instance of an app
+        slots: String,
+        /// Parameter name or index (0-based)
+        param: String,
+        /// Turn it on instead of flipping it
+        #[arg(long, conflicts_with = "off")]
+        on: bool,
+        /// Turn it off instead of flipping it
+        #[arg(long)]
+        off: bool,
+        /// Write even if the app's params changed on the device since they were read
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Set every CV range param at once (e.g. 0-10v, 0-5v, -5-5v)
+    SetRange {
+        /// Range to set
+        #[arg(allow_hyphen_values = true)]
+        range: String,
+        /// Every app in the layout
+        #[arg(long, required_unless_present = "slots")]
+        all: bool,
+        /// Only the apps on these fader slots (e.g. 8, 1-4, 1,3,9-12, @group)
+        #[arg(long, conflicts_with = "all")]
+        slots: Option<String>,
+    },
+
+    /// Randomize params, within the limits of a constraints file if given
+    Randomize {
+        /// Every app in the layout
+        #[arg(long, required_unless_present = "slots")]
+        all: bool,
+        /// Only the apps on these fader slots (e.g. 8, 1-4, 1,3,9-12, @group)
+        #[arg(long, conflicts_with = "all")]
+        slots: Option<String>,
+        /// Constraints file (YAML): frozen params, ranges and distributions per app
+        #[arg(long, value_name = "FILE")]
+        constraints: Option<String>,
+        /// Seed for the random values; the same seed gives the same values again
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+
+    /// List every param of one type across the rig, with its value (e.g. --type range)
+    Find {
+        /// Param type: int, float, bool, enum, curve, waveform, color, range, note, midicc,
+        /// midichannel, midiin, midimode, midinote, midiout, midinrpn, voltperoct
+        #[arg(long = "type", value_name = "TYPE", value_parser = parse_param_type)]
+        kind: &'static str,
+        /// Only the apps on these fader slots (e.g. 8, 1-4, 1,3,9-12, @group)
+        #[arg(long)]
+        slots: Option<String>,
+        /// Machine-readable JSON output
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Graph how a param changed over time, from the change log
+    History {
+        /// Fader slot (1-16)
+        #[arg(value_parser = slots::parse)]
+        slot: u8,
+        /// Parameter name or index (0-based)
+        param: String,
+        /// How far back to look (e.g. 30m, 1h, 2days)
+        #[arg(long, default_value = "1h", value_parser = humantime::parse_duration)]
+        last: Duration,
+    },
+}
+
+#[derive(Subcommand)]
+enum MidiAction {
+    /// Move every MIDI channel param on one channel to another
+    Remap {
+        /// Channel to replace (1-16)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(1..=16))]
+        from_channel: u8,
+        /// New channel (1-16)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(1..=16))]
+        to_channel: u8,
+        /// Only the apps on these fader slots (e.g. 8, 1-4, 1,3,9-12, @group)
+        #[arg(long)]
+        slots: Option<String>,
+    },
+
+    /// Give every CC param in the layout its own CC number, in fader order
+    AutoAssign {
+        /// First CC number to hand out
+        #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u16).range(0..=127))]
+        start: u16,
+    },
+
+    /// Set the CC params to a DAW project's controller mappings, in fader order
+    Import {
+        /// Mapping file (an Ableton set, or CSV)
+        file: String,
+        /// File format: ableton-xml, generic-csv
+        #[arg(long, value_parser = midimap::parse_format)]
+        format: midimap::Format,
+        /// Only the apps on these fader slots (e.g. 8, 1-4, 1,3,9-12, @group)
+        #[arg(long)]
+        slots: Option<String>,
+        /// Only print the assignments
+        #[arg(long)]
+        dry_run: bool,
     },
 }
 
@@ -151,7 +934,8 @@ enum ConfigAction {
 
     /// Set the BPM
     Bpm {
-        /// BPM value (e.g. 120.0)
+        /// BPM value (e.g. 120.0 or 120,5)
+        #[arg(value_parser = parse_float)]
         value: f32,
     },
 
@@ -161,6 +945,15 @@ enum ConfigAction {
         value: u8,
     },
 
+    /// Set the pulses per quarter note of the external clock input
+    Ppqn {
+        /// PPQN value (e.g. 24)
+        value: u8,
+    },
+
+    /// Show the value ranges the CLI checks setters against
+    Limits,
+
     /// Set takeover mode (pickup, jump, scale)
     Takeover {
         /// Mode name
@@ -172,24 +965,314 @@ enum ConfigAction {
         /// Source name
         source: String,
     },
+
+    /// Set the I2C mode (leader, follower, calibration)
+    I2c {
+        /// Mode name
+        mode: String,
+    },
+
+    /// Print the global config as NAME=value lines, for shell scripts and Makefiles
+    Env {
+        /// Variable name prefix
+        #[arg(long, default_value = "FP_")]
+        prefix: String,
+    },
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
+#[derive(Subcommand)]
+enum ClockAction {
+    /// Follow an Ableton Link session's tempo on the local network, writing it to the internal BPM
+    Link {
+        /// Also follow the session's start/stop: internal clock while playing, no clock when stopped
+        #[arg(long)]
+        transport: bool,
+    },
 
-    match cli.command {
-        Commands::Ping => cmd_ping().await,
-        Commands::Status => cmd_status().await,
-        Commands::Apps => cmd_apps().await,
-        Commands::Layout { action } => cmd_layout(action).await,
-        Commands::Param { action } => cmd_param(action).await,
-        Commands::Config { action } => cmd_config(action).await,
-        Commands::Save { path } => cmd_save(&path).await,
-        Commands::Load { path } => cmd_load(&path).await,
-        Commands::Completions { shell } => cmd_completions(shell),
-        Commands::Complete { what } => cmd_complete(what).await,
-    }
+    /// Time the MIDI clock the device sends and report its tempo and jitter
+    Measure {
+        /// Raw MIDI input the device's clock arrives on (e.g. /dev/snd/midiC1D0)
+        #[arg(long, value_name = "DEVICE")]
+        midi_in: String,
+        /// How long to listen
+        #[arg(long, default_value_t = 30)]
+        seconds: u64,
+        /// BPM to compare against (default: the device's internal BPM)
+        #[arg(long, value_parser = parse_float)]
+        expect: Option<f32>,
+    },
+}
+
+#[derive(Subcommand)]
+enum SimulateAction {
+    /// Draw a timing diagram of the aux clock and reset outputs
+    Clock {
+        /// BPM of the internal clock (default: the device's with --device, else 120)
+        #[arg(long, value_parser = parse_float)]
+        bpm: Option<f32>,
+        /// Aux 1 (Atom): divN (clock out every N ticks at 24 PPQN), reset, or off
+        #[arg(long, value_parser = parse_aux_mode)]
+        aux1: Option<protocol::AuxJackMode>,
+        /// Aux 2 (Meteor), as for --aux1
+        #[arg(long, value_parser = parse_aux_mode)]
+        aux2: Option<protocol::AuxJackMode>,
+        /// Aux 3 (Cube), as for --aux1
+        #[arg(long, value_parser = parse_aux_mode)]
+        aux3: Option<protocol::AuxJackMode>,
+        /// Number of 4/4 bars to draw
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=16))]
+        bars: u32,
+        /// Start from the connected device's BPM and aux jack settings
+        #[arg(long)]
+        device: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ExportAction {
+    /// A one-page summary of the jacks, MIDI and I2C connections the config implies, for the case lid
+    Patchsheet {
+        /// Output file path (Markdown for .md, else plain text), or - for stdout
+        #[arg(default_value = "-")]
+        path: String,
+        /// Write Markdown whatever the file extension
+        #[arg(long)]
+        markdown: bool,
+        /// Describe a snapshot or profile file instead of the connected device
+        #[arg(long, value_name = "FILE")]
+        from: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConformanceAction {
+    /// Set and read back every config field, boundary values and full layouts, and report pass/fail
+    Run {
+        /// Skip confirmation prompt
+        #[arg(short, long)]
+        force: bool,
+        /// Machine-readable JSON output
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match cli_main().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            display::print_error(&e, hints::hint(&e).as_deref());
+            if e.backtrace().status() == std::backtrace::BacktraceStatus::Captured {
+                eprintln!("\n{}", e.backtrace());
+            }
+            ExitCode::from(exitcode::of(&e))
+        }
+    }
+}
+
+async fn cli_main() -> Result<()> {
+    // A first parse without the settings answers --help and completions, so
+    // a config.toml that doesn't load can't get in their way
+    let args: Vec<OsString> = std::env::args_os().collect();
+    let settings = match Cli::try_parse_from(&args).map(|cli| cli.command) {
+        Err(e) if !e.use_stderr() => exit_usage(e),
+        Ok(Some(Commands::Completions { shell })) => return cmd_completions(shell),
+        // Completions never fail; a config they can't read counts as none
+        Ok(Some(Commands::Complete { .. })) => settings::Settings::load().unwrap_or_default(),
+        _ => settings::Settings::load()?,
+    };
+    // Slot arguments are parsed with the configured fader labels
+    slots::configure(settings.slot_labels()).context("Invalid slot_labels in settings")?;
+    let cli = parse_cli(with_defaults(args, &settings.defaults)?);
+    i18n::configure(cli.lang.as_deref())?;
+    let color = cli.color.unwrap_or_default();
+    display::set_stderr_color(color.enabled(std::io::stderr().is_terminal()));
+    // Decided before a pager takes over stdout, which it pipes
+    let stdout_color = color.enabled(std::io::stdout().is_terminal());
+    usb::configure_transfers(
+        cli.usb_transfer_size
+            .or(settings.usb_transfer_size)
+            .unwrap_or(usb::DEFAULT_TRANSFER_SIZE),
+        cli.usb_queue_depth
+            .or(settings.usb_queue_depth)
+            .unwrap_or(usb::DEFAULT_QUEUE_DEPTH),
+    )?;
+    usb::configure_pacing(
+        settings.usb_message_gap_ms.map(Duration::from_millis),
+        settings.usb_chunk_gap_ms.map(Duration::from_millis),
+    );
+    usb::set_strict(cli.strict);
+    usb::configure_device(
+        cli.vid.or(env_override("FP_VID", usb::parse_id)?).unwrap_or(usb::FADERPUNK_VID),
+        cli.pid.or(env_override("FP_PID", usb::parse_id)?).unwrap_or(usb::FADERPUNK_PID),
+        cli.interface.or(env_override("FP_INTERFACE", usb::parse_interface)?),
+    );
+
+    if let Some(path) = &cli.record {
+        session::start(path)?;
+    }
+    if cli.stdin {
+        if cli.command.is_some() {
+            exit_usage(Cli::command().error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--stdin reads its commands from stdin and can't be combined with one",
+            ));
+        }
+        if cli.quiet {
+            exit_usage(Cli::command().error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--stdin prints a result for every command and can't be combined with --quiet",
+            ));
+        }
+        return cmd_stdin().await;
+    }
+    let Some(command) = cli.command else {
+        exit_usage(Cli::command().error(
+            clap::error::ErrorKind::MissingSubcommand,
+            "a command is required (or --stdin)",
+        ));
+    };
+
+    if cli.record.is_some() {
+        session::command(&recorded_command_line())?;
+    }
+    if cli.profile {
+        timing::enable();
+    }
+    let start = std::time::Instant::now();
+    let paged = command.is_long_output() && !cli.no_pager && !cli.quiet;
+    let pager = if paged && settings.pager.unwrap_or(true) {
+        pager::start(settings.pager_command.as_deref())
+    } else {
+        None
+    };
+
+    let output = cli.output.unwrap_or_default();
+    if output.is_structured() || cli.quiet {
+        // Nobody to answer a prompt when a program reads the output, or
+        // to see it when nothing is printed
+        NON_INTERACTIVE.store(true, std::sync::atomic::Ordering::SeqCst);
+    }
+    // Everything printed is caught and dropped
+    let silenced = cli.quiet.then(capture::start).transpose()?;
+    // Taking the colors out would take the designer's screen handling too
+    let session = render::begin(output, stdout_color || command.is_full_screen());
+    let result = run(command).await;
+    session.end();
+    if let Some(silenced) = silenced {
+        silenced.finish();
+    }
+    if cli.profile {
+        // Wait for the pager so the report doesn't land in the middle of it
+        drop(pager);
+        print_profile(start.elapsed());
+    }
+    result
+}
+
+/// Print the --profile report to stderr: each device exchange, then how the
+/// total splits between the device (USB and firmware) and the CLI.
+fn print_profile(total: Duration) {
+    let timings = timing::take();
+    let device: Duration = timings.iter().map(|(_, d)| *d).sum();
+    let ms = |d: Duration| format!("{:>9.1} ms", d.as_secs_f64() * 1000.0);
+    let width = timings.iter().map(|(what, _)| what.chars().count()).max().unwrap_or(0).max(24);
+
+    eprintln!();
+    eprintln!("Profile:");
+    for (what, duration) in &timings {
+        eprintln!("  {:<width$} {}", what, ms(*duration));
+    }
+    eprintln!("  {:<width$} {}", "device (USB + firmware)", ms(device));
+    eprintln!(
+        "  {:<width$} {}",
+        "CLI (parsing, rendering)",
+        ms(total.saturating_sub(device))
+    );
+    eprintln!("  {:<width$} {}", "total", ms(total));
+}
+
+/// Run one parsed command.
+async fn run(command: Commands) -> Result<()> {
+    match command {
+        Commands::Ping => cmd_ping().await,
+        Commands::Batch {
+            commands,
+            keep_going,
+        } => cmd_batch(&commands, keep_going).await,
+        Commands::Status { cached } => cmd_status(cached).await,
+        Commands::Apps { action } => cmd_apps(action).await,
+        Commands::Stats => cmd_stats().await,
+        Commands::Monitor => cmd_monitor().await,
+        Commands::Layout { action } => cmd_layout(action).await,
+        Commands::Note { action } => cmd_note(action),
+        Commands::Group { action } => cmd_group(action),
+        Commands::Param { action } => cmd_param(action).await,
+        Commands::Pin { action } => cmd_pin(action).await,
+        Commands::Pinned {
+            assignments,
+            edit,
+            clamp,
+        } => cmd_pinned(&assignments, edit, clamp).await,
+        Commands::Midi { action } => cmd_midi(action).await,
+        Commands::Macro { name } => cmd_macro(name.as_deref()).await,
+        Commands::Play {
+            file,
+            midi_clock,
+            dry_run,
+        } => cmd_play(Path::new(&file), midi_clock.as_deref().map(Path::new), dry_run).await,
+        Commands::Session { action } => cmd_session(action).await,
+        Commands::Config { action } => cmd_config(action).await,
+        Commands::Clock { action } => cmd_clock(action).await,
+        Commands::Simulate { action } => cmd_simulate(action).await,
+        Commands::Export { action } => cmd_export(action).await,
+        Commands::Conformance { action } => cmd_conformance(action).await,
+        Commands::Ab { slot } => cmd_ab(slot).await,
+        Commands::Fingerprint { file, revision } => {
+            cmd_fingerprint(file.as_deref(), revision).await
+        }
+        Commands::Save { path, delta } => cmd_save(&path, delta.as_deref()).await,
+        Commands::Load {
+            path,
+            overlays,
+            vars,
+            fade,
+        } => cmd_load(&path, &overlays, &vars, fade).await,
+        Commands::Ensure {
+            path,
+            dry_run,
+            force,
+            vars,
+        } => cmd_ensure(&path, dry_run, force, &vars).await,
+        Commands::Edit { what, force } => cmd_edit(what, force).await,
+        Commands::Guard {
+            baseline,
+            restore,
+            allow,
+            allow_config,
+            interval,
+        } => cmd_guard(&baseline, restore, &allow, &allow_config, interval).await,
+        Commands::Sync {
+            path,
+            watch,
+            prefer,
+            interval,
+        } => cmd_sync(&path, watch, prefer, interval).await,
+        Commands::Mirror {
+            leader,
+            follower,
+            interval,
+        } => cmd_mirror(&leader, &follower, interval).await,
+        Commands::Daemon { action } => cmd_daemon(action).await,
+        Commands::Profile { action } => cmd_profile(action).await,
+        Commands::Init => cmd_init().await,
+        Commands::Log { action } => cmd_log(action),
+        Commands::Plumb { action } => cmd_plumb(action).await,
+        Commands::Debug { action } => cmd_debug(action).await,
+        Commands::Completions { shell } => cmd_completions(shell),
+        Commands::Complete { what } => cmd_complete(what).await,
+    }
 }
 
 async fn cmd_ping() -> Result<()> {
@@ -197,8 +1280,8 @@ async fn cmd_ping() -> Result<()> {
     let response = dev.send_receive(&ConfigMsgIn::Ping).await?;
 
     match response {
-        ConfigMsgOut::Pong => println!("Faderpunk is connected!"),
-        other => println!("Unexpected response: {:?}", other),
+        ConfigMsgOut::Pong => println!("{}", t!("Faderpunk is connected!")),
+        other => return Err(error::unexpected(&ConfigMsgIn::Ping, &other)),
     }
     Ok(())
 }
@@ -218,13 +1301,11 @@ async fn cmd_complete(what: CompleteTarget) -> Result<()> {
     let dev = FaderpunkDevice::open();
     if dev.is_err() {
         // Fall back to static values when device is disconnected
-        match what {
-            CompleteTarget::Slots => {
-                for i in 1..=16 {
-                    println!("{}", i);
-                }
+        // Can't list apps/params without device
+        if let CompleteTarget::Slots = what {
+            for i in 0..16 {
+                println!("{}", slots::label(i));
             }
-            _ => {} // Can't list apps/params without device
         }
         return Ok(());
     }
@@ -232,16 +1313,19 @@ async fn cmd_complete(what: CompleteTarget) -> Result<()> {
 
     match what {
         CompleteTarget::Apps => {
-            let responses = dev.send_receive_batch(&ConfigMsgIn::GetAllApps).await?;
-            for resp in responses {
-                if let ConfigMsgOut::AppConfig(app_id, channels, (_, name, desc, _, _, _)) = resp {
-                    // Tab-separated: value\tdescription (fish format)
-                    println!("{}\t[{}] {} ch — {}", name, app_id, channels, desc);
-                }
+            let app_info = read_app_info(&mut dev).await?;
+            let favorites = favorites::Favorites::load().unwrap_or_default();
+            for (app, mark) in favorites.order(&app_info) {
+                let mark = mark.map(|m| format!("{} ", m.symbol())).unwrap_or_default();
+                // Tab-separated: value\tdescription (fish format)
+                println!(
+                    "{}\t{}[{}] {} ch — {}",
+                    app.name, mark, app.app_id, app.channels, app.description
+                );
             }
         }
         CompleteTarget::Slots => {
-            let app_info = fetch_app_info(&mut dev).await.unwrap_or_default();
+            let app_info = read_app_info(&mut dev).await.unwrap_or_default();
             let layout = fetch_layout(&mut dev).await?;
             let entries = layout_entries(&layout);
 
@@ -254,63 +1338,59 @@ async fn cmd_complete(what: CompleteTarget) -> Result<()> {
                         .unwrap_or("?");
                     // Only show the label on the first fader of each app
                     if i as usize == entry.start + 1 {
-                        format!("{}", name)
+                        name.to_string()
                     } else {
                         format!("{} (cont.)", name)
                     }
                 } else {
                     "empty".to_string()
                 };
-                println!("{}\t{}", i, desc);
+                println!("{}\t{}", slots::label(i as usize - 1), desc);
             }
         }
         CompleteTarget::Params { slot } => {
-            if slot < 1 || slot > 16 {
+            if !(1..=16).contains(&slot) {
                 return Ok(());
             }
-            let app_info = fetch_app_info(&mut dev).await.unwrap_or_default();
+            let app_info = read_app_info(&mut dev).await.unwrap_or_default();
             let layout = fetch_layout(&mut dev).await?;
             let entries = layout_entries(&layout);
 
-            if let Some(entry) = find_entry_at_slot(&entries, slot) {
-                if let Some(app) = app_info.iter().find(|a| a.app_id == entry.app_id) {
-                    for (i, param) in app.params.iter().enumerate() {
-                        let name = display::get_param_name(param);
-                        if !name.is_empty() {
-                            println!("{}\t[{}] {}", name, i, format_param_type(param));
-                        }
+            if let Some(entry) = find_entry_at_slot(&entries, slot)
+                && let Some(app) = app_info.iter().find(|a| a.app_id == entry.app_id)
+            {
+                for (i, param) in app.params.iter().enumerate() {
+                    let name = display::get_param_name(param);
+                    if !name.is_empty() {
+                        println!("{}\t[{}] {}", name, i, display::format_param_type(param));
                     }
                 }
             }
         }
+        CompleteTarget::LayoutIds => {
+            let app_info = read_app_info(&mut dev).await.unwrap_or_default();
+            let layout = fetch_layout(&mut dev).await?;
+            for entry in layout_entries(&layout) {
+                let name = app_info
+                    .iter()
+                    .find(|a| a.app_id == entry.app_id)
+                    .map(|a| a.name.as_str())
+                    .unwrap_or("?");
+                println!("{}\t{} (fader {})", entry.layout_id, name, slots::label(entry.start));
+            }
+        }
     }
     Ok(())
 }
 
-fn format_param_type(param: &Param) -> &'static str {
-    match param {
-        Param::None => "",
-        Param::Int { .. } => "integer",
-        Param::Float { .. } => "number",
-        Param::Bool { .. } => "bool",
-        Param::Enum { .. } => "enum",
-        Param::Curve { .. } => "curve",
-        Param::Waveform { .. } => "waveform",
-        Param::Color { .. } => "color",
-        Param::Range { .. } => "range",
-        Param::Note { .. } => "note",
-        Param::MidiCc { .. } => "CC 0-127",
-        Param::MidiChannel { .. } => "channel 1-16",
-        Param::MidiIn => "midi in",
-        Param::MidiMode => "note/cc",
-        Param::MidiNote { .. } => "note 0-127",
-        Param::MidiOut => "midi out",
-        Param::MidiNrpn => "nrpn on/off",
-        Param::VoltPerOct => "v/oct std|buchla",
-    }
-}
-
-async fn cmd_status() -> Result<()> {
+/// Pings `status` makes to measure the connection.
+const STATUS_PINGS: usize = 5;
+const STATUS_PING_TIMEOUT: Duration = Duration::from_millis(500);
+
+async fn cmd_status(cached: bool) -> Result<()> {
+    if cached {
+        return status_cached();
+    }
     let mut dev = FaderpunkDevice::open()?;
 
     let config_resp = dev.send_receive(&ConfigMsgIn::GetGlobalConfig).await?;
@@ -324,24 +1404,457 @@ async fn cmd_status() -> Result<()> {
 
     let layout_resp = dev.send_receive(&ConfigMsgIn::GetLayout).await?;
     if let ConfigMsgOut::Layout(layout) = layout_resp {
-        display::print_layout(&layout, Some(&app_info));
+        display::print_layout(&layout, Some(&app_info), &groups::load()?, &notes::load()?);
+    }
+
+    let snapshot = fetch_snapshot(&mut dev).await?;
+    print_state_identity(&snapshot)?;
+
+    // A few pings, so a flaky cable shows up as numbers rather than as
+    // occasional timeouts elsewhere
+    let mut rtts = Vec::new();
+    for _ in 0..STATUS_PINGS {
+        if let Ok(rtt) = dev.ping(STATUS_PING_TIMEOUT).await {
+            rtts.push(rtt);
+        }
+    }
+    println!();
+    display::print_health(dev.usb_info(), dev.pacing(), &dev.health(), &rtts, STATUS_PINGS);
+
+    Ok(())
+}
+
+/// `status --cached`: the device state as last mirrored, without the device.
+fn status_cached() -> Result<()> {
+    let snapshot = mirror::read()?.context(
+        "No state mirror yet. Run 'daemon mirror' (or install it as a service) to keep one.",
+    )?;
+    display::print_global_config(&snapshot.global_config);
+    println!();
+
+    // The mirror names the apps but has no colors or icons for them
+    let apps: Vec<display::AppInfo> = snapshot
+        .metadata
+        .apps
+        .iter()
+        .map(|app| display::AppInfo {
+            app_id: app.app_id,
+            channels: app.channels,
+            name: app.name.clone(),
+            description: String::new(),
+            color: protocol::Color::White,
+            icon: protocol::AppIcon::Fader,
+            params: Vec::new(),
+        })
+        .collect();
+    display::print_layout(&snapshot.layout, Some(&apps), &groups::load()?, &notes::load()?);
+
+    print_state_identity(&snapshot)?;
+    println!();
+    match mirror::age(&snapshot) {
+        Some(age) => println!(
+            "Cached state from {} ago",
+            humantime::format_duration(Duration::from_secs(age.as_secs()))
+        ),
+        None => println!("Cached state (time unknown)"),
+    }
+    Ok(())
+}
+
+/// The saved profile the state matches, and its revision, where known.
+fn print_state_identity(snapshot: &Snapshot) -> Result<()> {
+    let profile = matching_profile(&snapshot.fingerprint()?)?;
+    if profile.is_none() && snapshot.metadata.revision.is_none() {
+        return Ok(());
+    }
+    println!();
+    if let Some(name) = profile {
+        println!("{}", t!("Matches profile '{}'", name));
+    }
+    if let Some(revision) = snapshot.metadata.revision {
+        println!("{}", t!("State revision {}", revision));
+    }
+    Ok(())
+}
+
+// ── Debug ──
+
+async fn cmd_debug(action: DebugAction) -> Result<()> {
+    match action {
+        DebugAction::DumpStream { seconds } => debug_dump_stream(seconds).await,
+    }
+}
+
+/// Print frames as they arrive. Reads the wire directly rather than through
+/// the usual connection, which gives up on a frame it can't decode.
+async fn debug_dump_stream(seconds: Option<u64>) -> Result<()> {
+    let mut frames = usb::RawFrames::open()?;
+    let start = std::time::Instant::now();
+    let deadline = async {
+        match seconds {
+            Some(s) => tokio::time::sleep(Duration::from_secs(s)).await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::pin!(deadline);
+    match seconds {
+        Some(s) => eprintln!("Dumping frames from the device for {}s (Ctrl-C to stop)...", s),
+        None => eprintln!("Dumping frames from the device (Ctrl-C to stop)..."),
+    }
+
+    let mut count = 0;
+    loop {
+        tokio::select! {
+            frame = frames.next() => {
+                let frame = frame?;
+                count += 1;
+                display::print_frame(count, start.elapsed(), &frame, &frame::inspect(&frame));
+            }
+            _ = &mut deadline => break,
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+    eprintln!("{} frame(s)", count);
+    Ok(())
+}
+
+// ── Plumbing ──
+
+async fn cmd_plumb(action: PlumbAction) -> Result<()> {
+    let mut dev = FaderpunkDevice::open()?;
+    match action {
+        PlumbAction::GetLayout => {
+            println!("{}", serde_json::to_string(&fetch_layout(&mut dev).await?)?);
+        }
+        PlumbAction::GetConfig => {
+            println!("{}", serde_json::to_string(&fetch_global_config(&mut dev).await?)?);
+        }
+        PlumbAction::GetParams { layout_id } => {
+            let values = fetch_layout_params(&mut dev, layout_id).await?;
+            println!("{}", serde_json::to_string(&values)?);
+        }
+        PlumbAction::SetParam {
+            layout_id,
+            index,
+            value,
+        } => {
+            let value: Value = serde_json::from_str(&value)
+                .with_context(|| format!("Invalid param value '{}'", value))?;
+            let current = fetch_layout_params(&mut dev, layout_id).await?;
+            let old = current
+                .get(index)
+                .with_context(|| format!("Layout ID {} has no param {}", layout_id, index))?;
+            if std::mem::discriminant(old) != std::mem::discriminant(&value) {
+                anyhow::bail!(
+                    "Param {} of layout ID {} holds {}, not {}",
+                    index,
+                    layout_id,
+                    serde_json::to_string(old)?,
+                    serde_json::to_string(&value)?
+                );
+            }
+            let values = param_change(&current, index, value);
+            dev.write_params(layout_id, &current, values, false).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Current param values of one app instance, failing if the layout has none
+/// with that ID.
+async fn fetch_layout_params(dev: &mut FaderpunkDevice, layout_id: u8) -> Result<Vec<Value>> {
+    let layout = fetch_layout(dev).await?;
+    if !layout.0.iter().flatten().any(|&(_, _, id)| id == layout_id) {
+        anyhow::bail!("No app with layout ID {} in the layout", layout_id);
     }
+    let msg = ConfigMsgIn::GetAppParams { layout_id };
+    match dev.send_receive(&msg).await? {
+        ConfigMsgOut::AppState(_, values) => Ok(values),
+        other => Err(error::unexpected(&msg, &other)),
+    }
+}
+
+// ── Batch ──
+
+async fn cmd_batch(commands: &[String], keep_going: bool) -> Result<()> {
+    // Parse everything first so a typo in the last command doesn't leave
+    // the device half-configured
+    let parsed = commands
+        .iter()
+        .map(|line| parse_command_line(line).with_context(|| format!("In '{}'", line)))
+        .collect::<Result<Vec<_>>>()?;
+
+    usb::share_connection();
+    let mut failed = 0;
+    for (line, command) in commands.iter().zip(parsed) {
+        display::print_step(line);
+        session::command(line)?;
+        if let Err(e) = Box::pin(run(command)).await {
+            display::print_error(&e, hints::hint(&e).as_deref());
+            failed += 1;
+            if !keep_going {
+                anyhow::bail!("Stopped after '{}' failed", line);
+            }
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!("{} of {} command(s) failed", failed, commands.len());
+    }
+    Ok(())
+}
+
+/// The process's command line for the session file, without the program
+/// name or --record itself. Arguments with spaces are quoted.
+fn recorded_command_line() -> String {
+    let mut words = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--record" {
+            args.next();
+        } else if !arg.starts_with("--record=") {
+            if arg.contains(char::is_whitespace) {
+                words.push(format!("'{}'", arg.replace('\'', "'\\''")));
+            } else {
+                words.push(arg);
+            }
+        }
+    }
+    words.join(" ")
+}
+
+/// An environment variable's value, parsed; None if it's unset or empty.
+fn env_override<T>(name: &str, parse: impl Fn(&str) -> Result<T>) -> Result<Option<T>> {
+    match std::env::var(name) {
+        Ok(value) if !value.is_empty() => {
+            let parsed = parse(&value);
+            Ok(Some(parsed.with_context(|| format!("Invalid {} in the environment", name))?))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Parse the process's command line.
+fn parse_cli(args: Vec<OsString>) -> Cli {
+    Cli::try_parse_from(args).unwrap_or_else(|e| exit_usage(e))
+}
+
+/// Print a usage error and exit with the invalid-input code rather than
+/// clap's own 2, which means no device here. Help and --version exit 0.
+fn exit_usage(err: clap::Error) -> ! {
+    let _ = err.print();
+    let code = if err.use_stderr() { exitcode::INVALID } else { 0 };
+    std::process::exit(code.into())
+}
+
+/// Parse one command line (as typed after `faderpunk-cli`) into a command.
+/// Commands that run until interrupted or that nest other commands are refused.
+fn parse_command_line(line: &str) -> Result<Commands> {
+    let args = std::iter::once("faderpunk-cli".to_string())
+        .chain(split_args(line)?)
+        .map(OsString::from)
+        .collect();
+    let defaults = settings::Settings::load()?.defaults;
+    let cli = Cli::try_parse_from(with_defaults(args, &defaults)?)?;
+    let command = cli.command.context("No command given")?;
+    if matches!(
+        command,
+        Commands::Batch { .. }
+            | Commands::Monitor
+            | Commands::Guard { .. }
+            | Commands::Sync { watch: true, .. }
+            | Commands::Mirror { .. }
+            | Commands::Ab { .. }
+            | Commands::Edit { .. }
+            | Commands::Init
+            | Commands::Complete { .. }
+    ) {
+        anyhow::bail!("This command can't be run from a batch or --stdin");
+    }
+    Ok(command)
+}
+
+/// Add the arguments configured under [defaults] for the command in `args`
+/// right after its command words, so options typed after them win.
+fn with_defaults(
+    mut args: Vec<OsString>,
+    defaults: &std::collections::BTreeMap<String, String>,
+) -> Result<Vec<OsString>> {
+    if defaults.is_empty() || args.iter().any(|a| a == "--no-defaults") {
+        return Ok(args);
+    }
+    let words = command_words(&args);
+    for n in (1..=words.len()).rev() {
+        let key = words[..n]
+            .iter()
+            .map(|(_, word)| word.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+        if let Some(extra) = defaults.get(&key) {
+            let extra = split_args(extra)
+                .with_context(|| format!("Invalid [defaults] entry for '{}'", key))?;
+            let at = words[n - 1].0 + 1;
+            args.splice(at..at, extra.into_iter().map(OsString::from));
+            break;
+        }
+    }
+    Ok(args)
+}
+
+/// Positions and names of the subcommands in `args` (after the program
+/// name), stepping over options and their values.
+fn command_words(args: &[OsString]) -> Vec<(usize, String)> {
+    let root = Cli::command();
+    let mut path = vec![&root];
+    let mut words = Vec::new();
+    let mut i = 1;
+    while let Some(arg) = args.get(i).and_then(|a| a.to_str()) {
+        if arg == "--" {
+            break;
+        }
+        if arg.starts_with('-') {
+            // A value in the next argument, unless given as --opt=value
+            let takes_value = |cmd: &&clap::Command| {
+                cmd.get_arguments().any(|a| {
+                    let named = match arg.strip_prefix("--") {
+                        Some(long) => a.get_long() == Some(long),
+                        None => arg.len() == 2 && a.get_short() == arg.chars().nth(1),
+                    };
+                    named && a.get_action().takes_values()
+                })
+            };
+            if path.iter().any(takes_value) {
+                i += 1;
+            }
+        } else if let Some(sub) = path.last().and_then(|cmd| cmd.find_subcommand(arg)) {
+            words.push((i, sub.get_name().to_string()));
+            path.push(sub);
+        } else {
+            break;
+        }
+        i += 1;
+    }
+    words
+}
+
+/// Split a command line into arguments, honoring single and double quotes
+/// and backslash escapes (outside single quotes).
+fn split_args(line: &str) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('\''), c) => current.push(c),
+            (_, '\\') => {
+                current.push(chars.next().context("Trailing backslash")?);
+                in_arg = true;
+            }
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        anyhow::bail!("Unterminated quote");
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
 
+// ── Stdin mode ──
+
+/// Set in stdin mode, where stdin carries commands and can't answer prompts.
+static NON_INTERACTIVE: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// One line of stdin mode output.
+#[derive(serde::Serialize)]
+struct StdinResult {
+    /// 1-based input line number
+    line: usize,
+    command: String,
+    ok: bool,
+    /// What the command printed (without colors)
+    output: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+async fn cmd_stdin() -> Result<()> {
+    NON_INTERACTIVE.store(true, std::sync::atomic::Ordering::SeqCst);
+    usb::share_connection();
+
+    let mut lines = tokio::io::AsyncBufReadExt::lines(tokio::io::BufReader::new(tokio::io::stdin()));
+    let mut n = 0;
+    while let Some(line) = lines.next_line().await? {
+        n += 1;
+        let text = line.trim();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+
+        session::command(text)?;
+        let (result, output) = match parse_command_line(text) {
+            Ok(command) => {
+                let capture = capture::start();
+                let result = Box::pin(run(command)).await;
+                (result, capture.map(|c| c.finish()).unwrap_or_default())
+            }
+            Err(e) => (Err(e), String::new()),
+        };
+        let record = StdinResult {
+            line: n,
+            command: text.to_string(),
+            ok: result.is_ok(),
+            output,
+            error: result.err().map(|e| format!("{:#}", e)),
+        };
+        println!("{}", serde_json::to_string(&record)?);
+        std::io::stdout().flush()?;
+    }
     Ok(())
 }
 
 // ── Helpers ──
 
-/// Fetch app metadata from device.
+/// Fetch app metadata from device, reporting any change since it was last seen.
 async fn fetch_app_info(dev: &mut FaderpunkDevice) -> Result<Vec<display::AppInfo>> {
+    let info = read_app_info(dev).await?;
+    if let Err(e) = appcache::check(&info) {
+        eprintln!("warning: could not update the app cache: {:#}", e);
+    }
+    Ok(info)
+}
+
+/// Fetch app metadata without touching the cache (for shell completions,
+/// which must stay quiet).
+async fn read_app_info(dev: &mut FaderpunkDevice) -> Result<Vec<display::AppInfo>> {
     let responses = dev.send_receive_batch(&ConfigMsgIn::GetAllApps).await?;
     let mut info = Vec::new();
     for resp in responses {
-        if let ConfigMsgOut::AppConfig(app_id, channels, (_, name, _, color, icon, params)) = resp {
+        if let ConfigMsgOut::AppConfig(app_id, channels, (_, name, description, color, icon, params)) = resp {
             info.push(display::AppInfo {
                 app_id,
                 channels,
                 name,
+                description,
                 color,
                 icon,
                 params,
@@ -375,7 +1888,7 @@ fn resolve_app(input: &str, apps: &[display::AppInfo]) -> Result<(u8, usize)> {
         if let Some(app) = apps.iter().find(|a| a.app_id == id) {
             return Ok((app.app_id, app.channels));
         }
-        anyhow::bail!("No app with ID {}. Use 'apps' to see available.", id);
+        invalid!("No app with ID {}. Use 'apps' to see available.", id);
     }
 
     // Try case-insensitive name match
@@ -386,11 +1899,11 @@ fn resolve_app(input: &str, apps: &[display::AppInfo]) -> Result<(u8, usize)> {
         .collect();
 
     match matches.len() {
-        0 => anyhow::bail!("No app matching '{}'. Use 'apps' to see available.", input),
+        0 => invalid!("No app matching '{}'. Use 'apps' to see available.", input),
         1 => Ok((matches[0].app_id, matches[0].channels)),
         _ => {
             let names: Vec<_> = matches.iter().map(|a| format!("{} [{}]", a.name, a.app_id)).collect();
-            anyhow::bail!(
+            invalid!(
                 "Ambiguous app name '{}'. Matches: {}. Use the app ID instead.",
                 input,
                 names.join(", ")
@@ -405,804 +1918,2527 @@ fn find_entry_at_slot(entries: &[display::LayoutEntry], slot: u8) -> Option<&dis
     entries.iter().find(|e| idx >= e.start && idx < e.start + e.size)
 }
 
+/// Get the global config from device.
+async fn fetch_global_config(dev: &mut FaderpunkDevice) -> Result<protocol::GlobalConfig> {
+    let resp = dev.send_receive(&ConfigMsgIn::GetGlobalConfig).await?;
+    match resp {
+        ConfigMsgOut::GlobalConfig(config) => Ok(config),
+        other => Err(error::unexpected(&ConfigMsgIn::GetGlobalConfig, &other)),
+    }
+}
+
 /// Get the current layout from device.
 async fn fetch_layout(dev: &mut FaderpunkDevice) -> Result<protocol::Layout> {
     let resp = dev.send_receive(&ConfigMsgIn::GetLayout).await?;
     match resp {
         ConfigMsgOut::Layout(layout) => Ok(layout),
-        _ => anyhow::bail!("Unexpected response for Layout"),
+        other => Err(error::unexpected(&ConfigMsgIn::GetLayout, &other)),
     }
 }
 
-/// Send a layout to device and return the validated layout.
-async fn send_layout(dev: &mut FaderpunkDevice, layout: protocol::Layout) -> Result<protocol::Layout> {
-    let resp = dev.send_receive(&ConfigMsgIn::SetLayout(layout)).await?;
-    match resp {
+/// Validate a layout, send it to the device, and return the firmware-validated layout.
+async fn send_layout(
+    dev: &mut FaderpunkDevice,
+    layout: protocol::Layout,
+    apps: &[display::AppInfo],
+) -> Result<protocol::Layout> {
+    layout::validate(&layout, apps)?;
+    let msg = ConfigMsgIn::SetLayout(layout);
+    match dev.send_receive(&msg).await? {
         ConfigMsgOut::Layout(validated) => Ok(validated),
-        _ => anyhow::bail!("Unexpected response for SetLayout"),
+        other => Err(error::unexpected(&msg, &other)),
     }
 }
 
 fn validate_slot(slot: u8) -> Result<()> {
-    if slot < 1 || slot > 16 {
-        anyhow::bail!("Slot must be 1-16, got {}", slot);
+    if !(1..=16).contains(&slot) {
+        invalid!("Slot must be 1-16, got {}", slot);
     }
     Ok(())
 }
 
 /// Prompt the user for confirmation. Returns true if they accept.
 fn confirm(message: &str) -> bool {
-    print!("{} [y/N] ", message);
+    if NON_INTERACTIVE.load(std::sync::atomic::Ordering::SeqCst) {
+        println!(
+            "{} {} n ({})",
+            message,
+            t!("[y/N]"),
+            t!("no prompts in this mode; use --force")
+        );
+        return false;
+    }
+    print!("{} {} ", message, t!("[y/N]"));
     std::io::stdout().flush().ok();
     let mut input = String::new();
     if std::io::stdin().lock().read_line(&mut input).is_err() {
         return false;
     }
-    matches!(input.trim().to_lowercase().as_str(), "y" | "yes")
+    let answer = input.trim().to_lowercase();
+    ["y", "yes", t!("y"), t!("yes")].contains(&answer.as_str())
 }
 
-/// Describe apps that would be displaced by placing an app at idx..end.
-fn describe_displaced(
-    layout: &protocol::Layout,
-    idx: usize,
-    end: usize,
-    app_info: &[display::AppInfo],
-) -> Vec<String> {
-    let mut displaced = Vec::new();
-    for i in 0..GLOBAL_CHANNELS {
-        if let Some((app_id, ch, _)) = layout.0[i] {
-            let app_end = i + ch;
-            if i < end && app_end > idx {
-                let name = app_info
-                    .iter()
-                    .find(|a| a.app_id == app_id)
-                    .map(|a| a.name.as_str())
-                    .unwrap_or("unknown");
-                let range = if ch == 1 {
-                    format!("fader {}", i + 1)
-                } else {
-                    format!("faders {}-{}", i + 1, i + ch)
-                };
-                displaced.push(format!("{} ({})", name, range));
-            }
+/// Ask a question with a default answer, re-asking until `parse` accepts the input.
+fn ask<T>(question: &str, default: &str, parse: impl Fn(&str) -> Result<T>) -> Result<T> {
+    loop {
+        print!("{} [{}] ", question, default);
+        std::io::stdout().flush().ok();
+        let mut input = String::new();
+        if std::io::stdin().lock().read_line(&mut input)? == 0 {
+            anyhow::bail!(t!("Input closed"));
+        }
+        let input = input.trim();
+        let answer = if input.is_empty() { default } else { input };
+        match parse(answer) {
+            Ok(v) => return Ok(v),
+            Err(e) => println!("  {}", e),
         }
     }
-    displaced
+}
+
+/// Wait for a single keypress without requiring Enter. Ctrl-C reads as Esc.
+async fn read_key() -> Result<crossterm::event::KeyCode> {
+    use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+
+    tokio::task::spawn_blocking(|| {
+        crossterm::terminal::enable_raw_mode()?;
+        let key = loop {
+            match event::read() {
+                Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+                        break Ok(KeyCode::Esc);
+                    }
+                    break Ok(key.code);
+                }
+                Ok(_) => continue,
+                Err(e) => break Err(e),
+            }
+        };
+        crossterm::terminal::disable_raw_mode()?;
+        Ok(key?)
+    })
+    .await?
 }
 
 // ── Apps ──
 
-async fn cmd_apps() -> Result<()> {
-    let mut dev = FaderpunkDevice::open()?;
-    let responses = dev.send_receive_batch(&ConfigMsgIn::GetAllApps).await?;
+async fn cmd_apps(action: Option<AppsAction>) -> Result<()> {
+    match action {
+        None | Some(AppsAction::List) => apps_list().await,
+        Some(AppsAction::ExportDocs { path }) => apps_export_docs(&path).await,
+        Some(AppsAction::ExportMeta { path }) => apps_export_meta(&path).await,
+        Some(AppsAction::Star { app }) => apps_star(&app, true).await,
+        Some(AppsAction::Unstar { app }) => apps_star(&app, false).await,
+    }
+}
 
-    let mut apps = Vec::new();
-    for resp in responses {
-        if let ConfigMsgOut::AppConfig(app_id, channels, (_, name, desc, color, icon, _)) = resp {
-            apps.push((app_id, channels, name, desc, color, icon));
+async fn apps_list() -> Result<()> {
+    let mut dev = FaderpunkDevice::open()?;
+    let favorites = favorites::Favorites::load()?;
+    if !favorites.is_empty() {
+        // Starred and recent apps go first, so the list can't be streamed
+        let app_info = fetch_app_info(&mut dev).await?;
+        display::print_app_list_header(app_info.len());
+        for (app, mark) in favorites.order(&app_info) {
+            display::print_app_row(
+                app.app_id,
+                app.channels,
+                &app.name,
+                &app.description,
+                &app.color,
+                &app.icon,
+                mark.map(favorites::Mark::symbol),
+            );
+        }
+        return Ok(());
+    }
+    // Print each app as it arrives rather than after the whole list
+    let mut apps = dev.stream_batch(&ConfigMsgIn::GetAllApps).await?;
+    display::print_app_list_header(apps.announced());
+    while let Some(resp) = apps.next().await {
+        if let ConfigMsgOut::AppConfig(app_id, channels, (_, name, desc, color, icon, _)) = resp? {
+            display::print_app_row(app_id, channels, &name, &desc, &color, &icon, None);
         }
     }
-
-    display::print_app_list(&apps);
     Ok(())
 }
 
-// ── Layout ──
+async fn apps_star(app_name: &str, star: bool) -> Result<()> {
+    let mut dev = FaderpunkDevice::open()?;
+    let app_info = fetch_app_info(&mut dev).await?;
+    let (app_id, _) = resolve_app(app_name, &app_info)?;
+    let name = &app_info.iter().find(|a| a.app_id == app_id).unwrap().name;
 
-async fn cmd_layout(action: Option<LayoutAction>) -> Result<()> {
-    match action.unwrap_or(LayoutAction::Show) {
-        LayoutAction::Show => layout_show().await,
-        LayoutAction::Set { slot, app, force } => layout_set(slot, &app, force).await,
-        LayoutAction::Remove { slot, force } => layout_remove(slot, force).await,
-        LayoutAction::Clear { force } => layout_clear(force).await,
-        LayoutAction::Fill { app, force } => layout_fill(&app, force).await,
+    let mut favorites = favorites::Favorites::load()?;
+    let changed = if star { favorites.star(name) } else { favorites.unstar(name) };
+    match (star, changed) {
+        (true, true) => println!("Starred {}", name),
+        (true, false) => println!("{} is already starred", name),
+        (false, true) => println!("Unstarred {}", name),
+        (false, false) => println!("{} isn't starred", name),
     }
+    favorites.save()
 }
 
-async fn layout_show() -> Result<()> {
+/// Remember the apps a command just placed, for listing them first next time.
+fn record_placed(app_ids: &[u8], app_info: &[display::AppInfo]) {
+    let names: Vec<&str> = app_ids
+        .iter()
+        .filter_map(|id| app_info.iter().find(|a| a.app_id == *id))
+        .map(|a| a.name.as_str())
+        .collect();
+    favorites::record_used(&names);
+}
+
+async fn apps_export_docs(path: &str) -> Result<()> {
     let mut dev = FaderpunkDevice::open()?;
     let app_info = fetch_app_info(&mut dev).await?;
-    let layout = fetch_layout(&mut dev).await?;
-    display::print_layout(&layout, Some(&app_info));
+    let notes = notes::load()?;
+    let note_rows = if notes.is_empty() {
+        Vec::new()
+    } else {
+        notes::rows(&notes, &fetch_layout(&mut dev).await?, &app_info)
+    };
+    let doc = appdocs::render(&app_info, &note_rows, appdocs::Format::for_path(path));
+    if path == "-" {
+        print!("{}", doc);
+        return Ok(());
+    }
+    std::fs::write(path, doc).with_context(|| format!("Failed to write {}", path))?;
+    println!("Documented {} app(s) in {}", app_info.len(), path);
     Ok(())
 }
 
-async fn layout_set(slot: u8, app_name: &str, force: bool) -> Result<()> {
-    validate_slot(slot)?;
+async fn apps_export_meta(path: &str) -> Result<()> {
     let mut dev = FaderpunkDevice::open()?;
     let app_info = fetch_app_info(&mut dev).await?;
-    let (app_id, channels) = resolve_app(app_name, &app_info)?;
-
-    let idx = slot as usize - 1;
-    let end = idx + channels;
-    if end > GLOBAL_CHANNELS {
-        anyhow::bail!(
-            "App '{}' needs {} fader(s), won't fit at slot {} (only {} slots remaining)",
-            app_name,
-            channels,
-            slot,
-            GLOBAL_CHANNELS - idx
-        );
+    let json = serde_json::to_string_pretty(&appmeta::AppMetaFile::new(&app_info))? + "\n";
+    if path == "-" {
+        print!("{}", json);
+        return Ok(());
     }
+    std::fs::write(path, json).with_context(|| format!("Failed to write {}", path))?;
+    println!("Wrote metadata of {} app(s) to {}", app_info.len(), path);
+    Ok(())
+}
 
-    let mut layout = fetch_layout(&mut dev).await?;
+// ── Monitor ──
 
-    // Check what would be displaced
-    let displaced = describe_displaced(&layout, idx, end, &app_info);
-    if !displaced.is_empty() && !force {
-        println!("This will displace:");
-        for d in &displaced {
-            println!("  - {}", d);
-        }
-        if !confirm("Continue?") {
-            println!("Cancelled.");
-            return Ok(());
-        }
-    }
+async fn cmd_monitor() -> Result<()> {
+    let mut conn = live::LiveConnection::open().await?;
+    let mut events = conn.device().subscribe();
+    let mut keepalive = tokio::time::interval(live::PING_INTERVAL);
+    println!("{}", t!("Watching for device events (Ctrl-C to stop)..."));
+    service::notify_ready();
 
-    // Clear any existing apps that overlap with the new placement
-    for i in 0..GLOBAL_CHANNELS {
-        if let Some((_, ch, _)) = layout.0[i] {
-            let app_end = i + ch;
-            if i < end && app_end > idx {
-                layout.0[i] = None;
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(msg) => display::print_event(&msg),
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                    eprintln!("warning: missed {} event(s)", n);
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                    anyhow::bail!("Device event stream closed");
+                }
+            },
+            _ = keepalive.tick() => {
+                if conn.check().await? {
+                    events = conn.device().subscribe();
+                }
             }
+            _ = tokio::signal::ctrl_c() => return Ok(()),
         }
     }
-
-    // Find next available layout_id
-    let used_ids: Vec<u8> = layout
-        .0
-        .iter()
-        .filter_map(|s| s.map(|(_, _, lid)| lid))
-        .collect();
-    let layout_id = (0..GLOBAL_CHANNELS as u8)
-        .find(|id| !used_ids.contains(id))
-        .unwrap_or(0);
-
-    // Place the app
-    layout.0[idx] = Some((app_id, channels, layout_id));
-
-    let validated = send_layout(&mut dev, layout).await?;
-
-    let app = app_info.iter().find(|a| a.app_id == app_id).unwrap();
-    println!(
-        "Placed {} at fader{} {}",
-        app.name,
-        if channels > 1 { "s" } else { "" },
-        if channels > 1 {
-            format!("{}-{}", slot, slot as usize + channels - 1)
-        } else {
-            format!("{}", slot)
-        }
-    );
-    println!();
-    display::print_layout(&validated, Some(&app_info));
-
-    Ok(())
 }
 
-async fn layout_remove(slot: u8, force: bool) -> Result<()> {
-    validate_slot(slot)?;
+// ── Stats ──
+
+async fn cmd_stats() -> Result<()> {
     let mut dev = FaderpunkDevice::open()?;
     let app_info = fetch_app_info(&mut dev).await?;
-    let mut layout = fetch_layout(&mut dev).await?;
+    let layout = fetch_layout(&mut dev).await?;
     let entries = layout_entries(&layout);
 
-    if let Some(entry) = find_entry_at_slot(&entries, slot) {
-        let name = app_info
-            .iter()
-            .find(|a| a.app_id == entry.app_id)
-            .map(|a| a.name.as_str())
-            .unwrap_or("unknown");
-
-        if !force {
-            let range = if entry.size == 1 {
-                format!("fader {}", entry.start + 1)
-            } else {
-                format!("faders {}-{}", entry.start + 1, entry.start + entry.size)
-            };
-            if !confirm(&format!("Remove {} from {}?", name, range)) {
-                println!("Cancelled.");
-                return Ok(());
+    let mut midi_in = [0usize; 2];
+    let mut midi_out = [0usize; 3];
+    let responses = dev.send_receive_batch(&ConfigMsgIn::GetAllAppParams).await?;
+    for resp in responses {
+        if let ConfigMsgOut::AppState(_, values) = resp {
+            for val in values {
+                match val {
+                    Value::MidiIn(protocol::MidiIn(ports)) => {
+                        for (count, on) in midi_in.iter_mut().zip(ports) {
+                            *count += on as usize;
+                        }
+                    }
+                    Value::MidiOut(protocol::MidiOut(ports)) => {
+                        for (count, on) in midi_out.iter_mut().zip(ports) {
+                            *count += on as usize;
+                        }
+                    }
+                    _ => {}
+                }
             }
         }
-
-        layout.0[entry.start] = None;
-        let validated = send_layout(&mut dev, layout).await?;
-        println!("Removed {} from fader {}", name, slot);
-        println!();
-        display::print_layout(&validated, Some(&app_info));
-    } else {
-        println!("Fader {} is already empty", slot);
     }
 
-    Ok(())
-}
+    let app_name = |app_id: u8| {
+        app_info
+            .iter()
+            .find(|a| a.app_id == app_id)
+            .map(|a| a.name.clone())
+            .unwrap_or_else(|| format!("App {}", app_id))
+    };
 
-async fn layout_clear(force: bool) -> Result<()> {
-    let mut dev = FaderpunkDevice::open()?;
+    let mut app_counts: Vec<(u8, usize)> = Vec::new();
+    for entry in &entries {
+        match app_counts.iter_mut().find(|(id, _)| *id == entry.app_id) {
+            Some((_, n)) => *n += 1,
+            None => app_counts.push((entry.app_id, 1)),
+        }
+    }
+    app_counts.sort_by_key(|(_, n)| std::cmp::Reverse(*n));
 
-    if !force {
-        let app_info = fetch_app_info(&mut dev).await?;
-        let layout = fetch_layout(&mut dev).await?;
-        let entries = layout_entries(&layout);
+    let slot_params = entries
+        .iter()
+        .map(|e| {
+            let count = app_info
+                .iter()
+                .find(|a| a.app_id == e.app_id)
+                .map(|a| a.params.iter().filter(|p| !matches!(p, Param::None)).count())
+                .unwrap_or(0);
+            (e.start, e.size, app_name(e.app_id), count)
+        })
+        .collect();
 
-        if !entries.is_empty() {
-            println!("Current layout has {} app(s):", entries.len());
-            for entry in &entries {
-                let name = app_info
-                    .iter()
-                    .find(|a| a.app_id == entry.app_id)
-                    .map(|a| a.name.as_str())
-                    .unwrap_or("unknown");
-                let range = if entry.size == 1 {
-                    format!("fader {}", entry.start + 1)
-                } else {
-                    format!("faders {}-{}", entry.start + 1, entry.start + entry.size)
-                };
-                println!("  - {} ({})", name, range);
+    // Historical usage: every app placed by a SetLayout in the audit log
+    let mut history: Vec<(u8, usize)> = Vec::new();
+    for entry in audit::read(None)?.iter().filter(|e| e.message == "SetLayout") {
+        for change in &entry.changes {
+            if change.old == change.new {
+                continue;
             }
-            if !confirm("Clear all?") {
-                println!("Cancelled.");
-                return Ok(());
+            let Some(app_id) = change.new.get(0).and_then(|v| v.as_u64()) else {
+                continue;
+            };
+            match history.iter_mut().find(|(id, _)| *id as u64 == app_id) {
+                Some((_, n)) => *n += 1,
+                None => history.push((app_id as u8, 1)),
             }
         }
     }
+    history.sort_by_key(|(_, n)| std::cmp::Reverse(*n));
 
-    let layout = protocol::Layout([None; GLOBAL_CHANNELS]);
-    send_layout(&mut dev, layout).await?;
-    println!("Layout cleared — all faders empty");
+    let stats = display::RigStats {
+        channels_used: entries.iter().map(|e| e.size).sum(),
+        app_counts: app_counts
+            .into_iter()
+            .map(|(id, n)| (app_name(id), n))
+            .collect(),
+        slot_params,
+        midi_in,
+        midi_out,
+        history: history.into_iter().map(|(id, n)| (app_name(id), n)).collect(),
+    };
+    display::print_stats(&stats);
     Ok(())
 }
 
-async fn layout_fill(app_name: &str, force: bool) -> Result<()> {
-    let mut dev = FaderpunkDevice::open()?;
-    let app_info = fetch_app_info(&mut dev).await?;
-    let (app_id, channels) = resolve_app(app_name, &app_info)?;
-
-    if !force {
-        let layout = fetch_layout(&mut dev).await?;
-        let entries = layout_entries(&layout);
+// ── Groups ──
 
-        if !entries.is_empty() {
-            println!("This will replace the current layout ({} app(s)):", entries.len());
-            for entry in &entries {
-                let name = app_info
-                    .iter()
-                    .find(|a| a.app_id == entry.app_id)
-                    .map(|a| a.name.as_str())
-                    .unwrap_or("unknown");
-                let range = if entry.size == 1 {
-                    format!("fader {}", entry.start + 1)
-                } else {
-                    format!("faders {}-{}", entry.start + 1, entry.start + entry.size)
-                };
-                println!("  - {} ({})", name, range);
+fn cmd_group(action: Option<GroupAction>) -> Result<()> {
+    let mut groups = groups::load()?;
+    match action.unwrap_or(GroupAction::List) {
+        GroupAction::List => {
+            if groups.is_empty() {
+                println!("No groups. Use 'group create <name> <slots>' to create one.");
             }
-            let app = app_info.iter().find(|a| a.app_id == app_id).unwrap();
-            if !confirm(&format!("Fill all faders with {}?", app.name)) {
-                println!("Cancelled.");
-                return Ok(());
+            for (name, members) in &groups {
+                println!("@{}  faders {}", name, groups::describe(members));
+            }
+        }
+        GroupAction::Create { name, slots } => {
+            groups::validate_name(&name)?;
+            if groups.contains_key(&name) {
+                anyhow::bail!("Group '{}' already exists; delete it first", name);
+            }
+            let members = groups::parse_slots(&slots)?;
+            println!("Group @{} created (faders {})", name, groups::describe(&members));
+            groups.insert(name, members);
+            groups::save(&groups)?;
+        }
+        GroupAction::Delete { name } => {
+            if groups.remove(&name).is_none() {
+                anyhow::bail!("No group named '{}'. Use 'group list' to see groups.", name);
             }
+            groups::save(&groups)?;
+            println!("Group @{} deleted", name);
         }
     }
+    Ok(())
+}
 
-    let mut layout = protocol::Layout([None; GLOBAL_CHANNELS]);
-    let mut pos = 0usize;
-    let mut layout_id = 0u8;
+// ── Notes ──
 
-    while pos + channels <= GLOBAL_CHANNELS {
-        layout.0[pos] = Some((app_id, channels, layout_id));
-        pos += channels;
-        layout_id += 1;
+fn cmd_note(action: Option<NoteAction>) -> Result<()> {
+    let mut notes = notes::load()?;
+    match action.unwrap_or(NoteAction::List) {
+        NoteAction::List => {
+            if notes.is_empty() {
+                println!("No notes. Use 'note set <slots> <text>' to add one.");
+            }
+            for (slot, text) in &notes {
+                println!("{:>4}  {}", slots::label(*slot as usize - 1), text);
+            }
+        }
+        NoteAction::Set { slots, text } => {
+            let members = groups::parse_slots(&slots)?;
+            for slot in &members {
+                notes.insert(*slot, text.clone());
+            }
+            notes::save(&notes)?;
+            println!("Noted faders {}", groups::describe(&members));
+        }
+        NoteAction::Clear { slots } => {
+            let members = groups::parse_slots(&slots)?;
+            let before = notes.len();
+            notes.retain(|slot, _| !members.contains(slot));
+            notes::save(&notes)?;
+            println!("Removed {} note(s)", before - notes.len());
+        }
     }
+    Ok(())
+}
 
-    let validated = send_layout(&mut dev, layout).await?;
+// ── Macros ──
 
-    let app = app_info.iter().find(|a| a.app_id == app_id).unwrap();
-    let count = GLOBAL_CHANNELS / channels;
-    println!(
-        "Filled layout with {} x {} ({} ch each)",
-        count, app.name, channels
-    );
-    println!();
-    display::print_layout(&validated, Some(&app_info));
+/// One step of a macro: `SLOT:PARAM=VALUE` sets a param as `param set` would,
+/// `SLOT:PARAM+N` / `SLOT:PARAM-N` nudges a number, by N% of its range when N
+/// ends in `%`.
+struct MacroStep {
+    slot: u8,
+    param: String,
+    change: MacroChange,
+}
 
-    Ok(())
+enum MacroChange {
+    Set(String),
+    Nudge { amount: f64, percent: bool },
 }
 
-// ── Params ──
+fn parse_macro_step(step: &str) -> Result<MacroStep> {
+    let (slot, rest) = step
+        .split_once(':')
+        .context("Expected SLOT:PARAM=VALUE or SLOT:PARAM+N / SLOT:PARAM-N")?;
+    let slot = slots::parse(slot)?;
 
-async fn cmd_param(action: Option<ParamAction>) -> Result<()> {
-    match action.unwrap_or(ParamAction::Show { slot: None }) {
-        ParamAction::Show { slot } => param_show(slot).await,
-        ParamAction::Set { slot, param, value } => param_set(slot, &param, &value).await,
+    if let Some((param, value)) = rest.split_once('=') {
+        return Ok(MacroStep {
+            slot,
+            param: param.trim().to_string(),
+            change: MacroChange::Set(value.trim().to_string()),
+        });
     }
+
+    let pos = rest
+        .rfind(['+', '-'])
+        .context("Expected PARAM=VALUE, PARAM+N or PARAM-N after the fader")?;
+    let (param, amount) = rest.split_at(pos);
+    let (amount, percent) = match amount.strip_suffix('%') {
+        Some(a) => (a, true),
+        None => (amount, false),
+    };
+    let amount: f64 = amount
+        .trim()
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid amount '{}'", &rest[pos..]))?;
+    Ok(MacroStep {
+        slot,
+        param: param.trim().to_string(),
+        change: MacroChange::Nudge { amount, percent },
+    })
 }
 
-async fn param_show(slot: Option<u8>) -> Result<()> {
-    let mut dev = FaderpunkDevice::open()?;
-    let app_info = fetch_app_info(&mut dev).await?;
-    let layout = fetch_layout(&mut dev).await?;
-    let entries = layout_entries(&layout);
+/// Move a numeric value by `amount` (or `amount`% of the param's range),
+/// stopping at the ends of the range.
+fn nudge_value(value: &Value, param: &Param, amount: f64, percent: bool) -> Result<Value> {
+    let (min, max) = match param {
+        Param::Int { min, max, .. } => (*min as f64, *max as f64),
+        Param::Float { min, max, .. } => (*min as f64, *max as f64),
+        Param::MidiCc { .. } | Param::MidiNote { .. } => (0.0, 127.0),
+        Param::MidiChannel { .. } => (1.0, 16.0),
+        _ => invalid!(
+            "Can't nudge a {} param; use PARAM=VALUE",
+            display::format_param_type(param)
+        ),
+    };
+    let delta = if percent { amount / 100.0 * (max - min) } else { amount };
+    let moved = |v: f64| (v + delta).clamp(min, max);
+    Ok(match *value {
+        Value::Int(v) => Value::Int(moved(v as f64).round() as i32),
+        Value::Float(v) => Value::Float(moved(v as f64) as f32),
+        Value::MidiCc(protocol::MidiCc(v)) => {
+            Value::MidiCc(protocol::MidiCc(moved(v as f64).round() as u16))
+        }
+        Value::MidiNote(protocol::MidiNote(v)) => {
+            Value::MidiNote(protocol::MidiNote(moved(v as f64).round() as u8))
+        }
+        Value::MidiChannel(protocol::MidiChannel(v)) => {
+            Value::MidiChannel(protocol::MidiChannel(moved(v as f64).round() as u8))
+        }
+        _ => invalid!("The value doesn't match the param type"),
+    })
+}
 
-    if let Some(slot) = slot {
-        validate_slot(slot)?;
-        let entry = find_entry_at_slot(&entries, slot)
-            .ok_or_else(|| anyhow::anyhow!("No app at fader {}", slot))?;
+async fn cmd_macro(name: Option<&str>) -> Result<()> {
+    let settings = settings::Settings::load()?;
+    let path = settings::Settings::path()?;
 
-        let resp = dev
-            .send_receive(&ConfigMsgIn::GetAppParams {
-                layout_id: entry.layout_id,
-            })
-            .await?;
-        if let ConfigMsgOut::AppState(layout_id, values) = resp {
-            display::print_app_params(layout_id, &values, Some(&entries), Some(&app_info));
+    let Some(name) = name else {
+        if settings.macros.is_empty() {
+            println!("No macros defined. Add them under [macros] in {}", path.display());
         }
-    } else {
-        let responses = dev.send_receive_batch(&ConfigMsgIn::GetAllAppParams).await?;
-        for resp in responses {
-            if let ConfigMsgOut::AppState(layout_id, values) = resp {
-                display::print_app_params(layout_id, &values, Some(&entries), Some(&app_info));
-            }
+        for (name, steps) in &settings.macros {
+            println!("{}: {}", name, steps.join(", "));
         }
-    }
+        return Ok(());
+    };
+    // Check every step before touching the device
+    let steps = macro_steps(&settings, name)?;
 
+    let mut dev = FaderpunkDevice::open()?;
+    if run_macro_steps(&mut dev, &steps).await? == 0 {
+        println!("Macro '{}' changed nothing", name);
+    }
     Ok(())
 }
 
-async fn param_set(slot: u8, param_ref: &str, value_str: &str) -> Result<()> {
-    validate_slot(slot)?;
-    let mut dev = FaderpunkDevice::open()?;
-    let app_info = fetch_app_info(&mut dev).await?;
-    let layout = fetch_layout(&mut dev).await?;
-    let entries = layout_entries(&layout);
-
-    let entry = find_entry_at_slot(&entries, slot)
-        .ok_or_else(|| anyhow::anyhow!("No app at fader {}", slot))?;
-
-    // Get current params to know the types
-    let resp = dev
-        .send_receive(&ConfigMsgIn::GetAppParams {
-            layout_id: entry.layout_id,
-        })
-        .await?;
-    let current_values = match resp {
-        ConfigMsgOut::AppState(_, values) => values,
-        _ => anyhow::bail!("Unexpected response"),
-    };
-
-    // Get param metadata for this app
-    let app = app_info
+/// The parsed steps of a macro from config.toml.
+fn macro_steps(settings: &settings::Settings, name: &str) -> Result<Vec<MacroStep>> {
+    let path = settings::Settings::path()?;
+    let steps = settings.macros.get(name).with_context(|| {
+        format!(
+            "No macro '{}' in {} (defined: {})",
+            name,
+            path.display(),
+            settings.macros.keys().cloned().collect::<Vec<_>>().join(", ")
+        )
+    })?;
+    steps
         .iter()
-        .find(|a| a.app_id == entry.app_id)
-        .ok_or_else(|| anyhow::anyhow!("App metadata not found"))?;
+        .map(|s| parse_macro_step(s).with_context(|| format!("In step '{}' of macro '{}'", s, name)))
+        .collect()
+}
 
-    // Resolve param reference — by index or by name
-    let param_idx = if let Ok(idx) = param_ref.parse::<usize>() {
-        if idx >= current_values.len() {
-            anyhow::bail!(
-                "Param index {} out of range (app has {} params)",
-                idx,
-                current_values.len()
-            );
-        }
-        idx
-    } else {
-        // Search by name (case-insensitive)
-        let lower = param_ref.to_lowercase();
-        let found: Vec<(usize, &Param)> = app
-            .params
-            .iter()
-            .enumerate()
-            .filter(|(_, p)| {
-                let name = display::get_param_name(p);
-                !name.is_empty() && name.to_lowercase().contains(&lower)
-            })
-            .collect();
+/// Apply macro steps, printing each change, with one param write per app
+/// touched. Returns how many apps changed.
+async fn run_macro_steps(dev: &mut FaderpunkDevice, steps: &[MacroStep]) -> Result<usize> {
+    let app_info = fetch_app_info(dev).await?;
+    let layout = fetch_layout(dev).await?;
+    let entries = layout_entries(&layout);
 
-        match found.len() {
-            0 => anyhow::bail!(
-                "No param matching '{}'. Use 'param show {}' to see available.",
-                param_ref,
-                slot
-            ),
-            1 => found[0].0,
-            _ => {
-                let names: Vec<_> = found
+    // (entry, app, values read, values to write) per app touched, in
+    // first-use order
+    let mut apps: Vec<(&display::LayoutEntry, &display::AppInfo, Vec<Value>, Vec<Value>)> =
+        Vec::new();
+    for step in steps {
+        let entry = find_entry_at_slot(&entries, step.slot)
+            .ok_or_else(|| error::invalid(t!("No app at fader {}", slots::label(step.slot as usize - 1))))?;
+        let pos = match apps.iter().position(|(e, ..)| e.layout_id == entry.layout_id) {
+            Some(pos) => pos,
+            None => {
+                let app = app_info
                     .iter()
-                    .map(|(i, p)| format!("{} [{}]", display::get_param_name(p), i))
-                    .collect();
-                anyhow::bail!(
-                    "Ambiguous param '{}'. Matches: {}. Use the index instead.",
-                    param_ref,
-                    names.join(", ")
-                );
+                    .find(|a| a.app_id == entry.app_id)
+                    .ok_or_else(|| anyhow::anyhow!(t!("App metadata not found")))?;
+                let (_, values) = fetch_app_params(dev, &[entry.layout_id])
+                    .await?
+                    .pop()
+                    .context("Device sent no params")?;
+                apps.push((entry, app, values.clone(), values));
+                apps.len() - 1
             }
-        }
-    };
-
-    let param_meta = app.params.get(param_idx);
-    let new_value = parse_value(value_str, param_meta, &current_values[param_idx])?;
+        };
+        let (_, app, _, values) = &mut apps[pos];
 
-    // Build the SetAppParams message — None for all params except the one we're changing
-    let mut values: [Option<Value>; APP_MAX_PARAMS] = [None; APP_MAX_PARAMS];
-    // Send all current values (firmware replaces all at once)
-    for (i, v) in current_values.iter().enumerate() {
-        if i < APP_MAX_PARAMS {
-            values[i] = Some(*v);
+        let idx = resolve_param(&step.param, app, values.len(), step.slot)?;
+        let param = &app.params[idx];
+        let old = values[idx];
+        let new = match &step.change {
+            MacroChange::Set(value) => parse_value(value, Some(param), &old, false)?,
+            MacroChange::Nudge { amount, percent } => nudge_value(&old, param, *amount, *percent)?,
+        };
+        if new == old {
+            continue;
         }
+        values[idx] = new;
+        println!(
+            "  fader {:>2} {}: {} {} → {}",
+            slots::label(entry.start),
+            app.name,
+            display::param_label(Some(param), idx),
+            display::format_value(&old, Some(param)),
+            display::format_value(&new, Some(param))
+        );
     }
-    values[param_idx] = Some(new_value);
-
-    let resp = dev
-        .send_receive(&ConfigMsgIn::SetAppParams {
-            layout_id: entry.layout_id,
-            values,
-        })
-        .await?;
-
-    let param_name = param_meta
-        .map(|p| display::get_param_name(p))
-        .unwrap_or_default();
-    let label = if param_name.is_empty() {
-        format!("param {}", param_idx)
-    } else {
-        param_name
-    };
-
-    println!("Set {} = {}", label, value_str);
 
-    // Show updated params
-    if let ConfigMsgOut::AppState(layout_id, values) = resp {
-        println!();
-        display::print_app_params(layout_id, &values, Some(&entries), Some(&app_info));
+    let mut sent = 0;
+    for (entry, _, read, values) in &apps {
+        if values != read {
+            dev.write_params(entry.layout_id, read, param_values(values), false)
+                .await?;
+            sent += 1;
+        }
     }
+    Ok(sent)
+}
 
-    Ok(())
+// ── Play ──
+
+/// A cue with everything it does checked and loaded up front, so nothing
+/// fails halfway through a show.
+struct PreparedCue<'a> {
+    cue: &'a automation::Cue,
+    steps: Vec<MacroStep>,
+    profile: Option<Snapshot>,
 }
 
-/// Parse a string value into the appropriate Value type based on param metadata.
-fn parse_value(s: &str, param: Option<&Param>, current: &Value) -> Result<Value> {
-    // Use param metadata if available, otherwise infer from current value type
-    match param {
-        Some(Param::Int { min, max, .. }) => {
-            let v: i32 = s.parse().map_err(|_| anyhow::anyhow!("Expected integer"))?;
-            if v < *min || v > *max {
-                anyhow::bail!("Value {} out of range ({}-{})", v, min, max);
-            }
-            Ok(Value::Int(v))
-        }
-        Some(Param::Float { min, max, .. }) => {
-            let v: f32 = s.parse().map_err(|_| anyhow::anyhow!("Expected number"))?;
-            if v < *min || v > *max {
-                anyhow::bail!("Value {} out of range ({}-{})", v, min, max);
-            }
-            Ok(Value::Float(v))
-        }
-        Some(Param::Bool { .. }) => {
-            let v = match s.to_lowercase().as_str() {
-                "true" | "on" | "1" | "yes" => true,
-                "false" | "off" | "0" | "no" => false,
-                _ => anyhow::bail!("Expected bool (true/false, on/off, 1/0)"),
-            };
-            Ok(Value::Bool(v))
-        }
-        Some(Param::Enum { variants, .. }) => {
-            // Try by index first
-            if let Ok(idx) = s.parse::<usize>() {
-                if idx >= variants.len() {
-                    anyhow::bail!("Index {} out of range (0-{})", idx, variants.len() - 1);
-                }
-                return Ok(Value::Enum(idx));
-            }
-            // Try by name
-            let lower = s.to_lowercase();
-            let found: Vec<(usize, _)> = variants
-                .iter()
-                .enumerate()
-                .filter(|(_, v)| v.to_lowercase().contains(&lower))
-                .collect();
-            match found.len() {
-                0 => anyhow::bail!("No variant matching '{}'. Options: {}", s, variants.join(", ")),
-                1 => Ok(Value::Enum(found[0].0)),
-                _ => {
-                    let names: Vec<_> = found.iter().map(|(i, v)| format!("{} [{}]", v, i)).collect();
-                    anyhow::bail!("Ambiguous '{}'. Matches: {}", s, names.join(", "));
-                }
-            }
-        }
-        Some(Param::Curve { variants, .. }) => {
-            let lower = s.to_lowercase();
-            for v in variants {
-                if format!("{:?}", v).to_lowercase() == lower {
-                    return Ok(Value::Curve(*v));
-                }
-            }
-            let options: Vec<_> = variants.iter().map(|v| format!("{:?}", v)).collect();
-            anyhow::bail!("Unknown curve '{}'. Options: {}", s, options.join(", "))
-        }
-        Some(Param::Waveform { variants, .. }) => {
-            let lower = s.to_lowercase();
-            for v in variants {
-                if format!("{:?}", v).to_lowercase() == lower {
-                    return Ok(Value::Waveform(*v));
-                }
-            }
-            let options: Vec<_> = variants.iter().map(|v| format!("{:?}", v)).collect();
-            anyhow::bail!("Unknown waveform '{}'. Options: {}", s, options.join(", "))
-        }
-        Some(Param::Range { variants, .. }) => {
-            let v = parse_range(s, variants)?;
-            Ok(Value::Range(v))
-        }
-        Some(Param::MidiCc { .. }) => {
-            let v: u16 = s.parse().map_err(|_| anyhow::anyhow!("Expected 0-127"))?;
-            if v > 127 {
-                anyhow::bail!("CC must be 0-127");
-            }
-            Ok(Value::MidiCc(protocol::MidiCc(v)))
-        }
-        Some(Param::MidiChannel { .. }) => {
-            let v: u8 = s.parse().map_err(|_| anyhow::anyhow!("Expected 1-16"))?;
-            if v < 1 || v > 16 {
-                anyhow::bail!("Channel must be 1-16");
-            }
-            Ok(Value::MidiChannel(protocol::MidiChannel(v)))
-        }
-        Some(Param::MidiNote { .. }) => {
-            let v: u8 = s.parse().map_err(|_| anyhow::anyhow!("Expected 0-127"))?;
-            if v > 127 {
-                anyhow::bail!("Note must be 0-127");
-            }
-            Ok(Value::MidiNote(protocol::MidiNote(v)))
-        }
-        Some(Param::MidiMode) => {
-            let v = match s.to_lowercase().as_str() {
-                "note" => protocol::MidiMode::Note,
-                "cc" => protocol::MidiMode::Cc,
-                _ => anyhow::bail!("Expected 'note' or 'cc'"),
-            };
-            Ok(Value::MidiMode(v))
-        }
-        Some(Param::MidiIn) => {
-            let (usb, din) = parse_midi_ports_in(s)?;
-            Ok(Value::MidiIn(protocol::MidiIn([usb, din])))
-        }
-        Some(Param::MidiOut) => {
-            let (usb, out1, out2) = parse_midi_ports_out(s)?;
-            Ok(Value::MidiOut(protocol::MidiOut([usb, out1, out2])))
-        }
-        Some(Param::MidiNrpn) => {
-            let v = match s.to_lowercase().as_str() {
-                "true" | "on" | "1" | "yes" => true,
-                "false" | "off" | "0" | "no" => false,
-                _ => anyhow::bail!("Expected bool (true/false, on/off, 1/0)"),
-            };
-            Ok(Value::MidiNrpn(v))
+async fn cmd_play(file: &Path, midi_clock: Option<&Path>, dry_run: bool) -> Result<()> {
+    let show = automation::Show::read(file)?;
+    if show.cues.is_empty() {
+        println!("{} has no cues", file.display());
+        return Ok(());
+    }
+    // (cue, seconds from the start or beats into the song), in firing order
+    let schedule = match midi_clock {
+        Some(_) => show.beats()?,
+        None => show.seconds()?,
+    };
+    let mut cues: Vec<PreparedCue> = Vec::with_capacity(show.cues.len());
+    let settings = settings::Settings::load()?;
+    for cue in &show.cues {
+        let context = || format!("In cue at {}", cue.when);
+        let mut steps = match &cue.macro_name {
+            Some(name) => macro_steps(&settings, name).with_context(context)?,
+            None => Vec::new(),
+        };
+        for step in &cue.params {
+            steps.push(
+                parse_macro_step(step)
+                    .with_context(|| format!("In step '{}'", step))
+                    .with_context(context)?,
+            );
         }
-        Some(Param::VoltPerOct) => {
-            let v = match s.to_lowercase().as_str() {
-                "standard" | "std" | "1v" | "1v/oct" => protocol::VoltPerOct::Standard,
-                "buchla" | "1.2v" | "1.2v/oct" => protocol::VoltPerOct::Buchla,
-                _ => anyhow::bail!("Expected 'standard' or 'buchla'"),
-            };
-            Ok(Value::VoltPerOct(v))
+        let profile = match &cue.profile {
+            Some(name) => Some(Snapshot::read(&profile::existing(name)?, &[]).with_context(context)?),
+            None => None,
+        };
+        cues.push(PreparedCue { cue, steps, profile });
+    }
+    if dry_run {
+        for &(i, at) in &schedule {
+            print_cue(&cues[i], at, midi_clock.is_some());
         }
-        Some(Param::Color { variants, .. }) => {
-            let lower = s.to_lowercase();
-            for v in variants {
-                if format!("{:?}", v).to_lowercase() == lower {
-                    return Ok(Value::Color(*v));
+        return Ok(());
+    }
+
+    let mut dev = FaderpunkDevice::open()?;
+    match midi_clock {
+        None => {
+            let start = tokio::time::Instant::now();
+            for &(i, at) in &schedule {
+                tokio::select! {
+                    _ = tokio::time::sleep_until(start + Duration::from_secs_f64(at)) => {}
+                    _ = tokio::signal::ctrl_c() => return Ok(()),
                 }
+                print_cue(&cues[i], at, false);
+                run_cue(&mut dev, &cues[i]).await?;
             }
-            let options: Vec<_> = variants.iter().map(|v| format!("{:?}", v)).collect();
-            anyhow::bail!("Unknown color '{}'. Options: {}", s, options.join(", "))
         }
-        Some(Param::Note { variants, .. }) => {
-            let lower = s.to_lowercase();
-            for v in variants {
-                if format!("{:?}", v).to_lowercase() == lower {
-                    return Ok(Value::Note(*v));
-                }
-            }
-            let options: Vec<_> = variants.iter().map(|v| format!("{:?}", v)).collect();
-            anyhow::bail!("Unknown note '{}'. Options: {}", s, options.join(", "))
-        }
-        Some(Param::None) | None => {
-            // Infer from current value type
-            match current {
-                Value::Int(_) => Ok(Value::Int(s.parse()?)),
-                Value::Float(_) => Ok(Value::Float(s.parse()?)),
-                Value::Bool(_) => {
-                    let v = matches!(s.to_lowercase().as_str(), "true" | "on" | "1" | "yes");
-                    Ok(Value::Bool(v))
+        Some(path) => {
+            let mut clock = automation::follow_clock(path)?;
+            println!("Waiting for MIDI clock on {} (Ctrl-C to stop)", path.display());
+            let tick_of = |&(_, beat): &(usize, f64)| (beat * automation::TICKS_PER_BEAT as f64).round() as u64;
+            let mut tick: u64 = 0;
+            let mut running = false;
+            // Index of the next cue to fire
+            let mut next = 0;
+            loop {
+                let msg = tokio::select! {
+                    msg = clock.recv() => msg,
+                    _ = tokio::signal::ctrl_c() => return Ok(()),
+                };
+                match msg.context("MIDI input closed")?.1 {
+                    automation::Clock::Start => {
+                        tick = 0;
+                        next = 0;
+                        running = true;
+                    }
+                    automation::Clock::Continue => running = true,
+                    automation::Clock::Stop => running = false,
+                    // After a jump, carry on from there without firing the cues skipped over
+                    automation::Clock::Position(at) => {
+                        tick = at;
+                        next = schedule.iter().take_while(|c| tick_of(c) < tick).count();
+                    }
+                    automation::Clock::Tick if running => {
+                        while next < schedule.len() && tick_of(&schedule[next]) <= tick {
+                            let (i, at) = schedule[next];
+                            print_cue(&cues[i], at, true);
+                            run_cue(&mut dev, &cues[i]).await?;
+                            next += 1;
+                        }
+                        tick += 1;
+                    }
+                    automation::Clock::Tick => {}
                 }
-                Value::Enum(_) => Ok(Value::Enum(s.parse()?)),
-                Value::MidiCc(_) => Ok(Value::MidiCc(protocol::MidiCc(s.parse()?))),
-                Value::MidiChannel(_) => Ok(Value::MidiChannel(protocol::MidiChannel(s.parse()?))),
-                _ => anyhow::bail!("Can't infer type for this parameter. Specify by index."),
             }
         }
     }
+    Ok(())
 }
 
-fn parse_range(s: &str, variants: &[protocol::Range]) -> Result<protocol::Range> {
-    let lower = s.to_lowercase().replace(' ', "");
-    for v in variants {
-        let label = match v {
-            protocol::Range::_0_10V => "0-10v",
-            protocol::Range::_0_5V => "0-5v",
-            protocol::Range::_Neg5_5V => "-5-5v",
-        };
-        if lower == label || lower == format!("{:?}", v).to_lowercase() {
-            return Ok(*v);
-        }
-    }
-    // Also accept common aliases
-    match lower.as_str() {
-        "10v" | "0-10" | "0-10v" => Ok(protocol::Range::_0_10V),
-        "5v" | "0-5" | "0-5v" => Ok(protocol::Range::_0_5V),
-        "bipolar" | "+-5v" | "+/-5v" | "-5-5v" | "-5v-5v" => Ok(protocol::Range::_Neg5_5V),
-        _ => {
-            let options: Vec<_> = variants.iter().map(|v| format!("{:?}", v)).collect();
-            anyhow::bail!("Unknown range '{}'. Options: {}", s, options.join(", "))
-        }
+fn print_cue(prepared: &PreparedCue, at: f64, beats: bool) {
+    let cue = prepared.cue;
+    let when = if beats {
+        cue.when.clone()
+    } else {
+        format!("{}:{:04.1}", (at / 60.0) as u64, at % 60.0)
+    };
+    let mut actions = Vec::new();
+    if let Some(name) = &cue.profile {
+        actions.push(format!("profile {}", name));
     }
-}
-
-fn parse_midi_ports_in(s: &str) -> Result<(bool, bool)> {
-    let lower = s.to_lowercase();
-    if lower == "none" || lower == "off" {
-        return Ok((false, false));
+    if let Some(bpm) = cue.bpm {
+        actions.push(format!("bpm {}", bpm));
     }
-    if lower == "all" || lower == "both" {
-        return Ok((true, true));
+    if let Some(name) = &cue.macro_name {
+        actions.push(format!("macro {}", name));
     }
-    let usb = lower.contains("usb");
-    let din = lower.contains("din");
-    if !usb && !din {
-        anyhow::bail!("Expected MIDI input ports: 'usb', 'din', 'usb+din', 'all', or 'none'");
+    actions.extend(cue.params.iter().cloned());
+    match &cue.label {
+        Some(label) => println!("[{}] {}: {}", when, label, actions.join(", ")),
+        None => println!("[{}] {}", when, actions.join(", ")),
     }
-    Ok((usb, din))
 }
 
-fn parse_midi_ports_out(s: &str) -> Result<(bool, bool, bool)> {
-    let lower = s.to_lowercase();
-    if lower == "none" || lower == "off" {
-        return Ok((false, false, false));
+/// Do everything a cue asks: profile, then BPM, then macro and param steps.
+async fn run_cue(dev: &mut FaderpunkDevice, prepared: &PreparedCue<'_>) -> Result<()> {
+    // Someone may have moved things on the device since the last cue
+    dev.clear_cache();
+    if let Some(snapshot) = &prepared.profile {
+        apply_snapshot(dev, snapshot).await?;
+        restore_groups(snapshot)?;
     }
-    if lower == "all" {
-        return Ok((true, true, true));
+    if let Some(bpm) = prepared.cue.bpm {
+        let request = ConfigMsgIn::GetGlobalConfig;
+        match dev.send_receive(&request).await? {
+            ConfigMsgOut::GlobalConfig(mut config) => {
+                config.clock.internal_bpm = bpm;
+                dev.send(&ConfigMsgIn::SetGlobalConfig(config)).await?;
+            }
+            other => return Err(error::unexpected(&request, &other)),
+        }
     }
-    let usb = lower.contains("usb");
-    let out1 = lower.contains("out1") || lower.contains("1");
-    let out2 = lower.contains("out2") || lower.contains("2");
-    if !usb && !out1 && !out2 {
-        anyhow::bail!("Expected MIDI output ports: 'usb', 'out1', 'out2', 'all', or 'none'");
+    if !prepared.steps.is_empty() {
+        run_macro_steps(dev, &prepared.steps).await?;
     }
-    Ok((usb, out1, out2))
+    Ok(())
 }
 
-// ── Config ──
+// ── Pins ──
 
-async fn cmd_config(action: ConfigAction) -> Result<()> {
-    let mut dev = FaderpunkDevice::open()?;
+const NO_PINS: &str = "No pins. Use 'pin add <slot>:<param>' to pin one.";
 
-    match action {
-        ConfigAction::Show => {
-            let resp = dev.send_receive(&ConfigMsgIn::GetGlobalConfig).await?;
-            if let ConfigMsgOut::GlobalConfig(config) = resp {
-                display::print_global_config(&config);
+async fn cmd_pin(action: Option<PinAction>) -> Result<()> {
+    let mut pins = pins::load()?;
+    match action.unwrap_or(PinAction::List) {
+        PinAction::List => {
+            if pins.is_empty() {
+                println!("{}", NO_PINS);
             }
-        }
-        ConfigAction::Bpm { value } => {
-            let resp = dev.send_receive(&ConfigMsgIn::GetGlobalConfig).await?;
-            if let ConfigMsgOut::GlobalConfig(mut config) = resp {
-                config.clock.internal_bpm = value;
-                dev.send(&ConfigMsgIn::SetGlobalConfig(config)).await?;
-                println!("BPM set to {}", value);
+            for pin in &pins {
+                println!("{}  ({})", pin.describe(), pin.app);
             }
         }
-        ConfigAction::Brightness { value } => {
-            if !(100..=255).contains(&value) {
-                anyhow::bail!("Brightness must be 100-255");
+        PinAction::Add { params } => {
+            let refs = params
+                .iter()
+                .map(|p| pins::parse_ref(p))
+                .collect::<Result<Vec<_>>>()?;
+            let mut dev = FaderpunkDevice::open()?;
+            let app_info = fetch_app_info(&mut dev).await?;
+            let layout = fetch_layout(&mut dev).await?;
+            let entries = layout_entries(&layout);
+
+            // Resolve them all before saving any
+            let mut added = Vec::new();
+            for (slot, param) in refs {
+                let entry = find_entry_at_slot(&entries, slot).ok_or_else(|| {
+                    error::invalid(t!("No app at fader {}", slots::label(slot as usize - 1)))
+                })?;
+                let app = app_info
+                    .iter()
+                    .find(|a| a.app_id == entry.app_id)
+                    .ok_or_else(|| anyhow::anyhow!(t!("App metadata not found")))?;
+                let idx = resolve_param(&param, app, app.params.len(), slot)?;
+                let name = display::get_param_name(&app.params[idx]);
+                let pin = pins::Pin {
+                    slot,
+                    app: app.name.clone(),
+                    param: if name.is_empty() { idx.to_string() } else { name },
+                };
+                if pins
+                    .iter()
+                    .chain(&added)
+                    .any(|p| p.slot == pin.slot && p.param == pin.param)
+                {
+                    println!("{} is already pinned", pin.describe());
+                    continue;
+                }
+                added.push(pin);
             }
-            let resp = dev.send_receive(&ConfigMsgIn::GetGlobalConfig).await?;
-            if let ConfigMsgOut::GlobalConfig(mut config) = resp {
-                config.led_brightness = value;
-                dev.send(&ConfigMsgIn::SetGlobalConfig(config)).await?;
-                println!("LED brightness set to {}", value);
+            for pin in &added {
+                println!("Pinned {} ({})", pin.describe(), pin.app);
             }
+            pins.extend(added);
+            pins::save(&pins)?;
         }
-        ConfigAction::Takeover { mode } => {
-            let takeover = match mode.to_lowercase().as_str() {
-                "pickup" => protocol::TakeoverMode::Pickup,
-                "jump" => protocol::TakeoverMode::Jump,
-                "scale" => protocol::TakeoverMode::Scale,
-                _ => anyhow::bail!("Unknown takeover mode: {} (use: pickup, jump, scale)", mode),
-            };
-            let resp = dev.send_receive(&ConfigMsgIn::GetGlobalConfig).await?;
-            if let ConfigMsgOut::GlobalConfig(mut config) = resp {
-                config.takeover_mode = takeover;
-                dev.send(&ConfigMsgIn::SetGlobalConfig(config)).await?;
-                println!("Takeover mode set to {:?}", takeover);
-            }
-        }
-        ConfigAction::Clocksrc { source } => {
-            let src = match source.to_lowercase().replace(['-', '_'], "").as_str() {
-                "internal" => protocol::ClockSrc::Internal,
-                "midiusb" | "usb" => protocol::ClockSrc::MidiUsb,
-                "midiin" | "din" => protocol::ClockSrc::MidiIn,
-                "atom" => protocol::ClockSrc::Atom,
-                "meteor" => protocol::ClockSrc::Meteor,
-                "cube" => protocol::ClockSrc::Cube,
-                "none" | "off" => protocol::ClockSrc::None,
-                _ => anyhow::bail!(
-                    "Unknown clock source: {} (use: internal, midiusb, midiin, atom, meteor, cube, none)",
-                    source
-                ),
-            };
-            let resp = dev.send_receive(&ConfigMsgIn::GetGlobalConfig).await?;
-            if let ConfigMsgOut::GlobalConfig(mut config) = resp {
-                config.clock.clock_src = src;
-                dev.send(&ConfigMsgIn::SetGlobalConfig(config)).await?;
-                println!("Clock source set to {:?}", src);
+        PinAction::Remove { params } => {
+            for param_ref in &params {
+                let (slot, param) = pins::parse_ref(param_ref)?;
+                let before = pins.len();
+                pins.retain(|p| !(p.slot == slot && p.param.eq_ignore_ascii_case(&param)));
+                if pins.len() == before {
+                    anyhow::bail!("'{}' isn't pinned. Use 'pin list' to see pins.", param_ref);
+                }
             }
+            pins::save(&pins)?;
+            println!("Unpinned {}", params.join(", "));
         }
     }
-
     Ok(())
 }
 
-// ── Save / Load ──
-
-async fn cmd_save(path: &str) -> Result<()> {
-    let mut dev = FaderpunkDevice::open()?;
+/// A pin found on the current layout.
+struct PinTarget<'a> {
+    layout_id: u8,
+    app: &'a display::AppInfo,
+    idx: usize,
+}
 
-    let config_resp = dev.send_receive(&ConfigMsgIn::GetGlobalConfig).await?;
-    let layout_resp = dev.send_receive(&ConfigMsgIn::GetLayout).await?;
+/// Find a pinned param on the current layout, or say why it isn't there.
+/// Names match exactly here: a pin on "Speed" shouldn't turn ambiguous next
+/// to "Speed Mod" the way a `param set` prefix would.
+fn find_pinned<'a>(
+    pin: &pins::Pin,
+    entries: &[display::LayoutEntry],
+    app_info: &'a [display::AppInfo],
+    current: &std::collections::BTreeMap<u8, Vec<Value>>,
+) -> std::result::Result<PinTarget<'a>, String> {
+    let entry = find_entry_at_slot(entries, pin.slot).ok_or("no app on this fader")?;
+    let app = app_info
+        .iter()
+        .find(|a| a.app_id == entry.app_id)
+        .ok_or("app metadata not found")?;
+    if !app.name.eq_ignore_ascii_case(&pin.app) {
+        return Err(format!("pinned on {}, now {}", pin.app, app.name));
+    }
+    let count = current.get(&entry.layout_id).map_or(0, Vec::len);
+    let idx = app
+        .params
+        .iter()
+        .position(|p| display::get_param_name(p).eq_ignore_ascii_case(&pin.param))
+        .or_else(|| pin.param.parse().ok())
+        .filter(|&i| i < count)
+        .ok_or_else(|| format!("{} has no param '{}'", app.name, pin.param))?;
+    Ok(PinTarget {
+        layout_id: entry.layout_id,
+        app,
+        idx,
+    })
+}
 
-    let config = match config_resp {
-        ConfigMsgOut::GlobalConfig(c) => c,
-        _ => anyhow::bail!("Unexpected response for GlobalConfig"),
+/// `pinned`: the pinned params in one numbered list. Rows are set as
+/// N=VALUE, or one after another with `--edit`; each app gets one write.
+async fn cmd_pinned(assignments: &[String], edit: bool, clamp: bool) -> Result<()> {
+    let pins = pins::load()?;
+    if pins.is_empty() {
+        println!("{}", NO_PINS);
+        return Ok(());
+    }
+    if edit && NON_INTERACTIVE.load(std::sync::atomic::Ordering::SeqCst) {
+        anyhow::bail!("pinned --edit prompts for values; use N=VALUE arguments instead");
+    }
+    let mut dev = FaderpunkDevice::open()?;
+    let app_info = fetch_app_info(&mut dev).await?;
+    let layout = fetch_layout(&mut dev).await?;
+    let entries = layout_entries(&layout);
+
+    let mut layout_ids: Vec<u8> = pins
+        .iter()
+        .filter_map(|p| find_entry_at_slot(&entries, p.slot))
+        .map(|e| e.layout_id)
+        .collect();
+    layout_ids.sort_unstable();
+    layout_ids.dedup();
+    let mut current: std::collections::BTreeMap<u8, Vec<Value>> =
+        fetch_app_params(&mut dev, &layout_ids).await?.into_iter().collect();
+    let targets: Vec<_> = pins
+        .iter()
+        .map(|p| find_pinned(p, &entries, &app_info, &current))
+        .collect();
+
+    let value_of = |t: &PinTarget| current[&t.layout_id][t.idx];
+    let row_label = |row: usize, t: &PinTarget| {
+        format!(
+            "{} {} {}",
+            slots::label(pins[row].slot as usize - 1),
+            t.app.name,
+            pins[row].param
+        )
+    };
+
+    // New values by row, all parsed before anything is sent
+    let mut changes: Vec<(usize, Value)> = Vec::new();
+    for assignment in assignments {
+        let (row, value) = assignment
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Expected N=VALUE, got '{}'", assignment))?;
+        let row = row
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|r| (1..=pins.len()).contains(r))
+            .ok_or_else(|| anyhow::anyhow!("Row must be 1-{}, got '{}'", pins.len(), row))?
+            - 1;
+        let target = targets[row].as_ref().map_err(|why| {
+            anyhow::anyhow!("Row {} ({}): {}", row + 1, pins[row].describe(), why)
+        })?;
+        let param = target.app.params.get(target.idx);
+        let value = parse_value(value.trim(), param, &value_of(target), clamp)
+            .with_context(|| format!("Row {} ({})", row + 1, pins[row].describe()))?;
+        changes.push((row, value));
+    }
+    if edit {
+        println!("Enter a new value, or press Enter to keep it.");
+        for (row, target) in targets.iter().enumerate() {
+            let Ok(target) = target else { continue };
+            let param = target.app.params.get(target.idx);
+            let old = value_of(target);
+            let question = format!(
+                "{:>3}  {} = {}",
+                row + 1,
+                row_label(row, target),
+                display::format_value(&old, param)
+            );
+            let answer = ask(&question, "keep", |s| match s {
+                "keep" => Ok(None),
+                s => parse_value(s, param, &old, clamp).map(Some),
+            })?;
+            if let Some(value) = answer.filter(|v| *v != old) {
+                changes.push((row, value));
+            }
+        }
+    }
+
+    if changes.is_empty() {
+        if edit {
+            println!("Nothing changed.");
+            return Ok(());
+        }
+        let rows: Vec<display::PinnedRow> = pins
+            .iter()
+            .zip(&targets)
+            .map(|(pin, target)| display::PinnedRow {
+                fader: slots::label(pin.slot as usize - 1),
+                app: target.as_ref().map_or(&pin.app, |t| &t.app.name).clone(),
+                param: pin.param.clone(),
+                value: match target {
+                    Ok(t) => Ok(display::format_value(&value_of(t), t.app.params.get(t.idx))),
+                    Err(why) => Err(why.clone()),
+                },
+            })
+            .collect();
+        display::print_pinned(&rows);
+        return Ok(());
+    }
+
+    let read = current.clone();
+    let mut touched: Vec<u8> = Vec::new();
+    for (row, value) in &changes {
+        let Ok(target) = &targets[*row] else { continue };
+        current.get_mut(&target.layout_id).unwrap()[target.idx] = *value;
+        if !touched.contains(&target.layout_id) {
+            touched.push(target.layout_id);
+        }
+        println!(
+            "  {} = {}",
+            row_label(*row, target),
+            display::format_value(value, target.app.params.get(target.idx))
+        );
+    }
+    if edit {
+        // The values were read before the prompts; check against the device
+        dev.clear_cache();
+    }
+    for layout_id in &touched {
+        let values = param_values(&current[layout_id]);
+        dev.write_params(*layout_id, &read[layout_id], values, false).await?;
+    }
+    println!("Set {} pinned value(s)", changes.len());
+    Ok(())
+}
+
+// ── Clock ──
+
+async fn cmd_clock(action: ClockAction) -> Result<()> {
+    match action {
+        ClockAction::Link { transport } => clock_link(transport).await,
+        ClockAction::Measure {
+            midi_in,
+            seconds,
+            expect,
+        } => clock_measure(Path::new(&midi_in), Duration::from_secs(seconds), expect).await,
+    }
+}
+
+/// Follow the Link session: tempo always, start/stop with `transport`.
+async fn clock_link(transport: bool) -> Result<()> {
+    let listener = link::Listener::bind()?;
+    let mut conn = live::LiveConnection::open().await?;
+    let original_src = conn.global_config.clock.clock_src;
+    let mut keepalive = tokio::time::interval(live::PING_INTERVAL);
+    println!(
+        "Listening for Ableton Link peers on UDP port {} (Ctrl-C to stop)...",
+        link::PORT
+    );
+    service::notify_ready();
+
+    let mut peers: Vec<u64> = Vec::new();
+    // The session as last heard, applied again after a reconnect
+    let mut tempo: Option<f32> = None;
+    let mut playing: Option<bool> = None;
+    loop {
+        tokio::select! {
+            announcement = listener.recv() => {
+                let announcement = announcement?;
+                if !peers.contains(&announcement.node) {
+                    peers.push(announcement.node);
+                    println!("Link peer found ({} in session)", peers.len());
+                }
+                // Link tempos carry more precision than anyone can hear
+                let heard_tempo = announcement.tempo.map(|t| ((t * 100.0).round() / 100.0) as f32);
+                let heard_playing = announcement.playing.filter(|_| transport);
+                if (heard_tempo.is_some() && heard_tempo != tempo)
+                    || (heard_playing.is_some() && heard_playing != playing)
+                {
+                    tempo = heard_tempo.or(tempo);
+                    playing = heard_playing.or(playing);
+                    apply_link_state(&mut conn, tempo, playing).await?;
+                }
+            }
+            _ = keepalive.tick() => {
+                if conn.check().await? {
+                    apply_link_state(&mut conn, tempo, playing).await?;
+                }
+            }
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    if transport && conn.global_config.clock.clock_src != original_src {
+        let mut config = conn.global_config.clone();
+        config.clock.clock_src = original_src;
+        conn.device().send(&ConfigMsgIn::SetGlobalConfig(config)).await?;
+        println!("Clock source restored to {:?}", original_src);
+    }
+    Ok(())
+}
+
+/// Write the Link tempo (and play state, as the clock source) to the device
+/// if it differs from what the device has.
+async fn apply_link_state(
+    conn: &mut live::LiveConnection,
+    tempo: Option<f32>,
+    playing: Option<bool>,
+) -> Result<()> {
+    let mut config = conn.global_config.clone();
+    if let Some(bpm) = tempo
+        && config.clock.internal_bpm != bpm
+    {
+        // Checked like `config bpm`; a tempo it refuses leaves the clock as is
+        match LIMITS.check_bpm(bpm) {
+            Ok(()) => {
+                config.clock.internal_bpm = bpm;
+                println!("Tempo {} BPM", bpm);
+            }
+            Err(e) => eprintln!("warning: ignoring the session's tempo of {}: {:#}", bpm, e),
+        }
+    }
+    if let Some(playing) = playing {
+        let src = if playing {
+            protocol::ClockSrc::Internal
+        } else {
+            protocol::ClockSrc::None
+        };
+        if config.clock.clock_src != src {
+            config.clock.clock_src = src;
+            println!("{}", if playing { "Playing" } else { "Stopped" });
+        }
+    }
+    if config.clock.internal_bpm != conn.global_config.clock.internal_bpm
+        || config.clock.clock_src != conn.global_config.clock.clock_src
+    {
+        conn.device()
+            .send(&ConfigMsgIn::SetGlobalConfig(config.clone()))
+            .await?;
+        conn.global_config = config;
+    }
+    Ok(())
+}
+
+/// Collect clock ticks from `midi_in` for `length`, then report the tempo
+/// they add up to and how evenly they came.
+async fn clock_measure(midi_in: &Path, length: Duration, expect: Option<f32>) -> Result<()> {
+    let expect = match expect {
+        Some(bpm) => Some(bpm),
+        None => {
+            let mut dev = FaderpunkDevice::open()?;
+            let config = fetch_global_config(&mut dev).await?;
+            if !config.midi.outs.iter().any(|out| out.send_clock) {
+                eprintln!("Warning: none of the device's MIDI outputs sends clock");
+            }
+            if config.clock.clock_src == protocol::ClockSrc::Internal {
+                Some(config.clock.internal_bpm)
+            } else {
+                println!(
+                    "The device follows {:?} clock; not comparing against its internal BPM",
+                    config.clock.clock_src
+                );
+                None
+            }
+        }
     };
-    let layout = match layout_resp {
-        ConfigMsgOut::Layout(l) => l,
-        _ => anyhow::bail!("Unexpected response for Layout"),
+
+    let mut clock = automation::follow_clock(midi_in)?;
+    println!(
+        "Listening for MIDI clock on {} for {} (Ctrl-C to stop early)...",
+        midi_in.display(),
+        humantime::format_duration(length)
+    );
+    // Runs of consecutive ticks; a stop or jump starts a new one
+    let mut runs: Vec<Vec<std::time::Instant>> = vec![Vec::new()];
+    let deadline = tokio::time::sleep(length);
+    tokio::pin!(deadline);
+    loop {
+        let msg = tokio::select! {
+            msg = clock.recv() => msg,
+            _ = &mut deadline => break,
+            _ = tokio::signal::ctrl_c() => break,
+        };
+        match msg {
+            Some((at, automation::Clock::Tick)) => runs.last_mut().unwrap().push(at),
+            Some(_) => runs.push(Vec::new()),
+            None => {
+                eprintln!("MIDI input closed; reporting what arrived until then");
+                break;
+            }
+        }
+    }
+
+    let stats = automation::clock_stats(&runs)
+        .ok_or_else(|| anyhow::anyhow!("No MIDI clock received on {}", midi_in.display()))?;
+    display::print_clock_stats(&stats, expect);
+    Ok(())
+}
+
+// ── Simulate ──
+
+async fn cmd_simulate(action: SimulateAction) -> Result<()> {
+    match action {
+        SimulateAction::Clock { bpm, aux1, aux2, aux3, bars, device } => {
+            let (mut tempo, mut aux) = (120.0, [protocol::AuxJackMode::None; 3]);
+            if device {
+                let mut dev = FaderpunkDevice::open()?;
+                let config = fetch_global_config(&mut dev).await?;
+                (tempo, aux) = (config.clock.internal_bpm, config.aux);
+            }
+            let tempo = bpm.unwrap_or(tempo);
+            LIMITS.check_bpm(tempo)?;
+            for (jack, mode) in aux.iter_mut().zip([aux1, aux2, aux3]) {
+                if let Some(mode) = mode {
+                    *jack = mode;
+                }
+            }
+            if aux.iter().all(|m| *m == protocol::AuxJackMode::None) {
+                anyhow::bail!("No aux jack is a clock or reset output (set one with --aux1 to --aux3)");
+            }
+            display::print_clock_sim(tempo, &aux, bars);
+        }
+    }
+    Ok(())
+}
+
+// ── Export ──
+
+async fn cmd_export(action: ExportAction) -> Result<()> {
+    match action {
+        ExportAction::Patchsheet { path, markdown, from } => {
+            let (state, source) = match &from {
+                Some(file) => (Snapshot::read(Path::new(file), &[])?, file.clone()),
+                None => {
+                    let mut dev = FaderpunkDevice::open()?;
+                    (fetch_snapshot(&mut dev).await?, "the device".to_string())
+                }
+            };
+            let format = if markdown {
+                patchsheet::Format::Markdown
+            } else {
+                patchsheet::Format::for_path(&path)
+            };
+            let sheet = patchsheet::render(&state, &source, format);
+            if path == "-" {
+                print!("{}", sheet);
+                return Ok(());
+            }
+            std::fs::write(&path, sheet).with_context(|| format!("Failed to write {}", path))?;
+            println!("Wrote patch sheet to {}", path);
+        }
+    }
+    Ok(())
+}
+
+// ── Conformance ──
+
+const CONFORMANCE_BACKUP: &str = "conformance-backup.json";
+
+async fn cmd_conformance(action: ConformanceAction) -> Result<()> {
+    let ConformanceAction::Run { force, json } = action;
+    let json = json || render::output().is_structured();
+    if !force {
+        println!("{}", t!("This writes hundreds of settings, layouts and params to the device."));
+        println!("{}", t!("The current state is saved first and put back at the end."));
+        if !confirm(t!("Run the conformance suite?")) {
+            println!("{}", t!("Cancelled."));
+            return Ok(());
+        }
+    }
+
+    let mut dev = FaderpunkDevice::open()?;
+    let saved = fetch_snapshot(&mut dev).await?;
+    // On disk too, in case the device can't be put back at the end
+    let backup = paths::data_dir()?.join(CONFORMANCE_BACKUP);
+    saved.write(&backup)?;
+    let app_info = fetch_app_info(&mut dev).await?;
+    if !json {
+        println!("{}", t!("Running checks..."));
+    }
+    let run = conformance::run(&mut dev, &app_info, &LIMITS).await;
+
+    // Put the device back even if the run stopped early
+    dev.clear_cache();
+    let restored = match conformance_restore(&mut dev, &saved, &app_info).await {
+        Ok(()) => fetch_snapshot(&mut dev).await?.diff(&saved)?,
+        Err(e) => anyhow::bail!(
+            "Couldn't restore the device state ({}); load it with: fp load {}",
+            e,
+            backup.display()
+        ),
     };
+    std::fs::remove_file(&backup).ok();
+    let mut outcomes = run?;
+    outcomes.push(conformance::Outcome {
+        group: "Connection",
+        name: "starting state restored".to_string(),
+        failure: (!restored.is_empty())
+            .then(|| restored.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")),
+    });
+
+    if json {
+        let out: Vec<serde_json::Value> = outcomes
+            .iter()
+            .map(|o| {
+                serde_json::json!({
+                    "group": o.group,
+                    "check": o.name,
+                    "pass": o.passed(),
+                    "failure": o.failure,
+                })
+            })
+            .collect();
+        display::print_data(&serde_json::to_value(&out)?);
+    } else {
+        display::print_conformance(&outcomes);
+    }
+
+    let failed = outcomes.iter().filter(|o| !o.passed()).count();
+    if failed > 0 {
+        anyhow::bail!("{} of {} checks failed", failed, outcomes.len());
+    }
+    Ok(())
+}
+
+/// `apply_snapshot` without the progress lines, which would get in the way
+/// of the report.
+async fn conformance_restore(
+    dev: &mut FaderpunkDevice,
+    saved: &Snapshot,
+    app_info: &[display::AppInfo],
+) -> Result<()> {
+    dev.send(&ConfigMsgIn::SetGlobalConfig(saved.global_config.clone())).await?;
+    let validated = send_layout(dev, saved.layout.clone(), app_info).await?;
+    for entry in layout_entries(&validated) {
+        if let Some(values) = saved.params_for(entry.layout_id) {
+            // Whatever the run left there is meant to be replaced
+            dev.write_params(entry.layout_id, &[], param_values(values), true)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+// ── Audit log ──
+
+fn cmd_log(action: Option<LogAction>) -> Result<()> {
+    match action.unwrap_or(LogAction::Show { since: None }) {
+        LogAction::Show { since } => {
+            let entries = audit::read(since)?;
+            display::print_audit_log(&entries);
+        }
+        LogAction::Path => println!("{}", audit::log_path()?.display()),
+    }
+    Ok(())
+}
+
+// ── A/B compare ──
+
+async fn cmd_ab(slot: u8) -> Result<()> {
+    use crossterm::event::KeyCode;
+
+    validate_slot(slot)?;
+    let mut dev = FaderpunkDevice::open()?;
+    let app_info = fetch_app_info(&mut dev).await?;
+    let layout = fetch_layout(&mut dev).await?;
+    let entries = layout_entries(&layout);
+    let entry = find_entry_at_slot(&entries, slot)
+        .ok_or_else(|| error::invalid(t!("No app at fader {}", slots::label(slot as usize - 1))))?;
+    let layout_id = entry.layout_id;
+    let app = app_info
+        .iter()
+        .find(|a| a.app_id == entry.app_id)
+        .ok_or_else(|| anyhow::anyhow!(t!("App metadata not found")))?;
+
+    let original = fetch_app_params(&mut dev, &[layout_id]).await?.remove(0).1;
+    // sides[0] is A, sides[1] is B; whichever is active lives on the device
+    let mut sides = [original.clone(), original.clone()];
+    let mut active = 1;
+
+    println!(
+        "A captured for {} on fader {}. Tweak the app to make B (on the device or with 'param set').",
+        app.name,
+        slots::label(slot as usize - 1)
+    );
+    println!("  space  switch A/B    a/b  pick a side    enter  keep the active side    esc  discard all changes");
+    println!("Now on B.");
+
+    loop {
+        let target = match read_key().await? {
+            KeyCode::Char(' ') | KeyCode::Tab => 1 - active,
+            KeyCode::Char('a') | KeyCode::Char('A') => 0,
+            KeyCode::Char('b') | KeyCode::Char('B') => 1,
+            KeyCode::Enter => {
+                println!("Kept {}.", ab_label(active));
+                return Ok(());
+            }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                ab_apply(&mut dev, layout_id, &original).await?;
+                println!("Restored the params as first captured.");
+                return Ok(());
+            }
+            _ => continue,
+        };
+        if target == active {
+            continue;
+        }
+
+        // Pick up any tweaks made to the side we're leaving
+        sides[active] = ab_apply(&mut dev, layout_id, &sides[target]).await?;
+        active = target;
+
+        println!("Now on {}.", ab_label(active));
+        for (i, (a, b)) in sides[0].iter().zip(&sides[1]).enumerate() {
+            if a != b {
+                let param = app.params.get(i);
+                println!(
+                    "  {:<16} A {}  B {}",
+                    display::param_label(param, i),
+                    display::format_value(a, param),
+                    display::format_value(b, param)
+                );
+            }
+        }
+    }
+}
+
+fn ab_label(side: usize) -> &'static str {
+    if side == 0 { "A" } else { "B" }
+}
+
+/// Put `values` on the device, returning what it had: the side being left,
+/// with any tweaks made to it since the last switch.
+async fn ab_apply(
+    dev: &mut FaderpunkDevice,
+    layout_id: u8,
+    values: &[Value],
+) -> Result<Vec<Value>> {
+    // The tweaks are made on the device, not through this handle
+    dev.clear_cache();
+    let (_, now) = fetch_app_params(dev, &[layout_id]).await?.remove(0);
+    dev.write_params(layout_id, &now, param_values(values), false).await?;
+    Ok(now)
+}
+
+// ── Fingerprint ──
+
+async fn cmd_fingerprint(file: Option<&str>, revision: bool) -> Result<()> {
+    if revision {
+        return fingerprint_revision(file);
+    }
+    let snapshot = match file {
+        Some(path) => Snapshot::read(Path::new(path), &[])?,
+        None => fetch_snapshot(&mut FaderpunkDevice::open()?).await?,
+    };
+    println!("{}", snapshot.fingerprint()?);
+    Ok(())
+}
+
+/// `fingerprint --revision`: read from a file instead of reading and
+/// hashing the device state, so tools can poll it.
+fn fingerprint_revision(file: Option<&str>) -> Result<()> {
+    let snapshot = match file {
+        Some(path) => Snapshot::read(Path::new(path), &[])?,
+        None => mirror::read()?.context("No state mirror yet; is 'daemon mirror' running?")?,
+    };
+    let revision = snapshot
+        .metadata
+        .revision
+        .context("The snapshot doesn't record a state revision")?;
+    println!("{}", revision);
+    Ok(())
+}
+
+/// First saved profile whose state has this fingerprint.
+fn matching_profile(fingerprint: &str) -> Result<Option<String>> {
+    for name in profile::list()? {
+        let Ok(snapshot) = Snapshot::read(&profile::path(&name)?, &[]) else {
+            continue;
+        };
+        if snapshot.fingerprint()? == fingerprint {
+            return Ok(Some(name));
+        }
+    }
+    Ok(None)
+}
+
+// ── Ensure ──
+
+async fn cmd_ensure(path: &str, dry_run: bool, force: bool, vars: &[(String, String)]) -> Result<()> {
+    let desired = Snapshot::read(Path::new(path), vars)?;
+    let mut dev = FaderpunkDevice::open()?;
+    let current = fetch_snapshot(&mut dev).await?;
+    let diffs = current.diff(&desired)?;
+
+    if diffs.is_empty() {
+        println!("{}", t!("No changes. The device matches {}.", path));
+        return Ok(());
+    }
+
+    println!("{}", t!("Changes needed to match {}:", path));
+    print_plan(&diffs);
+
+    if dry_run {
+        return Ok(());
+    }
+    if !force && !confirm(t!("Apply these changes?")) {
+        println!("{}", t!("Cancelled."));
+        return Ok(());
+    }
+    apply_differences(&mut dev, &current, &desired, &diffs).await
+}
+
+/// List the differences and how many of each kind need sending.
+fn print_plan(diffs: &[snapshot::Difference]) {
+    let count = |f: fn(&snapshot::Difference) -> bool| diffs.iter().filter(|d| f(d)).count();
+    println!();
+    for diff in diffs {
+        println!("  {} {}", diff.symbol(), diff);
+    }
+    println!();
+    println!(
+        "{}",
+        t!(
+            "Plan: {} config, {} layout, {} param change(s).",
+            count(|d| matches!(d, snapshot::Difference::Config(_))),
+            count(|d| matches!(d, snapshot::Difference::Layout { .. })),
+            count(|d| matches!(d, snapshot::Difference::Param { .. }))
+        )
+    );
+}
+
+/// Send the parts of `desired` that `diffs` (from `current`, the device's
+/// state as read, to `desired`) says differ, and nothing else. Params are
+/// only written over the values in `current`.
+async fn apply_differences(
+    dev: &mut FaderpunkDevice,
+    current: &Snapshot,
+    desired: &Snapshot,
+    diffs: &[snapshot::Difference],
+) -> Result<()> {
+    // `current` may be from before a prompt or an editor session
+    dev.clear_cache();
+    if diffs.iter().any(|d| matches!(d, snapshot::Difference::Config(_))) {
+        dev.send(&ConfigMsgIn::SetGlobalConfig(desired.global_config.clone()))
+            .await?;
+        println!("{}", t!("Global config updated."));
+    }
+
+    // Apps placed by a layout change start from the firmware's defaults, so
+    // their params are sent even if the old occupant of that layout_id matched
+    let mut placed: Vec<u8> = Vec::new();
+    if diffs.iter().any(|d| matches!(d, snapshot::Difference::Layout { .. })) {
+        let app_info = fetch_app_info(dev).await?;
+        send_layout(dev, desired.layout.clone(), &app_info).await?;
+        println!("{}", t!("Layout updated."));
+        for diff in diffs {
+            if let snapshot::Difference::Layout {
+                new: Some((_, _, layout_id)),
+                ..
+            } = diff
+            {
+                placed.push(*layout_id);
+            }
+        }
+    }
+    let mut layout_ids = placed.clone();
+    for diff in diffs {
+        if let snapshot::Difference::Param { layout_id, .. } = diff {
+            layout_ids.push(*layout_id);
+        }
+    }
+    layout_ids.sort_unstable();
+    layout_ids.dedup();
+
+    let mut updated = 0;
+    for layout_id in layout_ids {
+        let Some(values) = desired.params_for(layout_id) else {
+            continue;
+        };
+        if !desired.layout.0.iter().flatten().any(|(_, _, id)| *id == layout_id) {
+            continue;
+        }
+        let read = current.params_for(layout_id).unwrap_or_default();
+        let force = placed.contains(&layout_id);
+        dev.write_params(layout_id, read, param_values(values), force).await?;
+        updated += 1;
+    }
+    if updated > 0 {
+        println!("{}", t!("Params updated for {} app(s).", updated));
+    }
+    Ok(())
+}
+
+// ── Edit ──
+
+async fn cmd_edit(what: Option<EditTarget>, force: bool) -> Result<()> {
+    let mut dev = FaderpunkDevice::open()?;
+    let current = fetch_snapshot(&mut dev).await?;
+    let app_info = read_app_info(&mut dev).await?;
+
+    let mut state = serde_json::to_value(&current)?;
+    // Where in the snapshot the edited document goes
+    let pointer = match &what {
+        None => {
+            // Only device state is editable; metadata and version are kept as is
+            if let Some(obj) = state.as_object_mut() {
+                obj.remove("metadata");
+                obj.remove("version");
+            }
+            String::new()
+        }
+        Some(EditTarget::Layout) => "/layout".to_string(),
+        Some(EditTarget::Params { slot }) => {
+            let entries = layout_entries(&current.layout);
+            let entry = find_entry_at_slot(&entries, *slot)
+                .ok_or_else(|| error::invalid(t!("No app at fader {}", slots::label(*slot as usize - 1))))?;
+            let index = current
+                .params
+                .iter()
+                .position(|p| p.layout_id == entry.layout_id)
+                .with_context(|| format!("No params for the app at fader {}", slots::label(*slot as usize - 1)))?;
+            format!("/params/{}/values", index)
+        }
+    };
+    let original = state.pointer(&pointer).cloned().unwrap_or_default();
+
+    let path = std::env::temp_dir().join(format!("faderpunk-edit-{}.json", std::process::id()));
+    std::fs::write(&path, serde_json::to_string_pretty(&original)? + "\n")
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    let desired = edit_until_valid(&path, &current, &pointer, &app_info);
+    let _ = std::fs::remove_file(&path);
+    let Some(desired) = desired? else {
+        println!("{}", t!("Cancelled."));
+        return Ok(());
+    };
+
+    let diffs = current.diff(&desired)?;
+    if diffs.is_empty() {
+        println!("{}", t!("No changes."));
+        return Ok(());
+    }
+    println!("{}", t!("Changes:"));
+    print_plan(&diffs);
+    if !force && !confirm(t!("Apply these changes?")) {
+        println!("{}", t!("Cancelled."));
+        return Ok(());
+    }
+    apply_differences(&mut dev, &current, &desired, &diffs).await
+}
 
-    let snapshot = serde_json::json!({
-        "global_config": config,
-        "layout": layout,
+/// Open the editor on `path` until its contents, put in place of `pointer`
+/// in `current`, make a valid snapshot. None if the user gives up.
+fn edit_until_valid(
+    path: &Path,
+    current: &Snapshot,
+    pointer: &str,
+    app_info: &[display::AppInfo],
+) -> Result<Option<Snapshot>> {
+    loop {
+        run_editor(path)?;
+        let result = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))
+            .and_then(|text| {
+                let edited: serde_json::Value =
+                    serde_json::from_str(&text).context("Not valid JSON")?;
+                let mut state = serde_json::to_value(current)?;
+                match state.pointer_mut(pointer) {
+                    Some(target) if pointer.is_empty() => merge_state(target, edited),
+                    Some(target) => *target = edited,
+                    None => anyhow::bail!("Nothing to edit at {}", pointer),
+                }
+                let snapshot: Snapshot = serde_json::from_value(state)?;
+                check_snapshot(&snapshot, app_info)?;
+                Ok(snapshot)
+            });
+        match result {
+            Ok(snapshot) => return Ok(Some(snapshot)),
+            Err(e) => {
+                display::print_error(&e, None);
+                if !confirm("Edit again?") {
+                    return Ok(None);
+                }
+            }
+        }
+    }
+}
+
+/// Replace the device-state fields of a full snapshot with the edited ones,
+/// leaving metadata alone.
+fn merge_state(state: &mut serde_json::Value, edited: serde_json::Value) {
+    if let (Some(state), serde_json::Value::Object(edited)) = (state.as_object_mut(), edited) {
+        for key in ["global_config", "layout", "params"] {
+            match edited.get(key) {
+                Some(value) => state.insert(key.to_string(), value.clone()),
+                None => state.remove(key),
+            };
+        }
+    }
+}
+
+/// Open $VISUAL or $EDITOR (vi if neither is set) on a file and wait for it.
+fn run_editor(path: &Path) -> Result<()> {
+    let command = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut parts = command.split_whitespace();
+    let program = parts.next().context("$EDITOR is empty")?;
+    let status = std::process::Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .with_context(|| format!("Failed to start editor '{}'", command))?;
+    if !status.success() {
+        anyhow::bail!("Editor '{}' exited with {}", command, status);
+    }
+    Ok(())
+}
+
+/// Check that a snapshot's layout fits and every app's params have the
+/// count and types the app expects.
+fn check_snapshot(snapshot: &Snapshot, app_info: &[display::AppInfo]) -> Result<()> {
+    layout::validate(&snapshot.layout, app_info)?;
+    for entry in layout_entries(&snapshot.layout) {
+        let (Some(app), Some(values)) = (
+            app_info.iter().find(|a| a.app_id == entry.app_id),
+            snapshot.params_for(entry.layout_id),
+        ) else {
+            continue;
+        };
+        if values.len() != app.params.len() {
+            anyhow::bail!(
+                "fader {}: {} has {} params, not {}",
+                slots::label(entry.start),
+                app.name,
+                app.params.len(),
+                values.len()
+            );
+        }
+        for (i, (value, param)) in values.iter().zip(&app.params).enumerate() {
+            if !value_fits(value, param) {
+                anyhow::bail!(
+                    "fader {}: {}: param {} must be a {} value",
+                    slots::label(entry.start),
+                    app.name,
+                    i,
+                    display::format_param_type(param)
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+// ── Guard ──
+
+async fn cmd_guard(
+    baseline_path: &str,
+    restore: bool,
+    allow: &[String],
+    allow_config: &[String],
+    interval: u64,
+) -> Result<()> {
+    let mut baseline = Snapshot::read(Path::new(baseline_path), &[])?;
+    let mut conn = live::LiveConnection::open().await?;
+    let app_info = fetch_app_info(conn.device()).await?;
+    let mut allowed_params = resolve_allowed_params(allow, &baseline, &app_info)?;
+    let mut reload = live::ReloadSignal::new()?;
+
+    let mut ticker = tokio::time::interval(Duration::from_secs(interval.max(1)));
+    let mut reported: Vec<String> = Vec::new();
+    println!(
+        "Guarding against drift from {} (Ctrl-C to stop)...",
+        baseline_path
+    );
+    service::notify_ready();
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = reload.recv() => {
+                // Keep guarding with the old baseline if the new one is broken
+                let reloaded = Snapshot::read(Path::new(baseline_path), &[]).and_then(|b| {
+                    let allowed = resolve_allowed_params(allow, &b, &app_info)?;
+                    Ok((b, allowed))
+                });
+                match reloaded {
+                    Ok((b, allowed)) => {
+                        baseline = b;
+                        allowed_params = allowed;
+                        reported.clear();
+                        println!("{} Reloaded {}", timestamp(), baseline_path);
+                    }
+                    Err(e) => eprintln!(
+                        "{} error: reload failed, keeping the previous baseline: {:#}",
+                        timestamp(),
+                        e
+                    ),
+                }
+            }
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+        conn.check().await?;
+
+        let is_allowed = |d: &snapshot::Difference| match d {
+            snapshot::Difference::Config(c) => allow_config.iter().any(|f| {
+                c.field == *f
+                    || c.field.starts_with(&format!("{}.", f))
+                    || c.field.starts_with(&format!("{}[", f))
+            }),
+            snapshot::Difference::Layout { .. } => false,
+            snapshot::Difference::Param {
+                layout_id, index, ..
+            } => allowed_params.contains(&(*layout_id, *index)),
+        };
+        let current = fetch_snapshot(conn.device()).await?;
+        let diffs = baseline.diff(&current)?;
+        let (kept, drift): (Vec<_>, Vec<_>) = diffs.iter().partition(|d| is_allowed(d));
+        let lines: Vec<String> = drift.iter().map(|d| d.to_string()).collect();
+
+        if lines.is_empty() {
+            if !reported.is_empty() {
+                println!("{} Back in line with the baseline.", timestamp());
+            }
+            reported.clear();
+            continue;
+        }
+
+        if restore {
+            println!(
+                "{} Drift detected ({} change(s)), restoring:",
+                timestamp(),
+                lines.len()
+            );
+            for line in &lines {
+                println!("  {}", line);
+            }
+            let merged = baseline.with_changes_from(&current, &kept)?;
+            apply_snapshot(conn.device(), &merged).await?;
+            reported.clear();
+        } else if lines != reported {
+            // Ring the terminal bell so an unattended alert gets noticed
+            println!(
+                "\x07{} warning: drift from baseline ({} change(s)):",
+                timestamp(),
+                lines.len()
+            );
+            for line in &lines {
+                println!("  {}", line);
+            }
+            reported = lines;
+        }
+    }
+}
+
+/// Turn `--allow SLOT:PARAM` arguments into (layout_id, param index) pairs,
+/// using the baseline's layout to find which app sits on each slot.
+fn resolve_allowed_params(
+    allow: &[String],
+    baseline: &Snapshot,
+    app_info: &[display::AppInfo],
+) -> Result<Vec<(u8, usize)>> {
+    let entries = layout_entries(&baseline.layout);
+    allow
+        .iter()
+        .map(|spec| {
+            let (slot, param_ref) = spec
+                .split_once(':')
+                .ok_or_else(|| anyhow::anyhow!("Invalid --allow '{}': expected SLOT:PARAM", spec))?;
+            let slot = slots::parse(slot).with_context(|| format!("Invalid --allow '{}'", spec))?;
+            let entry = find_entry_at_slot(&entries, slot)
+                .ok_or_else(|| anyhow::anyhow!("No app at fader {} in the baseline", slots::label(slot as usize - 1)))?;
+            let app = app_info
+                .iter()
+                .find(|a| a.app_id == entry.app_id)
+                .ok_or_else(|| anyhow::anyhow!(t!("App metadata not found")))?;
+            let count = baseline
+                .params_for(entry.layout_id)
+                .map_or(app.params.len(), |v| v.len());
+            let index = resolve_param(param_ref.trim(), app, count, slot)?;
+            Ok((entry.layout_id, index))
+        })
+        .collect()
+}
+
+/// Current time (UTC, RFC 3339) for log-style output.
+fn timestamp() -> String {
+    humantime::format_rfc3339_seconds(std::time::SystemTime::now()).to_string()
+}
+
+// ── Sync ──
+
+/// Which side `sync` keeps where the file and the device changed the same value.
+#[derive(Clone, Copy, PartialEq)]
+enum SyncSide {
+    Device,
+    File,
+}
+
+fn parse_sync_side(s: &str) -> Result<SyncSide> {
+    match s.to_lowercase().as_str() {
+        "device" => Ok(SyncSide::Device),
+        "file" => Ok(SyncSide::File),
+        _ => anyhow::bail!("Unknown side '{}' (use: device, file)", s),
+    }
+}
+
+/// How long `sync --watch` lets a burst of device events or file saves
+/// settle before syncing.
+const SYNC_SETTLE: Duration = Duration::from_millis(250);
+/// How often `sync --watch` checks the file for edits.
+const SYNC_FILE_POLL: Duration = Duration::from_millis(500);
+
+async fn cmd_sync(path: &str, watch: bool, prefer: SyncSide, interval: u64) -> Result<()> {
+    let path = Path::new(path);
+    if !watch {
+        let mut dev = FaderpunkDevice::open()?;
+        if !sync_once(&mut dev, path, None, prefer).await?.1 {
+            println!("{}", t!("No changes. The device matches {}.", path.display()));
+        }
+        return Ok(());
+    }
+
+    let mut conn = live::LiveConnection::open().await?;
+    let mut events = conn.device().subscribe();
+    let mut ticker = tokio::time::interval(Duration::from_secs(interval.max(1)));
+    let mut file_poll = tokio::time::interval(SYNC_FILE_POLL);
+    let mut base = sync_once(conn.device(), path, None, prefer).await?.0;
+    let mut stamp = modified(path);
+    let mut last_error: Option<String> = None;
+    println!(
+        "Syncing {} with the device (Ctrl-C to stop)...",
+        path.display()
+    );
+    service::notify_ready();
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {
+                if conn.check().await? {
+                    events = conn.device().subscribe();
+                }
+            }
+            event = events.recv() => {
+                if let Err(tokio::sync::broadcast::error::RecvError::Closed) = event
+                    && conn.check().await?
+                {
+                    events = conn.device().subscribe();
+                }
+                tokio::time::sleep(SYNC_SETTLE).await;
+                while events.try_recv().is_ok() {}
+            }
+            _ = file_poll.tick() => {
+                if modified(path) == stamp {
+                    continue;
+                }
+                // Editors may save in several steps
+                tokio::time::sleep(SYNC_SETTLE).await;
+            }
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+        conn.device().clear_cache();
+        // A broken file (say, saved halfway through an edit) is reported once
+        // and skipped; syncing picks up again with the next good save
+        match sync_once(conn.device(), path, Some(&base), prefer).await {
+            Ok((synced, _)) => {
+                base = synced;
+                last_error = None;
+            }
+            Err(e) => {
+                let message = format!("{:#}", e);
+                if last_error.as_ref() != Some(&message) {
+                    eprintln!("{} error: {}", timestamp(), message);
+                }
+                last_error = Some(message);
+            }
+        }
+        stamp = modified(path);
+    }
+}
+
+fn modified(path: &Path) -> Option<std::time::SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Bring the file and the device together. `base` is the state both had
+/// after the last sync; without one, everything that differs counts as
+/// changed on the `prefer` side. Returns the synced state and whether
+/// anything changed.
+async fn sync_once(
+    dev: &mut FaderpunkDevice,
+    path: &Path,
+    base: Option<&Snapshot>,
+    prefer: SyncSide,
+) -> Result<(Snapshot, bool)> {
+    let device = fetch_snapshot(dev).await?;
+    if !path.exists() {
+        device.write(path)?;
+        println!("{} Wrote the device state to {}", timestamp(), path.display());
+        return Ok((device, true));
+    }
+    let file = Snapshot::read(path, &[])?;
+
+    let base = base.unwrap_or(match prefer {
+        SyncSide::Device => &file,
+        SyncSide::File => &device,
     });
+    let (merged, conflicts) = match prefer {
+        SyncSide::Device => base.merge(&device, &file)?,
+        SyncSide::File => base.merge(&file, &device)?,
+    };
+    for conflict in &conflicts {
+        let (kept, dropped) = match prefer {
+            SyncSide::Device => ("device", "file"),
+            SyncSide::File => ("file", "device"),
+        };
+        println!(
+            "{} warning: changed on both sides, kept the {} value over the {} change: {}",
+            timestamp(),
+            kept,
+            dropped,
+            conflict
+        );
+    }
+
+    let to_device = device.diff(&merged)?;
+    let synced = if to_device.is_empty() {
+        device
+    } else {
+        println!("{} {} → device:", timestamp(), path.display());
+        for diff in &to_device {
+            println!("  {} {}", diff.symbol(), diff);
+        }
+        apply_differences(dev, &device, &merged, &to_device).await?;
+        // Read back what the device made of it, e.g. a clamped value
+        dev.clear_cache();
+        fetch_snapshot(dev).await?
+    };
+
+    let to_file = file.diff(&synced)?;
+    if !to_file.is_empty() {
+        println!("{} device → {}:", timestamp(), path.display());
+        for diff in &to_file {
+            println!("  {} {}", diff.symbol(), diff);
+        }
+        synced.write(path)?;
+    }
+    let changed = !to_device.is_empty() || !to_file.is_empty();
+    Ok((synced, changed))
+}
+
+// ── Mirror ──
+
+/// How long `mirror` lets a burst of changes on either unit settle before
+/// copying.
+const LOCKSTEP_SETTLE: Duration = Duration::from_millis(250);
+
+async fn cmd_mirror(leader: &str, follower: &str, interval: u64) -> Result<()> {
+    if leader == follower {
+        anyhow::bail!("The leader and follower must be different units");
+    }
+    let mut lead = live::LiveConnection::open_serial(leader).await?;
+    let mut follow = live::LiveConnection::open_serial(follower).await?;
+    check_same_firmware(lead.device(), follow.device()).await?;
+    let mut lead_events = lead.device().subscribe();
+    let mut follow_events = follow.device().subscribe();
+    let mut ticker = tokio::time::interval(Duration::from_secs(interval.max(1)));
+    mirror_once(lead.device(), follow.device()).await?;
+    println!("Mirroring {} to {} (Ctrl-C to stop)...", leader, follower);
+    service::notify_ready();
+
+    let mut last_error: Option<String> = None;
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {
+                if lead.check().await? {
+                    lead_events = lead.device().subscribe();
+                }
+                if follow.check().await? {
+                    follow_events = follow.device().subscribe();
+                }
+            }
+            event = lead_events.recv() => {
+                if let Err(tokio::sync::broadcast::error::RecvError::Closed) = event
+                    && lead.check().await?
+                {
+                    lead_events = lead.device().subscribe();
+                }
+                tokio::time::sleep(LOCKSTEP_SETTLE).await;
+                while lead_events.try_recv().is_ok() {}
+            }
+            // Changes made on the follower itself are undone, so it keeps
+            // tracking the leader
+            event = follow_events.recv() => {
+                if let Err(tokio::sync::broadcast::error::RecvError::Closed) = event
+                    && follow.check().await?
+                {
+                    follow_events = follow.device().subscribe();
+                }
+                tokio::time::sleep(LOCKSTEP_SETTLE).await;
+                while follow_events.try_recv().is_ok() {}
+            }
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+        lead.device().clear_cache();
+        follow.device().clear_cache();
+        match mirror_once(lead.device(), follow.device()).await {
+            Ok(()) => last_error = None,
+            Err(e) => {
+                let message = format!("{:#}", e);
+                if last_error.as_ref() != Some(&message) {
+                    eprintln!("{} error: {}", timestamp(), message);
+                }
+                last_error = Some(message);
+            }
+        }
+    }
+}
 
-    std::fs::write(path, serde_json::to_string_pretty(&snapshot)?)?;
-    println!("Config saved to {}", path);
+/// Layouts and params are copied by app id and param index, which only mean
+/// the same on both units when they run the same firmware.
+async fn check_same_firmware(
+    leader: &mut FaderpunkDevice,
+    follower: &mut FaderpunkDevice,
+) -> Result<()> {
+    let lead_release = &leader.usb_info().release;
+    let follow_release = &follower.usb_info().release;
+    if lead_release != follow_release {
+        anyhow::bail!(
+            "The units run different firmware (leader {}, follower {}); update both to the same release",
+            lead_release,
+            follow_release
+        );
+    }
+    let lead_apps = appmeta::AppMetaFile::new(&fetch_app_info(leader).await?);
+    let follow_apps = appmeta::AppMetaFile::new(&fetch_app_info(follower).await?);
+    if serde_json::to_value(lead_apps)? != serde_json::to_value(follow_apps)? {
+        anyhow::bail!("The units report different apps; update both to the same firmware");
+    }
     Ok(())
 }
 
-async fn cmd_load(path: &str) -> Result<()> {
-    let data = std::fs::read_to_string(path)?;
-    let snapshot: serde_json::Value = serde_json::from_str(&data)?;
+/// Make the follower's layout and params match the leader's. Its global
+/// config is left alone.
+async fn mirror_once(leader: &mut FaderpunkDevice, follower: &mut FaderpunkDevice) -> Result<()> {
+    let current = fetch_snapshot(follower).await?;
+    let desired = Snapshot {
+        global_config: current.global_config.clone(),
+        ..fetch_snapshot(leader).await?
+    };
+    let diffs = current.diff(&desired)?;
+    if diffs.is_empty() {
+        return Ok(());
+    }
+    println!("{} leader → follower:", timestamp());
+    for diff in &diffs {
+        println!("  {} {}", diff.symbol(), diff);
+    }
+    apply_differences(follower, &current, &desired, &diffs).await
+}
+
+// ── Daemon ──
+
+async fn cmd_daemon(action: DaemonAction) -> Result<()> {
+    match action {
+        DaemonAction::InstallService {
+            name,
+            print,
+            force,
+            command,
+        } => {
+            if !service::is_long_running(&command) {
+                anyhow::bail!(
+                    "'{}' exits right away; a service should run one of: {}",
+                    command.join(" "),
+                    service::LONG_RUNNING.join(", ")
+                );
+            }
+            // The service doesn't start in this directory, so pin down file arguments
+            let args: Vec<String> = command
+                .iter()
+                .map(|arg| match Path::new(arg).canonicalize() {
+                    Ok(abs) if !arg.starts_with('-') => abs.to_string_lossy().into_owned(),
+                    _ => arg.clone(),
+                })
+                .collect();
+            let exe = std::env::current_exe().context("Could not locate the faderpunk-cli binary")?;
+            let unit = service::unit_file(&name, &exe, &args);
+            if print {
+                print!("{}", unit);
+                return Ok(());
+            }
+
+            let path = service::unit_path(&name)?;
+            if path.exists() && !force {
+                anyhow::bail!("{} already exists (use --force to replace it)", path.display());
+            }
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)
+                    .with_context(|| format!("Failed to create {}", dir.display()))?;
+            }
+            std::fs::write(&path, unit)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            println!("Service written to {}", path.display());
+            println!("Start it now and at every login with:");
+            println!("  {}", service::enable_hint(&name, &path));
+        }
+        DaemonAction::UninstallService { name } => {
+            let path = service::unit_path(&name)?;
+            if !path.exists() {
+                anyhow::bail!("No service named '{}' ({} not found)", name, path.display());
+            }
+            println!("Stop the service first if it's running:");
+            println!("  {}", service::disable_hint(&name, &path));
+            std::fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove {}", path.display()))?;
+            println!("Removed {}", path.display());
+        }
+        DaemonAction::Mirror { interval } => daemon_mirror(interval).await?,
+    }
+    Ok(())
+}
+
+/// How long `daemon mirror` lets a burst of events (faders being moved)
+/// settle before re-reading the device.
+const MIRROR_SETTLE: Duration = Duration::from_millis(250);
+
+/// Keep the state mirror current: refreshed after device events, and
+/// every `interval` seconds for changes that don't send one. Its revision
+/// goes up by one whenever a refresh finds the state changed, carrying on
+/// from the mirror an earlier run left.
+async fn daemon_mirror(interval: u64) -> Result<()> {
+    let mut conn = live::LiveConnection::open().await?;
+    let mut events = conn.device().subscribe();
+    let mut ticker = tokio::time::interval(Duration::from_secs(interval.max(1)));
+    println!(
+        "Mirroring the device state to {} (Ctrl-C to stop)...",
+        mirror::path()?.display()
+    );
+    service::notify_ready();
+
+    // (fingerprint, revision) of what the mirror holds
+    let mut last = match mirror::read().ok().flatten() {
+        Some(snapshot) => Some((snapshot.fingerprint()?, snapshot.metadata.revision.unwrap_or(0))),
+        None => None,
+    };
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {
+                if conn.check().await? {
+                    events = conn.device().subscribe();
+                }
+            }
+            event = events.recv() => {
+                if let Err(tokio::sync::broadcast::error::RecvError::Closed) = event
+                    && conn.check().await?
+                {
+                    events = conn.device().subscribe();
+                }
+                tokio::time::sleep(MIRROR_SETTLE).await;
+                while events.try_recv().is_ok() {}
+                conn.device().clear_cache();
+            }
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+        let mut snapshot = fetch_snapshot(conn.device()).await?;
+        let fingerprint = snapshot.fingerprint()?;
+        let revision = match &last {
+            Some((seen, revision)) if *seen == fingerprint => *revision,
+            Some((_, revision)) => revision + 1,
+            None => 1,
+        };
+        snapshot.metadata.revision = Some(revision);
+        mirror::write(&snapshot)?;
+        last = Some((fingerprint, revision));
+    }
+}
+
+// ── Profiles ──
 
+async fn cmd_profile(action: ProfileAction) -> Result<()> {
+    match action {
+        ProfileAction::List => {
+            let names = profile::list()?;
+            if names.is_empty() {
+                println!("{}", t!("No saved profiles. Use 'profile save <name>' to create one."));
+            }
+            for name in names {
+                println!("{}", name);
+            }
+        }
+        ProfileAction::Save { name } => {
+            let path = profile::path(&name)?;
+            let mut dev = FaderpunkDevice::open()?;
+            save_snapshot(&mut dev, &path).await?;
+            println!("{}", t!("Profile '{}' saved", name));
+        }
+        ProfileAction::Load { name, vars } => {
+            let snapshot = Snapshot::read(&profile::existing(&name)?, &vars)?;
+            let mut dev = FaderpunkDevice::open()?;
+            apply_snapshot(&mut dev, &snapshot).await?;
+            restore_groups(&snapshot)?;
+            println!("{}", t!("Profile '{}' loaded", name));
+        }
+        ProfileAction::Migrate { name, dry_run } => profile_migrate(&name, dry_run).await?,
+        ProfileAction::Delete { name } => {
+            std::fs::remove_file(profile::existing(&name)?)?;
+            println!("{}", t!("Profile '{}' deleted", name));
+        }
+    }
+    Ok(())
+}
+
+/// Remap a profile's apps by name to the installed firmware's app IDs, and fit
+/// each app's saved params to its current param list.
+async fn profile_migrate(name: &str, dry_run: bool) -> Result<()> {
+    let path = profile::existing(name)?;
+    let mut snapshot = Snapshot::read(&path, &[])?;
     let mut dev = FaderpunkDevice::open()?;
+    let app_info = fetch_app_info(&mut dev).await?;
+    let mut notes: Vec<String> = Vec::new();
 
-    if let Some(config_val) = snapshot.get("global_config") {
-        let config: protocol::GlobalConfig = serde_json::from_value(config_val.clone())?;
-        dev.send(&ConfigMsgIn::SetGlobalConfig(config)).await?;
-        println!("Global config applied.");
+    for i in 0..GLOBAL_CHANNELS {
+        let Some((app_id, channels, layout_id)) = snapshot.layout.0[i] else {
+            continue;
+        };
+        let saved_name = snapshot
+            .metadata
+            .apps
+            .iter()
+            .find(|a| a.app_id == app_id)
+            .map(|a| a.name.clone());
+        let app = match &saved_name {
+            Some(n) => app_info.iter().find(|a| a.name.eq_ignore_ascii_case(n)),
+            None => app_info.iter().find(|a| a.app_id == app_id),
+        };
+        let label = saved_name.unwrap_or_else(|| format!("app {}", app_id));
+        let Some(app) = app else {
+            notes.push(format!("fader {}: {} is not installed; removed", slots::label(i), label));
+            snapshot.layout.0[i] = None;
+            snapshot.params.retain(|p| p.layout_id != layout_id);
+            continue;
+        };
+
+        if app.app_id != app_id {
+            notes.push(format!(
+                "fader {}: {} moved from app ID {} to {}",
+                slots::label(i),
+                app.name,
+                app_id,
+                app.app_id
+            ));
+        }
+        if app.channels != channels {
+            notes.push(format!(
+                "fader {}: {} now uses {} channel(s) instead of {}",
+                slots::label(i),
+                app.name,
+                app.channels,
+                channels
+            ));
+        }
+        snapshot.layout.0[i] = Some((app.app_id, app.channels, layout_id));
+
+        if let Some(saved) = snapshot.params.iter_mut().find(|p| p.layout_id == layout_id) {
+            for note in fit_params(&mut saved.values, &app.params) {
+                notes.push(format!("fader {}: {}: {}", slots::label(i), app.name, note));
+            }
+        }
+    }
+
+    layout::validate(&snapshot.layout, &app_info).context(
+        "The migrated layout doesn't fit; edit the profile's layout by hand or re-save it",
+    )?;
+
+    if notes.is_empty() {
+        println!("Profile '{}' already matches the installed apps", name);
+        return Ok(());
+    }
+    for note in &notes {
+        println!("  {}", note);
+    }
+    if dry_run {
+        println!("Dry run: profile '{}' not changed", name);
+        return Ok(());
     }
 
-    if let Some(layout_val) = snapshot.get("layout") {
-        let layout: protocol::Layout = serde_json::from_value(layout_val.clone())?;
-        let resp = dev.send_receive(&ConfigMsgIn::SetLayout(layout)).await?;
-        if let ConfigMsgOut::Layout(_) = resp {
-            println!("Layout applied.");
+    let mut apps: Vec<snapshot::AppRef> = Vec::new();
+    for entry in layout_entries(&snapshot.layout) {
+        if let Some(info) = app_info.iter().find(|a| a.app_id == entry.app_id)
+            && !apps.iter().any(|a| a.app_id == info.app_id)
+        {
+            apps.push(snapshot::AppRef {
+                app_id: info.app_id,
+                name: info.name.clone(),
+                channels: info.channels,
+            });
         }
     }
+    snapshot.metadata = snapshot::Metadata {
+        groups: std::mem::take(&mut snapshot.metadata.groups),
+        notes: std::mem::take(&mut snapshot.metadata.notes),
+        ..snapshot::Metadata::now(apps)
+    };
+    snapshot.version = snapshot::CURRENT_VERSION;
 
-    println!("Config loaded from {}", path);
+    let backup = path.with_extension("json.bak");
+    std::fs::copy(&path, &backup)
+        .with_context(|| format!("Failed to back up {}", path.display()))?;
+    snapshot.write(&path)?;
+    println!(
+        "Profile '{}' migrated (previous version kept as {})",
+        name,
+        backup.display()
+    );
     Ok(())
 }
+
+/// Make saved param values fit an app's current param list: values whose type
+/// no longer matches are reset, missing ones are filled with defaults, extra
+/// ones are dropped. Returns a note for each adjustment.
+fn fit_params(values: &mut Vec<Value>, params: &[Param]) -> Vec<String> {
+    let mut notes = Vec::new();
+    if values.len() > params.len() {
+        notes.push(format!(
+            "dropped {} param value(s) the app no longer has",
+            values.len() - params.len()
+        ));
+        values.truncate(params.len());
+    }
+    for (i, param) in params.iter().enumerate() {
+        let label = display::param_label(Some(param), i);
+        match values.get(i) {
+            Some(v) if value_fits(v, param) => {}
+            Some(_) => {
+                if let Some(default) = default_value(param) {
+                    notes.push(format!(
+                        "{} changed type; reset to {}",
+                        label,
+                        display::format_value(&default, Some(param))
+                    ));
+                    values[i] = default;
+                }
+            }
+            None => {
+                let Some(default) = default_value(param) else {
+                    // Leave the rest unset; the device keeps its own values
+                    break;
+                };
+                notes.push(format!(
+                    "{} is new; set to {}",
+                    label,
+                    display::format_value(&default, Some(param))
+                ));
+                values.push(default);
+            }
+        }
+    }
+    notes
+}
+
+// ── Init ──
+
+async fn cmd_init() -> Result<()> {
+    let mut dev = FaderpunkDevice::open()?;
+    match dev.send_receive(&ConfigMsgIn::Ping).await? {
+        ConfigMsgOut::Pong => println!("Found Faderpunk. Press Enter to keep the value in brackets."),
+        other => return Err(error::unexpected(&ConfigMsgIn::Ping, &other)),
+    }
+    println!();
+
+    let mut config = match dev.send_receive(&ConfigMsgIn::GetGlobalConfig).await? {
+        ConfigMsgOut::GlobalConfig(c) => c,
+        other => return Err(error::unexpected(&ConfigMsgIn::GetGlobalConfig, &other)),
+    };
+    let app_info = fetch_app_info(&mut dev).await?;
+
+    config.clock.clock_src = ask(
+        "Clock source (internal, midiusb, midiin, atom, meteor, cube, none)?",
+        &format!("{:?}", config.clock.clock_src).to_lowercase(),
+        parse_clock_src,
+    )?;
+    if config.clock.clock_src == protocol::ClockSrc::Internal {
+        config.clock.internal_bpm = ask("BPM?", &config.clock.internal_bpm.to_string(), |s| {
+            let v = parse_float(s)?;
+            LIMITS.check_bpm(v)?;
+            Ok(v)
+        })?;
+    }
+
+    let current_ports = |pick: fn(&protocol::MidiOutConfig) -> bool| {
+        let labels = ["usb", "out1", "out2"];
+        let on: Vec<_> = config
+            .midi
+            .outs
+            .iter()
+            .zip(labels)
+            .filter(|(o, _)| pick(o))
+            .map(|(_, l)| l)
+            .collect();
+        if on.is_empty() { "none".to_string() } else { on.join("+") }
+    };
+    let clock_ports = ask(
+        "Send MIDI clock on (usb, out1, out2, all, none)?",
+        &current_ports(|o| o.send_clock),
+        parse_midi_ports_out,
+    )?;
+    let transport_ports = ask(
+        "Send MIDI transport on (usb, out1, out2, all, none)?",
+        &current_ports(|o| o.send_transport),
+        parse_midi_ports_out,
+    )?;
+    let clock_ports = [clock_ports.0, clock_ports.1, clock_ports.2];
+    let transport_ports = [transport_ports.0, transport_ports.1, transport_ports.2];
+    for (i, out) in config.midi.outs.iter_mut().enumerate() {
+        out.send_clock = clock_ports[i];
+        out.send_transport = transport_ports[i];
+    }
+
+    config.quantizer.key = ask(
+        "Quantizer key (e.g. chromatic, ionian, pentatonicmin, off)?",
+        &format!("{:?}", config.quantizer.key).to_lowercase(),
+        parse_key,
+    )?;
+
+    let favorites = favorites::Favorites::load()?;
+    if !favorites.is_empty() {
+        let usual: Vec<String> = favorites
+            .order(&app_info)
+            .into_iter()
+            .filter_map(|(app, mark)| mark.map(|m| format!("{} {}", m.symbol(), app.name)))
+            .collect();
+        println!("Your usual apps: {}", usual.join(", "));
+    }
+    let layout = ask(
+        "Starting layout (app names separated by spaces, or 'keep')?",
+        "keep",
+        |s| {
+            if s.eq_ignore_ascii_case("keep") {
+                return Ok(None);
+            }
+            let apps = s
+                .split_whitespace()
+                .map(|name| resolve_app(name, &app_info))
+                .collect::<Result<Vec<_>>>()?;
+            layout::pack(None, &apps).map(Some)
+        },
+    )?;
+
+    println!();
+    dev.send(&ConfigMsgIn::SetGlobalConfig(config)).await?;
+    println!("{}", t!("Global config applied."));
+    if let Some(layout) = layout {
+        let ids: Vec<u8> = layout.0.iter().flatten().map(|(id, ..)| *id).collect();
+        let validated = send_layout(&mut dev, layout, &app_info).await?;
+        record_placed(&ids, &app_info);
+        println!("{}", t!("Layout applied."));
+        println!();
+        display::print_layout(&validated, Some(&app_info), &groups::load()?, &notes::load()?);
+        println!();
+    }
+
+    let path = profile::path(profile::DEFAULT)?;
+    save_snapshot(&mut dev, &path).await?;
+    println!(
+        "Saved as profile '{}'. Recall it any time with 'profile load {}'.",
+        profile::DEFAULT,
+        profile::DEFAULT
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bad_input_exits_invalid() {
+        let errors = [
+            validate_slot(17).unwrap_err(),
+            parse_clock_src("sundial").unwrap_err(),
+        ];
+        for err in &errors {
+            assert_eq!(exitcode::of(err), exitcode::INVALID, "{}", err);
+        }
+    }
+}
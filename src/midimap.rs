@@ -0,0 +1,220 @@
+// Controller mappings read from DAW projects, for `midi import`: which CC
+// (and channel) each mapped control listens to, so the faders can be set up
+// to send exactly those.
+
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+
+use crate::slots;
+
+/// One mapped control: the device should send `cc` on `channel` for it.
+pub struct CcMapping {
+    /// Fader to put it on; None means the next free one.
+    pub slot: Option<u8>,
+    /// MIDI channel (1-16).
+    pub channel: u8,
+    pub cc: u16,
+    /// What it controls in the DAW, when the file says.
+    pub name: Option<String>,
+}
+
+#[derive(Clone, Copy)]
+pub enum Format {
+    /// An Ableton Live set (.als, or its XML uncompressed)
+    AbletonXml,
+    /// CSV with a header naming the columns: cc, and optionally channel, slot, name
+    GenericCsv,
+}
+
+pub fn parse_format(s: &str) -> Result<Format> {
+    match s.to_lowercase().as_str() {
+        "ableton-xml" | "ableton" => Ok(Format::AbletonXml),
+        "generic-csv" | "csv" => Ok(Format::GenericCsv),
+        _ => bail!(
+            "Unknown mapping format '{}' (use: ableton-xml, generic-csv)",
+            s
+        ),
+    }
+}
+
+pub fn read(path: &Path, format: Format) -> Result<Vec<CcMapping>> {
+    let data = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let mappings = match format {
+        Format::AbletonXml => parse_ableton(&data),
+        Format::GenericCsv => parse_csv(&String::from_utf8_lossy(&data)),
+    }
+    .with_context(|| format!("Invalid mapping file {}", path.display()))?;
+    if mappings.is_empty() {
+        bail!("No CC mappings found in {}", path.display());
+    }
+    Ok(mappings)
+}
+
+// ── Ableton ──
+
+/// Live stores each parameter's MIDI mapping in a `<KeyMidi>` element inside
+/// the parameter's own element:
+///
+/// ```xml
+/// <Volume>
+///   <KeyMidi>
+///     <IsNote Value="false" />
+///     <Channel Value="0" />
+///     <NoteOrController Value="7" />
+///   </KeyMidi>
+/// </Volume>
+/// ```
+///
+/// Note mappings and unmapped entries (channel or controller out of range)
+/// are skipped. A set saved by Live is gzipped XML; both forms are read.
+fn parse_ableton(data: &[u8]) -> Result<Vec<CcMapping>> {
+    let xml = if data.starts_with(&[0x1f, 0x8b]) {
+        let mut xml = String::new();
+        flate2::read::GzDecoder::new(data)
+            .read_to_string(&mut xml)
+            .context("Failed to decompress the Live set")?;
+        xml
+    } else {
+        String::from_utf8_lossy(data).into_owned()
+    };
+
+    use xmlparser::Token;
+    let mut mappings = Vec::new();
+    // Open elements, innermost last
+    let mut path: Vec<String> = Vec::new();
+    // Inside a KeyMidi: (is_note, channel, controller, owner)
+    let mut current: Option<(bool, i64, i64, Option<String>)> = None;
+    let mut element = String::new();
+
+    for token in xmlparser::Tokenizer::from(xml.as_str()) {
+        match token.context("Malformed XML")? {
+            Token::ElementStart { local, .. } => {
+                element = local.to_string();
+                if element == "KeyMidi" {
+                    current = Some((false, -1, -1, path.last().cloned()));
+                }
+            }
+            Token::Attribute { local, value, .. } if local.as_str() == "Value" => {
+                if let Some((is_note, channel, controller, _)) = &mut current {
+                    match element.as_str() {
+                        "IsNote" => *is_note = value.as_str() == "true",
+                        "Channel" => *channel = value.as_str().parse().unwrap_or(-1),
+                        "NoteOrController" => *controller = value.as_str().parse().unwrap_or(-1),
+                        _ => {}
+                    }
+                }
+            }
+            Token::ElementEnd { end, .. } => match end {
+                xmlparser::ElementEnd::Open => path.push(std::mem::take(&mut element)),
+                xmlparser::ElementEnd::Close(_, local) => {
+                    path.pop();
+                    if local.as_str() == "KeyMidi"
+                        && let Some((is_note, channel, controller, owner)) = current.take()
+                        && !is_note
+                        && (0..16).contains(&channel)
+                        && (0..128).contains(&controller)
+                    {
+                        mappings.push(CcMapping {
+                            slot: None,
+                            channel: channel as u8 + 1,
+                            cc: controller as u16,
+                            name: owner,
+                        });
+                    }
+                }
+                xmlparser::ElementEnd::Empty => {}
+            },
+            _ => {}
+        }
+    }
+    // The same control can be mapped in several places (e.g. a rack macro
+    // and the parameter behind it); one fader is enough
+    let mut seen = Vec::new();
+    mappings.retain(|m| {
+        let key = (m.channel, m.cc);
+        let new = !seen.contains(&key);
+        seen.push(key);
+        new
+    });
+    Ok(mappings)
+}
+
+// ── CSV ──
+
+/// CSV with a header row, e.g.
+///
+/// ```text
+/// slot,channel,cc,name
+/// 1,1,20,Filter cutoff
+/// ,1,21,Resonance
+/// ```
+///
+/// Only `cc` is required; `channel` defaults to 1 and a missing `slot` means
+/// the next free fader. Blank lines and lines starting with `#` are ignored.
+fn parse_csv(text: &str) -> Result<Vec<CcMapping>> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, l)| (i + 1, l.trim()))
+        .filter(|(_, l)| !l.is_empty() && !l.starts_with('#'));
+
+    let (_, header) = lines.next().context("Empty file")?;
+    let columns: Vec<String> = split_csv(header).iter().map(|c| c.to_lowercase()).collect();
+    let column = |name: &str| columns.iter().position(|c| c == name);
+    let cc_col = column("cc").context("Header has no 'cc' column")?;
+    let (channel_col, slot_col, name_col) = (column("channel"), column("slot"), column("name"));
+
+    let mut mappings = Vec::new();
+    for (line, row) in lines {
+        let fields = split_csv(row);
+        let field = |col: Option<usize>| {
+            col.and_then(|c| fields.get(c))
+                .map(String::as_str)
+                .filter(|f| !f.is_empty())
+        };
+        let cc: u16 = field(Some(cc_col))
+            .and_then(|f| f.parse().ok())
+            .filter(|cc| *cc < 128)
+            .with_context(|| format!("line {}: cc must be 0-127", line))?;
+        let channel: u8 = match field(channel_col) {
+            Some(f) => f
+                .parse()
+                .ok()
+                .filter(|ch| (1..=16).contains(ch))
+                .with_context(|| format!("line {}: channel must be 1-16", line))?,
+            None => 1,
+        };
+        let slot = field(slot_col)
+            .map(slots::parse)
+            .transpose()
+            .with_context(|| format!("line {}", line))?;
+        mappings.push(CcMapping {
+            slot,
+            channel,
+            cc,
+            name: field(name_col).map(str::to_string),
+        });
+    }
+    Ok(mappings)
+}
+
+/// Split a CSV row, honouring double quotes around fields with commas.
+fn split_csv(row: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = row.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields.iter().map(|f| f.trim().to_string()).collect()
+}
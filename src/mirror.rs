@@ -0,0 +1,44 @@
+// State mirror: `daemon mirror` keeps a copy of the device state in
+// `<data dir>/mirror.json`, in the snapshot format, so other local tools can
+// read the rig's configuration without USB traffic and `status --cached`
+// works with the device unplugged.
+//
+// Written to a temporary file and renamed over the old one, so readers
+// never see half a file.
+
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+
+use crate::paths;
+use crate::snapshot::Snapshot;
+
+const MIRROR_FILE: &str = "mirror.json";
+
+pub fn path() -> Result<PathBuf> {
+    Ok(paths::data_dir()?.join(MIRROR_FILE))
+}
+
+/// Replace the mirror with `snapshot`.
+pub fn write(snapshot: &Snapshot) -> Result<()> {
+    let path = path()?;
+    let tmp = path.with_extension("json.tmp");
+    snapshot.write(&tmp)?;
+    std::fs::rename(&tmp, &path).with_context(|| format!("Failed to replace {}", path.display()))
+}
+
+/// The mirrored state, or None if no mirror has been written yet.
+pub fn read() -> Result<Option<Snapshot>> {
+    let path = path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    Snapshot::read(&path, &[]).map(Some)
+}
+
+/// How long ago a snapshot was taken, if it says.
+pub fn age(snapshot: &Snapshot) -> Option<Duration> {
+    let saved_at = humantime::parse_rfc3339(snapshot.metadata.saved_at.as_deref()?).ok()?;
+    SystemTime::now().duration_since(saved_at).ok()
+}
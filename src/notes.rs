@@ -0,0 +1,79 @@
+// Free-text notes on faders ("send to delay return", "kick probability"), so
+// a rig file documents itself.
+//
+// Like groups, they're unknown to the device. The current ones live in
+// `<data dir>/notes.json`; snapshots and profiles carry a copy in their
+// metadata, and loading one brings its notes back.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+
+use crate::layout::AppInfo;
+use crate::paths;
+use crate::protocol::Layout;
+use crate::slots;
+
+const NOTES_FILE: &str = "notes.json";
+
+/// Fader slot (firmware number) → note.
+pub type Notes = BTreeMap<u8, String>;
+
+fn path() -> Result<PathBuf> {
+    Ok(paths::data_dir()?.join(NOTES_FILE))
+}
+
+/// The current notes. A missing file means none.
+pub fn load() -> Result<Notes> {
+    let path = path()?;
+    let data = match std::fs::read_to_string(&path) {
+        Ok(d) => d,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Notes::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    serde_json::from_str(&data).with_context(|| format!("Invalid notes in {}", path.display()))
+}
+
+pub fn save(notes: &Notes) -> Result<()> {
+    let path = path()?;
+    std::fs::write(&path, serde_json::to_string_pretty(notes)? + "\n")
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Notes on the faders `start..start + size` (0-based), joined, for an app
+/// that spans them.
+pub fn on(notes: &Notes, start: usize, size: usize) -> Option<String> {
+    let mut texts: Vec<&str> = (start..start + size)
+        .filter_map(|i| notes.get(&(i as u8 + 1)))
+        .map(String::as_str)
+        .collect();
+    // One note set on all of an app's faders reads once
+    texts.dedup();
+    (!texts.is_empty()).then(|| texts.join("; "))
+}
+
+/// (fader, app on it, note) for every note, in fader order. Faders with no
+/// app have an empty app name.
+pub fn rows(notes: &Notes, layout: &Layout, apps: &[AppInfo]) -> Vec<(String, String, String)> {
+    notes
+        .iter()
+        .map(|(&slot, text)| {
+            let index = slot as usize - 1;
+            let app = layout
+                .0
+                .iter()
+                .enumerate()
+                .find_map(|(start, entry)| match entry {
+                    Some((app_id, ch, _)) if (start..start + ch).contains(&index) => Some(*app_id),
+                    _ => None,
+                })
+                .map(|app_id| match apps.iter().find(|a| a.app_id == app_id) {
+                    Some(info) => info.name.clone(),
+                    None => format!("App {}", app_id),
+                })
+                .unwrap_or_default();
+            (slots::label(index), app, text.clone())
+        })
+        .collect()
+}
@@ -0,0 +1,87 @@
+// Send long output through a pager (like git does) when stdout is a terminal.
+//
+// The pager's stdin replaces our stdout at the file descriptor level, so every
+// println! in the program is paged without threading a writer around.
+
+use std::io::{IsTerminal, Write};
+use std::process::{Child, Command, Stdio};
+
+const DEFAULT_PAGER: &str = "less";
+
+/// Keeps the pager running; dropping it flushes output and waits for the user to quit.
+pub struct Pager {
+    child: Child,
+}
+
+/// Start a pager if stdout is a terminal. `command` overrides $PAGER.
+/// Returns None (and output goes straight to the terminal) if no pager can be started.
+pub fn start(command: Option<&str>) -> Option<Pager> {
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+
+    let command = command
+        .map(str::to_string)
+        .or_else(|| std::env::var("PAGER").ok())
+        .unwrap_or_else(|| DEFAULT_PAGER.to_string());
+    let mut parts = command.split_whitespace();
+    let program = parts.next()?;
+    if program == "cat" {
+        return None;
+    }
+
+    let mut cmd = Command::new(program);
+    cmd.args(parts).stdin(Stdio::piped());
+    // Quit if it fits on one screen, keep colors, don't clear the screen on exit
+    if std::env::var_os("LESS").is_none() {
+        cmd.env("LESS", "FRX");
+    }
+
+    let mut child = cmd.spawn().ok()?;
+    let stdin = child.stdin.take()?;
+    if !redirect_stdout(stdin) {
+        let _ = child.kill();
+        return None;
+    }
+    Some(Pager { child })
+}
+
+#[cfg(unix)]
+fn redirect_stdout(pipe: std::process::ChildStdin) -> bool {
+    use std::os::fd::AsRawFd;
+    // SAFETY: both descriptors are valid; dup2 atomically replaces fd 1.
+    // Restoring the default SIGPIPE action makes us exit quietly when the user
+    // quits the pager early, instead of panicking in println!.
+    let ok = unsafe {
+        libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+        libc::dup2(pipe.as_raw_fd(), libc::STDOUT_FILENO) != -1
+    };
+    // The pipe now lives on as fd 1; our copy can close
+    drop(pipe);
+    ok
+}
+
+#[cfg(not(unix))]
+fn redirect_stdout(_pipe: std::process::ChildStdin) -> bool {
+    false
+}
+
+impl Drop for Pager {
+    fn drop(&mut self) {
+        let _ = std::io::stdout().flush();
+        close_stdout();
+        let _ = self.child.wait();
+    }
+}
+
+/// Close our end of the pipe so the pager sees EOF.
+#[cfg(unix)]
+fn close_stdout() {
+    // SAFETY: closing fd 1 at shutdown; nothing writes to stdout afterwards
+    unsafe {
+        libc::close(libc::STDOUT_FILENO);
+    }
+}
+
+#[cfg(not(unix))]
+fn close_stdout() {}
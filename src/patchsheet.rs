@@ -0,0 +1,287 @@
+// One-page summary of the physical connections the device config implies
+// (export patchsheet): what each aux jack is for, where MIDI goes in and
+// out, the I2C role, and the output range of every fader's jack. Plain text
+// for printing and taping to the case, or Markdown.
+
+use crate::clocksim;
+use crate::display;
+use crate::notes;
+use crate::protocol::{
+    AuxJackMode, ClockSrc, GlobalConfig, I2cMode, MidiIn, MidiOutMode, ResetSrc, Value,
+};
+use crate::slots;
+use crate::snapshot::Snapshot;
+
+/// Output format, picked from the file extension.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Markdown,
+}
+
+impl Format {
+    pub fn for_path(path: &str) -> Self {
+        let lower = path.to_lowercase();
+        if lower.ends_with(".md") || lower.ends_with(".markdown") {
+            Format::Markdown
+        } else {
+            Format::Text
+        }
+    }
+}
+
+const AUX_JACKS: [&str; 3] = ["Atom", "Meteor", "Cube"];
+
+/// A titled table: header row, then body rows of the same width.
+struct Section {
+    title: &'static str,
+    rows: Vec<Vec<String>>,
+}
+
+/// The sheet for a device state, from the device or a snapshot file;
+/// `source` says which in the header. App names and notes come from the
+/// snapshot's metadata.
+pub fn render(state: &Snapshot, source: &str, format: Format) -> String {
+    let sections = [
+        clock(&state.global_config),
+        aux(&state.global_config),
+        midi(&state.global_config),
+        i2c(&state.global_config),
+        faders(state),
+    ];
+    match format {
+        Format::Text => text(&sections, source),
+        Format::Markdown => markdown(&sections, source),
+    }
+}
+
+fn generated_note(source: &str) -> String {
+    format!(
+        "From {}, by faderpunk-cli {} on {}.",
+        source,
+        env!("CARGO_PKG_VERSION"),
+        humantime::format_rfc3339_seconds(std::time::SystemTime::now())
+    )
+}
+
+fn text(sections: &[Section], source: &str) -> String {
+    let mut out = format!("FADERPUNK PATCH SHEET\n{}\n", generated_note(source));
+    for section in sections {
+        out += &format!("\n{}\n{}\n", section.title, "=".repeat(section.title.len()));
+        let columns = section.rows.iter().map(Vec::len).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..columns)
+            .map(|c| {
+                let cells = section.rows.iter().filter_map(|row| row.get(c));
+                cells.map(|cell| cell.chars().count()).max().unwrap_or(0)
+            })
+            .collect();
+        for (i, row) in section.rows.iter().enumerate() {
+            let line: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect();
+            out += line.join("  ").trim_end();
+            out += "\n";
+            if i == 0 {
+                let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+                out += &rule.join("  ");
+                out += "\n";
+            }
+        }
+    }
+    out
+}
+
+fn markdown(sections: &[Section], source: &str) -> String {
+    // Pipes would end a table cell early
+    let cell = |s: &String| s.replace('|', "\\|");
+
+    let mut out = format!("# Faderpunk patch sheet\n\n{}\n", generated_note(source));
+    for section in sections {
+        out += &format!("\n## {}\n\n", section.title);
+        for (i, row) in section.rows.iter().enumerate() {
+            let cells: Vec<String> = row.iter().map(cell).collect();
+            out += &format!("| {} |\n", cells.join(" | "));
+            if i == 0 {
+                out += &format!("|{}\n", "---|".repeat(row.len()));
+            }
+        }
+    }
+    out
+}
+
+fn row(cells: &[&str]) -> Vec<String> {
+    cells.iter().map(|c| c.to_string()).collect()
+}
+
+fn clock(config: &GlobalConfig) -> Section {
+    let clock = &config.clock;
+    let source = match clock.clock_src {
+        ClockSrc::Internal => format!("Internal, {} BPM", clock.internal_bpm),
+        ClockSrc::MidiUsb => "MIDI clock over USB".to_string(),
+        ClockSrc::MidiIn => "MIDI clock on the DIN input".to_string(),
+        ClockSrc::Atom | ClockSrc::Meteor | ClockSrc::Cube => {
+            format!("{:?} jack, {} PPQN", clock.clock_src, clock.ext_ppqn)
+        }
+        ClockSrc::None => "None (stopped)".to_string(),
+    };
+    let reset = match clock.reset_src {
+        ResetSrc::None => "None".to_string(),
+        jack => format!("{:?} jack", jack),
+    };
+    let mut rows = vec![
+        row(&["", "Setting"]),
+        row(&["Clock in", &source]),
+        row(&["Reset in", &reset]),
+    ];
+    if clock.swing_amount != 0 {
+        rows.push(row(&["Swing", &clock.swing_amount.to_string()]));
+    }
+    Section {
+        title: "Clock",
+        rows,
+    }
+}
+
+fn aux(config: &GlobalConfig) -> Section {
+    let clock_in = match config.clock.clock_src {
+        ClockSrc::Atom => Some(0),
+        ClockSrc::Meteor => Some(1),
+        ClockSrc::Cube => Some(2),
+        _ => None,
+    };
+    let reset_in = match config.clock.reset_src {
+        ResetSrc::Atom => Some(0),
+        ResetSrc::Meteor => Some(1),
+        ResetSrc::Cube => Some(2),
+        ResetSrc::None => None,
+    };
+    let mut rows = vec![row(&["Jack", "Direction", "Role"])];
+    for (i, mode) in config.aux.iter().enumerate() {
+        // A jack can be named as both an input and an output; list every role
+        let mut roles = Vec::new();
+        if clock_in == Some(i) {
+            roles.push(("in", format!("Clock, {} PPQN", config.clock.ext_ppqn)));
+        }
+        if reset_in == Some(i) {
+            roles.push(("in", "Reset".to_string()));
+        }
+        match mode {
+            AuxJackMode::ClockOut(div) => roles.push((
+                "out",
+                format!(
+                    "Clock ÷{} ({})",
+                    clocksim::ticks(*div),
+                    clocksim::note_value(*div)
+                ),
+            )),
+            AuxJackMode::ResetOut => roles.push(("out", "Reset".to_string())),
+            AuxJackMode::None => {}
+        }
+        if roles.is_empty() {
+            roles.push(("", "Unused".to_string()));
+        }
+        for (direction, role) in roles {
+            rows.push(vec![AUX_JACKS[i].to_string(), direction.to_string(), role]);
+        }
+    }
+    Section {
+        title: "Aux jacks",
+        rows,
+    }
+}
+
+fn ports(sources: &MidiIn) -> String {
+    match sources.0 {
+        [true, true] => "USB and DIN in".to_string(),
+        [true, false] => "USB".to_string(),
+        [false, true] => "DIN in".to_string(),
+        [false, false] => "nothing".to_string(),
+    }
+}
+
+fn midi(config: &GlobalConfig) -> Section {
+    let labels = ["USB", "DIN out 1", "DIN out 2"];
+    let mut rows = vec![row(&["Port", "Sends", "Clock", "Transport"])];
+    let yes_no = |on: bool| if on { "yes" } else { "no" }.to_string();
+    for (out, label) in config.midi.outs.iter().zip(labels) {
+        let sends = match out.mode {
+            MidiOutMode::None => "Nothing".to_string(),
+            MidiOutMode::Local => "Apps".to_string(),
+            MidiOutMode::MidiThru { sources } => format!("Thru from {}", ports(&sources)),
+            MidiOutMode::MidiMerge { sources } => format!("Apps, merged with {}", ports(&sources)),
+        };
+        rows.push(vec![
+            label.to_string(),
+            sends,
+            yes_no(out.send_clock),
+            yes_no(out.send_transport),
+        ]);
+    }
+    let din_in = config.clock.clock_src == ClockSrc::MidiIn
+        || config.midi.outs.iter().any(|out| match out.mode {
+            MidiOutMode::MidiThru { sources } | MidiOutMode::MidiMerge { sources } => sources.0[1],
+            _ => false,
+        });
+    let din_in_use = if din_in { "Used" } else { "Unused" };
+    rows.push(row(&["DIN in", din_in_use, "", ""]));
+    Section {
+        title: "MIDI",
+        rows,
+    }
+}
+
+fn i2c(config: &GlobalConfig) -> Section {
+    let role = match config.i2c_mode {
+        I2cMode::Leader => "Leader: drives followers on the I2C bus",
+        I2cMode::Follower => "Follower: controlled by a leader (e.g. Teletype)",
+        I2cMode::Calibration => "Calibration mode",
+    };
+    Section {
+        title: "I2C",
+        rows: vec![row(&["", "Setting"]), row(&["Role", role])],
+    }
+}
+
+/// Every app in the layout, with its jacks' output ranges and MIDI.
+fn faders(state: &Snapshot) -> Section {
+    let mut rows = vec![row(&["Faders", "App", "Range", "MIDI", "Note"])];
+    for (start, entry) in state.layout.0.iter().enumerate() {
+        let Some((app_id, channels, layout_id)) = *entry else {
+            continue;
+        };
+        let name = match state.metadata.apps.iter().find(|a| a.app_id == app_id) {
+            Some(app) => app.name.clone(),
+            None => format!("App {}", app_id),
+        };
+        let values = state
+            .params
+            .iter()
+            .find(|p| p.layout_id == layout_id)
+            .map_or(&[][..], |p| &p.values[..]);
+        let mut ranges = Vec::new();
+        let mut midi = Vec::new();
+        for value in values {
+            match value {
+                Value::Range(_) => ranges.push(display::format_value(value, None)),
+                Value::MidiChannel(_) | Value::MidiOut(_) => {
+                    midi.push(display::format_value(value, None))
+                }
+                _ => {}
+            }
+        }
+        ranges.dedup();
+        rows.push(vec![
+            slots::range(start, channels),
+            name,
+            ranges.join(", "),
+            midi.join(" "),
+            notes::on(&state.metadata.notes, start, channels).unwrap_or_default(),
+        ]);
+    }
+    Section {
+        title: "Faders",
+        rows,
+    }
+}
@@ -0,0 +1,25 @@
+// Locations of files the CLI keeps on the host (logs, caches, user config).
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+
+const APP_DIR: &str = "faderpunk-cli";
+
+/// Per-user data directory, e.g. `~/.local/share/faderpunk-cli` on Linux.
+/// Created on first use.
+pub fn data_dir() -> Result<PathBuf> {
+    let dir = dirs::data_dir()
+        .context("Could not determine the user data directory")?
+        .join(APP_DIR);
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    Ok(dir)
+}
+
+/// Per-user config directory, e.g. `~/.config/faderpunk-cli` on Linux.
+/// Not created; only read from unless the user writes a config file.
+pub fn config_dir() -> Result<PathBuf> {
+    Ok(dirs::config_dir()
+        .context("Could not determine the user config directory")?
+        .join(APP_DIR))
+}
@@ -0,0 +1,119 @@
+// Pinned params: the few values touched every session, e.g. a filter cutoff
+// on fader 3, gathered into one compact view (`pinned`) instead of full
+// `param show` dumps.
+//
+// Kept in `<data dir>/pins.json` by fader and param name. The app's name is
+// stored too, so the view can tell when a different app now sits there.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::invalid;
+use crate::paths;
+use crate::protocol::GLOBAL_CHANNELS;
+use crate::slots;
+
+const PINS_FILE: &str = "pins.json";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Pin {
+    /// Fader as given when pinned (firmware number); any fader the app covers.
+    pub slot: u8,
+    /// App that was there when pinned.
+    pub app: String,
+    /// Param name, or its index for unnamed params.
+    pub param: String,
+}
+
+impl Pin {
+    /// `SLOT:PARAM`, as `pin add` takes it.
+    pub fn describe(&self) -> String {
+        format!("{}:{}", slots::label(self.slot as usize - 1), self.param)
+    }
+}
+
+fn path() -> Result<PathBuf> {
+    Ok(paths::data_dir()?.join(PINS_FILE))
+}
+
+/// The saved pins, in the order pinned. A missing file means none.
+pub fn load() -> Result<Vec<Pin>> {
+    let path = path()?;
+    let data = match std::fs::read_to_string(&path) {
+        Ok(d) => d,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    parse(&data).with_context(|| format!("Invalid pins in {}", path.display()))
+}
+
+/// Read the pins file's contents. A pin on a fader the strip doesn't have is
+/// refused, so every loaded pin names a real fader.
+fn parse(data: &str) -> Result<Vec<Pin>> {
+    let pins: Vec<Pin> = serde_json::from_str(data)?;
+    if let Some(pin) = pins.iter().find(|p| p.slot == 0 || p.slot as usize > GLOBAL_CHANNELS) {
+        invalid!(
+            "Pin '{}' is on fader {}, but faders are 1-{}",
+            pin.param,
+            pin.slot,
+            GLOBAL_CHANNELS
+        );
+    }
+    Ok(pins)
+}
+
+pub fn save(pins: &[Pin]) -> Result<()> {
+    let path = path()?;
+    std::fs::write(&path, serde_json::to_string_pretty(pins)? + "\n")
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Parse `SLOT:PARAM`, e.g. `3:cutoff`, into the fader's firmware number and
+/// the param reference (name or index).
+pub fn parse_ref(s: &str) -> Result<(u8, String)> {
+    let Some((slot, param)) = s.split_once(':') else {
+        anyhow::bail!("Expected SLOT:PARAM (e.g. 3:cutoff), got '{}'", s);
+    };
+    let param = param.trim();
+    if param.is_empty() {
+        anyhow::bail!("Missing param name in '{}'", s);
+    }
+    Ok((slots::parse(slot)?, param.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ref_takes_a_fader_and_a_param() {
+        assert_eq!(parse_ref("3:cutoff").unwrap(), (3, "cutoff".to_string()));
+        assert_eq!(parse_ref("16: 2 ").unwrap(), (16, "2".to_string()));
+        assert!(parse_ref("3").is_err());
+        assert!(parse_ref("3: ").is_err());
+        assert!(parse_ref("17:cutoff").is_err());
+    }
+
+    #[test]
+    fn describe_reads_back_as_the_same_pin() {
+        let pin = Pin {
+            slot: 3,
+            app: "Filter".to_string(),
+            param: "cutoff".to_string(),
+        };
+        assert_eq!(pin.describe(), "3:cutoff");
+        assert_eq!(parse_ref(&pin.describe()).unwrap(), (pin.slot, pin.param));
+    }
+
+    #[test]
+    fn pins_off_the_strip_are_refused() {
+        let pin = |slot: u8| format!(r#"[{{"slot": {}, "app": "Filter", "param": "cutoff"}}]"#, slot);
+        assert_eq!(parse(&pin(16)).unwrap()[0].slot, 16);
+        for slot in [0, 17] {
+            let err = parse(&pin(slot)).unwrap_err();
+            assert_eq!(crate::exitcode::of(&err), crate::exitcode::INVALID, "{}", err);
+        }
+    }
+}
@@ -0,0 +1,51 @@
+// Named profiles: snapshot files kept in the user data dir so they can be
+// recalled by name instead of by path.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+
+use crate::paths;
+
+const PROFILE_DIR: &str = "profiles";
+
+/// Profile created by `init`.
+pub const DEFAULT: &str = "default";
+
+fn dir() -> Result<PathBuf> {
+    let dir = paths::data_dir()?.join(PROFILE_DIR);
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    Ok(dir)
+}
+
+/// File backing a profile. The file may not exist yet.
+pub fn path(name: &str) -> Result<PathBuf> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        anyhow::bail!("Invalid profile name '{}'", name);
+    }
+    Ok(dir()?.join(format!("{}.json", name)))
+}
+
+/// Names of all saved profiles, sorted.
+pub fn list() -> Result<Vec<String>> {
+    let mut names: Vec<String> = std::fs::read_dir(dir()?)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().into_owned()))
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// File backing an existing profile.
+pub fn existing(name: &str) -> Result<PathBuf> {
+    let path = path(name)?;
+    if !path.exists() {
+        anyhow::bail!(
+            "No profile named '{}'. Use 'profile list' to see saved profiles.",
+            name
+        );
+    }
+    Ok(path)
+}
@@ -308,7 +308,7 @@ pub enum Value {
 // ── Wire messages ──
 
 // Host → Device
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ConfigMsgIn {
     Ping,
     GetAllApps,
@@ -328,7 +328,8 @@ pub enum ConfigMsgIn {
 // Device → Host
 // Note: the firmware uses ConfigMsgOut<'a> with borrowed data, but for
 // deserialization on the host side we own all data (String, Vec).
-#[derive(Debug, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)] // mirrors the wire layout; not worth boxing
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ConfigMsgOut {
     Pong,
     BatchMsgStart(usize),
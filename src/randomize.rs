@@ -0,0 +1,149 @@
+// Constraint files for `param randomize`: per app, which params to leave
+// alone and how to pick each of the others, e.g.
+//
+//     apps:
+//       LFO:
+//         freeze: [range]
+//         params:
+//           speed: { min: 20, max: 60, distribution: normal }
+//           waveform: { choose: [sine, triangle] }
+//       "*":
+//         params:
+//           attack: { max: 30, distribution: low }
+//
+// "*" covers every app without an entry of its own. Params a file doesn't
+// mention are picked uniformly over their whole range.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+
+use faderpunk_cli::random::Rng;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Constraints {
+    #[serde(default)]
+    apps: BTreeMap<String, AppRules>,
+}
+
+/// Rules for one app. Params are named as in `param show`, or by index.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppRules {
+    /// Params that keep their current value.
+    #[serde(default)]
+    pub freeze: Vec<String>,
+    #[serde(default)]
+    pub params: BTreeMap<String, Rule>,
+}
+
+/// How to pick one param's value: from `choose`, or between `min` and `max`
+/// (each defaulting to the param's own bound) with the given distribution.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    #[serde(default)]
+    pub distribution: Distribution,
+    pub choose: Option<Vec<serde_yaml::Value>>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Distribution {
+    /// Every value in the range equally likely.
+    #[default]
+    Uniform,
+    /// Bunched around the middle of the range, rarely near the ends.
+    Normal,
+    /// Mostly near the bottom of the range.
+    Low,
+    /// Mostly near the top of the range.
+    High,
+}
+
+impl Constraints {
+    pub fn read(path: &Path) -> Result<Self> {
+        let data = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let constraints: Constraints = serde_yaml::from_str(&data)
+            .with_context(|| format!("Invalid constraints file {}", path.display()))?;
+        for (app, rules) in &constraints.apps {
+            for (param, rule) in &rules.params {
+                rule.check()
+                    .with_context(|| format!("{}: {} param '{}'", path.display(), app, param))?;
+            }
+        }
+        Ok(constraints)
+    }
+
+    /// The rules for an app (by name, any case), falling back to "*".
+    /// The bool says whether they were written for this app in particular.
+    pub fn for_app(&self, name: &str) -> Option<(&AppRules, bool)> {
+        let own = self
+            .apps
+            .iter()
+            .find(|(app, _)| app.eq_ignore_ascii_case(name));
+        match own {
+            Some((_, rules)) => Some((rules, true)),
+            None => self.apps.get("*").map(|rules| (rules, false)),
+        }
+    }
+
+    /// App names with rules of their own, to spot ones that match nothing.
+    pub fn app_names(&self) -> impl Iterator<Item = &str> {
+        self.apps
+            .keys()
+            .map(String::as_str)
+            .filter(|name| *name != "*")
+    }
+}
+
+impl Rule {
+    fn check(&self) -> Result<()> {
+        if self.choose.is_some() && (self.min.is_some() || self.max.is_some()) {
+            bail!("use either choose or min/max, not both");
+        }
+        if let Some(choices) = &self.choose
+            && choices.is_empty()
+        {
+            bail!("choose needs at least one value");
+        }
+        if let (Some(min), Some(max)) = (self.min, self.max)
+            && min > max
+        {
+            bail!("min {} is above max {}", min, max);
+        }
+        Ok(())
+    }
+}
+
+/// A value from `choose` as the text `param set` would take.
+pub fn choice_text(value: &serde_yaml::Value) -> Result<String> {
+    match value {
+        serde_yaml::Value::String(s) => Ok(s.clone()),
+        serde_yaml::Value::Number(n) => Ok(n.to_string()),
+        serde_yaml::Value::Bool(b) => Ok(b.to_string()),
+        other => bail!("can't choose {:?}; use a number or a name", other),
+    }
+}
+
+/// A number between `lo` and `hi` (inclusive of both, give or take rounding).
+pub fn sample(rng: &mut Rng, lo: f64, hi: f64, distribution: Distribution) -> f64 {
+    let u = rng.unit();
+    let t = match distribution {
+        Distribution::Uniform => u,
+        // Box-Muller, three standard deviations either side of the middle
+        Distribution::Normal => {
+            let z = (-2.0 * (1.0 - u).ln()).sqrt() * (std::f64::consts::TAU * rng.unit()).cos();
+            (0.5 + z / 6.0).clamp(0.0, 1.0)
+        }
+        Distribution::Low => u * u,
+        Distribution::High => 1.0 - u * u,
+    };
+    lo + t * (hi - lo)
+}
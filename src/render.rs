@@ -0,0 +1,639 @@
+// Output formats (--output). display.rs describes what it shows as sections,
+// labeled fields, table rows and free text, and the selected Renderer turns
+// that into a colored terminal view, plain text, Markdown, JSON, YAML, TOML
+// or NDJSON, so every command supports every format without code of its own.
+//
+// Anything a command prints itself (confirmations, progress) is ordinary
+// stdout. The text formats pass it through without colors; the structured
+// ones capture it and report it as messages at the end. The terminal view
+// loses its colors the same way when --color or NO_COLOR turns them off.
+
+use std::io::Write;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use owo_colors::OwoColorize;
+use serde_json::{Map, Value as Json};
+
+use crate::capture::{self, strip_ansi};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Output {
+    /// Colors and box drawing, for a terminal
+    #[default]
+    Pretty,
+    Plain,
+    Json,
+    Ndjson,
+    Markdown,
+    Yaml,
+    Toml,
+}
+
+pub fn parse_output(s: &str) -> Result<Output> {
+    match s.to_lowercase().as_str() {
+        "pretty" | "ansi" => Ok(Output::Pretty),
+        "plain" | "text" => Ok(Output::Plain),
+        "json" => Ok(Output::Json),
+        "ndjson" | "jsonl" => Ok(Output::Ndjson),
+        "markdown" | "md" => Ok(Output::Markdown),
+        "yaml" | "yml" => Ok(Output::Yaml),
+        "toml" => Ok(Output::Toml),
+        _ => anyhow::bail!(
+            "Unknown output format: {} (use: pretty, plain, json, ndjson, markdown, yaml, toml)",
+            s
+        ),
+    }
+}
+
+/// When the terminal view is colored (--color).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ColorChoice {
+    /// On a terminal, unless NO_COLOR is set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+pub fn parse_color(s: &str) -> Result<ColorChoice> {
+    match s.to_lowercase().as_str() {
+        "auto" => Ok(ColorChoice::Auto),
+        "always" => Ok(ColorChoice::Always),
+        "never" => Ok(ColorChoice::Never),
+        _ => anyhow::bail!("Unknown color setting: {} (use: auto, always, never)", s),
+    }
+}
+
+impl ColorChoice {
+    /// Whether output to a stream gets colors; `terminal` is whether the
+    /// stream is one.
+    pub fn enabled(self, terminal: bool) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                terminal && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+            }
+        }
+    }
+}
+
+impl Output {
+    /// Whether the output is for programs: commands don't prompt, and
+    /// commands with their own JSON put it in the output.
+    pub fn is_structured(self) -> bool {
+        matches!(self, Output::Json | Output::Ndjson | Output::Yaml | Output::Toml)
+    }
+
+    fn renderer(self) -> Box<dyn Renderer> {
+        match self {
+            Output::Pretty => Box::new(Pretty),
+            Output::Plain => Box::new(Plain),
+            Output::Json => Box::new(Document::new(Syntax::Json)),
+            Output::Ndjson => Box::<JsonLines>::default(),
+            Output::Markdown => Box::<Markdown>::default(),
+            Output::Yaml => Box::new(Document::new(Syntax::Yaml)),
+            Output::Toml => Box::new(Document::new(Syntax::Toml)),
+        }
+    }
+}
+
+/// Turns what display.rs shows into one output format.
+pub trait Renderer: Send {
+    /// Start a top-level section.
+    fn section(&mut self, title: &str);
+    /// Start a part of the current section; `detail` follows the title, as
+    /// the caller styled it.
+    fn subsection(&mut self, title: &str, detail: &str);
+    /// A labeled value.
+    fn field(&mut self, key: &str, value: &str);
+    /// A table row: `cells`, named by `columns`, for formats that keep the
+    /// structure, and `line`, the row laid out for a terminal.
+    fn row(&mut self, columns: &[&str], cells: &[String], line: &str);
+    /// A line with no structure of its own (diagrams, notes); empty for
+    /// spacing.
+    fn text(&mut self, line: &str);
+    /// Data a command builds itself, e.g. what its --json flag prints.
+    fn data(&mut self, value: &Json);
+    /// The command is done. `messages` is whatever else it printed, for
+    /// formats that capture it.
+    fn finish(&mut self, messages: &[String]);
+}
+
+static RENDERER: Mutex<Option<Box<dyn Renderer>>> = Mutex::new(None);
+static OUTPUT: Mutex<Output> = Mutex::new(Output::Pretty);
+/// Where renderers write while stdout is captured.
+static STDOUT: Mutex<Option<std::fs::File>> = Mutex::new(None);
+
+/// The selected format.
+pub fn output() -> Output {
+    *OUTPUT.lock().unwrap()
+}
+
+/// Call `f` with the selected renderer, the terminal one if none is.
+pub fn with<T>(f: impl FnOnce(&mut dyn Renderer) -> T) -> T {
+    let mut renderer = RENDERER.lock().unwrap();
+    f(renderer
+        .get_or_insert_with(|| Output::Pretty.renderer())
+        .as_mut())
+}
+
+/// Write a line to stdout, or past the capture while there is one.
+fn emit(line: &str) {
+    match STDOUT.lock().unwrap().as_mut() {
+        Some(out) => {
+            let _ = writeln!(out, "{}", line);
+        }
+        None => println!("{}", line),
+    }
+}
+
+/// The selected format in effect for one run of the CLI; `end` flushes it.
+pub struct Session {
+    capture: Option<capture::Capture>,
+    stripped: Option<capture::Stripped>,
+}
+
+/// Select a format and set up stdout for it; `color` is whether the
+/// terminal view keeps its colors. Where stdout can't be redirected, other
+/// output shows as is.
+pub fn begin(output: Output, color: bool) -> Session {
+    *OUTPUT.lock().unwrap() = output;
+    *RENDERER.lock().unwrap() = Some(output.renderer());
+    let mut session = Session {
+        capture: None,
+        stripped: None,
+    };
+    match output {
+        Output::Pretty if color => {}
+        Output::Pretty | Output::Plain | Output::Markdown => {
+            session.stripped = capture::strip_colors().ok()
+        }
+        Output::Json | Output::Ndjson | Output::Yaml | Output::Toml => {
+            if let Ok(capture) = capture::start() {
+                *STDOUT.lock().unwrap() = capture.stdout().ok();
+                session.capture = Some(capture);
+            }
+        }
+    }
+    session
+}
+
+impl Session {
+    pub fn end(self) {
+        let printed = self
+            .capture
+            .map(capture::Capture::finish)
+            .unwrap_or_default();
+        *STDOUT.lock().unwrap() = None;
+        let messages: Vec<String> = printed
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect();
+        with(|r| r.finish(&messages));
+        if let Some(stripped) = self.stripped {
+            stripped.finish();
+        }
+    }
+}
+
+// ── Pretty ──
+
+struct Pretty;
+
+impl Renderer for Pretty {
+    fn section(&mut self, title: &str) {
+        let bar = "─".repeat(title.chars().count() + 2);
+        println!("┌{}┐", bar);
+        println!("│ {} │", title.bold());
+        println!("└{}┘", bar);
+    }
+
+    fn subsection(&mut self, title: &str, detail: &str) {
+        println!();
+        if detail.is_empty() {
+            println!("  {} {}", "▸".dimmed(), title.bold());
+        } else {
+            println!("  {} {} {}", "▸".dimmed(), title.bold(), detail);
+        }
+    }
+
+    fn field(&mut self, key: &str, value: &str) {
+        println!("    {:<16} {}", format!("{}:", key).dimmed(), value);
+    }
+
+    fn row(&mut self, _columns: &[&str], _cells: &[String], line: &str) {
+        println!("{}", line);
+    }
+
+    fn text(&mut self, line: &str) {
+        println!("{}", line);
+    }
+
+    fn data(&mut self, value: &Json) {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(value).unwrap_or_default()
+        );
+    }
+
+    fn finish(&mut self, _messages: &[String]) {}
+}
+
+// ── Plain ──
+
+/// The terminal layout in plain text: no colors, and underlined headings
+/// instead of boxes.
+struct Plain;
+
+impl Renderer for Plain {
+    fn section(&mut self, title: &str) {
+        println!("{}", title);
+        println!("{}", "=".repeat(title.chars().count()));
+    }
+
+    fn subsection(&mut self, title: &str, detail: &str) {
+        let heading = strip_ansi(&format!("{} {}", title, detail))
+            .trim_end()
+            .to_string();
+        println!();
+        println!("  {}", heading);
+        println!("  {}", "-".repeat(heading.chars().count()));
+    }
+
+    fn field(&mut self, key: &str, value: &str) {
+        println!("    {:<16} {}", format!("{}:", key), strip_ansi(value));
+    }
+
+    fn row(&mut self, _columns: &[&str], _cells: &[String], line: &str) {
+        println!("{}", strip_ansi(line));
+    }
+
+    fn text(&mut self, line: &str) {
+        println!("{}", strip_ansi(line));
+    }
+
+    fn data(&mut self, value: &Json) {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(value).unwrap_or_default()
+        );
+    }
+
+    fn finish(&mut self, _messages: &[String]) {}
+}
+
+// ── Markdown ──
+
+#[derive(Default, PartialEq)]
+enum Block {
+    #[default]
+    None,
+    /// A table with these columns
+    Table(Vec<String>),
+    /// A code block of text lines
+    Code,
+}
+
+#[derive(Default)]
+struct Markdown {
+    block: Block,
+}
+
+impl Markdown {
+    /// End an open table or code block, unless it's the one `next` would
+    /// continue.
+    fn close(&mut self, next: &Block) {
+        if self.block == *next {
+            return;
+        }
+        match self.block {
+            Block::Code => println!("```\n"),
+            Block::Table(_) => println!(),
+            Block::None => {}
+        }
+        self.block = Block::None;
+    }
+}
+
+fn md_cell(s: &str) -> String {
+    strip_ansi(s).trim().replace('|', "\\|")
+}
+
+impl Renderer for Markdown {
+    fn section(&mut self, title: &str) {
+        self.close(&Block::None);
+        println!("# {}\n", strip_ansi(title));
+    }
+
+    fn subsection(&mut self, title: &str, detail: &str) {
+        self.close(&Block::None);
+        let heading = strip_ansi(&format!("{} {}", title, detail));
+        println!("## {}\n", heading.trim_end());
+    }
+
+    fn field(&mut self, key: &str, value: &str) {
+        self.close(&Block::None);
+        println!("- **{}:** {}", strip_ansi(key), strip_ansi(value).trim());
+    }
+
+    fn row(&mut self, columns: &[&str], cells: &[String], _line: &str) {
+        let table = Block::Table(columns.iter().map(|c| c.to_string()).collect());
+        self.close(&table);
+        if self.block == Block::None {
+            let header: Vec<String> = columns.iter().map(|c| md_cell(c)).collect();
+            println!("| {} |", header.join(" | "));
+            println!("|{}", "---|".repeat(columns.len()));
+            self.block = table;
+        }
+        let cells: Vec<String> = cells.iter().map(|c| md_cell(c)).collect();
+        println!("| {} |", cells.join(" | "));
+    }
+
+    fn text(&mut self, line: &str) {
+        let line = strip_ansi(line);
+        if line.trim().is_empty() && self.block != Block::Code {
+            return;
+        }
+        self.close(&Block::Code);
+        if self.block == Block::None {
+            println!("```");
+            self.block = Block::Code;
+        }
+        println!("{}", line.trim_end());
+    }
+
+    fn data(&mut self, value: &Json) {
+        self.close(&Block::None);
+        println!("```json");
+        println!(
+            "{}",
+            serde_json::to_string_pretty(value).unwrap_or_default()
+        );
+        println!("```\n");
+    }
+
+    fn finish(&mut self, _messages: &[String]) {
+        self.close(&Block::None);
+    }
+}
+
+// ── JSON, YAML, TOML ──
+
+/// A section of the document; parts left empty are left out.
+#[derive(Default)]
+struct Section {
+    title: Option<String>,
+    detail: Option<String>,
+    fields: Map<String, Json>,
+    rows: Vec<Json>,
+    text: Vec<String>,
+    sections: Vec<Section>,
+}
+
+impl Section {
+    fn titled(title: &str) -> Self {
+        Section {
+            title: Some(strip_ansi(title)),
+            ..Default::default()
+        }
+    }
+
+    fn into_json(self) -> Json {
+        let mut out = Map::new();
+        if let Some(title) = self.title {
+            out.insert("title".into(), title.into());
+        }
+        if let Some(detail) = self.detail {
+            out.insert("detail".into(), detail.into());
+        }
+        if !self.fields.is_empty() {
+            out.insert("fields".into(), self.fields.into());
+        }
+        if !self.rows.is_empty() {
+            out.insert("rows".into(), self.rows.into());
+        }
+        if !self.text.is_empty() {
+            out.insert("text".into(), self.text.into());
+        }
+        if !self.sections.is_empty() {
+            let sections: Vec<Json> = self.sections.into_iter().map(Section::into_json).collect();
+            out.insert("sections".into(), sections.into());
+        }
+        out.into()
+    }
+}
+
+/// A row as an object keyed by column name.
+fn row_json(columns: &[&str], cells: &[String]) -> Json {
+    let row: Map<String, Json> = columns
+        .iter()
+        .zip(cells)
+        .map(|(column, cell)| (column.to_string(), strip_ansi(cell).trim().into()))
+        .collect();
+    row.into()
+}
+
+/// How a `Document` is written out.
+#[derive(Clone, Copy)]
+enum Syntax {
+    Json,
+    Yaml,
+    Toml,
+}
+
+/// Everything collected into one object, printed at the end: `sections`,
+/// then `data` and `messages` when there are any.
+struct Document {
+    syntax: Syntax,
+    sections: Vec<Section>,
+    data: Vec<Json>,
+}
+
+impl Document {
+    fn new(syntax: Syntax) -> Self {
+        Document {
+            syntax,
+            sections: Vec::new(),
+            data: Vec::new(),
+        }
+    }
+
+    /// The innermost open section, starting an untitled one if there's none.
+    fn current(&mut self) -> &mut Section {
+        if self.sections.is_empty() {
+            self.sections.push(Section::default());
+        }
+        let section = self.sections.last_mut().unwrap();
+        if section.sections.is_empty() {
+            section
+        } else {
+            section.sections.last_mut().unwrap()
+        }
+    }
+}
+
+impl Renderer for Document {
+    fn section(&mut self, title: &str) {
+        self.sections.push(Section::titled(title));
+    }
+
+    fn subsection(&mut self, title: &str, detail: &str) {
+        let mut section = Section::titled(title);
+        let detail = strip_ansi(detail);
+        if !detail.trim().is_empty() {
+            section.detail = Some(detail.trim().to_string());
+        }
+        if self.sections.is_empty() {
+            self.sections.push(Section::default());
+        }
+        self.sections.last_mut().unwrap().sections.push(section);
+    }
+
+    fn field(&mut self, key: &str, value: &str) {
+        let value = strip_ansi(value).trim().to_string();
+        self.current().fields.insert(strip_ansi(key), value.into());
+    }
+
+    fn row(&mut self, columns: &[&str], cells: &[String], _line: &str) {
+        self.current().rows.push(row_json(columns, cells));
+    }
+
+    fn text(&mut self, line: &str) {
+        let line = strip_ansi(line).trim_end().to_string();
+        if !line.trim().is_empty() {
+            self.current().text.push(line);
+        }
+    }
+
+    fn data(&mut self, value: &Json) {
+        self.data.push(value.clone());
+    }
+
+    fn finish(&mut self, messages: &[String]) {
+        let mut out = Map::new();
+        let sections: Vec<Json> = std::mem::take(&mut self.sections)
+            .into_iter()
+            .map(Section::into_json)
+            .collect();
+        out.insert("sections".into(), sections.into());
+        match std::mem::take(&mut self.data).as_slice() {
+            [] => {}
+            [one] => {
+                out.insert("data".into(), one.clone());
+            }
+            many => {
+                out.insert("data".into(), many.to_vec().into());
+            }
+        }
+        if !messages.is_empty() {
+            out.insert("messages".into(), messages.to_vec().into());
+        }
+        let out = Json::Object(out);
+        let text = match self.syntax {
+            Syntax::Json => serde_json::to_string_pretty(&out).unwrap_or_default(),
+            Syntax::Yaml => serde_yaml::to_string(&out).unwrap_or_default(),
+            Syntax::Toml => toml::to_string_pretty(&without_nulls(out)).unwrap_or_default(),
+        };
+        emit(text.trim_end());
+    }
+}
+
+/// `value` with null object members left out and null array items dropped,
+/// as TOML has no null.
+fn without_nulls(value: Json) -> Json {
+    match value {
+        Json::Object(map) => map
+            .into_iter()
+            .filter(|(_, v)| !v.is_null())
+            .map(|(k, v)| (k, without_nulls(v)))
+            .collect::<Map<String, Json>>()
+            .into(),
+        Json::Array(items) => items
+            .into_iter()
+            .filter(|v| !v.is_null())
+            .map(without_nulls)
+            .collect::<Vec<Json>>()
+            .into(),
+        other => other,
+    }
+}
+
+// ── NDJSON ──
+
+/// One JSON object per line as output happens, each naming the section it
+/// belongs to.
+#[derive(Default)]
+struct JsonLines {
+    section: Option<String>,
+    subsection: Option<String>,
+}
+
+impl JsonLines {
+    fn record(&self, key: &str, value: Json) {
+        let mut out = Map::new();
+        if let Some(section) = &self.section {
+            out.insert("section".into(), section.clone().into());
+        }
+        if let Some(subsection) = &self.subsection {
+            out.insert("subsection".into(), subsection.clone().into());
+        }
+        out.insert(key.into(), value);
+        emit(&Json::Object(out).to_string());
+    }
+}
+
+impl Renderer for JsonLines {
+    fn section(&mut self, title: &str) {
+        self.section = Some(strip_ansi(title));
+        self.subsection = None;
+    }
+
+    fn subsection(&mut self, title: &str, detail: &str) {
+        self.subsection = Some(strip_ansi(title));
+        let detail = strip_ansi(detail);
+        if !detail.trim().is_empty() {
+            self.record("detail", detail.trim().into());
+        }
+    }
+
+    fn field(&mut self, key: &str, value: &str) {
+        let mut field = Map::new();
+        field.insert("key".into(), strip_ansi(key).into());
+        field.insert("value".into(), strip_ansi(value).trim().into());
+        self.record("field", field.into());
+    }
+
+    fn row(&mut self, columns: &[&str], cells: &[String], _line: &str) {
+        self.record("row", row_json(columns, cells));
+    }
+
+    fn text(&mut self, line: &str) {
+        let line = strip_ansi(line).trim_end().to_string();
+        if !line.trim().is_empty() {
+            self.record("text", line.into());
+        }
+    }
+
+    fn data(&mut self, value: &Json) {
+        match value {
+            Json::Array(items) => {
+                for item in items {
+                    self.record("data", item.clone());
+                }
+            }
+            other => self.record("data", other.clone()),
+        }
+    }
+
+    fn finish(&mut self, messages: &[String]) {
+        self.section = None;
+        self.subsection = None;
+        for message in messages {
+            self.record("message", message.clone().into());
+        }
+    }
+}
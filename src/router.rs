@@ -0,0 +1,268 @@
+// Routes messages from the device's read task to whoever is waiting for them.
+//
+// The protocol has no request IDs, so a response is matched to the oldest
+// pending request that expects that kind of message (and, for params, that
+// layout_id). Anything nobody is waiting for is an event and goes to subscribers.
+// An error from the device can't be matched by kind, so it goes to the oldest
+// pending request: the device answers in order, so that's the one it refused.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::{broadcast, mpsc};
+
+use crate::error::{ConnectionError, DeviceError, DeviceErrorKind, message_name};
+use crate::protocol::{ConfigMsgIn, ConfigMsgOut};
+
+const EVENT_CAPACITY: usize = 64;
+
+/// What kind of reply a request produces.
+enum Expect {
+    /// A single message.
+    One(fn(&ConfigMsgOut) -> bool),
+    /// BatchMsgStart, items matching the predicate, then BatchMsgEnd.
+    Batch(fn(&ConfigMsgOut) -> bool),
+}
+
+impl Expect {
+    /// The reply expected for a request, or None if the device doesn't answer it.
+    fn for_request(msg: &ConfigMsgIn) -> Option<Expect> {
+        Some(match msg {
+            ConfigMsgIn::Ping => Expect::One(|m| matches!(m, ConfigMsgOut::Pong)),
+            ConfigMsgIn::GetGlobalConfig => {
+                Expect::One(|m| matches!(m, ConfigMsgOut::GlobalConfig(_)))
+            }
+            ConfigMsgIn::GetLayout | ConfigMsgIn::SetLayout(_) => {
+                Expect::One(|m| matches!(m, ConfigMsgOut::Layout(_)))
+            }
+            ConfigMsgIn::GetAppParams { .. } | ConfigMsgIn::SetAppParams { .. } => {
+                Expect::One(|m| matches!(m, ConfigMsgOut::AppState(..)))
+            }
+            ConfigMsgIn::GetAllApps => Expect::Batch(|m| matches!(m, ConfigMsgOut::AppConfig(..))),
+            ConfigMsgIn::GetAllAppParams => {
+                Expect::Batch(|m| matches!(m, ConfigMsgOut::AppState(..)))
+            }
+            ConfigMsgIn::SetGlobalConfig(_) | ConfigMsgIn::FactoryReset => return None,
+        })
+    }
+
+    fn is_batch(&self) -> bool {
+        matches!(self, Expect::Batch(_))
+    }
+}
+
+/// What a request's channel delivers: its replies, or the device's error.
+pub type Reply = Result<ConfigMsgOut, DeviceError>;
+
+struct Pending {
+    /// Request name, for errors.
+    request: String,
+    expect: Expect,
+    /// For param requests: only the reply for this app instance matches.
+    layout_id: Option<u8>,
+    tx: mpsc::UnboundedSender<Reply>,
+    /// For batches: BatchMsgStart has been delivered.
+    started: bool,
+}
+
+impl Pending {
+    fn accepts(&self, msg: &ConfigMsgOut) -> bool {
+        match (&self.expect, msg) {
+            (Expect::One(pred), ConfigMsgOut::AppState(id, _)) => {
+                pred(msg) && self.layout_id.is_none_or(|want| want == *id)
+            }
+            (Expect::One(pred), _) => pred(msg),
+            (Expect::Batch(_), ConfigMsgOut::BatchMsgStart(_)) => !self.started,
+            (Expect::Batch(_), ConfigMsgOut::BatchMsgEnd) => self.started,
+            (Expect::Batch(item), _) => self.started && item(msg),
+        }
+    }
+}
+
+struct Inner {
+    pending: VecDeque<Pending>,
+    /// Set once the read task stops; new requests fail with this reason.
+    closed: Option<String>,
+}
+
+/// Shared between the device handle and its read task.
+#[derive(Clone)]
+pub struct Router {
+    inner: Arc<Mutex<Inner>>,
+    events: broadcast::Sender<ConfigMsgOut>,
+}
+
+impl Router {
+    pub fn new() -> Self {
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        Router {
+            inner: Arc::new(Mutex::new(Inner {
+                pending: VecDeque::new(),
+                closed: None,
+            })),
+            events,
+        }
+    }
+
+    /// Register interest in the reply to a request. Must be called before the
+    /// request is sent so a fast reply can't slip past. Returns None for
+    /// requests the device doesn't answer.
+    pub fn expect(
+        &self,
+        request: &ConfigMsgIn,
+    ) -> anyhow::Result<Option<mpsc::UnboundedReceiver<Reply>>> {
+        let Some(expect) = Expect::for_request(request) else {
+            return Ok(None);
+        };
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(reason) = &inner.closed {
+            return Err(ConnectionError::Closed(reason.clone()).into());
+        }
+        let (tx, rx) = mpsc::unbounded_channel();
+        let layout_id = match request {
+            ConfigMsgIn::GetAppParams { layout_id }
+            | ConfigMsgIn::SetAppParams { layout_id, .. } => Some(*layout_id),
+            _ => None,
+        };
+        inner.pending.push_back(Pending {
+            request: message_name(request),
+            expect,
+            layout_id,
+            tx,
+            started: false,
+        });
+        Ok(Some(rx))
+    }
+
+    /// Receive unsolicited messages (anything no request was waiting for).
+    pub fn subscribe(&self) -> broadcast::Receiver<ConfigMsgOut> {
+        self.events.subscribe()
+    }
+
+    /// Hand a message from the device to the request waiting for it, or to subscribers.
+    pub fn dispatch(&self, msg: ConfigMsgOut) {
+        let mut inner = self.inner.lock().unwrap();
+        // Drop requests whose caller has gone away
+        inner.pending.retain(|p| !p.tx.is_closed());
+
+        let Some(idx) = inner.pending.iter().position(|p| p.accepts(&msg)) else {
+            drop(inner);
+            let _ = self.events.send(msg);
+            return;
+        };
+
+        let pending = &mut inner.pending[idx];
+        let done = match &msg {
+            ConfigMsgOut::BatchMsgStart(_) => {
+                pending.started = true;
+                false
+            }
+            ConfigMsgOut::BatchMsgEnd => true,
+            _ => !pending.expect.is_batch(),
+        };
+        let _ = pending.tx.send(Ok(msg));
+        if done {
+            inner.pending.remove(idx);
+        }
+    }
+
+    /// Fail the oldest pending request with an error from the device. Returns
+    /// the error back if no request was waiting.
+    pub fn reject(&self, kind: DeviceErrorKind) -> Result<(), DeviceErrorKind> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.pending.retain(|p| !p.tx.is_closed());
+        let Some(pending) = inner.pending.pop_front() else {
+            return Err(kind);
+        };
+        let _ = pending.tx.send(Err(DeviceError {
+            request: pending.request,
+            kind,
+        }));
+        Ok(())
+    }
+
+    /// Mark the connection as closed and fail everything still waiting.
+    pub fn close(&self, reason: String) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.closed = Some(reason);
+        // Dropping the senders wakes every waiting receiver with None
+        inner.pending.clear();
+    }
+
+    /// Why the connection closed, if it has.
+    pub fn closed_reason(&self) -> Option<String> {
+        self.inner.lock().unwrap().closed.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(layout_id: u8) -> ConfigMsgIn {
+        ConfigMsgIn::GetAppParams { layout_id }
+    }
+
+    #[test]
+    fn param_replies_go_to_the_request_for_their_app() {
+        let router = Router::new();
+        let mut first = router.expect(&params(1)).unwrap().unwrap();
+        let mut second = router.expect(&params(2)).unwrap().unwrap();
+        router.dispatch(ConfigMsgOut::AppState(2, Vec::new()));
+        router.dispatch(ConfigMsgOut::AppState(1, Vec::new()));
+        assert!(matches!(first.try_recv(), Ok(Ok(ConfigMsgOut::AppState(1, _)))));
+        assert!(matches!(second.try_recv(), Ok(Ok(ConfigMsgOut::AppState(2, _)))));
+        // Each request is done after its one reply
+        assert!(first.try_recv().is_err());
+    }
+
+    #[test]
+    fn batches_collect_until_the_end_and_the_rest_are_events() {
+        let router = Router::new();
+        let mut events = router.subscribe();
+        let mut batch = router.expect(&ConfigMsgIn::GetAllAppParams).unwrap().unwrap();
+
+        // Nothing has started the batch yet, so this is an event
+        router.dispatch(ConfigMsgOut::AppState(0, Vec::new()));
+        router.dispatch(ConfigMsgOut::BatchMsgStart(1));
+        router.dispatch(ConfigMsgOut::AppState(3, Vec::new()));
+        router.dispatch(ConfigMsgOut::BatchMsgEnd);
+        router.dispatch(ConfigMsgOut::Pong);
+
+        assert!(matches!(batch.try_recv(), Ok(Ok(ConfigMsgOut::BatchMsgStart(1)))));
+        assert!(matches!(batch.try_recv(), Ok(Ok(ConfigMsgOut::AppState(3, _)))));
+        assert!(matches!(batch.try_recv(), Ok(Ok(ConfigMsgOut::BatchMsgEnd))));
+        assert!(matches!(events.try_recv(), Ok(ConfigMsgOut::AppState(0, _))));
+        assert!(matches!(events.try_recv(), Ok(ConfigMsgOut::Pong)));
+    }
+
+    #[test]
+    fn errors_go_to_the_oldest_request() {
+        let router = Router::new();
+        let rejected = || DeviceErrorKind::Rejected {
+            code: 1,
+            reason: "no".to_string(),
+        };
+        assert!(router.reject(rejected()).is_err());
+
+        let mut first = router.expect(&ConfigMsgIn::GetLayout).unwrap().unwrap();
+        let mut second = router.expect(&ConfigMsgIn::Ping).unwrap().unwrap();
+        router.reject(rejected()).unwrap();
+        let Ok(Err(err)) = first.try_recv() else {
+            panic!("the first request wasn't rejected");
+        };
+        assert_eq!(err.request, "GetLayout");
+        assert!(second.try_recv().is_err());
+    }
+
+    #[test]
+    fn unanswered_and_closed() {
+        let router = Router::new();
+        assert!(router.expect(&ConfigMsgIn::FactoryReset).unwrap().is_none());
+        let mut waiting = router.expect(&ConfigMsgIn::Ping).unwrap().unwrap();
+        router.close("unplugged".to_string());
+        assert!(waiting.try_recv().is_err());
+        assert!(router.expect(&ConfigMsgIn::Ping).is_err());
+        assert_eq!(router.closed_reason().as_deref(), Some("unplugged"));
+    }
+}
@@ -0,0 +1,172 @@
+// Running long-lived commands (guard, monitor, clock link) as a login service: unit files
+// for systemd (Linux) and launchd (macOS), and systemd readiness notification.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+/// Subcommands that keep running and make sense as a service.
+pub const LONG_RUNNING: &[&str] = &[
+    "guard",
+    "monitor",
+    "clock link",
+    "daemon mirror",
+    "mirror",
+];
+
+/// Whether a command line starts with one of the `LONG_RUNNING` commands.
+pub fn is_long_running(command: &[String]) -> bool {
+    LONG_RUNNING.iter().any(|c| {
+        let words: Vec<&str> = c.split(' ').collect();
+        command.len() >= words.len() && command.iter().zip(&words).all(|(a, w)| a == w)
+    })
+}
+
+const LAUNCHD_PREFIX: &str = "org.faderpunk-cli.";
+
+/// Where the service definition for `name` goes on this platform.
+pub fn unit_path(name: &str) -> Result<PathBuf> {
+    if cfg!(target_os = "macos") {
+        Ok(dirs::home_dir()
+            .context("Could not determine the home directory")?
+            .join("Library/LaunchAgents")
+            .join(format!("{}{}.plist", LAUNCHD_PREFIX, name)))
+    } else {
+        Ok(dirs::config_dir()
+            .context("Could not determine the user config directory")?
+            .join("systemd/user")
+            .join(format!("{}.service", name)))
+    }
+}
+
+/// Service definition that runs this executable with `args`.
+pub fn unit_file(name: &str, exe: &Path, args: &[String]) -> String {
+    if cfg!(target_os = "macos") {
+        launchd_plist(name, exe, args)
+    } else {
+        systemd_unit(exe, args)
+    }
+}
+
+/// Commands that activate the installed service.
+pub fn enable_hint(name: &str, path: &Path) -> String {
+    if cfg!(target_os = "macos") {
+        format!("launchctl load -w {}", path.display())
+    } else {
+        format!("systemctl --user daemon-reload && systemctl --user enable --now {}", name)
+    }
+}
+
+/// Commands that stop the service before its file is removed.
+pub fn disable_hint(name: &str, path: &Path) -> String {
+    if cfg!(target_os = "macos") {
+        format!("launchctl unload -w {}", path.display())
+    } else {
+        format!("systemctl --user disable --now {}", name)
+    }
+}
+
+fn systemd_unit(exe: &Path, args: &[String]) -> String {
+    let quote = |s: &str| {
+        if s.contains(|c: char| c.is_whitespace() || c == '"' || c == '\\') {
+            format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+        } else {
+            s.to_string()
+        }
+    };
+    let mut command = vec![quote(&exe.to_string_lossy()), "--no-pager".to_string()];
+    command.extend(args.iter().map(|a| quote(a)));
+
+    format!(
+        "[Unit]\n\
+         Description=Faderpunk CLI ({})\n\
+         \n\
+         [Service]\n\
+         Type=notify\n\
+         ExecStart={}\n\
+         Restart=on-failure\n\
+         RestartSec=5\n\
+         \n\
+         [Install]\n\
+         WantedBy=default.target\n",
+        args.first().map_or("", |s| s.as_str()),
+        command.join(" ")
+    )
+}
+
+fn launchd_plist(name: &str, exe: &Path, args: &[String]) -> String {
+    let esc = |s: &str| {
+        s.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    };
+    let mut program = format!("        <string>{}</string>\n", esc(&exe.to_string_lossy()));
+    for arg in ["--no-pager"].into_iter().chain(args.iter().map(String::as_str)) {
+        program += &format!("        <string>{}</string>\n", esc(arg));
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+         <plist version=\"1.0\">\n\
+         <dict>\n\
+         \x20   <key>Label</key>\n\
+         \x20   <string>{}{}</string>\n\
+         \x20   <key>ProgramArguments</key>\n\
+         \x20   <array>\n\
+         {}\
+         \x20   </array>\n\
+         \x20   <key>RunAtLoad</key>\n\
+         \x20   <true/>\n\
+         \x20   <key>KeepAlive</key>\n\
+         \x20   <dict>\n\
+         \x20       <key>SuccessfulExit</key>\n\
+         \x20       <false/>\n\
+         \x20   </dict>\n\
+         </dict>\n\
+         </plist>\n",
+        LAUNCHD_PREFIX,
+        esc(name),
+        program
+    )
+}
+
+/// Tell systemd the service is up (`Type=notify`). Does nothing when not
+/// started by systemd.
+pub fn notify_ready() {
+    #[cfg(unix)]
+    {
+        let Some(socket) = std::env::var_os("NOTIFY_SOCKET") else {
+            return;
+        };
+        if let Err(e) = notify(&socket, "READY=1") {
+            eprintln!("warning: could not notify systemd: {}", e);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn notify(socket: &std::ffi::OsStr, state: &str) -> std::io::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::net::UnixDatagram;
+
+    let sock = UnixDatagram::unbound()?;
+    let bytes = socket.as_bytes();
+    // A leading '@' means a Linux abstract-namespace socket
+    if let Some(name) = bytes.strip_prefix(b"@") {
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::linux::net::SocketAddrExt;
+            let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+            sock.send_to_addr(state.as_bytes(), &addr)?;
+            return Ok(());
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = name;
+            return Err(std::io::Error::other("abstract sockets are Linux-only"));
+        }
+    }
+    sock.send_to(state.as_bytes(), Path::new(socket))?;
+    Ok(())
+}
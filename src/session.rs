@@ -0,0 +1,103 @@
+// Session recordings for --record and `session replay`: the commands a run of
+// the CLI executed and every change it sent to the device, with the time since
+// recording started, so the sequence can be played back later.
+//
+// A session file has one JSON event per line. Commands are only there to show
+// what was run; replaying re-sends the recorded messages, which also covers
+// the designer and anything else that doesn't go through a command line.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::audit;
+use crate::protocol::ConfigMsgIn;
+
+/// The open session file and when recording started; None while not recording.
+static RECORDING: Mutex<Option<(File, Instant)>> = Mutex::new(None);
+
+/// One recorded step: a command, or a message sent to the device.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Event {
+    /// Milliseconds since recording started.
+    pub at_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<ConfigMsgIn>,
+}
+
+impl Event {
+    pub fn at(&self) -> Duration {
+        Duration::from_millis(self.at_ms)
+    }
+}
+
+/// Start recording to `path`, replacing anything already there.
+pub fn start(path: &Path) -> Result<()> {
+    let file =
+        File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    *RECORDING.lock().unwrap() = Some((file, Instant::now()));
+    Ok(())
+}
+
+/// Record a command about to run. Does nothing unless recording.
+pub fn command(line: &str) -> Result<()> {
+    append(Some(line.to_string()), None)
+}
+
+/// Record a message about to be sent. Read-only requests are left out, as
+/// replaying them changes nothing. Does nothing unless recording.
+pub fn message(msg: &ConfigMsgIn) -> Result<()> {
+    if audit::mutation_name(msg).is_none() {
+        return Ok(());
+    }
+    append(None, Some(msg.clone()))
+}
+
+fn append(command: Option<String>, message: Option<ConfigMsgIn>) -> Result<()> {
+    let mut recording = RECORDING.lock().unwrap();
+    let Some((file, start)) = recording.as_mut() else {
+        return Ok(());
+    };
+    let event = Event {
+        at_ms: start.elapsed().as_millis() as u64,
+        command,
+        message,
+    };
+    writeln!(file, "{}", serde_json::to_string(&event)?)?;
+    Ok(())
+}
+
+/// Read a session file. Unlike the audit log, a line that doesn't parse is an
+/// error: replaying with a step missing would leave the device somewhere the
+/// recording never was.
+pub fn read(path: &Path) -> Result<Vec<Event>> {
+    let data = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    data.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line)
+                .with_context(|| format!("{}, line {}: not a session event", path.display(), i + 1))
+        })
+        .collect()
+}
+
+/// Parse a replay speed such as `2x`, `0.5x` or `1.5`.
+pub fn parse_speed(s: &str) -> Result<f64> {
+    let number = s.trim().strip_suffix(['x', 'X']).unwrap_or(s.trim());
+    let speed: f64 = number
+        .parse()
+        .with_context(|| format!("Invalid speed '{}' (expected e.g. 2x or 0.5x)", s))?;
+    if !speed.is_finite() || speed <= 0.0 {
+        anyhow::bail!("Invalid speed '{}': must be above 0", s);
+    }
+    Ok(speed)
+}
@@ -0,0 +1,99 @@
+// User settings for the CLI itself (not the device), read from
+// `<config dir>/config.toml`. Every field is optional; a missing file means defaults.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::paths;
+use crate::slots;
+
+const SETTINGS_FILE: &str = "config.toml";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Send long output through a pager when writing to a terminal (default: true).
+    pub pager: Option<bool>,
+    /// Pager command, overriding $PAGER (e.g. "less -R").
+    pub pager_command: Option<String>,
+    /// Bytes per USB IN request (multiple of 64; default 512).
+    pub usb_transfer_size: Option<usize>,
+    /// USB IN requests kept queued at once (default 4).
+    pub usb_queue_depth: Option<usize>,
+    /// Milliseconds between messages sent to the device, overriding the
+    /// pacing picked for its firmware release.
+    pub usb_message_gap_ms: Option<u64>,
+    /// Milliseconds between the 64-byte packets of one message, likewise.
+    pub usb_chunk_gap_ms: Option<u64>,
+    /// Fader numbering: "firmware" (1 is the leftmost fader of the unit as
+    /// shipped) or "reversed" (for a unit mounted the other way round).
+    pub slot_order: Option<SlotOrder>,
+    /// One label per fader in firmware order, e.g. ["A1", ..., "B8"];
+    /// overrides slot_order.
+    pub slot_labels: Option<Vec<String>>,
+    /// Named param changes run with `macro <name>`, each a list of steps
+    /// like "3:cutoff-10%" or "1:range=0-5v".
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub macros: BTreeMap<String, Vec<String>>,
+    /// Arguments added to a command as if typed right after it, keyed by its
+    /// command words, e.g. `"param show" = "--ascii"`. The longest match wins.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub defaults: BTreeMap<String, String>,
+    /// Headings for `param show`, keyed by app name: each group's params are
+    /// listed under its name, in the order given.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub param_groups: BTreeMap<String, Vec<ParamGroup>>,
+}
+
+/// A heading in `param show` and the params under it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParamGroup {
+    pub name: String,
+    /// Param names (case doesn't matter) or indices.
+    pub params: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SlotOrder {
+    Firmware,
+    Reversed,
+}
+
+impl Settings {
+    /// How faders are named, from slot_labels or slot_order.
+    pub fn slot_labels(&self) -> slots::Labels {
+        match (&self.slot_labels, self.slot_order) {
+            (Some(labels), _) => slots::Labels::Custom(labels.clone()),
+            (None, Some(SlotOrder::Reversed)) => slots::Labels::Reversed,
+            _ => slots::Labels::Firmware,
+        }
+    }
+
+    /// The param groups set for an app, matching its name without regard
+    /// to case.
+    pub fn param_groups_for(&self, app: &str) -> Option<&[ParamGroup]> {
+        self.param_groups
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(app))
+            .map(|(_, groups)| groups.as_slice())
+    }
+
+    pub fn path() -> Result<PathBuf> {
+        Ok(paths::config_dir()?.join(SETTINGS_FILE))
+    }
+
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+        let data = match std::fs::read_to_string(&path) {
+            Ok(d) => d,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        toml::from_str(&data).with_context(|| format!("Invalid settings in {}", path.display()))
+    }
+}
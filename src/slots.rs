@@ -0,0 +1,147 @@
+// How faders are named on screen and on the command line.
+//
+// The firmware numbers faders 1-16 from the left of the unit as it ships.
+// Someone who mounts it the other way round can number them from the other
+// end, or give each fader its own label. Every fader the CLI reads or shows
+// goes through here, so everything else keeps using the firmware's numbers
+// (as do snapshot files, JSON output and the audit log).
+
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result, bail};
+
+use crate::invalid;
+use crate::protocol::GLOBAL_CHANNELS;
+
+/// How faders are named.
+#[derive(Clone, Debug, Default)]
+pub enum Labels {
+    /// 1-16, as the firmware numbers them.
+    #[default]
+    Firmware,
+    /// 16-1: fader 1 is the firmware's fader 16.
+    Reversed,
+    /// One label per fader, in firmware order.
+    Custom(Vec<String>),
+}
+
+static LABELS: OnceLock<Labels> = OnceLock::new();
+
+/// Set the naming for the rest of the process. Must run before any slot is
+/// parsed or shown; later calls are ignored.
+pub fn configure(labels: Labels) -> Result<()> {
+    if let Labels::Custom(names) = &labels {
+        if names.len() != GLOBAL_CHANNELS {
+            bail!(
+                "Expected {} fader labels, got {}",
+                GLOBAL_CHANNELS,
+                names.len()
+            );
+        }
+        for (i, name) in names.iter().enumerate() {
+            if name.is_empty()
+                || name.contains([',', '-', ':', '@'])
+                || name.contains(char::is_whitespace)
+            {
+                bail!("Invalid fader label '{}' (no spaces or , - : @)", name);
+            }
+            if names[..i].iter().any(|n| n.eq_ignore_ascii_case(name)) {
+                bail!("Fader label '{}' is used twice", name);
+            }
+        }
+    }
+    let _ = LABELS.set(labels);
+    Ok(())
+}
+
+// Read without setting, so a parse before `configure` doesn't fix the naming
+fn labels() -> &'static Labels {
+    LABELS.get().unwrap_or(&Labels::Firmware)
+}
+
+/// Whether the faders are numbered from the firmware's last one, so views
+/// of the whole strip should be drawn right to left.
+pub fn reversed() -> bool {
+    matches!(labels(), Labels::Reversed)
+}
+
+/// Name of the fader at 0-based firmware index `index`.
+pub fn label(index: usize) -> String {
+    match labels() {
+        Labels::Firmware => (index + 1).to_string(),
+        Labels::Reversed => (GLOBAL_CHANNELS - index).to_string(),
+        Labels::Custom(names) => names
+            .get(index)
+            .cloned()
+            .unwrap_or_else(|| (index + 1).to_string()),
+    }
+}
+
+/// Name of `size` faders from firmware index `start`, e.g. "3" or "3-5".
+/// Reversed numbering still reads low to high ("12-14").
+pub fn range(start: usize, size: usize) -> String {
+    if size <= 1 {
+        return label(start);
+    }
+    let (first, last) = (label(start), label(start + size - 1));
+    if reversed() {
+        format!("{}-{}", last, first)
+    } else {
+        format!("{}-{}", first, last)
+    }
+}
+
+/// Parse one fader name into its 1-based firmware number.
+pub fn parse(s: &str) -> Result<u8> {
+    let s = s.trim();
+    let index = match labels() {
+        Labels::Custom(names) => match names.iter().position(|n| n.eq_ignore_ascii_case(s)) {
+            Some(i) => i,
+            None => invalid!(
+                "Unknown fader '{}' (faders are labeled {})",
+                s,
+                names.join(", ")
+            ),
+        },
+        numbered => match s.parse::<usize>() {
+            Ok(n) if (1..=GLOBAL_CHANNELS).contains(&n) => match numbered {
+                Labels::Reversed => GLOBAL_CHANNELS - n,
+                _ => n - 1,
+            },
+            _ => invalid!("Slot must be 1-{}, got '{}'", GLOBAL_CHANNELS, s),
+        },
+    };
+    Ok(index as u8 + 1)
+}
+
+/// Parse a slot list like `8`, `1-4`, or `1,3,9-12` into sorted, unique
+/// firmware numbers.
+pub fn parse_list(s: &str) -> Result<Vec<u8>> {
+    // Position of a fader in naming order, for checking a range's direction
+    let order = |slot: u8| {
+        if reversed() {
+            GLOBAL_CHANNELS as u8 + 1 - slot
+        } else {
+            slot
+        }
+    };
+    let mut slots = Vec::new();
+    for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (first, last) = match part.split_once('-') {
+            Some((a, b)) => (a.trim(), b.trim()),
+            None => (part, part),
+        };
+        let parse = |v: &str| parse(v).with_context(|| format!("Invalid slot '{}' in '{}'", v, s));
+        let (first, last) = (parse(first)?, parse(last)?);
+        if order(first) > order(last) {
+            invalid!("Invalid slot range '{}' (start is after end)", part);
+        }
+        slots.extend(first.min(last)..=first.max(last));
+    }
+    if slots.is_empty() {
+        invalid!("No slots given");
+    }
+    slots.sort_unstable();
+    slots.dedup();
+    Ok(slots)
+}
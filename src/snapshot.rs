@@ -0,0 +1,767 @@
+// Snapshot files: the JSON format written by `save` and read by `load`,
+// profiles, and anything else that stores device state on disk.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::audit::{self, Change};
+use crate::protocol::{GLOBAL_CHANNELS, GlobalConfig, Layout, Value};
+use crate::slots;
+use crate::template;
+
+/// Format version written by this build. Bump when the layout of the file changes.
+pub const CURRENT_VERSION: u32 = 1;
+
+/// Complete device state as stored on disk.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Files written before versioning have no version field and count as 0.
+    #[serde(default)]
+    pub version: u32,
+    pub global_config: GlobalConfig,
+    pub layout: Layout,
+    /// Param values per app instance. Missing in older files.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub params: Vec<AppParams>,
+    #[serde(default)]
+    pub metadata: Metadata,
+}
+
+/// Param values for one app instance.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AppParams {
+    pub layout_id: u8,
+    pub values: Vec<Value>,
+}
+
+/// Informational fields; never sent to the device.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Metadata {
+    /// When the snapshot was taken (RFC 3339).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub saved_at: Option<String>,
+    /// Version of the CLI that wrote the file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cli_version: Option<String>,
+    /// Apps referenced by the layout, so the file stays readable (and
+    /// remappable) without the device that produced it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub apps: Vec<AppRef>,
+    /// Fader groups (name → slots) when the snapshot was taken; loading the
+    /// snapshot makes them the current groups.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub groups: BTreeMap<String, Vec<u8>>,
+    /// Notes on faders (slot → text), restored on load like the groups.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub notes: BTreeMap<u8, String>,
+    /// State revision, in the state mirror: `daemon mirror` adds one each
+    /// time it finds the device state changed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<u32>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AppRef {
+    pub app_id: u8,
+    pub name: String,
+    pub channels: usize,
+}
+
+impl Metadata {
+    /// Metadata stamped with the current time and CLI version.
+    pub fn now(apps: Vec<AppRef>) -> Self {
+        Metadata {
+            saved_at: Some(
+                humantime::format_rfc3339_seconds(std::time::SystemTime::now()).to_string(),
+            ),
+            cli_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            apps,
+            groups: BTreeMap::new(),
+            notes: BTreeMap::new(),
+            revision: None,
+        }
+    }
+}
+
+impl Snapshot {
+    /// Read and parse a snapshot file, resolving template variables from
+    /// `vars` and the environment. Parse errors include the line and column.
+    pub fn read(path: &Path, vars: &[(String, String)]) -> Result<Self> {
+        Self::read_layered(path, &[], vars)
+    }
+
+    /// Read a snapshot file with overlays applied in order. Overlays are
+    /// partial snapshots merged in with JSON merge patch (RFC 7396) semantics:
+    /// objects merge key by key, `null` removes a key, anything else replaces.
+    pub fn read_layered(
+        path: &Path,
+        overlays: &[&Path],
+        vars: &[(String, String)],
+    ) -> Result<Self> {
+        let mut json = read_json(path)?;
+        for overlay in overlays {
+            merge_patch(&mut json, read_json(overlay)?);
+        }
+        Self::from_json(json, vars).with_context(|| format!("Invalid snapshot {}", path.display()))
+    }
+
+    fn from_json(mut json: serde_json::Value, vars: &[(String, String)]) -> Result<Self> {
+        if json.get("devices").is_some() {
+            anyhow::bail!("this file describes a surface of several units; only 'load' takes it");
+        }
+        template::expand(&mut json, vars)?;
+        let snapshot: Snapshot = serde_json::from_value(json)?;
+        if snapshot.version > CURRENT_VERSION {
+            anyhow::bail!(
+                "snapshot format version {} is newer than this CLI supports ({}); please upgrade",
+                snapshot.version,
+                CURRENT_VERSION
+            );
+        }
+        Ok(snapshot)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Stable hash of the device state in the snapshot (config, layout,
+    /// params), as hex. Metadata doesn't count, and params are hashed in
+    /// layout_id order so the order the device reported them in doesn't matter.
+    pub fn fingerprint(&self) -> Result<String> {
+        let mut params: Vec<&AppParams> = self.params.iter().collect();
+        params.sort_by_key(|p| p.layout_id);
+        // serde_json objects keep keys sorted, so this encoding is canonical
+        let state = serde_json::json!({
+            "global_config": self.global_config,
+            "layout": self.layout,
+            "params": params,
+        });
+        let digest = Sha256::digest(serde_json::to_vec(&state)?);
+        Ok(digest.iter().map(|b| format!("{:02x}", b)).collect())
+    }
+
+    /// An overlay holding only what differs from `base`, for `load --overlay`.
+    /// Metadata is left out. Arrays (layout, params) are included whole when
+    /// anything in them changed, as merge patches can't address elements.
+    pub fn delta_from(&self, base: &Snapshot) -> Result<serde_json::Value> {
+        let mut base = serde_json::to_value(base)?;
+        let mut this = serde_json::to_value(self)?;
+        for json in [&mut base, &mut this] {
+            if let Some(map) = json.as_object_mut() {
+                map.remove("metadata");
+                map.remove("version");
+            }
+        }
+        Ok(merge_diff(&base, &this).unwrap_or_else(|| serde_json::json!({})))
+    }
+
+    /// Stored param values for an app instance, if any.
+    pub fn params_for(&self, layout_id: u8) -> Option<&[Value]> {
+        self.params
+            .iter()
+            .find(|p| p.layout_id == layout_id)
+            .map(|p| p.values.as_slice())
+    }
+}
+
+// ── Surfaces ──
+
+/// A snapshot file, as `load` takes it: one unit's state, or a surface
+/// spanning several.
+pub enum SnapshotFile {
+    Device(Box<Snapshot>),
+    Surface(Box<Surface>),
+}
+
+impl SnapshotFile {
+    /// Read either kind of file, with overlays and template variables as for
+    /// `Snapshot::read_layered`. Surfaces are told apart by their `devices`.
+    pub fn read_layered(
+        path: &Path,
+        overlays: &[&Path],
+        vars: &[(String, String)],
+    ) -> Result<Self> {
+        let mut json = read_json(path)?;
+        for overlay in overlays {
+            merge_patch(&mut json, read_json(overlay)?);
+        }
+        if json.get("devices").is_none() {
+            return Snapshot::from_json(json, vars)
+                .map(|snapshot| SnapshotFile::Device(Box::new(snapshot)))
+                .with_context(|| format!("Invalid snapshot {}", path.display()));
+        }
+        Surface::from_json(json, vars)
+            .map(|surface| SnapshotFile::Surface(Box::new(surface)))
+            .with_context(|| format!("Invalid surface {}", path.display()))
+    }
+}
+
+/// Several units managed as one row of faders: the first unit in `devices`
+/// takes faders 1-16 of the layout, the second 17-32, and so on.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Surface {
+    #[serde(default)]
+    pub version: u32,
+    pub devices: Vec<SurfaceDevice>,
+    /// Config for every unit that doesn't have its own.
+    pub global_config: GlobalConfig,
+    /// 16 entries per unit; layout_ids are unique across the whole surface.
+    pub layout: Vec<Option<(u8, usize, u8)>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub params: Vec<AppParams>,
+    #[serde(default)]
+    pub metadata: Metadata,
+}
+
+/// A unit of a surface.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SurfaceDevice {
+    /// USB serial number.
+    pub serial: String,
+    /// Overrides the surface's config for this unit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub global_config: Option<GlobalConfig>,
+}
+
+impl Surface {
+    fn from_json(mut json: serde_json::Value, vars: &[(String, String)]) -> Result<Self> {
+        template::expand(&mut json, vars)?;
+        let surface: Surface = serde_json::from_value(json)?;
+        if surface.version > CURRENT_VERSION {
+            anyhow::bail!(
+                "snapshot format version {} is newer than this CLI supports ({}); please upgrade",
+                surface.version,
+                CURRENT_VERSION
+            );
+        }
+        Ok(surface)
+    }
+
+    /// One snapshot per unit, by serial number. Fails if the layout doesn't
+    /// have 16 faders per unit, an app would straddle two units, or a
+    /// layout_id is used twice.
+    pub fn split(&self) -> Result<Vec<(String, Snapshot)>> {
+        if self.devices.is_empty() {
+            anyhow::bail!("no devices listed");
+        }
+        let faders = GLOBAL_CHANNELS * self.devices.len();
+        if self.layout.len() != faders {
+            anyhow::bail!(
+                "layout has {} faders; {} unit(s) need exactly {}",
+                self.layout.len(),
+                self.devices.len(),
+                faders
+            );
+        }
+        let mut seen: Vec<u8> = Vec::new();
+        for (i, slot) in self.layout.iter().enumerate() {
+            let Some((app_id, ch, layout_id)) = *slot else {
+                continue;
+            };
+            if i % GLOBAL_CHANNELS + ch > GLOBAL_CHANNELS {
+                anyhow::bail!(
+                    "app {} at fader {} needs {} faders and would straddle two units",
+                    app_id,
+                    i + 1,
+                    ch
+                );
+            }
+            if seen.contains(&layout_id) {
+                anyhow::bail!("layout_id {} is used twice (fader {})", layout_id, i + 1);
+            }
+            seen.push(layout_id);
+        }
+
+        let mut parts = Vec::new();
+        for (device, faders) in self.devices.iter().zip(self.layout.chunks(GLOBAL_CHANNELS)) {
+            let mut layout = Layout([None; GLOBAL_CHANNELS]);
+            layout.0.copy_from_slice(faders);
+            let placed = faders.iter().flatten();
+            let params = self
+                .params
+                .iter()
+                .filter(|p| placed.clone().any(|(_, _, id)| *id == p.layout_id))
+                .cloned()
+                .collect();
+            let apps = self
+                .metadata
+                .apps
+                .iter()
+                .filter(|a| placed.clone().any(|(id, _, _)| *id == a.app_id))
+                .cloned()
+                .collect();
+            let global_config = device.global_config.as_ref().unwrap_or(&self.global_config);
+            let snapshot = Snapshot {
+                version: CURRENT_VERSION,
+                global_config: global_config.clone(),
+                layout,
+                params,
+                metadata: Metadata {
+                    apps,
+                    ..Metadata::default()
+                },
+            };
+            parts.push((device.serial.clone(), snapshot));
+        }
+        Ok(parts)
+    }
+}
+
+fn read_json(path: &Path) -> Result<serde_json::Value> {
+    let data = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&data).with_context(|| format!("Invalid JSON in {}", path.display()))
+}
+
+/// Apply a JSON merge patch (RFC 7396) to `target`.
+fn merge_patch(target: &mut serde_json::Value, patch: serde_json::Value) {
+    let serde_json::Value::Object(patch) = patch else {
+        *target = patch;
+        return;
+    };
+    if !target.is_object() {
+        *target = serde_json::Value::Object(Default::default());
+    }
+    let map = target.as_object_mut().expect("target is an object");
+    for (key, value) in patch {
+        if value.is_null() {
+            map.remove(&key);
+        } else {
+            merge_patch(map.entry(key).or_insert(serde_json::Value::Null), value);
+        }
+    }
+}
+
+/// The merge patch that turns `base` into `target`; None if they're equal.
+fn merge_diff(base: &serde_json::Value, target: &serde_json::Value) -> Option<serde_json::Value> {
+    let (Some(base), Some(target)) = (base.as_object(), target.as_object()) else {
+        return (base != target).then(|| target.clone());
+    };
+    let mut patch = serde_json::Map::new();
+    for (key, value) in target {
+        let old = base.get(key).unwrap_or(&serde_json::Value::Null);
+        if let Some(sub) = merge_diff(old, value) {
+            patch.insert(key.clone(), sub);
+        }
+    }
+    for key in base.keys().filter(|k| !target.contains_key(*k)) {
+        patch.insert(key.clone(), serde_json::Value::Null);
+    }
+    (!patch.is_empty()).then_some(serde_json::Value::Object(patch))
+}
+
+/// One difference between two snapshots.
+#[derive(Debug)]
+pub enum Difference {
+    /// A global config field; the path is relative to `global_config`.
+    Config(Change),
+    /// A layout slot (0-based fader index).
+    Layout {
+        fader: usize,
+        old: Option<(u8, usize, u8)>,
+        new: Option<(u8, usize, u8)>,
+    },
+    /// A param value of an app instance.
+    Param {
+        layout_id: u8,
+        index: usize,
+        old: Option<Value>,
+        new: Option<Value>,
+    },
+}
+
+impl Difference {
+    /// Plan marker: `+` something appears, `-` it goes away, `~` it changes.
+    pub fn symbol(&self) -> char {
+        let (was, is) = match self {
+            Difference::Config(_) => (true, true),
+            Difference::Layout { old, new, .. } => (old.is_some(), new.is_some()),
+            Difference::Param { old, new, .. } => (old.is_some(), new.is_some()),
+        };
+        match (was, is) {
+            (false, true) => '+',
+            (true, false) => '-',
+            _ => '~',
+        }
+    }
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let opt = |v: Option<String>| v.unwrap_or_else(|| "-".to_string());
+        match self {
+            Difference::Config(c) => write!(f, "config {}: {} → {}", c.field, c.old, c.new),
+            Difference::Layout { fader, old, new } => write!(
+                f,
+                "fader {}: {} → {}",
+                slots::label(*fader),
+                opt(old.map(|(app, ch, lid)| format!("app {} ({} ch, layout_id {})", app, ch, lid))),
+                opt(new.map(|(app, ch, lid)| format!("app {} ({} ch, layout_id {})", app, ch, lid))),
+            ),
+            Difference::Param {
+                layout_id,
+                index,
+                old,
+                new,
+            } => write!(
+                f,
+                "layout_id {} param {}: {} → {}",
+                layout_id,
+                index,
+                opt(old.map(|v| format!("{:?}", v))),
+                opt(new.map(|v| format!("{:?}", v))),
+            ),
+        }
+    }
+}
+
+impl Snapshot {
+    /// Everything that differs going from `self` to `other`. Metadata is ignored.
+    pub fn diff(&self, other: &Snapshot) -> Result<Vec<Difference>> {
+        let mut out: Vec<Difference> = audit::diff(
+            &serde_json::to_value(&self.global_config)?,
+            &serde_json::to_value(&other.global_config)?,
+        )
+        .into_iter()
+        .map(Difference::Config)
+        .collect();
+
+        for (fader, (old, new)) in self.layout.0.iter().zip(&other.layout.0).enumerate() {
+            if old != new {
+                out.push(Difference::Layout {
+                    fader,
+                    old: *old,
+                    new: *new,
+                });
+            }
+        }
+
+        let mut layout_ids: Vec<u8> = self
+            .params
+            .iter()
+            .chain(&other.params)
+            .map(|p| p.layout_id)
+            .collect();
+        layout_ids.sort_unstable();
+        layout_ids.dedup();
+        for layout_id in layout_ids {
+            let old = self.params_for(layout_id).unwrap_or_default();
+            let new = other.params_for(layout_id).unwrap_or_default();
+            for index in 0..old.len().max(new.len()) {
+                let (o, n) = (old.get(index).copied(), new.get(index).copied());
+                if o != n {
+                    out.push(Difference::Param {
+                        layout_id,
+                        index,
+                        old: o,
+                        new: n,
+                    });
+                }
+            }
+        }
+
+        Ok(out)
+    }
+    /// A copy of `self` with the given differences (as found by
+    /// `self.diff(other)`) taken from `other` instead. Layout differences
+    /// can't be taken piecemeal and are ignored.
+    pub fn with_changes_from(&self, other: &Snapshot, keep: &[&Difference]) -> Result<Snapshot> {
+        let mut merged = self.clone();
+        let mut config = serde_json::to_value(&merged.global_config)?;
+        for diff in keep {
+            match diff {
+                Difference::Config(change) => {
+                    audit::set(&mut config, &change.field, change.new.clone());
+                }
+                Difference::Layout { .. } => {}
+                Difference::Param {
+                    layout_id, index, ..
+                } => {
+                    let Some(value) = other
+                        .params_for(*layout_id)
+                        .and_then(|v| v.get(*index))
+                        .copied()
+                    else {
+                        continue;
+                    };
+                    if let Some(p) = merged.params.iter_mut().find(|p| p.layout_id == *layout_id)
+                        && let Some(slot) = p.values.get_mut(*index)
+                    {
+                        *slot = value;
+                    }
+                }
+            }
+        }
+        merged.global_config = serde_json::from_value(config)?;
+        Ok(merged)
+    }
+
+    /// Three-way merge of two snapshots that both started out as `self`.
+    /// Changes made on one side only are all kept. Where both sides changed
+    /// the same value differently, `ours` wins and the change `theirs` made
+    /// is returned as a conflict. The layout counts as one value, since a
+    /// layout can't be merged fader by fader. Metadata comes from `ours`.
+    pub fn merge(&self, ours: &Snapshot, theirs: &Snapshot) -> Result<(Snapshot, Vec<Difference>)> {
+        let mut merged = ours.clone();
+        let mut conflicts: Vec<Difference> = Vec::new();
+
+        if ours.layout.0 == self.layout.0 {
+            merged.layout = theirs.layout.clone();
+        } else if theirs.layout.0 != self.layout.0 && theirs.layout.0 != ours.layout.0 {
+            conflicts.extend(
+                self.diff(theirs)?
+                    .into_iter()
+                    .filter(|d| matches!(d, Difference::Layout { .. })),
+            );
+        }
+
+        let base_config = serde_json::to_value(&self.global_config)?;
+        let mut config = serde_json::to_value(&ours.global_config)?;
+        let ours_changes = audit::diff(&base_config, &config);
+        for change in audit::diff(&base_config, &serde_json::to_value(&theirs.global_config)?) {
+            match ours_changes.iter().find(|c| c.field == change.field) {
+                Some(c) if c.new != change.new => conflicts.push(Difference::Config(change)),
+                Some(_) => {}
+                None => {
+                    audit::set(&mut config, &change.field, change.new);
+                }
+            }
+        }
+        merged.global_config = serde_json::from_value(config)?;
+
+        // Params follow the merged layout. A side only counts for a layout_id
+        // if the same app sits there, so values never cross between apps.
+        let app_of = |s: &Snapshot, layout_id: u8| {
+            s.layout
+                .0
+                .iter()
+                .flatten()
+                .find(|(_, _, id)| *id == layout_id)
+                .map(|(app_id, ..)| *app_id)
+        };
+        merged.params.clear();
+        for (app_id, _, layout_id) in merged.layout.0.iter().flatten() {
+            if merged.params.iter().any(|p| p.layout_id == *layout_id) {
+                continue;
+            }
+            let side = |s: &Snapshot| match app_of(s, *layout_id) {
+                Some(id) if id == *app_id => s.params_for(*layout_id).unwrap_or_default().to_vec(),
+                _ => Vec::new(),
+            };
+            let (base, o, t) = (side(self), side(ours), side(theirs));
+            let mut values = Vec::new();
+            for index in 0..base.len().max(o.len()).max(t.len()) {
+                let (b, o, t) = (base.get(index), o.get(index), t.get(index));
+                let value = if o == t || t == b {
+                    o
+                } else if o == b {
+                    t
+                } else {
+                    conflicts.push(Difference::Param {
+                        layout_id: *layout_id,
+                        index,
+                        old: b.copied(),
+                        new: t.copied(),
+                    });
+                    o
+                };
+                match value {
+                    Some(v) => values.push(*v),
+                    None => break,
+                }
+            }
+            if !values.is_empty() {
+                merged.params.push(AppParams {
+                    layout_id: *layout_id,
+                    values,
+                });
+            }
+        }
+
+        Ok((merged, conflicts))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{
+        AuxJackMode, ClockConfig, ClockSrc, I2cMode, Key, MidiConfig, MidiOutConfig, MidiOutMode,
+        Note, QuantizerConfig, ResetSrc, TakeoverMode,
+    };
+    use serde_json::json;
+
+    fn config(bpm: f32) -> GlobalConfig {
+        let out = MidiOutConfig {
+            send_clock: false,
+            send_transport: false,
+            mode: MidiOutMode::None,
+        };
+        GlobalConfig {
+            aux: [AuxJackMode::None; 3],
+            clock: ClockConfig {
+                clock_src: ClockSrc::Internal,
+                ext_ppqn: 24,
+                reset_src: ResetSrc::None,
+                internal_bpm: bpm,
+                swing_amount: 0,
+            },
+            i2c_mode: I2cMode::Leader,
+            led_brightness: 150,
+            midi: MidiConfig {
+                outs: [out.clone(), out.clone(), out],
+            },
+            quantizer: QuantizerConfig {
+                key: Key::Chromatic,
+                tonic: Note::C,
+            },
+            takeover_mode: TakeoverMode::Pickup,
+        }
+    }
+
+    /// One app (app_id 1, layout_id 0) on the first fader, with Int params.
+    fn snapshot(bpm: f32, params: &[i32]) -> Snapshot {
+        let mut layout = Layout([None; GLOBAL_CHANNELS]);
+        layout.0[0] = Some((1, 1, 0));
+        Snapshot {
+            version: CURRENT_VERSION,
+            global_config: config(bpm),
+            layout,
+            params: vec![AppParams {
+                layout_id: 0,
+                values: params.iter().map(|v| Value::Int(*v)).collect(),
+            }],
+            metadata: Metadata::default(),
+        }
+    }
+
+    #[test]
+    fn merge_patch_merges_objects_and_removes_nulls() {
+        let mut target = json!({"a": {"b": 1, "c": 2}, "d": [1, 2], "e": 5});
+        merge_patch(&mut target, json!({"a": {"b": 3}, "d": [3], "e": null, "f": {"g": 1}}));
+        assert_eq!(target, json!({"a": {"b": 3, "c": 2}, "d": [3], "f": {"g": 1}}));
+
+        let mut scalar = json!(1);
+        merge_patch(&mut scalar, json!({"a": 1}));
+        assert_eq!(scalar, json!({"a": 1}));
+    }
+
+    #[test]
+    fn merge_diff_undoes_to_the_target() {
+        let base = json!({"a": {"b": 1, "c": 2}, "d": [1, 2], "e": 5});
+        let target = json!({"a": {"b": 1, "c": 3}, "d": [1], "f": true});
+        let patch = merge_diff(&base, &target).unwrap();
+        assert_eq!(patch, json!({"a": {"c": 3}, "d": [1], "e": null, "f": true}));
+
+        let mut applied = base.clone();
+        merge_patch(&mut applied, patch);
+        assert_eq!(applied, target);
+        assert_eq!(merge_diff(&target, &target), None);
+    }
+
+    #[test]
+    fn merge_keeps_changes_from_both_sides() {
+        let base = snapshot(120.0, &[1, 2, 3]);
+        let ours = snapshot(120.0, &[9, 2, 3]);
+        let theirs = snapshot(90.0, &[1, 2, 7]);
+        let (merged, conflicts) = base.merge(&ours, &theirs).unwrap();
+        assert!(conflicts.is_empty(), "{:?}", conflicts);
+        assert_eq!(merged.global_config.clock.internal_bpm, 90.0);
+        assert_eq!(
+            merged.params_for(0).unwrap(),
+            [Value::Int(9), Value::Int(2), Value::Int(7)]
+        );
+    }
+
+    #[test]
+    fn merge_reports_conflicts_and_keeps_ours() {
+        let base = snapshot(120.0, &[1]);
+        let ours = snapshot(100.0, &[5]);
+        let theirs = snapshot(90.0, &[6]);
+        let (merged, conflicts) = base.merge(&ours, &theirs).unwrap();
+        assert_eq!(merged.global_config.clock.internal_bpm, 100.0);
+        assert_eq!(merged.params_for(0).unwrap(), [Value::Int(5)]);
+        assert_eq!(conflicts.len(), 2);
+        assert!(matches!(
+            conflicts[1],
+            Difference::Param {
+                layout_id: 0,
+                index: 0,
+                new: Some(Value::Int(6)),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn merge_keeps_params_with_their_app() {
+        let base = snapshot(120.0, &[1, 2]);
+        let ours = snapshot(120.0, &[1, 5]);
+        let mut theirs = snapshot(120.0, &[4]);
+        theirs.layout.0[0] = Some((2, 1, 0));
+        let (merged, conflicts) = base.merge(&ours, &theirs).unwrap();
+        assert!(conflicts.is_empty(), "{:?}", conflicts);
+        assert_eq!(merged.layout.0[0], Some((2, 1, 0)));
+        // The new app gets its own values, not the old app's edits
+        assert_eq!(merged.params_for(0).unwrap(), [Value::Int(4)]);
+    }
+
+    fn surface(layout: Vec<Option<(u8, usize, u8)>>) -> Surface {
+        let device = |serial: &str| SurfaceDevice {
+            serial: serial.to_string(),
+            global_config: None,
+        };
+        Surface {
+            version: CURRENT_VERSION,
+            devices: vec![device("A"), device("B")],
+            global_config: config(120.0),
+            layout,
+            params: vec![
+                AppParams {
+                    layout_id: 0,
+                    values: vec![Value::Int(1)],
+                },
+                AppParams {
+                    layout_id: 1,
+                    values: vec![Value::Int(2)],
+                },
+            ],
+            metadata: Metadata::default(),
+        }
+    }
+
+    #[test]
+    fn split_gives_each_unit_its_faders_and_params() {
+        let mut layout = vec![None; 2 * GLOBAL_CHANNELS];
+        layout[0] = Some((1, 1, 0));
+        layout[GLOBAL_CHANNELS] = Some((2, 4, 1));
+        let parts = surface(layout).split().unwrap();
+        assert_eq!(parts.len(), 2);
+        let (serial, second) = &parts[1];
+        assert_eq!(serial, "B");
+        assert_eq!(second.layout.0[0], Some((2, 4, 1)));
+        assert_eq!(second.params_for(1).unwrap(), [Value::Int(2)]);
+        assert!(second.params_for(0).is_none());
+    }
+
+    #[test]
+    fn split_refuses_bad_layouts() {
+        assert!(surface(vec![None; GLOBAL_CHANNELS]).split().is_err());
+
+        let mut straddling = vec![None; 2 * GLOBAL_CHANNELS];
+        straddling[GLOBAL_CHANNELS - 1] = Some((2, 2, 0));
+        assert!(surface(straddling).split().is_err());
+
+        let mut reused = vec![None; 2 * GLOBAL_CHANNELS];
+        reused[0] = Some((1, 1, 0));
+        reused[GLOBAL_CHANNELS] = Some((1, 1, 0));
+        assert!(surface(reused).split().is_err());
+    }
+}
@@ -0,0 +1,125 @@
+// Template variables in snapshot files: `${NAME}` and `${NAME:-default}`
+// placeholders in JSON strings, resolved from `--var NAME=value` or the
+// environment when the file is read.
+
+use anyhow::Result;
+use serde_json::Value as Json;
+
+/// Replace every placeholder in `json`. A string that is exactly one
+/// placeholder takes the type of its value (`"${BPM:-120}"` becomes the
+/// number 120); placeholders inside longer strings are interpolated as text.
+pub fn expand(json: &mut Json, vars: &[(String, String)]) -> Result<()> {
+    match json {
+        Json::String(s) if s.contains("${") => {
+            let text = interpolate(s, vars)?;
+            *json = if is_single_placeholder(s) {
+                serde_json::from_str(&text).unwrap_or(Json::String(text))
+            } else {
+                Json::String(text)
+            };
+        }
+        Json::Array(items) => {
+            for item in items {
+                expand(item, vars)?;
+            }
+        }
+        Json::Object(map) => {
+            for value in map.values_mut() {
+                expand(value, vars)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Parse a `NAME=value` argument.
+pub fn parse_var(s: &str) -> Result<(String, String)> {
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("Invalid variable '{}': expected NAME=value", s))?;
+    if name.is_empty() {
+        anyhow::bail!("Invalid variable '{}': name is empty", s);
+    }
+    Ok((name.to_string(), value.to_string()))
+}
+
+fn is_single_placeholder(s: &str) -> bool {
+    s.starts_with("${") && s.find('}') == Some(s.len() - 1)
+}
+
+fn interpolate(s: &str, vars: &[(String, String)]) -> Result<String> {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| anyhow::anyhow!("Unterminated placeholder in \"{}\"", s))?;
+        out.push_str(&resolve(&after[..end], vars)?);
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Value of one placeholder body (`NAME` or `NAME:-default`). `--var` wins
+/// over the environment; the default is used when neither is set.
+fn resolve(body: &str, vars: &[(String, String)]) -> Result<String> {
+    let (name, default) = match body.split_once(":-") {
+        Some((name, default)) => (name, Some(default)),
+        None => (body, None),
+    };
+    if let Some((_, value)) = vars.iter().rev().find(|(n, _)| n == name) {
+        return Ok(value.clone());
+    }
+    if let Ok(value) = std::env::var(name) {
+        return Ok(value);
+    }
+    default.map(str::to_string).ok_or_else(|| {
+        anyhow::anyhow!(
+            "Variable '{}' is not set; pass --var {}=... or set it in the environment",
+            name,
+            name
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn expand_types_whole_placeholders_and_interpolates_the_rest() {
+        let mut json = json!({
+            "bpm": "${FP_TEST_BPM}",
+            "name": "set ${FP_TEST_NAME}!",
+            "list": ["${FP_TEST_UNSET:-true}", 3],
+        });
+        expand(&mut json, &vars(&[("FP_TEST_BPM", "96.5"), ("FP_TEST_NAME", "A")])).unwrap();
+        assert_eq!(json, json!({"bpm": 96.5, "name": "set A!", "list": [true, 3]}));
+    }
+
+    #[test]
+    fn expand_uses_the_last_var_and_fails_on_unset() {
+        let mut json = json!("${FP_TEST_X}");
+        expand(&mut json, &vars(&[("FP_TEST_X", "1"), ("FP_TEST_X", "2")])).unwrap();
+        assert_eq!(json, json!(2));
+
+        assert!(expand(&mut json!("${FP_TEST_UNSET}"), &[]).is_err());
+        assert!(expand(&mut json!("${FP_TEST_X"), &[]).is_err());
+    }
+
+    #[test]
+    fn parse_var_needs_a_name() {
+        assert_eq!(parse_var("A=b=c").unwrap(), ("A".to_string(), "b=c".to_string()));
+        assert!(parse_var("=b").is_err());
+        assert!(parse_var("A").is_err());
+    }
+}
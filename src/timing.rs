@@ -0,0 +1,32 @@
+// Timing of device I/O for --profile: opening the device and every protocol
+// exchange, so a slow command can be traced to USB/firmware or to the CLI.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Recorded (label, duration) pairs; None while profiling is off.
+static TIMINGS: Mutex<Option<Vec<(String, Duration)>>> = Mutex::new(None);
+
+/// Start recording. Until this is called, `record` does nothing.
+pub fn enable() {
+    *TIMINGS.lock().unwrap() = Some(Vec::new());
+}
+
+/// Record that `what` took from `start` until now. The label is only built
+/// when profiling is on.
+pub fn record(what: impl FnOnce() -> String, start: Instant) {
+    let elapsed = start.elapsed();
+    if let Some(timings) = TIMINGS.lock().unwrap().as_mut() {
+        timings.push((what(), elapsed));
+    }
+}
+
+/// Everything recorded so far, in order, leaving the list empty.
+pub fn take() -> Vec<(String, Duration)> {
+    TIMINGS
+        .lock()
+        .unwrap()
+        .as_mut()
+        .map(std::mem::take)
+        .unwrap_or_default()
+}
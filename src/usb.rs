@@ -1,167 +1,1198 @@
 // USB transport layer for communicating with the Faderpunk.
 //
-// Protocol: postcard-serialized messages, framed with COBS encoding.
-// Wire format: [2-byte big-endian payload length] [postcard payload] → COBS encode → [0x00 delimiter]
+// Messages are framed as described in frame.rs and sent over the vendor-class
+// bulk endpoints.
+
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context as TaskContext, Poll};
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result, bail};
+use futures_core::Stream;
 use nusb::Interface;
-use nusb::transfer::RequestBuffer;
+use nusb::transfer::{Direction, RequestBuffer};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
 
-use crate::protocol::{ConfigMsgIn, ConfigMsgOut};
+use crate::audit;
+use crate::error::{self, ConnectionError, InterfaceHolder, message_name};
+use crate::frame::{self, Decoded};
+use crate::protocol::{APP_MAX_PARAMS, ConfigMsgIn, ConfigMsgOut, Value};
+use crate::router::{Reply, Router};
+use crate::session;
+use crate::slots;
+use crate::timing;
 
-const FADERPUNK_VID: u16 = 0xf569;
-const FADERPUNK_PID: u16 = 0x0001;
+/// USB vendor and product id of the Faderpunk.
+pub const FADERPUNK_VID: u16 = 0xf569;
+pub const FADERPUNK_PID: u16 = 0x0001;
 const USB_CLASS_VENDOR: u8 = 0xff;
-const USB_TRANSFER_SIZE: usize = 512;
-const FRAME_DELIMITER: u8 = 0x00;
+
+/// Words in an interface name that mark the configurator, for firmware that
+/// exposes more than one vendor-class interface.
+const CONFIG_INTERFACE_NAMES: &[&str] = &["config", "webusb"];
+
+/// The vendor and product id to look for, and the interface to claim (picked
+/// among the vendor-class ones if None); see `configure_device`.
+static DEVICE_IDS: Mutex<(u16, u16)> = Mutex::new((FADERPUNK_VID, FADERPUNK_PID));
+static INTERFACE: Mutex<Option<InterfaceChoice>> = Mutex::new(None);
+
+/// An interface asked for by number, or by (part of) its name.
+#[derive(Clone, Debug, PartialEq)]
+pub enum InterfaceChoice {
+    Number(u8),
+    Name(String),
+}
+
+impl std::fmt::Display for InterfaceChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InterfaceChoice::Number(number) => write!(f, "{}", number),
+            InterfaceChoice::Name(name) => write!(f, "'{}'", name),
+        }
+    }
+}
+
+/// An interface number, or a name to match (case-insensitively) against
+/// interface names.
+pub fn parse_interface(s: &str) -> Result<InterfaceChoice> {
+    if s.trim().is_empty() {
+        bail!("Empty interface (give a number or a name)");
+    }
+    Ok(match s.parse() {
+        Ok(number) => InterfaceChoice::Number(number),
+        Err(_) => InterfaceChoice::Name(s.to_string()),
+    })
+}
+
+/// USB full-speed bulk packet size; IN requests are a multiple of it.
+const MAX_PACKET_SIZE: usize = 64;
+
+pub const DEFAULT_TRANSFER_SIZE: usize = 512;
+pub const DEFAULT_QUEUE_DEPTH: usize = 4;
+const MAX_QUEUE_DEPTH: usize = 32;
+
+/// Bytes asked for by each IN request, and how many requests are kept queued
+/// at once so the device never waits for the host to ask for the next chunk.
+static TRANSFER_SIZE: AtomicUsize = AtomicUsize::new(DEFAULT_TRANSFER_SIZE);
+static QUEUE_DEPTH: AtomicUsize = AtomicUsize::new(DEFAULT_QUEUE_DEPTH);
+
+/// Set the IN transfer size and queue depth for connections opened from now on.
+pub fn configure_transfers(size: usize, depth: usize) -> Result<()> {
+    if size == 0 || !size.is_multiple_of(MAX_PACKET_SIZE) || size > 65536 {
+        bail!(
+            "USB transfer size must be a multiple of {} up to 65536, got {}",
+            MAX_PACKET_SIZE,
+            size
+        );
+    }
+    if !(1..=MAX_QUEUE_DEPTH).contains(&depth) {
+        bail!("USB queue depth must be 1-{}, got {}", MAX_QUEUE_DEPTH, depth);
+    }
+    TRANSFER_SIZE.store(size, Ordering::SeqCst);
+    QUEUE_DEPTH.store(depth, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Gaps the host leaves between writes, for firmware that drops frames
+/// arriving back to back.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Pacing {
+    /// Between the end of one message and the start of the next.
+    pub message_gap: Duration,
+    /// Between the 64-byte packets of one message.
+    pub chunk_gap: Duration,
+}
+
+impl Pacing {
+    pub fn is_none(&self) -> bool {
+        self.message_gap.is_zero() && self.chunk_gap.is_zero()
+    }
+}
+
+/// Firmware known to drop frames, by device release (bcdDevice): a release
+/// up to and including the first value gets that pacing. Anything newer
+/// than every entry gets none.
+const PACING_TABLE: &[(u16, Pacing)] = &[
+    // Pre-1.0 firmware handled one USB packet at a time between tasks
+    (
+        0x0099,
+        Pacing {
+            message_gap: Duration::from_millis(5),
+            chunk_gap: Duration::from_millis(1),
+        },
+    ),
+];
+
+/// Longest frame the CLI sends. The firmware reads each frame into a fixed
+/// buffer and drops one that doesn't fit, but libfp doesn't publish the
+/// buffer's size, so this is a host-side guess: every message the CLI builds
+/// from sane input fits well within it.
+pub const MAX_FRAME_SIZE: usize = 512;
+
+/// Pacing from config.toml, field by field over the table's.
+static PACING_OVERRIDE: Mutex<(Option<Duration>, Option<Duration>)> = Mutex::new((None, None));
+
+/// Override the message and/or packet gap for connections opened from now on.
+pub fn configure_pacing(message_gap: Option<Duration>, chunk_gap: Option<Duration>) {
+    *PACING_OVERRIDE.lock().unwrap() = (message_gap, chunk_gap);
+}
+
+/// Pacing for a device release: the table's, then any overrides.
+fn pacing_for(release: u16) -> Pacing {
+    let mut pacing = PACING_TABLE
+        .iter()
+        .find(|(up_to, _)| release <= *up_to)
+        .map(|(_, pacing)| *pacing)
+        .unwrap_or_default();
+    let (message_gap, chunk_gap) = *PACING_OVERRIDE.lock().unwrap();
+    pacing.message_gap = message_gap.unwrap_or(pacing.message_gap);
+    pacing.chunk_gap = chunk_gap.unwrap_or(pacing.chunk_gap);
+    pacing
+}
+
+/// Look for devices with this vendor and product id instead of the
+/// Faderpunk's (a development board, a fork), and claim `interface` instead
+/// of picking one. For connections opened from now on.
+pub fn configure_device(vid: u16, pid: u16, interface: Option<InterfaceChoice>) {
+    *DEVICE_IDS.lock().unwrap() = (vid, pid);
+    *INTERFACE.lock().unwrap() = interface;
+}
+
+/// The vendor and product id devices are matched by.
+pub fn device_ids() -> (u16, u16) {
+    *DEVICE_IDS.lock().unwrap()
+}
+
+/// A vendor or product id in hex, with or without 0x.
+pub fn parse_id(s: &str) -> Result<u16> {
+    let digits = s
+        .strip_prefix("0x")
+        .or_else(|| s.strip_prefix("0X"))
+        .unwrap_or(s);
+    u16::from_str_radix(digits, 16)
+        .with_context(|| format!("Invalid USB id '{}' (4 hex digits, e.g. f569)", s))
+}
+
+fn matches_ids(device: &nusb::DeviceInfo) -> bool {
+    (device.vendor_id(), device.product_id()) == device_ids()
+}
+
+/// Fail writes the device applied differently than sent; see `set_strict`.
+static STRICT: AtomicBool = AtomicBool::new(false);
+
+/// Make writes whose reply shows the firmware corrected them (a layout it
+/// rearranged, a param it clamped) fail with `DeviceErrorKind::Corrected`
+/// instead of being accepted as the device's answer.
+pub fn set_strict(strict: bool) {
+    STRICT.store(strict, Ordering::SeqCst);
+}
 
 /// Represents a connected Faderpunk device.
+///
+/// A background task reads frames from the device and hands them to a
+/// `Router`, which delivers replies to the request that is waiting for them
+/// and broadcasts everything else to `subscribe`rs.
 pub struct FaderpunkDevice {
     iface: Interface,
-    recv_buf: Vec<u8>,
+    router: Router,
+    reader: Arc<Reader>,
+    tracker: audit::Tracker,
+    audit: bool,
+    cache: ReadCache,
+    usb: UsbInfo,
+    health: Arc<Mutex<Health>>,
+    pacing: Pacing,
+    /// When the last write to this connection finished, for `Pacing::message_gap`.
+    last_write: Arc<Mutex<Option<Instant>>>,
+}
+
+/// What the USB bus reports about the device.
+#[derive(Clone, Debug, Default)]
+pub struct UsbInfo {
+    /// Serial number, which tells units apart.
+    pub serial: Option<String>,
+    /// Negotiated bus speed, e.g. "full (12 Mbit/s)".
+    pub speed: Option<&'static str>,
+    /// Device release number (bcdDevice), as major.minor. The protocol has
+    /// no version handshake; firmware releases bump this instead.
+    pub release: String,
+    /// The same, raw.
+    pub version: u16,
+}
+
+/// How the connection has been doing, shared by every handle on it: for a
+/// batch or stdin session these cover the whole session.
+#[derive(Clone, Debug, Default)]
+pub struct Health {
+    /// Messages sent to the device.
+    pub sent: u64,
+    /// Requests the device refused, or that got no reply.
+    pub errors: u64,
+    /// Pings that got no answer in time.
+    pub timeouts: u64,
+    /// Round trip of the last ping answered.
+    pub last_rtt: Option<Duration>,
+}
+
+/// Replies to read requests already made on this handle, so a command whose
+/// helpers each ask for the layout or the app list only pays for it once.
+/// Each command opens its own handle, so nothing is reused across commands.
+/// A param write drops only that app's params, and the device's answer to it
+/// takes their place; any other write clears everything except the app list,
+/// which writes can't change. Entries are keyed by the serialized request.
+#[derive(Default)]
+struct ReadCache(Vec<(Vec<u8>, Vec<ConfigMsgOut>)>);
+
+impl ReadCache {
+    fn cacheable(msg: &ConfigMsgIn) -> bool {
+        matches!(
+            msg,
+            ConfigMsgIn::GetAllApps
+                | ConfigMsgIn::GetGlobalConfig
+                | ConfigMsgIn::GetLayout
+                | ConfigMsgIn::GetAllAppParams
+                | ConfigMsgIn::GetAppParams { .. }
+        )
+    }
+
+    fn key(msg: &ConfigMsgIn) -> Option<Vec<u8>> {
+        if !Self::cacheable(msg) {
+            return None;
+        }
+        postcard::to_allocvec(msg).ok()
+    }
+
+    fn get(&self, msg: &ConfigMsgIn) -> Option<Vec<ConfigMsgOut>> {
+        let key = Self::key(msg)?;
+        self.0
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, replies)| replies.clone())
+    }
+
+    fn store(&mut self, msg: &ConfigMsgIn, replies: &[ConfigMsgOut]) {
+        if let Some(key) = Self::key(msg) {
+            self.put(key, replies.to_vec());
+        }
+        // One app's params are also in a read of every app's, and in the
+        // device's answer to a write of them
+        if matches!(msg, ConfigMsgIn::GetAllAppParams | ConfigMsgIn::SetAppParams { .. }) {
+            for reply in replies {
+                if let ConfigMsgOut::AppState(layout_id, _) = reply
+                    && let Some(key) = Self::key(&ConfigMsgIn::GetAppParams {
+                        layout_id: *layout_id,
+                    })
+                {
+                    self.put(key, vec![reply.clone()]);
+                }
+            }
+        }
+    }
+
+    fn put(&mut self, key: Vec<u8>, replies: Vec<ConfigMsgOut>) {
+        self.0.retain(|(k, _)| *k != key);
+        self.0.push((key, replies));
+    }
+
+    fn forget(&mut self, msg: &ConfigMsgIn) {
+        let key = Self::key(msg);
+        self.0.retain(|(k, _)| Some(k) != key.as_ref());
+    }
+
+    fn invalidate_after(&mut self, msg: &ConfigMsgIn) {
+        if let ConfigMsgIn::SetAppParams { layout_id, .. } = msg {
+            self.forget(&ConfigMsgIn::GetAllAppParams);
+            self.forget(&ConfigMsgIn::GetAppParams {
+                layout_id: *layout_id,
+            });
+        } else if audit::mutation_name(msg).is_some() {
+            let apps = Self::key(&ConfigMsgIn::GetAllApps);
+            self.0.retain(|(k, _)| Some(k) == apps.as_ref());
+        }
+    }
+}
+
+/// The read task; stopped when the last handle using it goes away.
+struct Reader(JoinHandle<()>);
+
+impl Drop for Reader {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// When set, `open` hands out handles to one connection instead of opening
+/// a new one each time (see `share_connection`).
+static SHARING: AtomicBool = AtomicBool::new(false);
+static SHARED: Mutex<Option<FaderpunkDevice>> = Mutex::new(None);
+
+/// Make every later `open` reuse a single connection, so a run of commands
+/// (batch, stdin mode) talks to the device over one USB session.
+pub fn share_connection() {
+    SHARING.store(true, Ordering::SeqCst);
 }
 
 impl FaderpunkDevice {
-    /// Find and connect to a Faderpunk device.
+    /// Find and connect to a Faderpunk device, or reuse the shared connection.
     pub fn open() -> Result<Self> {
-        let device_info = nusb::list_devices()?
-            .find(|d| d.vendor_id() == FADERPUNK_VID && d.product_id() == FADERPUNK_PID)
-            .context("Faderpunk not found — is it connected via USB?")?;
+        if !SHARING.load(Ordering::SeqCst) {
+            return Self::connect(None);
+        }
+        let mut shared = SHARED.lock().unwrap();
+        if let Some(dev) = shared.as_ref()
+            && dev.router.closed_reason().is_none()
+        {
+            return Ok(dev.handle());
+        }
+        let dev = Self::connect(None)?;
+        let handle = dev.handle();
+        *shared = Some(dev);
+        Ok(handle)
+    }
 
-        let device = device_info.open()?;
+    /// Another handle on the same connection.
+    fn handle(&self) -> Self {
+        FaderpunkDevice {
+            iface: self.iface.clone(),
+            router: self.router.clone(),
+            reader: self.reader.clone(),
+            tracker: audit::Tracker::default(),
+            audit: true,
+            cache: ReadCache::default(),
+            usb: self.usb.clone(),
+            health: self.health.clone(),
+            pacing: self.pacing,
+            last_write: self.last_write.clone(),
+        }
+    }
 
-        // Find the vendor-class interface (0xff)
-        let config = device.active_configuration()?;
-        let iface_num = config
-            .interfaces()
-            .find(|i| {
-                i.alt_settings()
-                    .any(|a| a.class() == USB_CLASS_VENDOR)
-            })
-            .context("No WebUSB interface found on device")?
-            .interface_number();
+    /// Connect to the Faderpunk with this USB serial number, for when more
+    /// than one is plugged in. Never shared.
+    pub fn open_serial(serial: &str) -> Result<Self> {
+        Self::connect(Some(serial))
+    }
+
+    fn connect(serial: Option<&str>) -> Result<Self> {
+        let start = Instant::now();
+        let dev = Self::find_and_claim(serial);
+        timing::record(|| "find and open device".to_string(), start);
+        dev
+    }
 
-        let iface = device.claim_interface(iface_num)?;
+    fn find_and_claim(serial: Option<&str>) -> Result<Self> {
+        let (iface, usb) = claim(serial)?;
+        let ep_in = endpoint(&iface, Direction::In)?;
+
+        let router = Router::new();
+        let reader = tokio::spawn(read_loop(iface.clone(), ep_in, router.clone()));
 
         Ok(FaderpunkDevice {
             iface,
-            recv_buf: Vec::new(),
+            router,
+            reader: Arc::new(Reader(reader)),
+            tracker: audit::Tracker::default(),
+            audit: true,
+            cache: ReadCache::default(),
+            pacing: pacing_for(usb.version),
+            usb,
+            health: Arc::default(),
+            last_write: Arc::default(),
         })
     }
 
-    /// Send a message to the device.
-    pub async fn send(&self, msg: &ConfigMsgIn) -> Result<()> {
-        let serialized =
-            postcard::to_allocvec(msg).context("Failed to serialize message")?;
-
-        // Prepend 2-byte big-endian length
-        let payload_len = serialized.len();
-        let mut with_len = Vec::with_capacity(payload_len + 2);
-        with_len.push(((payload_len >> 8) & 0xFF) as u8);
-        with_len.push((payload_len & 0xFF) as u8);
-        with_len.extend_from_slice(&serialized);
-
-        // COBS encode
-        let mut cobs_buf = vec![0u8; with_len.len() + with_len.len() / 254 + 2];
-        let cobs_len = cobs::try_encode(&with_len, &mut cobs_buf)
-            .map_err(|_| anyhow::anyhow!("COBS encoding failed"))?;
-
-        // Append frame delimiter
-        let mut frame = Vec::with_capacity(cobs_len + 1);
-        frame.extend_from_slice(&cobs_buf[..cobs_len]);
-        frame.push(FRAME_DELIMITER);
-
-        // Find the bulk OUT endpoint
-        let ep_out = self
-            .iface
-            .descriptors()
-            .next()
-            .context("No alt setting")?
-            .endpoints()
-            .find(|e| e.direction() == nusb::transfer::Direction::Out)
-            .context("No OUT endpoint found")?
-            .address();
+    /// Send a message to the device without waiting for a reply.
+    /// Mutating messages are recorded in the audit log (and the session, with --record).
+    pub async fn send(&mut self, msg: &ConfigMsgIn) -> Result<()> {
+        let start = Instant::now();
+        let entry = self.audit_entry(msg);
+        self.write(msg).await?;
+        self.log_change(msg, entry);
+        timing::record(|| format!("{} (no reply)", message_name(msg)), start);
+        Ok(())
+    }
 
-        // Send in 64-byte chunks (USB max packet size)
-        for chunk in frame.chunks(64) {
-            self.iface.bulk_out(ep_out, chunk.to_vec()).await.into_result()?;
+    /// The audit log entry for `msg`, worked out before it's sent, while the
+    /// tracker still has the state it changes.
+    fn audit_entry(&self, msg: &ConfigMsgIn) -> Option<audit::Entry> {
+        if !self.audit {
+            return None;
         }
+        self.tracker.entry(msg).unwrap_or_else(|e| {
+            eprintln!("warning: could not write audit log: {:#}", e);
+            None
+        })
+    }
 
-        Ok(())
+    /// Log a change once the device has taken it.
+    fn log_change(&mut self, msg: &ConfigMsgIn, entry: Option<audit::Entry>) {
+        if let Some(entry) = entry
+            && let Err(e) = self.tracker.record(msg, &entry)
+        {
+            eprintln!("warning: could not write audit log: {:#}", e);
+        }
     }
 
-    /// Receive a single message from the device.
-    pub async fn receive(&mut self) -> Result<ConfigMsgOut> {
-        let ep_in = self
-            .iface
-            .descriptors()
-            .next()
-            .context("No alt setting")?
-            .endpoints()
-            .find(|e| e.direction() == nusb::transfer::Direction::In)
-            .context("No IN endpoint found")?
-            .address();
-
-        loop {
-            // Check if we already have a complete frame in the buffer
-            if let Some(delim_pos) = self.recv_buf.iter().position(|&b| b == FRAME_DELIMITER) {
-                let packet: Vec<u8> = self.recv_buf.drain(..=delim_pos).collect();
-                let frame = &packet[..packet.len() - 1]; // strip delimiter
-
-                if frame.is_empty() {
-                    continue;
-                }
+    async fn write(&mut self, msg: &ConfigMsgIn) -> Result<()> {
+        let frame = frame::encode(msg)?;
+        if frame.len() > MAX_FRAME_SIZE {
+            return Err(error::too_large(msg, frame.len(), MAX_FRAME_SIZE));
+        }
+
+        self.cache.invalidate_after(msg);
+        if let Err(e) = session::message(msg) {
+            eprintln!("warning: could not record the session: {:#}", e);
+        }
 
-                // COBS decode
-                let mut decode_buf = frame.to_vec();
-                let decoded_len = cobs::decode_in_place(&mut decode_buf)
-                    .map_err(|_| anyhow::anyhow!("COBS decode failed"))?;
+        self.health.lock().unwrap().sent += 1;
 
-                if decoded_len < 2 {
-                    bail!("Corrupted message (too short after COBS decode)");
-                }
+        let ep_out = endpoint(&self.iface, Direction::Out)?;
 
-                // Skip the 2-byte length prefix, deserialize the rest
-                let msg: ConfigMsgOut = postcard::from_bytes(&decode_buf[2..decoded_len])
-                    .context("Failed to deserialize device response")?;
+        let gap = self.pacing.message_gap;
+        let wait = self
+            .last_write
+            .lock()
+            .unwrap()
+            .map(|last| (last + gap).saturating_duration_since(Instant::now()));
+        if let Some(wait) = wait.filter(|w| !w.is_zero()) {
+            tokio::time::sleep(wait).await;
+        }
 
-                return Ok(msg);
+        // Send in 64-byte chunks (USB max packet size)
+        for (i, chunk) in frame.chunks(64).enumerate() {
+            if i > 0 && !self.pacing.chunk_gap.is_zero() {
+                tokio::time::sleep(self.pacing.chunk_gap).await;
             }
+            self.iface.bulk_out(ep_out, chunk.to_vec()).await.into_result()?;
+        }
+        *self.last_write.lock().unwrap() = Some(Instant::now());
+
+        Ok(())
+    }
+
+    /// Register for the reply to a request, then send it.
+    async fn request(&mut self, msg: &ConfigMsgIn) -> Result<Replies> {
+        let replies = self
+            .router
+            .expect(msg)?
+            .with_context(|| format!("{:?} has no reply", msg))?;
+        self.write(msg).await?;
+        Ok(replies)
+    }
 
-            // Need more data from USB
-            let data = self.iface.bulk_in(ep_in, RequestBuffer::new(USB_TRANSFER_SIZE)).await.into_result()?;
-            self.recv_buf.extend_from_slice(&data);
+    /// Wait for the next reply on a channel from `request`.
+    async fn next(&mut self, replies: &mut Replies) -> Result<ConfigMsgOut> {
+        match replies.recv().await {
+            Some(Ok(msg)) => {
+                self.tracker.observe(&msg);
+                Ok(msg)
+            }
+            Some(Err(e)) => {
+                self.health.lock().unwrap().errors += 1;
+                Err(e.into())
+            }
+            None => {
+                self.health.lock().unwrap().errors += 1;
+                let reason = self
+                    .router
+                    .closed_reason()
+                    .unwrap_or_else(|| "no reply".to_string());
+                Err(ConnectionError::Closed(reason).into())
+            }
         }
     }
 
     /// Send a message and receive the response.
     pub async fn send_receive(&mut self, msg: &ConfigMsgIn) -> Result<ConfigMsgOut> {
-        self.send(msg).await?;
-        self.receive().await
+        if let Some(reply) = self.cache.get(msg).and_then(|r| r.into_iter().next()) {
+            return Ok(reply);
+        }
+        let start = Instant::now();
+        let entry = self.audit_entry(msg);
+        let mut replies = self.request(msg).await?;
+        let reply = self.next(&mut replies).await?;
+        self.log_change(msg, entry);
+        timing::record(|| message_name(msg), start);
+        self.cache.store(msg, std::slice::from_ref(&reply));
+        check_strict(msg, &reply)?;
+        Ok(reply)
+    }
+
+    /// Write `values` to an app whose params were read as `read`, unless they
+    /// changed on the device since (or `force`). The check reads the params
+    /// from the device again, past the read cache, right before the write, so
+    /// only a change in the gap between the two goes unnoticed. Returns the
+    /// values the app has now.
+    pub async fn write_params(
+        &mut self,
+        layout_id: u8,
+        read: &[Value],
+        values: [Option<Value>; APP_MAX_PARAMS],
+        force: bool,
+    ) -> Result<Vec<Value>> {
+        let msg = ConfigMsgIn::SetAppParams { layout_id, values };
+        if !force {
+            let request = ConfigMsgIn::GetAppParams { layout_id };
+            // A cached reply may be the very read the caller started from
+            self.forget(&request);
+            let now = match self.send_receive(&request).await? {
+                ConfigMsgOut::AppState(_, values) => values,
+                other => return Err(error::unexpected(&request, &other)),
+            };
+            if now != read {
+                return Err(error::changed(&msg, param_differences(read, &now)));
+            }
+        }
+        match self.send_receive(&msg).await? {
+            ConfigMsgOut::AppState(_, values) => Ok(values),
+            other => Err(error::unexpected(&msg, &other)),
+        }
+    }
+
+    /// Send several requests back to back, then collect one response per request.
+    /// Pipelining avoids paying a full USB round trip for every message.
+    pub async fn send_receive_pipelined(&mut self, msgs: &[ConfigMsgIn]) -> Result<Vec<ConfigMsgOut>> {
+        let start = Instant::now();
+        // Only what isn't cached goes to the device
+        let mut results: Vec<Option<ConfigMsgOut>> = msgs
+            .iter()
+            .map(|msg| self.cache.get(msg).and_then(|r| r.into_iter().next()))
+            .collect();
+        let mut pending = Vec::new();
+        for (i, msg) in msgs.iter().enumerate() {
+            if results[i].is_none() {
+                let entry = self.audit_entry(msg);
+                pending.push((i, entry, self.request(msg).await?));
+            }
+        }
+        for (i, entry, replies) in &mut pending {
+            let reply = self.next(replies).await?;
+            self.log_change(&msgs[*i], entry.take());
+            self.cache.store(&msgs[*i], std::slice::from_ref(&reply));
+            check_strict(&msgs[*i], &reply)?;
+            results[*i] = Some(reply);
+        }
+        let results: Vec<ConfigMsgOut> = results.into_iter().flatten().collect();
+        timing::record(
+            || match msgs.first() {
+                Some(msg) => format!("{} ×{} (pipelined)", message_name(msg), msgs.len()),
+                None => "nothing (pipelined)".to_string(),
+            },
+            start,
+        );
+        Ok(results)
     }
 
     /// Send a message that triggers a batch response, collect all messages.
+    ///
+    /// Messages that don't belong to the batch (e.g. something the device pushes
+    /// mid-batch) go to subscribers instead of failing the batch.
+    /// A batch that ends early returns the items that did arrive.
     pub async fn send_receive_batch(&mut self, msg: &ConfigMsgIn) -> Result<Vec<ConfigMsgOut>> {
-        self.send(msg).await?;
+        let mut batch = self.stream_batch(msg).await?;
+        let mut results = Vec::with_capacity(batch.announced());
+        while let Some(item) = batch.next().await {
+            results.push(item?);
+        }
+        Ok(results)
+    }
+
+    /// Send a message that triggers a batch response and return the items as
+    /// a stream, so callers can use each one as soon as it arrives.
+    pub async fn stream_batch<'a>(&'a mut self, msg: &'a ConfigMsgIn) -> Result<BatchStream<'a>> {
+        if let Some(replies) = self.cache.get(msg) {
+            return Ok(BatchStream {
+                announced: replies.len(),
+                source: BatchSource::Cached(replies.into_iter()),
+                received: Vec::new(),
+                start: Instant::now(),
+                dev: self,
+                msg,
+            });
+        }
+        let start = Instant::now();
+        let mut replies = self.request(msg).await?;
 
         // First response should be BatchMsgStart(count)
-        let start = self.receive().await?;
-        let count = match start {
+        let count = match self.next(&mut replies).await? {
             ConfigMsgOut::BatchMsgStart(n) => n,
-            other => bail!("Expected BatchMsgStart, got: {:?}", other),
+            other => return Err(error::unexpected(msg, &other)),
         };
 
-        let mut results = Vec::with_capacity(count);
-        for _ in 0..count {
-            results.push(self.receive().await?);
+        Ok(BatchStream {
+            dev: self,
+            msg,
+            source: BatchSource::Live(replies),
+            announced: count,
+            received: Vec::with_capacity(count),
+            start,
+        })
+    }
+
+    /// Ping the device, failing if no Pong arrives within `timeout`.
+    /// Returns the round trip time.
+    pub async fn ping(&mut self, timeout: Duration) -> Result<Duration> {
+        let start = Instant::now();
+        match tokio::time::timeout(timeout, self.send_receive(&ConfigMsgIn::Ping)).await {
+            Ok(Ok(ConfigMsgOut::Pong)) => {
+                let rtt = start.elapsed();
+                self.health.lock().unwrap().last_rtt = Some(rtt);
+                Ok(rtt)
+            }
+            Ok(Ok(other)) => Err(error::unexpected(&ConfigMsgIn::Ping, &other)),
+            Ok(Err(e)) => Err(e),
+            Err(_) => {
+                self.health.lock().unwrap().timeouts += 1;
+                Err(error::timeout(&ConfigMsgIn::Ping, timeout))
+            }
         }
+    }
+
+    /// The unit's USB serial number, if it reports one.
+    pub fn serial_number(&self) -> Option<&str> {
+        self.usb.serial.as_deref()
+    }
+
+    pub fn usb_info(&self) -> &UsbInfo {
+        &self.usb
+    }
+
+    /// Gaps left between writes to this device.
+    pub fn pacing(&self) -> Pacing {
+        self.pacing
+    }
+
+    /// Counters for this connection so far.
+    pub fn health(&self) -> Health {
+        self.health.lock().unwrap().clone()
+    }
+
+    /// Forget cached replies, so the next reads go to the device. For
+    /// long-running commands that poll for changes made elsewhere.
+    pub fn clear_cache(&mut self) {
+        self.cache = ReadCache::default();
+    }
+
+    /// Forget the cached reply to one read, so the next one goes to the device.
+    pub fn forget(&mut self, msg: &ConfigMsgIn) {
+        self.cache.forget(msg);
+    }
+
+    /// Turn audit logging off for a burst of intermediate writes (such as the
+    /// steps of a fade) whose end result is logged once afterwards.
+    pub fn set_audit(&mut self, enabled: bool) {
+        self.audit = enabled;
+    }
+
+    /// Receive messages the device sends that no request is waiting for.
+    pub fn subscribe(&self) -> broadcast::Receiver<ConfigMsgOut> {
+        self.router.subscribe()
+    }
+}
+
+/// Frames straight off the wire, before any decoding, for inspecting
+/// messages this build may not understand. Unlike `FaderpunkDevice`, a frame
+/// that doesn't decode doesn't end the connection.
+pub struct RawFrames {
+    rx: tokio::sync::mpsc::UnboundedReceiver<Vec<u8>>,
+    reader: JoinHandle<String>,
+}
 
-        // Expect BatchMsgEnd
-        let end = self.receive().await?;
-        if !matches!(end, ConfigMsgOut::BatchMsgEnd) {
-            bail!("Expected BatchMsgEnd, got: {:?}", end);
+impl RawFrames {
+    /// Find a Faderpunk and read its frames.
+    pub fn open() -> Result<Self> {
+        let (iface, _) = claim(None)?;
+        let ep_in = endpoint(&iface, Direction::In)?;
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let reader = tokio::spawn(async move {
+            read_frames(iface, ep_in, |frame| {
+                tx.send(frame.to_vec())
+                    .map_err(|_| "nobody is reading frames".to_string())
+            })
+            .await
+        });
+        Ok(RawFrames { rx, reader })
+    }
+
+    /// The next frame (COBS encoded, delimiter stripped). Fails once the
+    /// connection has.
+    pub async fn next(&mut self) -> Result<Vec<u8>> {
+        match self.rx.recv().await {
+            Some(frame) => Ok(frame),
+            None => match (&mut self.reader).await {
+                Ok(reason) => Err(ConnectionError::Closed(reason).into()),
+                Err(e) => Err(ConnectionError::Closed(e.to_string()).into()),
+            },
         }
+    }
+}
 
-        Ok(results)
+impl Drop for RawFrames {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+/// The items of a batch response (between BatchMsgStart and BatchMsgEnd),
+/// yielded as they arrive. See `FaderpunkDevice::stream_batch`.
+pub struct BatchStream<'a> {
+    dev: &'a mut FaderpunkDevice,
+    msg: &'a ConfigMsgIn,
+    source: BatchSource,
+    announced: usize,
+    /// Everything yielded so far, cached once the batch is complete
+    received: Vec<ConfigMsgOut>,
+    start: Instant,
+}
+
+enum BatchSource {
+    Live(Replies),
+    Cached(std::vec::IntoIter<ConfigMsgOut>),
+    Done,
+}
+
+impl BatchStream<'_> {
+    /// Number of items the device said the batch holds.
+    pub fn announced(&self) -> usize {
+        self.announced
+    }
+
+    /// The next item, or None once the batch is complete.
+    pub async fn next(&mut self) -> Option<Result<ConfigMsgOut>> {
+        std::future::poll_fn(|cx| Pin::new(&mut *self).poll_next(cx)).await
+    }
+
+    fn finish(&mut self) {
+        self.source = BatchSource::Done;
+        if self.received.len() != self.announced {
+            eprintln!(
+                "warning: device announced {} item(s) but sent {}",
+                self.announced,
+                self.received.len()
+            );
+        }
+        let received = std::mem::take(&mut self.received);
+        timing::record(
+            || format!("{} ({} replies)", message_name(self.msg), received.len()),
+            self.start,
+        );
+        self.dev.cache.store(self.msg, &received);
+    }
+}
+
+impl Stream for BatchStream<'_> {
+    type Item = Result<ConfigMsgOut>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let replies = match &mut this.source {
+            BatchSource::Live(replies) => replies,
+            BatchSource::Cached(items) => return Poll::Ready(items.next().map(Ok)),
+            BatchSource::Done => return Poll::Ready(None),
+        };
+        match replies.poll_recv(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Some(Ok(ConfigMsgOut::BatchMsgEnd))) => {
+                this.finish();
+                Poll::Ready(None)
+            }
+            Poll::Ready(Some(Ok(item))) => {
+                this.dev.tracker.observe(&item);
+                this.received.push(item.clone());
+                Poll::Ready(Some(Ok(item)))
+            }
+            Poll::Ready(Some(Err(e))) => {
+                this.source = BatchSource::Done;
+                Poll::Ready(Some(Err(e.into())))
+            }
+            Poll::Ready(None) => {
+                this.source = BatchSource::Done;
+                let reason = this
+                    .dev
+                    .router
+                    .closed_reason()
+                    .unwrap_or_else(|| "no reply".to_string());
+                Poll::Ready(Some(Err(ConnectionError::Closed(reason).into())))
+            }
+        }
+    }
+}
+
+type Replies = tokio::sync::mpsc::UnboundedReceiver<Reply>;
+
+/// Address of the interface's bulk endpoint in the given direction.
+fn endpoint(iface: &Interface, direction: Direction) -> Result<u8> {
+    Ok(iface
+        .descriptors()
+        .next()
+        .context("No alt setting")?
+        .endpoints()
+        .find(|e| e.direction() == direction)
+        .with_context(|| format!("No {:?} endpoint found", direction))?
+        .address())
+}
+
+/// One line per param that differs between two reads of an app's values.
+fn param_differences(read: &[Value], now: &[Value]) -> Vec<String> {
+    let format = |value: Option<&Value>| match value {
+        Some(value) => format!("{:?}", value),
+        None => "nothing".to_string(),
+    };
+    (0..read.len().max(now.len()))
+        .filter(|&i| read.get(i) != now.get(i))
+        .map(|i| {
+            let (read, now) = (format(read.get(i)), format(now.get(i)));
+            format!("param {}: read {}, device has {}", i, read, now)
+        })
+        .collect()
+}
+
+/// In strict mode, fail if the reply to a write shows the device applied
+/// something other than what was sent.
+fn check_strict(sent: &ConfigMsgIn, reply: &ConfigMsgOut) -> Result<()> {
+    if !STRICT.load(Ordering::SeqCst) {
+        return Ok(());
+    }
+    let changes = corrections(sent, reply);
+    if changes.is_empty() {
+        return Ok(());
+    }
+    Err(error::DeviceError {
+        request: message_name(sent),
+        kind: error::DeviceErrorKind::Corrected(changes),
+    }
+    .into())
+}
+
+/// Where the device's reply to a write differs from what was written.
+fn corrections(sent: &ConfigMsgIn, reply: &ConfigMsgOut) -> Vec<String> {
+    match (sent, reply) {
+        (ConfigMsgIn::SetLayout(sent), ConfigMsgOut::Layout(got)) => {
+            let describe = |entry: &Option<(u8, usize, u8)>| match entry {
+                Some((app_id, channels, layout_id)) => {
+                    format!("app {} ({} ch, layout ID {})", app_id, channels, layout_id)
+                }
+                None => "nothing".to_string(),
+            };
+            sent.0
+                .iter()
+                .zip(&got.0)
+                .enumerate()
+                .filter(|(_, (sent, got))| sent != got)
+                .map(|(i, (sent, got))| {
+                    format!(
+                        "fader {}: sent {}, device has {}",
+                        slots::label(i),
+                        describe(sent),
+                        describe(got)
+                    )
+                })
+                .collect()
+        }
+        (ConfigMsgIn::SetAppParams { layout_id, values }, ConfigMsgOut::AppState(id, got))
+            if layout_id == id =>
+        {
+            values
+                .iter()
+                .enumerate()
+                .filter_map(|(i, sent)| {
+                    let sent = sent.as_ref()?;
+                    match got.get(i) {
+                        Some(got) if got == sent => None,
+                        Some(got) => Some(format!(
+                            "layout ID {} param {}: sent {:?}, device has {:?}",
+                            layout_id, i, sent, got
+                        )),
+                        None => Some(format!(
+                            "layout ID {} param {}: sent {:?}, device has no such param",
+                            layout_id, i, sent
+                        )),
+                    }
+                })
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Serial numbers of the connected Faderpunks.
+pub fn serial_numbers() -> Result<Vec<String>> {
+    Ok(nusb::list_devices()?
+        .filter(matches_ids)
+        .filter_map(|d| d.serial_number().map(str::to_string))
+        .collect())
+}
+
+/// Find the Faderpunk (the one with `serial`, if given) and claim its
+/// vendor-class interface. Also returns what the bus reports about it.
+fn claim(serial: Option<&str>) -> Result<(Interface, UsbInfo)> {
+    let device_info = nusb::list_devices()?
+        .filter(matches_ids)
+        .find(|d| serial.is_none() || d.serial_number() == serial);
+    let device_info = match (device_info, serial) {
+        (Some(info), _) => info,
+        (None, None) => return Err(ConnectionError::NotFound.into()),
+        (None, Some(serial)) => {
+            let connected = serial_numbers()?;
+            if connected.is_empty() {
+                return Err(ConnectionError::NotFound.into());
+            }
+            bail!(
+                "No Faderpunk with serial {} (connected: {})",
+                serial,
+                connected.join(", ")
+            );
+        }
+    };
+
+    let device = device_info.open()?;
+
+    let wanted = INTERFACE.lock().unwrap().clone();
+    let iface_num = pick_interface(&device_info, wanted.as_ref())?;
+    let iface = claim_interface(&device, &device_info, iface_num)?;
+    let speed = device_info.speed().map(|speed| match speed {
+        nusb::Speed::Low => "low (1.5 Mbit/s)",
+        nusb::Speed::Full => "full (12 Mbit/s)",
+        nusb::Speed::High => "high (480 Mbit/s)",
+        nusb::Speed::Super => "super (5 Gbit/s)",
+        nusb::Speed::SuperPlus => "super+ (10 Gbit/s)",
+        _ => "other",
+    });
+    let version = device_info.device_version();
+    let usb = UsbInfo {
+        serial: device_info.serial_number().map(str::to_string),
+        speed,
+        release: format!("{:x}.{:02x}", version >> 8, version & 0xff),
+        version,
+    };
+    Ok((iface, usb))
+}
+
+/// Linux errno for a device claimed by another driver or process.
+pub const EBUSY: i32 = 16;
+
+fn is_busy(err: &std::io::Error) -> bool {
+    err.kind() == std::io::ErrorKind::ResourceBusy || err.raw_os_error() == Some(EBUSY)
+}
+
+/// Claim interface `number`. On Linux, a kernel driver bound to it is
+/// detached first, where permitted; an interface another program has is
+/// reported with the programs that have the device open.
+fn claim_interface(
+    device: &nusb::Device,
+    info: &nusb::DeviceInfo,
+    number: u8,
+) -> Result<Interface> {
+    match device.claim_interface(number) {
+        Ok(iface) => Ok(iface),
+        Err(e) if is_busy(&e) => claim_busy(device, info, number),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn claim_busy(device: &nusb::Device, info: &nusb::DeviceInfo, number: u8) -> Result<Interface> {
+    // "usbfs" is another program's claim, which isn't ours to break
+    let holder = match bound_driver(info, number) {
+        Some((driver, interface)) if driver != "usbfs" => {
+            match device.detach_and_claim_interface(number) {
+                Ok(iface) => {
+                    eprintln!(
+                        "warning: detached kernel driver '{}' from interface {}",
+                        driver, number
+                    );
+                    return Ok(iface);
+                }
+                Err(_) => InterfaceHolder::Driver { driver, interface },
+            }
+        }
+        _ => match processes_holding(info) {
+            processes if processes.is_empty() => InterfaceHolder::Unknown,
+            processes => InterfaceHolder::Processes(processes),
+        },
+    };
+    Err(ConnectionError::Busy { interface: number, holder }.into())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn claim_busy(_device: &nusb::Device, _info: &nusb::DeviceInfo, number: u8) -> Result<Interface> {
+    Err(ConnectionError::Busy {
+        interface: number,
+        holder: InterfaceHolder::Unknown,
+    }
+    .into())
+}
+
+/// The driver bound to an interface, and the interface's sysfs name.
+#[cfg(target_os = "linux")]
+fn bound_driver(info: &nusb::DeviceInfo, number: u8) -> Option<(String, String)> {
+    let device = info.sysfs_path();
+    let config = std::fs::read_to_string(device.join("bConfigurationValue")).ok()?;
+    let name = format!(
+        "{}:{}.{}",
+        device.file_name()?.to_string_lossy(),
+        config.trim(),
+        number
+    );
+    let driver = std::fs::read_link(device.join(&name).join("driver")).ok()?;
+    Some((driver.file_name()?.to_string_lossy().into_owned(), name))
+}
+
+/// Other processes with the device's node open, as "PID (name)". Only the
+/// processes we may look into are seen, which is all of them as root.
+#[cfg(target_os = "linux")]
+fn processes_holding(info: &nusb::DeviceInfo) -> Vec<String> {
+    let node = std::path::PathBuf::from(format!(
+        "/dev/bus/usb/{:03}/{:03}",
+        info.bus_number(),
+        info.device_address()
+    ));
+    let own = std::process::id().to_string();
+    let Ok(procs) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    let mut holders = Vec::new();
+    for proc in procs.flatten() {
+        let pid = proc.file_name().to_string_lossy().into_owned();
+        if pid == own || !pid.chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+        let Ok(fds) = std::fs::read_dir(proc.path().join("fd")) else {
+            continue;
+        };
+        if fds.flatten().any(|fd| std::fs::read_link(fd.path()).is_ok_and(|t| t == node)) {
+            let name = std::fs::read_to_string(proc.path().join("comm")).unwrap_or_default();
+            holders.push(format!("{} ({})", pid, name.trim()));
+        }
+    }
+    holders
+}
+
+/// The configurator interface: the one asked for, else the only
+/// vendor-class interface, else the vendor-class interface named like the
+/// configurator or the only one with subclass and protocol 0.
+fn pick_interface(device: &nusb::DeviceInfo, wanted: Option<&InterfaceChoice>) -> Result<u8> {
+    let describe = |i: &nusb::InterfaceInfo| {
+        format!(
+            "{} ({}, {:02x}/{:02x}/{:02x})",
+            i.interface_number(),
+            i.interface_string().unwrap_or("unnamed"),
+            i.class(),
+            i.subclass(),
+            i.protocol()
+        )
+    };
+    let named = |i: &nusb::InterfaceInfo, name: &str| {
+        i.interface_string()
+            .is_some_and(|s| s.to_lowercase().contains(&name.to_lowercase()))
+    };
+
+    if let Some(wanted) = wanted {
+        let found: Vec<&nusb::InterfaceInfo> = device
+            .interfaces()
+            .filter(|i| match wanted {
+                InterfaceChoice::Number(number) => i.interface_number() == *number,
+                InterfaceChoice::Name(name) => named(i, name),
+            })
+            .collect();
+        return match found[..] {
+            [iface] => Ok(iface.interface_number()),
+            [] => Err(ConnectionError::MissingInterface {
+                wanted: wanted.to_string(),
+                available: device.interfaces().map(describe).collect(),
+            }
+            .into()),
+            _ => Err(ConnectionError::AmbiguousInterface(
+                found.into_iter().map(describe).collect(),
+            )
+            .into()),
+        };
+    }
+
+    let vendor: Vec<&nusb::InterfaceInfo> = device
+        .interfaces()
+        .filter(|i| i.class() == USB_CLASS_VENDOR)
+        .collect();
+    let by_name: Vec<&nusb::InterfaceInfo> = vendor
+        .iter()
+        .copied()
+        .filter(|i| CONFIG_INTERFACE_NAMES.iter().any(|name| named(i, name)))
+        .collect();
+    let plain: Vec<&nusb::InterfaceInfo> = vendor
+        .iter()
+        .copied()
+        .filter(|i| i.subclass() == 0 && i.protocol() == 0)
+        .collect();
+    match (&vendor[..], &by_name[..], &plain[..]) {
+        ([], _, _) => Err(ConnectionError::NoInterface.into()),
+        ([iface], _, _) | (_, [iface], _) | (_, [], [iface]) => Ok(iface.interface_number()),
+        _ => Err(
+            ConnectionError::AmbiguousInterface(vendor.into_iter().map(describe).collect()).into(),
+        ),
+    }
+}
+
+/// Read frames from the device until the connection fails, dispatching each
+/// decoded message through the router.
+async fn read_loop(iface: Interface, ep_in: u8, router: Router) {
+    let reason = read_frames(iface, ep_in, |frame| match frame::decode(frame) {
+        Ok(Decoded::Message(msg)) => {
+            router.dispatch(msg);
+            Ok(())
+        }
+        Ok(Decoded::Error(kind)) => {
+            if let Err(kind) = router.reject(kind) {
+                eprintln!("warning: device sent an error nothing was waiting for: {}", kind);
+            }
+            Ok(())
+        }
+        Err(e) => Err(format!("{:#}", e)),
+    })
+    .await;
+    router.close(reason);
+}
+
+/// Read from the device, handing each frame (delimiter stripped) to
+/// `on_frame`, until the connection fails or `on_frame` does. Returns why
+/// it stopped.
+async fn read_frames(
+    iface: Interface,
+    ep_in: u8,
+    mut on_frame: impl FnMut(&[u8]) -> std::result::Result<(), String>,
+) -> String {
+    let mut recv_buf: Vec<u8> = Vec::new();
+    let size = TRANSFER_SIZE.load(Ordering::SeqCst);
+    let depth = QUEUE_DEPTH.load(Ordering::SeqCst);
+    // Completions come back in submission order, so data stays in sequence
+    let mut queue = iface.bulk_in_queue(ep_in);
+
+    loop {
+        // Handle every complete frame already in the buffer
+        while let Some(delim_pos) = recv_buf.iter().position(|&b| b == frame::DELIMITER) {
+            let packet: Vec<u8> = recv_buf.drain(..=delim_pos).collect();
+            let frame = &packet[..packet.len() - 1]; // strip delimiter
+
+            if frame.is_empty() {
+                continue;
+            }
+            if let Err(reason) = on_frame(frame) {
+                return reason;
+            }
+        }
+
+        // Need more data from USB
+        while queue.pending() < depth {
+            queue.submit(RequestBuffer::new(size));
+        }
+        let completion = queue.next_complete().await;
+        match completion.status {
+            Ok(()) => {
+                recv_buf.extend_from_slice(&completion.data);
+                queue.submit(RequestBuffer::reuse(completion.data, size));
+            }
+            Err(e) => return format!("USB read failed: {}", e),
+        }
     }
 }